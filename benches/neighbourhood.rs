@@ -0,0 +1,90 @@
+//! Timings of the fits that lean on neighbour queries, over the seeded datasets. Run with `cargo bench`;
+//! pass a name filter after `--` to run only matching cases, as in `cargo bench -- "dbscan search"`.
+
+use clust_rs::datasets::{blobs, moons};
+use clust_rs::dbscan::{Dbscan, Metric, NeighbourSearch};
+use clust_rs::optics::Optics;
+use criterion::{criterion_group, criterion_main, Criterion};
+use ndarray::prelude::*;
+use ndarray::s;
+
+fn datasets() -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let centers = Array2::from_shape_fn((8, 3), |(row, col)| ((row * 7 + col * 3) % 10) as f64 * 4.0);
+    let (blobs_3d, _) = blobs(20_000, &centers, 1.0, 1);
+    let wide_centers = Array2::from_shape_fn((8, 32), |(row, col)| ((row * 5 + col) % 9) as f64 * 3.0);
+    let (blobs_32d, _) = blobs(5_000, &wide_centers, 1.0, 2);
+    let (moons_2d, _) = moons(20_000, 0.05, 3);
+    (blobs_3d, blobs_32d, moons_2d)
+}
+
+fn fits(c: &mut Criterion) {
+    let (blobs_3d, blobs_32d, moons_2d) = datasets();
+    let mut group = c.benchmark_group("dbscan");
+    group.sample_size(10);
+    group.bench_function("new blobs 20k x 3", |b| b.iter(|| Dbscan::with_seed(&blobs_3d, 0.8, 5, true, 0)));
+    group.bench_function("new_brute_force blobs 20k x 3", |b| b.iter(|| Dbscan::new_brute_force(&blobs_3d, 0.8, 5, true)));
+    group.bench_function("new_union_find blobs 20k x 3", |b| b.iter(|| Dbscan::new_union_find(&blobs_3d, 0.8, 5, true)));
+    group.bench_function("new blobs 5k x 32", |b| b.iter(|| Dbscan::with_seed(&blobs_32d, 4.0, 5, true, 0)));
+    group.bench_function("new moons 20k x 2", |b| b.iter(|| Dbscan::with_seed(&moons_2d, 0.05, 5, true, 0)));
+    let moons_5k = moons_2d.slice(s![..5_000, ..]).to_owned();
+    group.bench_function("manhattan moons 5k x 2", |b| b.iter(|| Dbscan::new_with_metric(&moons_5k, 0.05, 5, true, Metric::Manhattan)));
+    group.finish();
+
+    let mut group = c.benchmark_group("optics");
+    group.sample_size(10);
+    group.bench_function("moons 20k x 2", |b| b.iter(|| Optics::new(&moons_2d, 5, 0.1)));
+    group.finish();
+}
+
+/// The brute-force scan on wide data, where the distance kernel is nearly all of the time. Run it with and
+/// without `--features simd` to compare the `wide` vectors with the auto-vectorized accumulators.
+fn brute_force(c: &mut Criterion) {
+    let centers = Array2::from_shape_fn((4, 128), |(row, col)| ((row * 3 + col) % 5) as f64 * 2.0);
+    let (blobs_128d, _) = blobs(1_000, &centers, 1.0, 4);
+    let blobs_128d_f32 = blobs_128d.mapv(|x| x as f32);
+    let mut group = c.benchmark_group("brute force");
+    group.sample_size(10);
+    group.bench_function("f64 blobs 1k x 128", |b| b.iter(|| Dbscan::new_brute_force(&blobs_128d, 12.0, 5, true)));
+    group.bench_function("f32 blobs 1k x 128", |b| b.iter(|| Dbscan::new_brute_force(&blobs_128d_f32, 12.0, 5, true)));
+    group.finish();
+}
+
+/// `new_parallel` against the sequential `new` it matches, where the region queries dominate the fit.
+#[cfg(feature = "rayon")]
+fn parallel(c: &mut Criterion) {
+    let (blobs_3d, blobs_32d, _) = datasets();
+    let mut group = c.benchmark_group("dbscan parallel");
+    group.sample_size(10);
+    for (name, data, eps) in [("blobs 20k x 3", &blobs_3d, 0.8), ("blobs 5k x 32", &blobs_32d, 4.0)].iter() {
+        group.bench_function(format!("new {}", name), |b| b.iter(|| Dbscan::with_seed(*data, *eps, 5, true, 0)));
+        group.bench_function(format!("new_parallel {}", name), |b| b.iter(|| Dbscan::new_parallel(*data, *eps, 5, true)));
+    }
+    group.finish();
+}
+
+/// Every `NeighbourSearch` backend on the same low- and high-dimensional data.
+fn searches(c: &mut Criterion) {
+    let (blobs_3d, blobs_32d, _) = datasets();
+    let searches = [
+        ("index", NeighbourSearch::Index),
+        ("brute force", NeighbourSearch::BruteForce),
+        ("ball tree", NeighbourSearch::BallTree),
+        ("lsh", NeighbourSearch::Lsh),
+        ("gpu", NeighbourSearch::Gpu),
+    ];
+    let mut group = c.benchmark_group("dbscan search");
+    group.sample_size(10);
+    for (name, search) in searches.iter() {
+        group.bench_function(format!("{} blobs 20k x 3", name), |b| b.iter(|| Dbscan::new_with_search(&blobs_3d, 0.8, 5, true, *search)));
+        group.bench_function(format!("{} blobs 5k x 32", name), |b| b.iter(|| Dbscan::new_with_search(&blobs_32d, 4.0, 5, true, *search)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, fits, brute_force, searches);
+#[cfg(feature = "rayon")]
+criterion_group!(parallel_benches, parallel);
+#[cfg(feature = "rayon")]
+criterion_main!(benches, parallel_benches);
+#[cfg(not(feature = "rayon"))]
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+/* C interface to clust-rs, built with the `capi` feature. Labels use 0 for noise and number clusters from 1.
+ * Panics inside the library are caught and reported as failures rather than unwinding into C.
+ * Generated by cbindgen from src/capi.rs; don't edit by hand. */
+
+#ifndef CLUST_H
+#define CLUST_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/*
+ A fitted model with its training data, as `DbscanBuilder::fit` returns it.
+ */
+typedef struct ClustDbscan ClustDbscan;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Fits DBSCAN to `rows` × `cols` row-major values, seeding the fit with `seed` so the labels are
+ reproducible. Returns null if `data` is null, the values are empty or not all finite, `eps` is negative
+ or NaN, `min_points` is 0, or the fit panics.
+
+ # Safety
+
+ `data` must point to `rows * cols` readable doubles.
+ */
+ClustDbscan *clust_dbscan_fit(const double *data,
+                              size_t rows,
+                              size_t cols,
+                              double eps,
+                              size_t min_points,
+                              bool borders,
+                              uint64_t seed);
+
+/*
+ Number of training points, which is the length of `clust_dbscan_labels`, or 0 for a null model.
+
+ # Safety
+
+ `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+ */
+size_t clust_dbscan_n_points(const ClustDbscan *model);
+
+/*
+ Number of clusters, or 0 for a null model.
+
+ # Safety
+
+ `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+ */
+size_t clust_dbscan_n_clusters(const ClustDbscan *model);
+
+/*
+ The training points' labels, owned by the model and valid until it is freed, or null for a null model.
+
+ # Safety
+
+ `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+ */
+const size_t *clust_dbscan_labels(const ClustDbscan *model);
+
+/*
+ Writes a label for each of the `rows` new points to `labels`: the lowest-numbered cluster within eps of a
+ core point, or 0. Returns 0, or -1 without writing anything if an argument is null, `cols` differs from
+ the training data's, a value isn't finite or the prediction panics.
+
+ # Safety
+
+ `model` must come from `clust_dbscan_fit` and not have been freed, `data` must point to `rows * cols`
+ readable doubles and `labels` to `rows` writable labels.
+ */
+int32_t clust_dbscan_predict(const ClustDbscan *model,
+                             const double *data,
+                             size_t rows,
+                             size_t cols,
+                             size_t *labels);
+
+/*
+ Releases a model. Null is ignored.
+
+ # Safety
+
+ `model` must be null or come from `clust_dbscan_fit`, and must not be used again.
+ */
+void clust_dbscan_free(ClustDbscan *model);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CLUST_H */
@@ -0,0 +1,143 @@
+use crate::index::{row_slice, squared_euclidean};
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Affinity propagation (Frey & Dueck, 2007): points exchange responsibility and availability messages over
+/// negative squared Euclidean similarities until a set of exemplars emerges. The number of clusters follows
+/// from `preference`, each point's similarity to itself: higher preferences give more exemplars. `clusters`
+/// indexes into `exemplars`, which holds row indices of `data`, as `Kmeans` labels index into its centers.
+#[derive(Debug)]
+pub struct AffinityPropagation<T: Float + One + Zero> {
+    pub preference: T,
+    pub exemplars: Vec<usize>,
+    pub clusters: Vec<usize>,
+    pub n_iter: usize,
+    pub converged: bool,
+}
+
+impl<T: Float + One + Zero> AffinityPropagation<T> {
+    /// Updates messages as `damping * old + (1 - damping) * new`, with `damping` in [0.5, 1), and stops once the
+    /// exemplars are unchanged for `convergence_iter` iterations or after `max_iter`. Without a `preference` it
+    /// uses the median similarity, giving a moderate number of clusters. Needs O(n^2) memory.
+    pub fn new(data: &Array2<T>, preference: Option<T>, damping: T, max_iter: usize, convergence_iter: usize) -> AffinityPropagation<T> {
+        assert!(damping >= T::from(0.5).expect("T::from(f64)") && damping < T::one(), "damping must be in [0.5, 1)");
+        let n = data.rows();
+        let mut similarity = Array2::from_shape_fn((n, n), |(a, b)| -squared_euclidean(&row_slice(data.row(a)), &row_slice(data.row(b))));
+        let preference = preference.unwrap_or_else(|| {
+            let mut off_diagonal = similarity.indexed_iter().filter(|((a, b), _)| a != b).map(|(_, s)| *s).collect::<Vec<T>>();
+            off_diagonal.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd similarity"));
+            match off_diagonal.len() {
+                0 => T::zero(),
+                len if len % 2 == 1 => off_diagonal[len / 2],
+                len => (off_diagonal[len / 2 - 1] + off_diagonal[len / 2]) / (T::one() + T::one()),
+            }
+        });
+        similarity.diag_mut().fill(preference);
+        // Tiny noise breaks the ties between equally good exemplars that otherwise make the messages oscillate.
+        let mut rng = StdRng::seed_from_u64(0);
+        let tiny = T::epsilon() * T::from(100.0).expect("T::from(f64)");
+        similarity.mapv_inplace(|s| s + (T::epsilon() * s.abs() + tiny) * T::from(rng.gen_range(0.0, 1.0)).expect("T::from(f64)"));
+
+        let mut responsibility = Array2::<T>::zeros((n, n));
+        let mut availability = Array2::<T>::zeros((n, n));
+        let mut exemplars = Vec::new();
+        let (mut stable, mut n_iter, mut converged) = (0, 0, false);
+        while n_iter < max_iter {
+            n_iter += 1;
+            // r(i, k) = s(i, k) - max over k' != k of (a(i, k') + s(i, k'))
+            for i in 0..n {
+                let (mut best, mut second, mut best_k) = (T::neg_infinity(), T::neg_infinity(), 0);
+                for k in 0..n {
+                    let value = availability[[i, k]] + similarity[[i, k]];
+                    if value > best {
+                        second = best;
+                        best = value;
+                        best_k = k;
+                    } else if value > second {
+                        second = value;
+                    }
+                }
+                for k in 0..n {
+                    let competing = if k == best_k { second } else { best };
+                    responsibility[[i, k]] = damping * responsibility[[i, k]] + (T::one() - damping) * (similarity[[i, k]] - competing);
+                }
+            }
+            // a(i, k) = min(0, r(k, k) + sum over i' not in {i, k} of max(0, r(i', k))), and a(k, k) = that sum
+            for k in 0..n {
+                let positive = (0..n).filter(|i| *i != k).fold(T::zero(), |acc, i| acc + responsibility[[i, k]].max(T::zero()));
+                for i in 0..n {
+                    let updated = if i == k {
+                        positive
+                    } else {
+                        (responsibility[[k, k]] + positive - responsibility[[i, k]].max(T::zero())).min(T::zero())
+                    };
+                    availability[[i, k]] = damping * availability[[i, k]] + (T::one() - damping) * updated;
+                }
+            }
+
+            let current = (0..n).filter(|k| responsibility[[*k, *k]] + availability[[*k, *k]] > T::zero()).collect::<Vec<usize>>();
+            stable = if current == exemplars && !current.is_empty() { stable + 1 } else { 0 };
+            exemplars = current;
+            if stable >= convergence_iter {
+                converged = true;
+                break;
+            }
+        }
+
+        if exemplars.is_empty() && n > 0 {
+            let best = (0..n).max_by(|a, b| (responsibility[[*a, *a]] + availability[[*a, *a]]).partial_cmp(&(responsibility[[*b, *b]] + availability[[*b, *b]])).expect("PartialOrd criterion"));
+            exemplars.extend(best);
+        }
+        let clusters = (0..n)
+            .map(|i| match exemplars.binary_search(&i) {
+                Ok(position) => position,
+                Err(_) => {
+                    (0..exemplars.len())
+                        .max_by(|a, b| similarity[[i, exemplars[*a]]].partial_cmp(&similarity[[i, exemplars[*b]]]).expect("PartialOrd similarity"))
+                        .expect("at least one exemplar")
+                }
+            })
+            .collect::<Vec<usize>>();
+
+        AffinityPropagation { preference, exemplars, clusters, n_iter, converged }
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for AffinityPropagation<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+
+    #[test]
+    fn test_affinity_propagation() {
+        let data = Array2::from_shape_vec(
+            (9, 2),
+            vec![0.0, 0.0, 0.3, 0.1, 0.1, 0.3, 10.0, 10.0, 10.2, 10.1, 9.9, 10.3, 20.0, 0.0, 20.2, 0.2, 19.8, 0.1],
+        )
+        .unwrap();
+        let model = AffinityPropagation::new(&data, None, 0.5, 200, 15);
+        assert!(model.converged && model.exemplars.len() == 3, "{:?}", model);
+        assert!(model.clusters.chunks(3).all(|group| group.iter().all(|c| *c == group[0])));
+        assert!(model.clusters[0] != model.clusters[3] && model.clusters[3] != model.clusters[6] && model.clusters[0] != model.clusters[6]);
+        assert!(model.exemplars.iter().enumerate().all(|(c, exemplar)| model.clusters[*exemplar] == c));
+        assert!(AffinityPropagation::new(&column_major(&data), None, 0.5, 200, 15).clusters == model.clusters);
+
+        let single = AffinityPropagation::new(&data, Some(-1000.0), 0.9, 500, 15);
+        assert!(single.exemplars.len() == 1 && single.clusters == vec![0; 9]);
+    }
+}
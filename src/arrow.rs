@@ -0,0 +1,119 @@
+//! Arrow record batches as `Array2`, so a pipeline can stream Arrow IPC into the fits without building the
+//! arrays itself. `read_ipc_stream` decodes a stream a batch at a time; its batches go straight to
+//! `PartialFit::partial_fit_stream` for BIRCH or mini-batch k-means, or to `Dbscan::try_new_chunked`, which
+//! gathers them into the one matrix DBSCAN needs.
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::Float64Type;
+use arrow_array::RecordBatch;
+use arrow_ipc::reader::StreamReader;
+use arrow_schema::{ArrowError, DataType};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+
+/// The named `columns` of `batch` in the order given, or every column when `columns` is empty, as one row
+/// per record. Columns must be of a numeric type and hold no nulls.
+pub fn batch_to_array<T: Float>(batch: &RecordBatch, columns: &[&str]) -> io::Result<Array2<T>> {
+    let names = if columns.is_empty() { batch.schema().fields().iter().map(|field| field.name().clone()).collect::<Vec<String>>() } else { columns.iter().map(|name| name.to_string()).collect() };
+    let mut data = Array2::zeros((batch.num_rows(), names.len()));
+    for (col, name) in names.iter().enumerate() {
+        let array = batch.column_by_name(name).ok_or_else(|| invalid(format!("no column named {}", name)))?;
+        if !array.data_type().is_numeric() {
+            return Err(invalid(format!("column {} is not numeric", name)));
+        }
+        if array.null_count() > 0 {
+            return Err(invalid(format!("column {} has nulls", name)));
+        }
+        let values = arrow_cast::cast(array, &DataType::Float64).map_err(arrow_error)?;
+        for (row, x) in values.as_primitive::<Float64Type>().values().iter().enumerate() {
+            data[[row, col]] = T::from(*x).ok_or_else(|| invalid(format!("column {} row {} doesn't fit the float type", name, row)))?;
+        }
+    }
+    Ok(data)
+}
+
+/// The record batches of an Arrow IPC stream, each as the `Array2` `batch_to_array` gives.
+pub struct IpcBatches<R: Read, T> {
+    reader: StreamReader<R>,
+    columns: Vec<String>,
+    float: PhantomData<T>,
+}
+
+/// Starts decoding the Arrow IPC stream in `reader`, keeping `columns` of every batch as `batch_to_array`
+/// does. The stream's schema is read here, so a stream that doesn't open with one is an error now rather
+/// than on the first batch.
+pub fn read_ipc_stream<R: Read, T: Float>(reader: R, columns: &[&str]) -> io::Result<IpcBatches<R, T>> {
+    let reader = StreamReader::try_new(reader, None).map_err(arrow_error)?;
+    Ok(IpcBatches { reader, columns: columns.iter().map(|name| name.to_string()).collect(), float: PhantomData })
+}
+
+impl<R: Read, T: Float> Iterator for IpcBatches<R, T> {
+    type Item = io::Result<Array2<T>>;
+
+    fn next(&mut self) -> Option<io::Result<Array2<T>>> {
+        let columns = self.columns.iter().map(String::as_str).collect::<Vec<&str>>();
+        self.reader.next().map(|batch| batch.map_err(arrow_error).and_then(|batch| batch_to_array(&batch, &columns)))
+    }
+}
+
+fn arrow_error(error: ArrowError) -> io::Error {
+    match error {
+        ArrowError::IoError(_, error) => error,
+        error => invalid(error.to_string()),
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::birch::Birch;
+    use crate::dbscan::Dbscan;
+    use crate::traits::{Clustering, PartialFit};
+    use arrow_array::{Float32Array, Int64Array, StringArray};
+    use arrow_ipc::writer::StreamWriter;
+    use ndarray::s;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Arc;
+
+    fn batch(x: Vec<f32>, y: Vec<i64>) -> RecordBatch {
+        let names = StringArray::from(x.iter().map(|x| format!("point {}", x)).collect::<Vec<String>>());
+        RecordBatch::try_from_iter(vec![("x", Arc::new(Float32Array::from(x)) as _), ("y", Arc::new(Int64Array::from(y)) as _), ("name", Arc::new(names) as _)]).unwrap()
+    }
+
+    #[test]
+    fn test_ipc_stream() {
+        let batches = [batch(vec![1.0, 1.5, 2.0], vec![0, 0, 1]), batch(vec![20.0, 20.5, 21.0, 1.25], vec![10, 10, 11, 0])];
+        let mut stream = Vec::new();
+        {
+            let mut writer = StreamWriter::try_new(&mut stream, &batches[0].schema()).unwrap();
+            for batch in batches.iter() {
+                writer.write(batch).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        let data = arr2(&[[1.0, 0.0], [1.5, 0.0], [2.0, 1.0], [20.0, 10.0], [20.5, 10.0], [21.0, 11.0], [1.25, 0.0]]);
+        let decoded = read_ipc_stream::<_, f64>(stream.as_slice(), &["x", "y"]).unwrap().collect::<io::Result<Vec<Array2<f64>>>>().unwrap();
+        assert!(decoded.len() == 2 && decoded[0] == data.slice(s![..3, ..]) && decoded[1] == data.slice(s![3.., ..]));
+        assert!(batch_to_array::<f32>(&batches[0], &["y", "x"]).unwrap() == arr2(&[[0.0, 1.0], [0.0, 1.5], [1.0, 2.0]]));
+
+        let streamed = Dbscan::try_new_chunked_with_rng(read_ipc_stream::<_, f64>(stream.as_slice(), &["x", "y"]).unwrap(), 1.5, 2, true, &mut StdRng::seed_from_u64(4)).unwrap();
+        assert!(streamed.clusters == Dbscan::with_seed(&data, 1.5, 2, true, 4).clusters);
+        let mut birch = Birch::new(1.5, 10, Some(2));
+        assert!(birch.partial_fit_stream(read_ipc_stream::<_, f64>(stream.as_slice(), &["x", "y"]).unwrap()).unwrap() == 7);
+        let mut direct = Birch::new(1.5, 10, Some(2));
+        direct.partial_fit(&data);
+        assert!(birch.labels() == direct.labels());
+
+        assert!(batch_to_array::<f64>(&batches[0], &[]).unwrap_err().to_string() == "column name is not numeric");
+        assert!(batch_to_array::<f64>(&batches[0], &["z"]).unwrap_err().to_string() == "no column named z");
+        assert!(Dbscan::try_new_chunked(read_ipc_stream::<_, f64>(&stream[..stream.len() / 2], &["x"]).unwrap(), 1.5, 2, true).is_err());
+        assert!(read_ipc_stream::<_, f64>(&b""[..], &["x"]).is_err());
+    }
+}
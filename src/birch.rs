@@ -0,0 +1,286 @@
+use crate::hierarchical::{Agglomerative, Linkage};
+use crate::index::{row_slice, squared_euclidean};
+use crate::traits::{Clustering, PartialFit, Predict};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+
+/// BIRCH (Zhang, Ramakrishnan & Livny, 1996): points are summarised into a CF-tree of subclusters whose radius
+/// stays within `threshold`, with up to `branching_factor` entries per node, so memory grows with the number
+/// of subclusters rather than points. Labels come from a global clustering of the subcluster centroids, by
+/// Ward agglomeration into `n_clusters` when given and one cluster per subcluster otherwise, numbered from 0
+/// as in `Kmeans`.
+#[derive(Debug)]
+pub struct Birch<T: Float + One + Zero> {
+    pub threshold: T,
+    pub branching_factor: usize,
+    pub n_clusters: Option<usize>,
+    root: CfNode<T>,
+    subclusters: Vec<usize>,
+    next_subcluster: usize,
+}
+
+/// Clustering feature: point count, linear sum and sum of squared norms, enough for centroid and radius.
+#[derive(Debug, Clone)]
+struct Cf<T> {
+    n: usize,
+    linear_sum: Vec<T>,
+    squared_sum: T,
+}
+
+#[derive(Debug)]
+struct CfEntry<T> {
+    cf: Cf<T>,
+    child: Option<Box<CfNode<T>>>,
+    subcluster: usize,
+}
+
+#[derive(Debug)]
+struct CfNode<T> {
+    entries: Vec<CfEntry<T>>,
+}
+
+impl<T: Float> Cf<T> {
+    fn from_point(point: &[T]) -> Cf<T> {
+        Cf {
+            n: 1,
+            linear_sum: point.to_vec(),
+            squared_sum: point.iter().fold(T::zero(), |acc, x| acc + *x * *x),
+        }
+    }
+
+    fn merged(&self, other: &Cf<T>) -> Cf<T> {
+        Cf {
+            n: self.n + other.n,
+            linear_sum: self.linear_sum.iter().zip(other.linear_sum.iter()).map(|(a, b)| *a + *b).collect::<Vec<T>>(),
+            squared_sum: self.squared_sum + other.squared_sum,
+        }
+    }
+
+    fn centroid(&self) -> Vec<T> {
+        let n = T::from(self.n).expect("T::from(usize)");
+        self.linear_sum.iter().map(|x| *x / n).collect::<Vec<T>>()
+    }
+
+    /// Root mean squared distance of the points from their centroid.
+    fn radius(&self) -> T {
+        let centroid = self.centroid();
+        let norm = centroid.iter().fold(T::zero(), |acc, x| acc + *x * *x);
+        (self.squared_sum / T::from(self.n).expect("T::from(usize)") - norm).max(T::zero()).sqrt()
+    }
+}
+
+impl<T: Float> CfNode<T> {
+    fn cf(&self) -> Cf<T> {
+        let mut entries = self.entries.iter();
+        let first = entries.next().expect("non-empty CF node").cf.clone();
+        entries.fold(first, |acc, entry| acc.merged(&entry.cf))
+    }
+
+    fn closest(&self, point: &[T]) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| squared_euclidean(&entry.cf.centroid(), point))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance"))
+            .expect("non-empty CF node")
+            .0
+    }
+
+    /// Splits off the entries closer to the second of the two most distant entries once the node is over full.
+    fn split(&mut self) -> CfNode<T> {
+        let centroids = self.entries.iter().map(|entry| entry.cf.centroid()).collect::<Vec<Vec<T>>>();
+        let (mut seeds, mut farthest) = ((0, 1), T::neg_infinity());
+        for a in 0..centroids.len() {
+            for b in a + 1..centroids.len() {
+                let distance = squared_euclidean(&centroids[a], &centroids[b]);
+                if distance > farthest {
+                    seeds = (a, b);
+                    farthest = distance;
+                }
+            }
+        }
+        let (kept, moved): (Vec<_>, Vec<_>) = self
+            .entries
+            .drain(..)
+            .zip(centroids.iter())
+            .enumerate()
+            .partition(|(i, (_, centroid))| *i == seeds.0 || (*i != seeds.1 && squared_euclidean(centroid, &centroids[seeds.0]) <= squared_euclidean(centroid, &centroids[seeds.1])));
+        self.entries = kept.into_iter().map(|(_, (entry, _))| entry).collect::<Vec<CfEntry<T>>>();
+        CfNode { entries: moved.into_iter().map(|(_, (entry, _))| entry).collect::<Vec<CfEntry<T>>>() }
+    }
+}
+
+impl<T: Float + One + Zero> Birch<T> {
+    pub fn new(threshold: T, branching_factor: usize, n_clusters: Option<usize>) -> Birch<T> {
+        assert!(branching_factor >= 2, "branching_factor must be at least 2");
+        Birch {
+            threshold,
+            branching_factor,
+            n_clusters,
+            root: CfNode { entries: Vec::new() },
+            subclusters: Vec::new(),
+            next_subcluster: 0,
+        }
+    }
+
+    /// Adds `point` to the tree, returning the id of the leaf subcluster that absorbed it.
+    pub fn insert(&mut self, point: &[T]) -> usize {
+        let (subcluster, sibling) = insert_into(&mut self.root, point, self.threshold, self.branching_factor, &mut self.next_subcluster);
+        if let Some(sibling) = sibling {
+            let old_root = std::mem::replace(&mut self.root, CfNode { entries: Vec::new() });
+            for node in [old_root, sibling] {
+                self.root.entries.push(CfEntry { cf: node.cf(), child: Some(Box::new(node)), subcluster: 0 });
+            }
+        }
+        self.subclusters.push(subcluster);
+        subcluster
+    }
+
+    /// Centroids of the leaf subclusters, in order of subcluster id.
+    pub fn subcluster_centers(&self) -> Vec<Array1<T>> {
+        let mut leaves = Vec::new();
+        collect_leaves(&self.root, &mut leaves);
+        leaves.sort_by_key(|(id, _)| *id);
+        leaves.into_iter().map(|(_, cf)| Array1::from_vec(cf.centroid())).collect::<Vec<Array1<T>>>()
+    }
+
+    /// Label of each row of `data`: the global cluster of its nearest subcluster. Empty when nothing has been
+    /// inserted yet, as there are no clusters to label the rows with.
+    pub fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        let (centers, labels) = self.global_clustering();
+        if centers.is_empty() {
+            return Vec::new();
+        }
+        data.outer_iter()
+            .map(|row| {
+                let nearest = centers
+                    .iter()
+                    .map(|center| squared_euclidean(&row_slice(center.view()), &row.to_vec()))
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance"))
+                    .expect("at least one subcluster")
+                    .0;
+                labels[nearest]
+            })
+            .collect::<Vec<usize>>()
+    }
+
+    /// Subcluster centroids by id, with the global label of each.
+    fn global_clustering(&self) -> (Vec<Array1<T>>, Vec<usize>) {
+        let centers = self.subcluster_centers();
+        let labels = match self.n_clusters {
+            Some(k) if centers.len() > 1 => {
+                let mut stacked = Array2::zeros((centers.len(), centers[0].len()));
+                for (mut row, center) in stacked.outer_iter_mut().zip(centers.iter()) {
+                    row.assign(center);
+                }
+                Agglomerative::new(&stacked, Linkage::Ward).cut(k).into_iter().map(|label| label - 1).collect::<Vec<usize>>()
+            }
+            _ => (0..centers.len()).collect::<Vec<usize>>(),
+        };
+        (centers, labels)
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for Birch<T> {
+    /// Labels of every point inserted so far, through its subcluster; the global clustering is redone each call.
+    fn labels(&self) -> Vec<usize> {
+        let (_, labels) = self.global_clustering();
+        self.subclusters.iter().map(|subcluster| labels[*subcluster]).collect::<Vec<usize>>()
+    }
+}
+
+impl<T: Float + One + Zero> Predict<T> for Birch<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        Birch::predict(self, data)
+    }
+}
+
+impl<T: Float + One + Zero> PartialFit<T> for Birch<T> {
+    fn partial_fit(&mut self, batch: &Array2<T>) {
+        for row in batch.outer_iter() {
+            self.insert(&row.to_vec());
+        }
+    }
+}
+
+/// Inserts `point` below `node`, returning the absorbing subcluster and, if `node` had to split, its new sibling.
+fn insert_into<T: Float>(node: &mut CfNode<T>, point: &[T], threshold: T, branching_factor: usize, next_subcluster: &mut usize) -> (usize, Option<CfNode<T>>) {
+    let added = Cf::from_point(point);
+    let subcluster = if node.entries.is_empty() {
+        None
+    } else {
+        let closest = node.closest(point);
+        let entry = &mut node.entries[closest];
+        match entry.child {
+            Some(ref mut child) => {
+                let (subcluster, sibling) = insert_into(child, point, threshold, branching_factor, next_subcluster);
+                match sibling {
+                    Some(sibling) => {
+                        entry.cf = child.cf();
+                        node.entries.push(CfEntry { cf: sibling.cf(), child: Some(Box::new(sibling)), subcluster: 0 });
+                    }
+                    None => entry.cf = entry.cf.merged(&added),
+                }
+                Some(subcluster)
+            }
+            None => {
+                let merged = entry.cf.merged(&added);
+                if merged.radius() <= threshold {
+                    entry.cf = merged;
+                    Some(entry.subcluster)
+                } else {
+                    None
+                }
+            }
+        }
+    };
+    let subcluster = subcluster.unwrap_or_else(|| {
+        *next_subcluster += 1;
+        node.entries.push(CfEntry { cf: added, child: None, subcluster: *next_subcluster - 1 });
+        *next_subcluster - 1
+    });
+    let sibling = if node.entries.len() > branching_factor { Some(node.split()) } else { None };
+    (subcluster, sibling)
+}
+
+fn collect_leaves<T: Clone>(node: &CfNode<T>, leaves: &mut Vec<(usize, Cf<T>)>) {
+    for entry in node.entries.iter() {
+        match entry.child {
+            Some(ref child) => collect_leaves(child, leaves),
+            None => leaves.push((entry.subcluster, entry.cf.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+    use ndarray::s;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_birch() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let blobs = [(0.0, 0.0), (8.0, 0.0), (0.0, 8.0)];
+        let values = (0..600).flat_map(|i| {
+            let (x, y) = blobs[i % 3];
+            vec![x + rng.gen_range(-1.0, 1.0), y + rng.gen_range(-1.0, 1.0)]
+        });
+        let data = Array2::from_shape_vec((600, 2), values.collect::<Vec<f64>>()).unwrap();
+        let mut model = Birch::new(0.5, 4, Some(3));
+        for start in (0..600).step_by(100) {
+            model.partial_fit(&data.slice(s![start..start + 100, ..]).to_owned());
+        }
+        let subclusters = model.subcluster_centers();
+        assert!(subclusters.len() > 3 && subclusters.len() < 200);
+        let labels = model.labels();
+        assert!(labels.len() == 600 && (0..600).all(|i| labels[i] == labels[i % 3]));
+        assert!(labels[0] != labels[1] && labels[1] != labels[2] && labels[0] != labels[2]);
+        assert!(model.predict(&data) == labels && model.predict(&column_major(&data)) == labels);
+        assert!(Birch::new(0.5, 4, None).labels().is_empty() && Birch::new(0.5, 4, Some(3)).predict(&data).is_empty());
+    }
+}
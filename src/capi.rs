@@ -0,0 +1,165 @@
+//! C bindings for DBSCAN over row-major `double` buffers, for calling the crate from C, C++, Python or Julia.
+//! Build a shared library with `cargo rustc --release --features capi --crate-type cdylib` and include
+//! `include/clust.h`, which cbindgen generates from this module with the settings in `cbindgen.toml`. Models
+//! are opaque pointers owned by the caller and released with `clust_dbscan_free`; labels follow `Dbscan`,
+//! with 0 for noise and clusters from 1. A panic never unwinds into the caller: fitting and predicting catch
+//! it and report it as a failure.
+
+use crate::dbscan::{validate, DbscanBuilder, DbscanModel};
+use ndarray::prelude::*;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+/// A fitted model with its training data, as `DbscanBuilder::fit` returns it.
+pub struct ClustDbscan {
+    model: DbscanModel<f64>,
+}
+
+/// Fits DBSCAN to `rows` × `cols` row-major values, seeding the fit with `seed` so the labels are
+/// reproducible. Returns null if `data` is null, the values are empty or not all finite, `eps` is negative
+/// or NaN, `min_points` is 0, or the fit panics.
+///
+/// # Safety
+///
+/// `data` must point to `rows * cols` readable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_fit(data: *const f64, rows: usize, cols: usize, eps: f64, min_points: usize, borders: bool, seed: u64) -> *mut ClustDbscan {
+    if eps.is_nan() || eps < 0.0 || min_points == 0 {
+        return ptr::null_mut();
+    }
+    let data = match array(data, rows, cols) {
+        Some(data) => data,
+        None => return ptr::null_mut(),
+    };
+    match panic::catch_unwind(|| DbscanBuilder::new(eps, min_points).borders(borders).seed(seed).fit(&data)) {
+        Ok(model) => Box::into_raw(Box::new(ClustDbscan { model })),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Number of training points, which is the length of `clust_dbscan_labels`, or 0 for a null model.
+///
+/// # Safety
+///
+/// `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_n_points(model: *const ClustDbscan) -> usize {
+    match model.as_ref() {
+        Some(model) => model.model.clusters().len(),
+        None => 0,
+    }
+}
+
+/// Number of clusters, or 0 for a null model.
+///
+/// # Safety
+///
+/// `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_n_clusters(model: *const ClustDbscan) -> usize {
+    match model.as_ref() {
+        Some(model) => model.model.dbscan.n_clusters(),
+        None => 0,
+    }
+}
+
+/// The training points' labels, owned by the model and valid until it is freed, or null for a null model.
+///
+/// # Safety
+///
+/// `model` must be null or come from `clust_dbscan_fit` and not have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_labels(model: *const ClustDbscan) -> *const usize {
+    match model.as_ref() {
+        Some(model) => model.model.clusters().as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Writes a label for each of the `rows` new points to `labels`: the lowest-numbered cluster within eps of a
+/// core point, or 0. Returns 0, or -1 without writing anything if an argument is null, `cols` differs from
+/// the training data's, a value isn't finite or the prediction panics.
+///
+/// # Safety
+///
+/// `model` must come from `clust_dbscan_fit` and not have been freed, `data` must point to `rows * cols`
+/// readable doubles and `labels` to `rows` writable labels.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_predict(model: *const ClustDbscan, data: *const f64, rows: usize, cols: usize, labels: *mut usize) -> i32 {
+    let model = match model.as_ref() {
+        Some(model) if !labels.is_null() && cols == model.model.data().cols() => &model.model,
+        _ => return -1,
+    };
+    let data = match array(data, rows, cols) {
+        Some(data) => data,
+        None => return -1,
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| model.predict_single(&data))) {
+        Ok(predicted) => {
+            for (row, label) in predicted.into_iter().enumerate() {
+                *labels.add(row) = label.unwrap_or(0);
+            }
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Releases a model. Null is ignored.
+///
+/// # Safety
+///
+/// `model` must be null or come from `clust_dbscan_fit`, and must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn clust_dbscan_free(model: *mut ClustDbscan) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
+
+unsafe fn array(data: *const f64, rows: usize, cols: usize) -> Option<Array2<f64>> {
+    if data.is_null() {
+        return None;
+    }
+    let len = rows.checked_mul(cols)?;
+    let data = Array2::from_shape_vec((rows, cols), slice::from_raw_parts(data, len).to_vec()).ok()?;
+    validate(&data).ok().map(|_| data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capi() {
+        let values = [0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 20.0, 20.0];
+        unsafe {
+            let model = clust_dbscan_fit(values.as_ptr(), 7, 2, 0.5, 3, true, 1);
+            assert!(!model.is_null() && clust_dbscan_n_points(model) == 7 && clust_dbscan_n_clusters(model) == 2);
+            let labels = slice::from_raw_parts(clust_dbscan_labels(model), 7);
+            assert!(labels[..3] == [labels[0]; 3] && labels[3..6] == [labels[3]; 3] && labels[0] != labels[3] && labels[6] == 0);
+
+            let new_data = [0.05, 0.05, 5.05, 5.05, 10.0, 10.0];
+            let mut predicted = [9; 3];
+            assert!(clust_dbscan_predict(model, new_data.as_ptr(), 3, 2, predicted.as_mut_ptr()) == 0);
+            assert!(predicted == [labels[0], labels[3], 0]);
+            assert!(clust_dbscan_predict(model, new_data.as_ptr(), 2, 3, predicted.as_mut_ptr()) == -1);
+            clust_dbscan_free(model);
+
+            assert!(clust_dbscan_fit([0.0, f64::NAN].as_ptr(), 1, 2, 0.5, 3, true, 1).is_null() && clust_dbscan_fit(ptr::null(), 7, 2, 0.5, 3, true, 1).is_null());
+            for (eps, min_points) in [(f64::NAN, 3), (-0.5, 3), (0.5, 0)].iter() {
+                assert!(clust_dbscan_fit(values.as_ptr(), 7, 2, *eps, *min_points, true, 1).is_null());
+            }
+            assert!(clust_dbscan_n_points(ptr::null()) == 0 && clust_dbscan_n_clusters(ptr::null()) == 0 && clust_dbscan_labels(ptr::null()).is_null());
+            assert!(clust_dbscan_predict(ptr::null(), new_data.as_ptr(), 3, 2, predicted.as_mut_ptr()) == -1);
+            clust_dbscan_free(ptr::null_mut());
+        }
+
+        // The header declares every exported function.
+        let header = include_str!("../include/clust.h");
+        for name in ["clust_dbscan_fit", "clust_dbscan_n_points", "clust_dbscan_n_clusters", "clust_dbscan_labels", "clust_dbscan_predict", "clust_dbscan_free"].iter() {
+            assert!(header.contains(&format!("{}(", name)), "{} missing from clust.h", name);
+        }
+    }
+}
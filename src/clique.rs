@@ -0,0 +1,202 @@
+//! Subspace clustering for high-dimensional data whose clusters are dense only in some of its dimensions, and
+//! so invisible to full-space density. Subspace clusters overlap, a point belonging to one cluster in each
+//! subspace where it is dense, so there is no flat labelling and `Clique` doesn't implement `Clustering`.
+
+use crate::union_find::UnionFind;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// A cluster in the subspace of `dims`: connected dense grid cells, each given by its interval in every one
+/// of `dims`, and the points in them, all in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubspaceCluster {
+    pub dims: Vec<usize>,
+    pub units: Vec<Vec<usize>>,
+    pub points: Vec<usize>,
+}
+
+/// CLIQUE (Agrawal et al., 1998): each dimension is cut into `intervals` equal intervals between its minimum
+/// and maximum, and a grid cell in some subset of the dimensions is dense when at least `min_points` points
+/// fall in it. Dense cells are found bottom up, a cell only being counted when every cell it projects onto
+/// is dense, and adjacent dense cells in the same subspace form a cluster. `clusters` holds the clusters of
+/// every subspace, lowest-dimensional first; the projections of a k-dimensional cluster are dense too, so
+/// `maximal` is usually what's wanted.
+#[derive(Debug, Clone)]
+pub struct Clique {
+    pub intervals: usize,
+    pub min_points: usize,
+    pub clusters: Vec<SubspaceCluster>,
+}
+
+type Unit = Vec<(usize, usize)>;
+
+impl Clique {
+    pub fn new<T: Float>(data: &Array2<T>, intervals: usize, min_points: usize) -> Clique {
+        assert!(intervals > 0, "intervals must be positive");
+        // With no points needed every cell would be dense, the empty ones included.
+        assert!(min_points > 0, "min_points must be positive");
+        let cells = grid_cells(data, intervals);
+        let mut level = BTreeMap::new();
+        for dim in 0..data.cols() {
+            let mut points = vec![Vec::new(); intervals];
+            for (point, row) in cells.iter().enumerate() {
+                points[row[dim]].push(point);
+            }
+            level.extend(points.into_iter().enumerate().filter(|(_, points)| points.len() >= min_points).map(|(interval, points)| (vec![(dim, interval)], points)));
+        }
+
+        let mut clusters = Vec::new();
+        while !level.is_empty() {
+            clusters.extend(connect(&level));
+            level = join(&level, min_points);
+        }
+        Clique { intervals, min_points, clusters }
+    }
+
+    /// Clusters not overlapping a cluster in a higher-dimensional subspace containing theirs, highest
+    /// dimensional first.
+    pub fn maximal(&self) -> Vec<&SubspaceCluster> {
+        let covered = |cluster: &SubspaceCluster| {
+            self.clusters.iter().any(|other| {
+                other.dims.len() > cluster.dims.len() && cluster.dims.iter().all(|dim| other.dims.contains(dim)) && cluster.points.iter().any(|point| other.points.binary_search(point).is_ok())
+            })
+        };
+        self.clusters.iter().rev().filter(|cluster| !covered(cluster)).collect::<Vec<&SubspaceCluster>>()
+    }
+}
+
+/// Each point's interval in each dimension. Constant dimensions put every point in interval 0.
+fn grid_cells<T: Float>(data: &Array2<T>, intervals: usize) -> Vec<Vec<usize>> {
+    let bounds = data
+        .gencolumns()
+        .into_iter()
+        .map(|column| (column.iter().cloned().fold(T::infinity(), T::min), column.iter().cloned().fold(T::neg_infinity(), T::max)))
+        .collect::<Vec<(T, T)>>();
+    let count = T::from(intervals).expect("T::from(usize)");
+    data.outer_iter()
+        .map(|row| {
+            row.iter()
+                .zip(bounds.iter())
+                .map(|(x, (min, max))| match *max > *min {
+                    true => ((*x - *min) / (*max - *min) * count).to_usize().unwrap_or(0).min(intervals - 1),
+                    false => 0,
+                })
+                .collect::<Vec<usize>>()
+        })
+        .collect::<Vec<Vec<usize>>>()
+}
+
+/// The dense k-dimensional units from pairs of dense (k-1)-dimensional ones agreeing on all but their last
+/// dimension, keeping candidates whose every projection is dense and which hold enough points.
+fn join(level: &BTreeMap<Unit, Vec<usize>>, min_points: usize) -> BTreeMap<Unit, Vec<usize>> {
+    let units = level.keys().collect::<Vec<&Unit>>();
+    let mut next = BTreeMap::new();
+    for (i, a) in units.iter().enumerate() {
+        let prefix = &a[..a.len() - 1];
+        for b in units[i + 1..].iter().take_while(|b| &b[..b.len() - 1] == prefix) {
+            let (last_a, last_b) = (a[a.len() - 1], b[b.len() - 1]);
+            if last_a.0 == last_b.0 {
+                continue;
+            }
+            let mut candidate = a.to_vec();
+            candidate.push(last_b);
+            let projections_dense = (0..candidate.len() - 2).all(|skip| {
+                let projection = candidate.iter().enumerate().filter(|(j, _)| *j != skip).map(|(_, unit)| *unit).collect::<Unit>();
+                level.contains_key(&projection)
+            });
+            if projections_dense {
+                let points = intersect(&level[*a], &level[*b]);
+                if points.len() >= min_points {
+                    next.insert(candidate, points);
+                }
+            }
+        }
+    }
+    next
+}
+
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let (mut i, mut j) = (0, 0);
+    let mut both = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => {
+                both.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    both
+}
+
+/// Clusters of one level's dense units: units in the same subspace are joined when they differ by one
+/// interval in a single dimension.
+fn connect(level: &BTreeMap<Unit, Vec<usize>>) -> Vec<SubspaceCluster> {
+    let units = level.keys().collect::<Vec<&Unit>>();
+    let positions = units.iter().enumerate().map(|(i, unit)| (*unit, i)).collect::<BTreeMap<&Unit, usize>>();
+    let mut components = UnionFind::new(units.len());
+    for (i, unit) in units.iter().enumerate() {
+        for dim in 0..unit.len() {
+            let mut neighbour = unit.to_vec();
+            neighbour[dim].1 += 1;
+            if let Some(j) = positions.get(&neighbour) {
+                components.union(i, *j);
+            }
+        }
+    }
+    let mut clusters: BTreeMap<(Vec<usize>, usize), SubspaceCluster> = BTreeMap::new();
+    for (i, unit) in units.iter().enumerate() {
+        let dims = unit.iter().map(|(dim, _)| *dim).collect::<Vec<usize>>();
+        let cluster = clusters.entry((dims.clone(), components.find(i))).or_insert_with(|| SubspaceCluster { dims, units: Vec::new(), points: Vec::new() });
+        cluster.units.push(unit.iter().map(|(_, interval)| *interval).collect::<Vec<usize>>());
+        cluster.points.extend_from_slice(&level[*unit]);
+    }
+    let mut clusters = clusters.into_values().collect::<Vec<SubspaceCluster>>();
+    for cluster in clusters.iter_mut() {
+        cluster.points.sort_unstable();
+    }
+    clusters.sort_by(|a, b| (&a.dims, a.points[0]).cmp(&(&b.dims, b.points[0])));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_clique() {
+        // One cluster dense in dimensions 0 and 1 and spread along 2, another dense in 1 and 2 and spread
+        // along 0, and uniform noise with the corners fixing the grid to unit intervals on [0, 10].
+        let mut rng = SmallRng::seed_from_u64(5);
+        let mut rows = Vec::new();
+        for _ in 0..60 {
+            rows.push([rng.gen_range(2.2, 2.8), rng.gen_range(2.2, 2.8), rng.gen_range(0.0, 10.0)]);
+        }
+        for _ in 0..60 {
+            rows.push([rng.gen_range(0.0, 10.0), rng.gen_range(7.2, 7.8), rng.gen_range(7.2, 7.8)]);
+        }
+        for _ in 0..58 {
+            rows.push([rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 10.0), rng.gen_range(0.0, 10.0)]);
+        }
+        rows.push([0.0; 3]);
+        rows.push([10.0; 3]);
+        let data = Array2::from_shape_fn((rows.len(), 3), |(row, col)| rows[row][col]);
+
+        let clique = Clique::new(&data, 10, 25);
+        assert!(clique.clusters.iter().filter(|c| c.dims.len() == 1).count() == 4 && clique.clusters.iter().all(|c| c.dims.len() <= 2));
+        let maximal = clique.maximal();
+        assert!(maximal.iter().map(|c| (c.dims.clone(), c.units.clone())).collect::<Vec<(Vec<usize>, Vec<Vec<usize>>)>>() == vec![(vec![1, 2], vec![vec![7, 7]]), (vec![0, 1], vec![vec![2, 2]])]);
+        assert!((60..120).all(|point| maximal[0].points.contains(&point)) && maximal[0].points.len() < 70);
+        assert!((0..60).all(|point| maximal[1].points.contains(&point)) && maximal[1].points.len() < 70);
+
+        assert!(intersect(&[1, 3, 5, 7], &[2, 3, 7, 8]) == vec![3, 7]);
+        assert!(std::panic::catch_unwind(|| Clique::new(&data, 10, 0)).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+//! Clustering as connected components of a graph the caller builds, from any similarity they like: points
+//! joined by a path of edges share a cluster. DBSCAN is this on the eps-graph of its core points, and
+//! `Dbscan::new_with_graph` hands that graph out for reuse. Labels follow the density-based convention,
+//! with 0 for points in components too small to keep and clusters numbered from 1 by their lowest point.
+
+use crate::union_find::UnionFind;
+use std::collections::HashMap;
+
+/// Components of the graph on `n` points with the given undirected edges. Components of fewer than
+/// `min_size` points, including isolated points when `min_size` is over 1, are labelled 0.
+pub fn connected_components<I: IntoIterator<Item = (usize, usize)>>(n: usize, edges: I, min_size: usize) -> Vec<usize> {
+    let mut components = UnionFind::new(n);
+    for (a, b) in edges {
+        assert!(a < n && b < n, "edge ({}, {}) out of range for {} points", a, b, n);
+        components.union(a, b);
+    }
+    let roots = (0..n).map(|point| components.find(point)).collect::<Vec<usize>>();
+    let mut sizes = HashMap::new();
+    for root in roots.iter() {
+        *sizes.entry(*root).or_insert(0) += 1;
+    }
+    let mut numbers = HashMap::new();
+    roots
+        .iter()
+        .map(|root| match sizes[root] >= min_size {
+            true => {
+                let next = numbers.len() + 1;
+                *numbers.entry(*root).or_insert(next)
+            }
+            false => 0,
+        })
+        .collect::<Vec<usize>>()
+}
+
+/// `connected_components` of a graph given as adjacency lists, one per point.
+pub fn adjacency_components(adjacency: &[Vec<usize>], min_size: usize) -> Vec<usize> {
+    connected_components(adjacency.len(), adjacency.iter().enumerate().flat_map(|(a, neighbours)| neighbours.iter().map(move |b| (a, *b))), min_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_components() {
+        let edges = vec![(0, 1), (1, 2), (4, 3), (6, 6)];
+        assert!(connected_components(7, edges.clone(), 1) == vec![1, 1, 1, 2, 2, 3, 4]);
+        assert!(connected_components(7, edges, 3) == vec![1, 1, 1, 0, 0, 0, 0]);
+        assert!(adjacency_components(&[vec![2], vec![], vec![0], vec![1]], 2) == vec![1, 2, 1, 2]);
+
+        // Components of the eps-graph are DBSCAN's clusters when every point is core.
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.1, 0.2, 1.0, 1.1, 1.2]).unwrap();
+        let (model, graph) = Dbscan::new_with_graph(&data, 0.15, 2, true);
+        let adjacency = (0..graph.len()).map(|point| graph.neighbours(point).to_vec()).collect::<Vec<Vec<usize>>>();
+        assert!(adjacency_components(&adjacency, 2) == Dbscan::new_union_find(&data, 0.15, 2, true).clusters && model.n_clusters() == 2);
+    }
+}
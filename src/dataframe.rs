@@ -0,0 +1,66 @@
+//! Polars interop: numeric DataFrame columns in as an `Array2`, and labels back out as a `Series` with one
+//! entry per input row, ready to add to the frame the data came from.
+
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use polars::prelude::{DataFrame, DataType, NamedFrom, Series};
+use std::io;
+
+/// The named `columns` of `frame` in the order given, or every column when `columns` is empty, as one row
+/// per frame row. Columns must be of a numeric type and hold no nulls.
+pub fn dataframe_to_array<T: Float>(frame: &DataFrame, columns: &[&str]) -> io::Result<Array2<T>> {
+    let names = if columns.is_empty() { frame.get_column_names().iter().map(|name| name.to_string()).collect::<Vec<String>>() } else { columns.iter().map(|name| name.to_string()).collect() };
+    let mut data = Array2::zeros((frame.height(), names.len()));
+    for (col, name) in names.iter().enumerate() {
+        let column = frame.column(name).map_err(|_| invalid(format!("no column named {}", name)))?;
+        if !column.dtype().is_primitive_numeric() {
+            return Err(invalid(format!("column {} is not numeric", name)));
+        }
+        if column.null_count() > 0 {
+            return Err(invalid(format!("column {} has nulls", name)));
+        }
+        let values = column.cast(&DataType::Float64).map_err(|error| invalid(error.to_string()))?;
+        for (row, x) in values.f64().map_err(|error| invalid(error.to_string()))?.into_no_null_iter().enumerate() {
+            data[[row, col]] = T::from(x).ok_or_else(|| invalid(format!("column {} row {} doesn't fit the float type", name, row)))?;
+        }
+    }
+    Ok(data)
+}
+
+/// `labels_array` of `clustering` as an `i64` series called `name`, in the row order of the data it was fit
+/// on, so `frame.with_column(labels_series(&model, "cluster").into())` labels each row of `frame`.
+pub fn labels_series<C: Clustering + ?Sized>(clustering: &C, name: &str) -> Series {
+    Series::new(name.into(), clustering.labels_array().to_vec())
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use polars::df;
+
+    #[test]
+    fn test_dataframe() {
+        let mut frame = df!(
+            "name" => ["a", "b", "c", "d", "e"],
+            "x" => [1.0f32, 1.1, 0.9, 5.0, 9.0],
+            "y" => [2i64, 2, 2, 5, 1],
+        )
+        .unwrap();
+        let data = dataframe_to_array::<f64>(&frame, &["x", "y"]).unwrap();
+        assert!(data == arr2(&[[1.0f32 as f64, 2.0], [1.1f32 as f64, 2.0], [0.9f32 as f64, 2.0], [5.0, 5.0], [9.0, 1.0]]));
+        assert!(dataframe_to_array::<f32>(&frame, &["y"]).unwrap() == arr2(&[[2.0], [2.0], [2.0], [5.0], [1.0]]));
+        assert!(dataframe_to_array::<f64>(&frame, &[]).unwrap_err().to_string() == "column name is not numeric");
+        assert!(dataframe_to_array::<f64>(&frame, &["z"]).unwrap_err().to_string() == "no column named z");
+
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        frame.with_column(labels_series(&model, "cluster").into()).unwrap();
+        let labels = frame.column("cluster").unwrap().i64().unwrap().into_no_null_iter().collect::<Vec<i64>>();
+        assert!(labels == model.labels_array().to_vec() && labels[0] == labels[2] && labels[3] == 0);
+    }
+}
@@ -0,0 +1,96 @@
+//! Seeded synthetic datasets for trying out and benchmarking the clusterers. Each generator returns the
+//! points with the index of the group each was drawn from, numbered from 0, and equal seeds give equal data.
+
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use rand::distributions::StandardNormal;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+/// `n` points spread round-robin over Gaussian blobs around the rows of `centers`, with standard deviation
+/// `std` in every dimension.
+pub fn blobs<T: Float>(n: usize, centers: &Array2<T>, std: T, seed: u64) -> (Array2<T>, Vec<usize>) {
+    assert!(centers.rows() > 0, "need at least one center");
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let labels = (0..n).map(|idx| idx % centers.rows()).collect::<Vec<usize>>();
+    let data = Array2::from_shape_fn((n, centers.cols()), |(idx, col)| centers[[labels[idx], col]] + std * normal(&mut rng));
+    (data, labels)
+}
+
+/// Blobs stretched and sheared by `transform`, a square matrix each point is multiplied by on the right, so
+/// the clusters are elongated ellipses that centroid methods split badly.
+pub fn anisotropic<T: Float + 'static>(n: usize, centers: &Array2<T>, std: T, transform: &Array2<T>, seed: u64) -> (Array2<T>, Vec<usize>) {
+    assert!(transform.rows() == centers.cols() && transform.cols() == centers.cols(), "transform must be square over the data's dimensions");
+    let (data, labels) = blobs(n, centers, std, seed);
+    (data.dot(transform), labels)
+}
+
+/// Two interleaving half circles in 2D, the upper one labelled 0, with Gaussian `noise` added to each point.
+pub fn moons<T: Float>(n: usize, noise: T, seed: u64) -> (Array2<T>, Vec<usize>) {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let upper = n.div_ceil(2);
+    let mut data = Array2::zeros((n, 2));
+    let labels = (0..n).map(|idx| if idx < upper { 0 } else { 1 }).collect::<Vec<usize>>();
+    for (idx, mut row) in data.outer_iter_mut().enumerate() {
+        let (x, y) = if idx < upper {
+            let angle = PI * idx as f64 / (upper.max(2) - 1) as f64;
+            (angle.cos(), angle.sin())
+        } else {
+            let angle = PI * (idx - upper) as f64 / ((n - upper).max(2) - 1) as f64;
+            (1.0 - angle.cos(), 0.5 - angle.sin())
+        };
+        row[0] = T::from(x).expect("T::from(f64)") + noise * normal(&mut rng);
+        row[1] = T::from(y).expect("T::from(f64)") + noise * normal(&mut rng);
+    }
+    (data, labels)
+}
+
+/// Two concentric circles in 2D: the outer one of radius 1 labelled 0 and the inner one of radius `factor`,
+/// with Gaussian `noise` added to each point.
+pub fn circles<T: Float>(n: usize, factor: T, noise: T, seed: u64) -> (Array2<T>, Vec<usize>) {
+    assert!(factor > T::zero() && factor < T::one(), "factor must be in (0, 1)");
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let outer = n.div_ceil(2);
+    let mut data = Array2::zeros((n, 2));
+    let labels = (0..n).map(|idx| if idx < outer { 0 } else { 1 }).collect::<Vec<usize>>();
+    for (idx, mut row) in data.outer_iter_mut().enumerate() {
+        let (position, count, radius) = if idx < outer { (idx, outer, T::one()) } else { (idx - outer, n - outer, factor) };
+        let angle = 2.0 * PI * position as f64 / count as f64;
+        row[0] = radius * T::from(angle.cos()).expect("T::from(f64)") + noise * normal(&mut rng);
+        row[1] = radius * T::from(angle.sin()).expect("T::from(f64)") + noise * normal(&mut rng);
+    }
+    (data, labels)
+}
+
+pub(crate) fn normal<T: Float, R: Rng>(rng: &mut R) -> T {
+    T::from(rng.sample::<f64, _>(StandardNormal)).expect("T::from(f64)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use crate::kmedoids::KMedoids;
+    use crate::metrics::adjusted_rand_index;
+
+    #[test]
+    fn test_datasets() {
+        let centers = Array2::from_shape_vec((3, 2), vec![0.0, 0.0, 10.0, 0.0, 0.0, 10.0]).unwrap();
+        let (data, labels) = blobs(90, &centers, 0.5, 1);
+        assert!(data.dim() == (90, 2) && labels.iter().filter(|l| **l == 2).count() == 30);
+        assert!(blobs(90, &centers, 0.5, 1).0 == data && blobs(90, &centers, 0.5, 2).0 != data);
+        assert!(adjusted_rand_index(&KMedoids::new(&data, 3, 10).clusters, &labels) == 1.0);
+
+        // Density-based clustering separates the shapes that medoid or centroid methods can't.
+        for (data, labels) in [moons(200, 0.05, 3), circles(200, 0.5, 0.03, 3)] {
+            assert!(data.rows() == 200 && labels.iter().filter(|l| **l == 0).count() == 100);
+            assert!(adjusted_rand_index(&Dbscan::new(&data, 0.2, 4, true).clusters, &labels) > 0.95);
+            assert!(adjusted_rand_index(&KMedoids::new(&data, 2, 10).clusters, &labels) < 0.5);
+        }
+
+        let shear = Array2::from_shape_vec((2, 2), vec![0.6, -0.6, -0.4, 0.8]).unwrap();
+        let (stretched, _) = anisotropic(90, &centers, 0.5, &shear, 1);
+        assert!(stretched == data.dot(&shear));
+    }
+}
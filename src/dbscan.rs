@@ -1,153 +1,3796 @@
+use crate::error::Error;
+use crate::export::convex_hull;
+use crate::index::{ball_tree_rows, brute_force_within, index_owned_rows, index_rows, lsh_rows, row_major_copy, row_slice, Index};
 use crate::itertools::Itertools;
-use kdtree::distance::squared_euclidean;
-use kdtree::KdTree;
+use crate::metrics::{calinski_harabasz, davies_bouldin, silhouette_score};
+use crate::outlier::{local_outlier_factor, lof_from_index};
+use crate::rng::default_rng;
+use crate::sparse::{DotAccumulator, SparseRows};
+use crate::labels::LabelSet;
+use crate::traits::{Clustering, Fit, Predict, Progress};
+use crate::union_find::{AtomicUnionFind, UnionFind};
 use ndarray::prelude::*;
+use ndarray::Data;
 use num_traits::float::Float;
 use num_traits::identities::{One, Zero};
-use rand::prelude::thread_rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rand::seq::index::sample;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::convert::Infallible;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+#[cfg(feature = "model-io")]
+use std::io::{self, BufRead, Write};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Dbscan<T: Float + One + Zero> {
     pub eps: T,
     pub min_points: usize,
     pub clusters: Vec<usize>,
+    /// Whether each point is a core point, a border point within eps of one, or noise. Border points are
+    /// told apart from noise even when the fit didn't assign them to clusters.
+    pub point_types: Vec<PointType>,
+    pub labels: HashMap<usize, String>,
+    /// How the fit decided which points neighbour each other, which predictions ask again of new points.
+    pub neighbourhood: Neighbourhood,
 }
 
 impl<T: Float + One + Zero> Dbscan<T> {
-    pub fn new(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
-        let mut c = 1;
-        let mut neighbours = Vec::with_capacity(data.rows());
-        let mut sub_neighbours = Vec::with_capacity(data.rows());
-        let mut visited = vec![false; data.rows()];
+    /// Fits `data` given as any 2-D array or view; rows that aren't contiguous in memory are copied first.
+    pub fn new<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    /// Like `new`, but draws the point visit order from `rng`, so a seeded generator gives reproducible labels.
+    pub fn new_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        Dbscan::new_with_expansion(data, eps, min_points, borders, ExpansionOrder::DepthFirst, rng)
+    }
+
+    /// Like `new`, but checks `data` first instead of panicking on it: it must be non-empty and finite.
+    pub fn try_new<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool) -> Result<Dbscan<T>, Error> {
+        validate(data)?;
+        Ok(Dbscan::new(data, eps, min_points, borders))
+    }
+
+    /// Like `predict`, checking both arrays as `try_new` does and that `new_data` has as many columns as `data`.
+    pub fn try_predict<S: Data<Elem = T>, N: Data<Elem = T>>(&self, data: &ArrayBase<S, Ix2>, new_data: &ArrayBase<N, Ix2>) -> Result<Vec<Vec<usize>>, Error> {
+        validate(data)?;
+        validate(new_data)?;
+        if new_data.cols() != data.cols() {
+            return Err(Error::DimensionMismatch { expected: data.cols(), found: new_data.cols() });
+        }
+        Ok(self.predict(data, new_data))
+    }
+
+    /// Like `new`, with the visit order drawn from a `StdRng` seeded with `seed`, so equal seeds give equal labels.
+    pub fn with_seed<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, seed: u64) -> Dbscan<T> {
+        Dbscan::new_with_rng(data, eps, min_points, borders, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Fits rows arriving in chunks, as read a block at a time from a file or socket, without the caller holding
+    /// the whole input a second time as one array. Each chunk is copied into a single flat buffer and dropped,
+    /// so every row is still held in memory; for more rows than fit there, write the chunks to disk with
+    /// `mmap::write_rows` and fit a view of the mapped file. Chunks must agree on their number of columns.
+    /// Labels follow the order the rows arrive in.
+    pub fn new_chunked<I: IntoIterator<Item = Array2<T>>>(chunks: I, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_chunked_with_rng(chunks, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_chunked_with_rng<I: IntoIterator<Item = Array2<T>>, R: Rng>(chunks: I, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        match Dbscan::try_new_chunked_with_rng(chunks.into_iter().map(Ok::<Array2<T>, Infallible>), eps, min_points, borders, rng) {
+            Ok(dbscan) => dbscan,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Like `new_chunked`, for a fallible source such as `arrow::read_ipc_stream`: the first error stops the
+    /// read and is returned without fitting.
+    pub fn try_new_chunked<E, I: IntoIterator<Item = Result<Array2<T>, E>>>(chunks: I, eps: T, min_points: usize, borders: bool) -> Result<Dbscan<T>, E> {
+        Dbscan::try_new_chunked_with_rng(chunks, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn try_new_chunked_with_rng<E, I: IntoIterator<Item = Result<Array2<T>, E>>, R: Rng>(chunks: I, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Result<Dbscan<T>, E> {
+        let (mut points, mut cols) = (Vec::new(), None);
+        for chunk in chunks {
+            let chunk = chunk?;
+            assert!(*cols.get_or_insert(chunk.cols()) == chunk.cols(), "chunks must have the same number of columns");
+            points.extend(chunk.iter().cloned());
+        }
+        let cols = cols.unwrap_or(0);
+        let rows = points.len().checked_div(cols).unwrap_or(0);
+        Ok(Dbscan::new_with_rng(&Array2::from_shape_vec((rows, cols), points).expect("whole rows"), eps, min_points, borders, rng))
+    }
+
+    /// Searches neighbourhoods in an `f32` copy of `data`, for `f64` inputs where memory rather than time runs
+    /// short: the copy and its index take half the space an `f64` copy would. This is not an out-of-core fit,
+    /// since the copy is held in memory and `data` is read in full twice up front, to round it and to find its
+    /// largest coordinate; after that `data` is only read to settle pairs whose rounded distance lies within
+    /// rounding error of eps. Labels are those `new_with_rng` gives on `data` with the same rng.
+    pub fn new_quantized<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_quantized_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_quantized_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let quantized = data.mapv(|x| x.to_f32().expect("T::to_f32"));
+        // Rounding moves each point by at most sqrt(dims) ulps of the largest coordinate, and the f32
+        // arithmetic adds about dims ulps of the distance itself; the margin allows twice both.
+        let dims = T::from(data.cols()).expect("T::from(usize)");
+        let largest = data.iter().fold(T::zero(), |acc, x| acc.max(x.abs()));
+        let two = T::one() + T::one();
+        let margin = T::from(f32::EPSILON).expect("T::from(f32)") * two * (two * dims.sqrt() * largest + dims * eps);
+        let (inner, outer) = (eps - margin, eps + margin);
+        let index = index_rows(&quantized);
+        let search = outer.to_f32().expect("T::to_f32");
+        let mut candidates = Vec::new();
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            candidates.clear();
+            let row = quantized.row(row_idx);
+            index.within(&row_slice(row.view()), search, &mut candidates);
+            neighbours.extend(candidates.iter().cloned().filter(|other| {
+                let rounded = T::from(squared_distance(&row, &quantized.row(*other)).sqrt()).expect("T::from(f32)");
+                rounded <= inner || (rounded <= outer && squared_distance(&data.row(row_idx), &data.row(*other)) <= eps * eps)
+            }));
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Checks every pair of points instead of using a neighbour index, comparing distances in SIMD lanes. At
+    /// high dimension, where a KdTree prunes almost nothing, this is often the faster choice up to some tens of
+    /// thousands of points, and with no index to get wrong it's the reference the indexed fits are tested against.
+    pub fn new_brute_force<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_brute_force_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_brute_force_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let copy;
+        let points = match data.as_slice() {
+            Some(points) => points,
+            None => {
+                copy = data.iter().cloned().collect::<Vec<T>>();
+                &copy
+            }
+        };
+        let dims = data.cols();
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            brute_force_within(points, dims, &points[row_idx * dims..(row_idx + 1) * dims], eps, neighbours)
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Like `new_with_rng`, choosing the order clusters grow in. Final labels don't depend on `expansion`.
+    pub fn new_with_expansion<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, expansion: ExpansionOrder, rng: &mut R) -> Dbscan<T> {
+        Dbscan::fit_with_counts(data, eps, min_points, borders, expansion, rng).0
+    }
+
+    /// Like `new`, choosing how region queries find neighbours; see `NeighbourSearch`.
+    pub fn new_with_search<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, search: NeighbourSearch) -> Dbscan<T> {
+        Dbscan::new_with_search_and_rng(data, eps, min_points, borders, search, &mut default_rng())
+    }
+
+    pub fn new_with_search_and_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, search: NeighbourSearch, rng: &mut R) -> Dbscan<T> {
+        match search {
+            NeighbourSearch::Index => Dbscan::new_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::BruteForce => Dbscan::new_brute_force_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::BallTree => Dbscan::new_ball_tree_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::Lsh => Dbscan::new_lsh_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::Gpu => Dbscan::new_gpu_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::Auto if data.cols() > BALL_TREE_COLUMNS => Dbscan::new_ball_tree_with_rng(data, eps, min_points, borders, rng),
+            NeighbourSearch::Auto => Dbscan::new_with_rng(data, eps, min_points, borders, rng),
+        }
+    }
+
+    fn new_ball_tree_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        if let Some(copy) = row_major_copy(data) {
+            return Dbscan::new_ball_tree_with_rng(&copy, eps, min_points, borders, rng);
+        }
+        let index = ball_tree_rows(data);
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            index.within(&row_slice(data.row(row_idx)), eps, neighbours)
+        });
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    fn new_lsh_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        if let Some(copy) = row_major_copy(data) {
+            return Dbscan::new_lsh_with_rng(&copy, eps, min_points, borders, rng);
+        }
+        let index = lsh_rows(data, eps);
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            index.within(&row_slice(data.row(row_idx)), eps, neighbours)
+        });
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    #[cfg(feature = "gpu")]
+    fn new_gpu_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let neighbourhoods = match crate::gpu::region_queries(data, eps) {
+            Some(neighbourhoods) => neighbourhoods,
+            None => return Dbscan::new_with_rng(data, eps, min_points, borders, rng),
+        };
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            neighbours.extend_from_slice(&neighbourhoods[row_idx])
+        });
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    #[cfg(not(feature = "gpu"))]
+    fn new_gpu_with_rng<S: Data<Elem = T>, R: Rng>(data: &ArrayBase<S, Ix2>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        Dbscan::new_with_rng(data, eps, min_points, borders, rng)
+    }
+
+    /// Like `new`, also returning each point's number of neighbours within eps (itself included), as counted
+    /// by the fit's own region queries.
+    pub fn new_with_counts(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> (Dbscan<T>, Vec<usize>) {
+        Dbscan::fit_with_counts(data, eps, min_points, borders, ExpansionOrder::DepthFirst, &mut default_rng())
+    }
+
+    fn fit_with_counts<S: Data<Elem = T>, R: Rng>(
+        data: &ArrayBase<S, Ix2>,
+        eps: T,
+        min_points: usize,
+        borders: bool,
+        expansion: ExpansionOrder,
+        rng: &mut R,
+    ) -> (Dbscan<T>, Vec<usize>) {
+        if let Some(copy) = row_major_copy(data) {
+            return Dbscan::fit_with_counts(&copy, eps, min_points, borders, expansion, rng);
+        }
+        let index = index_rows(data);
+        let mut counts = vec![0; data.rows()];
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, expansion, rng, |row_idx, neighbours| {
+            region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours);
+            counts[row_idx] = neighbours.len();
+        });
+
+        (Dbscan::from_clusters(eps, min_points, clusters, point_types), counts)
+    }
+
+    /// Like `new`, also returning the eps-neighbourhood graph the fit's region queries found, so graph code
+    /// downstream doesn't have to search the neighbourhoods again.
+    pub fn new_with_graph(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> (Dbscan<T>, NeighbourGraph) {
+        Dbscan::new_with_graph_and_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_with_graph_and_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> (Dbscan<T>, NeighbourGraph) {
+        let index = index_rows(data);
+        let mut rows = vec![Vec::new(); data.rows()];
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours);
+            rows[row_idx] = neighbours.iter().cloned().sorted().collect::<Vec<usize>>();
+        });
+
+        let mut graph = NeighbourGraph { offsets: Vec::with_capacity(rows.len() + 1), indices: Vec::new() };
+        graph.offsets.push(0);
+        for row in rows {
+            graph.indices.extend(row);
+            graph.offsets.push(graph.indices.len());
+        }
+        (Dbscan::from_clusters(eps, min_points, clusters, point_types), graph)
+    }
+
+    /// Like `new`, reporting to `progress` after every `every` points visited with the fraction visited and
+    /// the clusters found so far. Returning false from `progress` stops the fit with `Error::Cancelled`, so a
+    /// cancellation flag can be checked there: `|_, _| !cancelled.load(Ordering::Relaxed)`.
+    pub fn new_with_progress<P: Progress>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, every: usize, progress: &mut P) -> Result<Dbscan<T>, Error> {
+        Dbscan::new_with_progress_and_rng(data, eps, min_points, borders, every, progress, &mut default_rng())
+    }
+
+    pub fn new_with_progress_and_rng<P: Progress, R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, every: usize, progress: &mut P, rng: &mut R) -> Result<Dbscan<T>, Error> {
+        assert!(every > 0, "every must be positive");
+        let index = index_rows(data);
+        let rows = data.rows();
+        let mut cancelled = false;
+        let (clusters, point_types) = expand_clusters_by(
+            rows,
+            |neighbours| neighbours.len() >= min_points,
+            borders,
+            ExpansionOrder::DepthFirst,
+            rng,
+            |row_idx, neighbours| region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours),
+            |visited, clusters| {
+                cancelled = visited % every == 0 && visited > 0 && !progress.report(visited as f64 / rows as f64, clusters);
+                !cancelled
+            },
+        );
+        if cancelled {
+            return Err(Error::Cancelled);
+        }
+        progress.report(1.0, clusters.iter().filter(|c| **c > 0).unique().count());
+        Ok(Dbscan::from_clusters(eps, min_points, clusters, point_types))
+    }
+
+    /// Like `new`, stopping once `budget` is spent, for callers that need bounded latency more than exact
+    /// labels. Returns the labels so far and whether the fit finished: clusters found before the budget ran out
+    /// are whole except the one growing at the time, and points not yet reached are noise. The budget is
+    /// checked before each region query, so a time budget is overrun by at most one query.
+    pub fn new_with_budget(data: &Array2<T>, eps: T, min_points: usize, borders: bool, budget: Budget) -> (Dbscan<T>, bool) {
+        Dbscan::new_with_budget_and_rng(data, eps, min_points, borders, budget, &mut default_rng())
+    }
+
+    pub fn new_with_budget_and_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, budget: Budget, rng: &mut R) -> (Dbscan<T>, bool) {
+        let index = index_rows(data);
+        let start = Instant::now();
+        let mut completed = true;
+        let (clusters, point_types) = expand_clusters_by(
+            data.rows(),
+            |neighbours| neighbours.len() >= min_points,
+            borders,
+            ExpansionOrder::DepthFirst,
+            rng,
+            |row_idx, neighbours| region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours),
+            |visited, _| {
+                completed = match budget {
+                    Budget::Time(limit) => start.elapsed() < limit,
+                    Budget::Queries(limit) => visited < limit,
+                };
+                completed
+            },
+        );
+        (Dbscan::from_clusters(eps, min_points, clusters, point_types), completed)
+    }
+
+    /// Like `new`, for rows that each stand for `weights[i]` observations: a point is core when the weights of
+    /// its neighbours within eps, its own included, sum to at least `min_points`.
+    pub fn new_weighted(data: &Array2<T>, weights: &[T], eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_weighted_with_rng(data, weights, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_weighted_with_rng<R: Rng>(data: &Array2<T>, weights: &[T], eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(weights.len() == data.rows(), "need one weight per row");
+        let index = index_rows(data);
+        let threshold = T::from(min_points).expect("T::from(usize)");
+        let is_core = |neighbours: &[usize]| neighbours.iter().fold(T::zero(), |acc, idx| acc + weights[*idx]) >= threshold;
+        let query = |row_idx: usize, neighbours: &mut Vec<usize>| region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours);
+        let (clusters, point_types) = expand_clusters_by(data.rows(), is_core, borders, ExpansionOrder::DepthFirst, rng, query, |_, _| true);
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Like `new`, running every region query up front on rayon's thread pool, then expanding clusters
+    /// sequentially from the stored neighbourhoods. Holds all neighbourhoods in memory at once.
+    #[cfg(feature = "rayon")]
+    pub fn new_parallel(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T>
+    where
+        T: Send + Sync,
+    {
+        Dbscan::new_parallel_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn new_parallel_with_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T>
+    where
+        T: Send + Sync,
+    {
+        let index = index_rows(data);
+        let neighbourhoods = parallel_map(data.rows(), |row_idx, neighbours| {
+            neighbours.clear();
+            region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours);
+            neighbours.clone()
+        });
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            neighbours.extend_from_slice(&neighbourhoods[row_idx])
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// DBSCAN as connected components: every point's neighbourhood is counted to find the core points, core
+    /// points within eps of each other are joined in a lock-free union-find, and each border point attaches to
+    /// its lowest-indexed core neighbour. With the `rayon` feature the queries run on its thread pool. Nothing
+    /// depends on a visit order, so the labels are the same on every run, numbered by each cluster's lowest
+    /// point index.
+    pub fn new_union_find(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T>
+    where
+        T: Send + Sync,
+    {
+        let index = index_rows(data);
+        let query = |row_idx: usize, neighbours: &mut Vec<usize>| {
+            neighbours.clear();
+            region_query(&row_slice(data.row(row_idx)), eps, &index, neighbours);
+        };
+        let core = parallel_map(data.rows(), |row_idx, neighbours| {
+            query(row_idx, neighbours);
+            neighbours.len() >= min_points
+        });
+        let components = AtomicUnionFind::new(data.rows());
+        let attached = parallel_map(data.rows(), |row_idx, neighbours| {
+            query(row_idx, neighbours);
+            let core_neighbours = neighbours.iter().cloned().filter(|other| core[*other]);
+            if core[row_idx] {
+                for other in core_neighbours.filter(|other| *other < row_idx) {
+                    components.union(row_idx, other);
+                }
+                Some(row_idx)
+            } else {
+                core_neighbours.min()
+            }
+        });
+
+        let mut numbers = HashMap::new();
         let mut clusters = vec![0; data.rows()];
-        let kdt = kdtree_init(&data);
+        let mut point_types = vec![PointType::Noise; data.rows()];
+        for (row_idx, attached) in attached.iter().enumerate() {
+            if let Some(core_idx) = attached {
+                point_types[row_idx] = if core[row_idx] { PointType::Core } else { PointType::Border };
+                if core[row_idx] || borders {
+                    let next = numbers.len() + 1;
+                    clusters[row_idx] = *numbers.entry(components.find(*core_idx)).or_insert(next);
+                }
+            }
+        }
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
 
-        let indices = sample(&mut thread_rng(), data.rows(), data.rows());
-        for row_idx in indices.iter() {
-            let row = data.row(row_idx);
-            if !visited[row_idx] {
-                visited[row_idx] = true;
+    /// DBSCAN exactly as scikit-learn's `DBSCAN(eps=eps, min_samples=min_points).fit(data)` runs it, for checking
+    /// results against an existing Python pipeline: neighbourhoods hold the point itself and points at exactly
+    /// eps, clusters grow depth-first from each unlabelled core point in index order, and a border point joins
+    /// the first cluster that reaches it rather than its nearest or lowest-indexed core neighbour. Borders are
+    /// always assigned, as in scikit-learn; `labels_sklearn` gives the labels in its convention.
+    pub fn new_sklearn(data: &Array2<T>, eps: T, min_points: usize) -> Dbscan<T> {
+        let index = index_rows(data);
+        let neighbourhoods = (0..data.rows())
+            .map(|row_idx| {
+                let mut neighbours = Vec::new();
+                region_query(&row_slice(data.row(row_idx)), eps, &index, &mut neighbours);
+                neighbours.sort_unstable();
+                neighbours
+            })
+            .collect::<Vec<Vec<usize>>>();
+        let core = neighbourhoods.iter().map(|neighbours| neighbours.len() >= min_points).collect::<Vec<bool>>();
 
-                neighbours.clear();
-                region_query(row.as_slice().unwrap(), eps, &kdt, &mut neighbours);
-                neighbours.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-                neighbours.dedup();
-
-                if neighbours.len() >= min_points {
-                    clusters[row_idx] = c;
-                    while let Some(neighbour_idx) = neighbours.pop() {
-                        if borders {
-                            clusters[neighbour_idx] = c;
-                        }
-                        if !visited[neighbour_idx] {
-                            visited[neighbour_idx] = true;
-                            sub_neighbours.clear();
-                            region_query(data.row(neighbour_idx).as_slice().unwrap(), eps, &kdt, &mut sub_neighbours);
-
-                            if sub_neighbours.len() >= min_points {
-                                if !borders {
-                                    clusters[neighbour_idx] = c;
-                                }
-                                neighbours.extend_from_slice(&sub_neighbours);
-                                neighbours.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-                                neighbours.dedup();
-                            }
+        let mut clusters = vec![0; data.rows()];
+        let mut stack = Vec::new();
+        let mut next = 1;
+        for seed in (0..data.rows()).filter(|row_idx| core[*row_idx]) {
+            if clusters[seed] > 0 {
+                continue;
+            }
+            stack.push(seed);
+            while let Some(row_idx) = stack.pop() {
+                if clusters[row_idx] > 0 {
+                    continue;
+                }
+                clusters[row_idx] = next;
+                if core[row_idx] {
+                    stack.extend(neighbourhoods[row_idx].iter().cloned().filter(|other| clusters[*other] == 0));
+                }
+            }
+            next += 1;
+        }
+
+        let point_types = (0..data.rows())
+            .map(|row_idx| match (core[row_idx], clusters[row_idx] > 0) {
+                (true, _) => PointType::Core,
+                (false, true) => PointType::Border,
+                (false, false) => PointType::Noise,
+            })
+            .collect::<Vec<PointType>>();
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// DBSCAN with pairwise constraints from labelled points. A `must_link` pair always shares a cluster, even
+    /// when neither is dense enough to be core, and clusters never grow to hold both points of a `cannot_link`
+    /// pair: expansion stops at the link that would join them, so a chain of points between two labelled
+    /// classes is cut rather than merged. Links are followed in index order as in `new_union_find`, and the
+    /// labels are numbered by each cluster's lowest point index. Must-links that imply a cannot-linked pair
+    /// share a cluster are an error.
+    pub fn new_constrained(data: &Array2<T>, eps: T, min_points: usize, borders: bool, must_link: &[(usize, usize)], cannot_link: &[(usize, usize)]) -> Result<Dbscan<T>, Error> {
+        let n = data.rows();
+        let index = index_rows(data);
+        let neighbourhoods = (0..n)
+            .map(|row_idx| {
+                let mut neighbours = Vec::new();
+                region_query(&row_slice(data.row(row_idx)), eps, &index, &mut neighbours);
+                neighbours.sort_unstable();
+                neighbours
+            })
+            .collect::<Vec<Vec<usize>>>();
+        let core = neighbourhoods.iter().map(|neighbours| neighbours.len() >= min_points).collect::<Vec<bool>>();
+
+        let mut cannot: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (a, b) in cannot_link.iter() {
+            cannot.entry(*a).or_default().push(*b);
+            cannot.entry(*b).or_default().push(*a);
+        }
+        // Cannot-linked members of each component, keyed by its root, so a union can be checked against them.
+        let mut constrained: HashMap<usize, Vec<usize>> = cannot.keys().map(|point| (*point, vec![*point])).collect();
+        let mut components = UnionFind::new(n);
+        let mut join = |a: usize, b: usize| {
+            let (root_a, root_b) = (components.find(a), components.find(b));
+            if root_a == root_b {
+                return true;
+            }
+            let members_b = constrained.get(&root_b).cloned().unwrap_or_default();
+            let blocked = constrained.get(&root_a).is_some_and(|members_a| {
+                members_a.iter().any(|member| cannot[member].iter().any(|other| members_b.contains(other)))
+            });
+            if blocked {
+                return false;
+            }
+            let root = components.union(a, b);
+            let mut members = constrained.remove(&root_a).unwrap_or_default();
+            members.extend(constrained.remove(&root_b).unwrap_or_default());
+            if !members.is_empty() {
+                constrained.insert(root, members);
+            }
+            true
+        };
+
+        let mut forced = vec![false; n];
+        for (a, b) in must_link.iter() {
+            if !join(*a, *b) {
+                return Err(Error::ConflictingConstraints { a: *a, b: *b });
+            }
+            forced[*a] = true;
+            forced[*b] = true;
+        }
+        for row_idx in (0..n).filter(|row_idx| core[*row_idx]) {
+            for other in neighbourhoods[row_idx].iter().filter(|other| core[**other] && **other > row_idx) {
+                join(row_idx, *other);
+            }
+        }
+        let mut point_types = vec![PointType::Noise; n];
+        for row_idx in 0..n {
+            if core[row_idx] {
+                point_types[row_idx] = PointType::Core;
+            } else if neighbourhoods[row_idx].iter().any(|other| core[*other]) {
+                point_types[row_idx] = PointType::Border;
+                if borders {
+                    for other in neighbourhoods[row_idx].iter().filter(|other| core[**other]) {
+                        if join(row_idx, *other) {
+                            forced[row_idx] = true;
+                            break;
                         }
                     }
-                    c += 1;
                 }
             }
         }
 
-        Dbscan {
-            eps: eps,
-            min_points: min_points,
-            clusters: clusters,
+        let mut numbers = HashMap::new();
+        let clusters = (0..n)
+            .map(|row_idx| match core[row_idx] || forced[row_idx] {
+                true => {
+                    let next = numbers.len() + 1;
+                    *numbers.entry(components.find(row_idx)).or_insert(next)
+                }
+                false => 0,
+            })
+            .collect::<Vec<usize>>();
+        Ok(Dbscan::from_clusters(eps, min_points, clusters, point_types))
+    }
+
+    /// DBSCAN++ (Jang & Jiang, 2019): only `m` sampled points are tested for being core, so a fit makes m region
+    /// queries over the data instead of n. Core samples within eps of each other share a cluster, and every
+    /// other point within eps of a core sample is a border point of the nearest one's cluster. Points not
+    /// sampled are never core, so clusters can lose their thinner edges as `m` shrinks.
+    pub fn new_plus_plus(data: &Array2<T>, eps: T, min_points: usize, borders: bool, m: usize, sampling: CoreSampling) -> Dbscan<T> {
+        Dbscan::new_plus_plus_with_rng(data, eps, min_points, borders, m, sampling, &mut default_rng())
+    }
+
+    pub fn new_plus_plus_with_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, m: usize, sampling: CoreSampling, rng: &mut R) -> Dbscan<T> {
+        let n = data.rows();
+        let m = m.min(n);
+        let row = |idx: usize| row_slice(data.row(idx));
+        let candidates = match sampling {
+            CoreSampling::Uniform => sample(rng, n, m).into_vec(),
+            CoreSampling::KCenter if m > 0 => {
+                // Greedily add the point farthest from every candidate so far.
+                let mut candidates = vec![rng.gen_range(0, n)];
+                let mut farthest = (0..n).map(|idx| squared_distance(&data.row(idx), &data.row(candidates[0]))).collect::<Vec<T>>();
+                while candidates.len() < m {
+                    let next = (0..n).fold(0, |best, idx| if farthest[idx] > farthest[best] { idx } else { best });
+                    candidates.push(next);
+                    for (idx, distance) in farthest.iter_mut().enumerate() {
+                        *distance = distance.min(squared_distance(&data.row(idx), &data.row(next)));
+                    }
+                }
+                candidates
+            }
+            CoreSampling::KCenter => Vec::new(),
+        };
+
+        let index = index_rows(data);
+        let mut neighbours = Vec::new();
+        let cores = candidates
+            .into_iter()
+            .filter(|candidate| {
+                neighbours.clear();
+                region_query(&row(*candidate), eps, &index, &mut neighbours);
+                neighbours.len() >= min_points
+            })
+            .sorted()
+            .collect::<Vec<usize>>();
+        let mut core_index = Index::new(data.cols());
+        for (position, core) in cores.iter().enumerate() {
+            core_index.add(row(*core), position);
         }
+        let mut components = UnionFind::new(cores.len());
+        for (position, core) in cores.iter().enumerate() {
+            neighbours.clear();
+            core_index.within(&row(*core), eps, &mut neighbours);
+            for other in neighbours.iter() {
+                components.union(position, *other);
+            }
+        }
+
+        let mut numbers = HashMap::new();
+        let mut clusters = vec![0; n];
+        let mut point_types = vec![PointType::Noise; n];
+        for row_idx in 0..n {
+            if let Some((distance, position)) = core_index.nearest(&row(row_idx), 1).first() {
+                if *distance <= eps {
+                    point_types[row_idx] = if cores[*position] == row_idx { PointType::Core } else { PointType::Border };
+                    if point_types[row_idx] == PointType::Core || borders {
+                        let next = numbers.len() + 1;
+                        clusters[row_idx] = *numbers.entry(components.find(*position)).or_insert(next);
+                    }
+                }
+            }
+        }
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Fits with eps chosen by `estimate_eps`, at the knee of the k-distance curve. The knee is where
+    /// distances to the (min_points - 1)-th neighbour stop growing slowly inside clusters and start rising
+    /// sharply towards outliers; border points are assigned. Read the chosen eps back from the model.
+    pub fn fit_auto(data: &Array2<T>, min_points: usize) -> Dbscan<T> {
+        Dbscan::new(data, estimate_eps(data, min_points), min_points, true)
+    }
+
+    /// Like `fit_auto`, with eps from `estimate_eps_for_noise` so that at most about `noise_fraction` of the
+    /// points fall short of being core. Some of those end up as border points, so the noise is usually less.
+    pub fn fit_auto_with_noise(data: &Array2<T>, min_points: usize, noise_fraction: f64) -> Dbscan<T> {
+        Dbscan::new(data, estimate_eps_for_noise(data, min_points, noise_fraction), min_points, true)
+    }
+
+    /// VDBSCAN (Liu et al., 2007) for data mixing tight and diffuse clusters, where any single eps either
+    /// shatters the diffuse ones or merges the tight ones. Each point's k-distance for `k = min_points - 1` is
+    /// sorted, the noise tail past the knee `estimate_eps` finds is set aside, and the rest is split at the
+    /// `levels - 1` largest ratios between neighbouring values into density levels. Levels are then clustered
+    /// densest first, each with eps at the largest k-distance in it, on the points earlier levels left
+    /// unclustered. Clusters are numbered level by level, and `eps` on the result is the sparsest level's, or
+    /// 0 for empty data.
+    pub fn new_multi_density(data: &Array2<T>, min_points: usize, borders: bool, levels: usize) -> Dbscan<T>
+    where
+        T: Send + Sync,
+    {
+        if data.rows() == 0 {
+            return Dbscan::from_clusters(T::zero(), min_points, Vec::new(), Vec::new());
+        }
+        let mut distances = point_kdist(data, min_points.saturating_sub(1).max(1));
+        distances.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd distance"));
+        // The noise tail past the curve's knee would always hold the largest jump, so levels are split below it.
+        let top = knee(&distances).or_else(|| distances.len().checked_sub(1)).unwrap_or(0);
+        let mut breaks = (1..=top).filter(|i| distances[i - 1] > T::zero()).collect::<Vec<usize>>();
+        breaks.sort_by(|a, b| (distances[*b] / distances[b - 1]).partial_cmp(&(distances[*a] / distances[a - 1])).expect("PartialOrd ratio"));
+        breaks.truncate(levels.max(1) - 1);
+        breaks.sort_unstable();
+        let eps_levels = breaks.iter().map(|i| distances[i - 1]).chain(distances.get(top).cloned()).collect::<Vec<T>>();
+        let mut clusters = vec![0; data.rows()];
+        let mut point_types = vec![PointType::Noise; data.rows()];
+        let mut next = 1;
+        for eps in eps_levels.iter() {
+            let remaining = (0..data.rows()).filter(|row| clusters[*row] == 0).collect::<Vec<usize>>();
+            let level = Dbscan::new_union_find(&data.select(Axis(0), &remaining), *eps, min_points, borders);
+            for (row, (cluster, point_type)) in remaining.iter().zip(level.clusters.iter().zip(level.point_types.iter())) {
+                point_types[*row] = *point_type;
+                if *cluster > 0 {
+                    clusters[*row] = next + cluster - 1;
+                }
+            }
+            next += level.n_clusters();
+        }
+        Dbscan::from_clusters(*eps_levels.last().expect("sparsest level"), min_points, clusters, point_types)
+    }
+
+    /// Fits at each candidate eps and keeps the fit with the highest silhouette score. Fits with fewer than
+    /// two clusters are skipped; if every candidate is degenerate the first candidate's fit is returned.
+    pub fn fit_best_silhouette(data: &Array2<T>, min_points: usize, borders: bool, eps_candidates: &[T]) -> Dbscan<T> {
+        eps_candidates
+            .iter()
+            .map(|eps| {
+                let model = Dbscan::new(data, *eps, min_points, borders);
+                let nclust = model.clusters.iter().filter(|c| **c > 0).unique().count();
+                let score = if nclust >= 2 { silhouette_score(data, &model.clusters) } else { T::neg_infinity() };
+                (model, score)
+            })
+            .fold(None, |best: Option<(Dbscan<T>, T)>, (model, score)| match best {
+                Some(best) if best.1 >= score => Some(best),
+                _ => Some((model, score)),
+            })
+            .expect("at least one eps candidate")
+            .0
+    }
+
+    /// Fits every combination of `eps_values` and `min_points_values` and scores each fit by `score`, returning all
+    /// of them best first. Fits with fewer than two clusters have no score and come last.
+    pub fn grid_search(data: &Array2<T>, eps_values: &[T], min_points_values: &[usize], borders: bool, score: InternalScore) -> Vec<GridSearchResult<T>> {
+        let grid = eps_values.iter().flat_map(|eps| min_points_values.iter().map(move |min_points| (*eps, *min_points))).collect::<Vec<(T, usize)>>();
+        rank_grid(grid.into_iter().map(|(eps, min_points)| GridSearchResult::fit(data, eps, min_points, borders, score)).collect(), score)
+    }
+
+    /// Like `grid_search`, fitting the configurations on rayon's thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn grid_search_parallel(data: &Array2<T>, eps_values: &[T], min_points_values: &[usize], borders: bool, score: InternalScore) -> Vec<GridSearchResult<T>>
+    where
+        T: Send + Sync,
+    {
+        let grid = eps_values.iter().flat_map(|eps| min_points_values.iter().map(move |min_points| (*eps, *min_points))).collect::<Vec<(T, usize)>>();
+        let results = grid.par_iter().map(|(eps, min_points)| GridSearchResult::fit(data, *eps, *min_points, borders, score)).collect::<Vec<GridSearchResult<T>>>();
+        rank_grid(results, score)
+    }
+
+    /// Fits once per value of `eps_values`, in that order, with a single round of neighbour queries: each point's
+    /// neighbours within the largest eps are found once and sorted by distance, and every fit reads the prefix
+    /// within its own eps. This trades memory for those lists against repeating the search per eps.
+    pub fn fit_sweep(data: &Array2<T>, eps_values: &[T], min_points: usize, borders: bool) -> Vec<Dbscan<T>> {
+        Dbscan::fit_sweep_with_rng(data, eps_values, min_points, borders, &mut default_rng())
     }
 
-    pub fn predict(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<Vec<usize>> {
+    pub fn fit_sweep_with_rng<R: Rng>(data: &Array2<T>, eps_values: &[T], min_points: usize, borders: bool, rng: &mut R) -> Vec<Dbscan<T>> {
+        let max_eps = match eps_values.iter().cloned().fold(None, |max: Option<T>, eps| Some(max.map_or(eps, |max| max.max(eps)))) {
+            Some(max_eps) => max_eps,
+            None => return Vec::new(),
+        };
+        let index = index_rows(data);
         let mut neighbours = Vec::with_capacity(data.rows());
-        let kdt = kdtree_init(&data);
-        new_data
+        let sorted_neighbours = data
             .outer_iter()
             .map(|row| {
                 neighbours.clear();
-                region_query(row.as_slice().unwrap(), self.eps, &kdt, &mut neighbours);
-                let neighbour_clusters = neighbours.iter().map(|idx| self.clusters[*idx]).unique().filter(|c| *c > 0).collect::<Vec<usize>>();
-                if neighbour_clusters.len() > 0 {
-                    neighbour_clusters
-                } else {
-                    vec![0]
+                region_query(&row_slice(row.view()), max_eps, &index, &mut neighbours);
+                let mut by_distance = neighbours.iter().map(|idx| (squared_distance(&row, &data.row(*idx)), *idx)).collect::<Vec<(T, usize)>>();
+                by_distance.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd distance"));
+                by_distance
+            })
+            .collect::<Vec<Vec<(T, usize)>>>();
+
+        eps_values
+            .iter()
+            .map(|eps| {
+                let squared_eps = eps.powi(2);
+                let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+                    let within = &sorted_neighbours[row_idx];
+                    neighbours.extend(within[..within.partition_point(|(d, _)| *d <= squared_eps)].iter().map(|(_, idx)| *idx))
+                });
+                Dbscan::from_clusters(*eps, min_points, clusters, point_types)
+            })
+            .collect::<Vec<Dbscan<T>>>()
+    }
+
+    /// Clusters the columns of `data` rather than its rows, so `clusters` is indexed by column.
+    pub fn new_transposed(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_transposed_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_transposed_with_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let mut transposed = Array2::zeros((data.cols(), data.rows()));
+        transposed.assign(&data.t());
+        Dbscan::new_with_rng(&transposed, eps, min_points, borders, rng)
+    }
+
+    /// Clusters rows by the angle between them, with `eps` in radians, leaving magnitudes out of the distance
+    /// without normalizing `data`. Neighbours are found by brute force, which is O(n^2). Zero rows have no
+    /// direction and are always noise.
+    pub fn new_angular(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_angular_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_angular_with_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            brute_force_query(data, row_idx, eps, angular_distance, neighbours)
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Clusters one-dimensional data in a single linear scan, without a KdTree. `sorted_data` must be in
+    /// ascending order; the result is meaningless otherwise. Clusters are numbered from 1 in ascending order,
+    /// and a border point between two clusters joins the cluster below it.
+    pub fn new_sorted_1d(sorted_data: &[T], eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        debug_assert!(sorted_data.windows(2).all(|w| w[0] <= w[1]), "new_sorted_1d needs ascending data");
+        let n = sorted_data.len();
+        let (mut lo, mut hi) = (0, 0);
+        let core = sorted_data
+            .iter()
+            .map(|x| {
+                while *x - sorted_data[lo] > eps {
+                    lo += 1;
+                }
+                while hi < n && sorted_data[hi] - *x <= eps {
+                    hi += 1;
                 }
+                hi - lo >= min_points
             })
-            .collect::<Vec<Vec<usize>>>()
+            .collect::<Vec<bool>>();
+
+        let mut clusters = vec![0; n];
+        let mut c = 0;
+        let mut previous: Option<usize> = None;
+        for idx in (0..n).filter(|idx| core[*idx]) {
+            if previous.map_or(true, |p| sorted_data[idx] - sorted_data[p] > eps) {
+                c += 1;
+            }
+            clusters[idx] = c;
+            previous = Some(idx);
+        }
+
+        let mut point_types = core.iter().map(|is_core| if *is_core { PointType::Core } else { PointType::Noise }).collect::<Vec<PointType>>();
+        let mut below: Option<usize> = None;
+        for (idx, x) in sorted_data.iter().enumerate() {
+            if core[idx] {
+                below = Some(idx);
+            } else if let Some(core_idx) = below.filter(|b| *x - sorted_data[*b] <= eps) {
+                point_types[idx] = PointType::Border;
+                if borders {
+                    clusters[idx] = clusters[core_idx];
+                }
+            }
+        }
+        let mut above: Option<usize> = None;
+        for (idx, x) in sorted_data.iter().enumerate().rev() {
+            if core[idx] {
+                above = Some(idx);
+            } else if let Some(core_idx) = above.filter(|a| point_types[idx] == PointType::Noise && sorted_data[*a] - *x <= eps) {
+                point_types[idx] = PointType::Border;
+                if borders {
+                    clusters[idx] = clusters[core_idx];
+                }
+            }
+        }
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
     }
-}
 
-fn kdtree_init<'a, T: Float + One + Zero>(data: &'a Array2<T>) -> KdTree<T, usize, &'a [T]> {
-    let mut kdt = KdTree::new(data.cols());
-    for (idx, row) in data.outer_iter().enumerate() {
-        kdt.add(row.into_slice().unwrap(), idx).unwrap();
+    /// Clusters sparse rows, such as TF-IDF vectors, by cosine distance `1 - cos`. Rows are L2-normalized and
+    /// compared over their shared nonzero columns only. With eps below 1, neighbours must share a column, so
+    /// they are found through an inverted index over columns; otherwise every pair is compared, in O(n^2).
+    pub fn from_sparse_cosine(rows: SparseRows<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::from_sparse_cosine_with_rng(rows, eps, min_points, borders, &mut default_rng())
     }
-    kdt
-}
 
-fn region_query<'a, T: Float + One + Zero>(row: &'a [T], eps: T, kdt: &KdTree<T, usize, &'a [T]>, neighbours: &mut Vec<usize>) {
-    for (_, neighbour_idx) in kdt.within(row, eps.powi(2), &squared_euclidean).expect("KdTree error checking point") {
-        neighbours.push(*neighbour_idx);
+    pub fn from_sparse_cosine_with_rng<R: Rng>(mut rows: SparseRows<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        rows.normalize();
+        let (clusters, point_types) = if eps < T::one() {
+            let postings = rows.postings();
+            let mut accumulator = DotAccumulator::new(rows.rows());
+            expand_clusters(rows.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+                accumulator.accumulate(&rows, row_idx, &postings);
+                neighbours.extend(accumulator.touched.iter().filter(|other| T::one() - accumulator.dots[**other] <= eps));
+            })
+        } else {
+            expand_clusters(rows.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+                neighbours.extend((0..rows.rows()).filter(|other| T::one() - rows.dot(row_idx, *other) <= eps))
+            })
+        };
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
     }
-}
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum ClusterPrediction {
-    Core(Vec<usize>),
-    Border(Vec<usize>),
-    Noise,
-}
+    /// Clusters sparse rows by Euclidean distance without densifying them, using `|a - b|^2 = |a|^2 + |b|^2 - 2 a.b`.
+    /// Rows whose norms differ by more than eps are never compared, and dot products come from an inverted
+    /// index over columns, so each query touches only rows of similar norm and the columns it shares with them.
+    pub fn from_sparse_euclidean(rows: &SparseRows<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::from_sparse_euclidean_with_rng(rows, eps, min_points, borders, &mut default_rng())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn from_sparse_euclidean_with_rng<R: Rng>(rows: &SparseRows<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let n = rows.rows();
+        let squared_norms = (0..n).map(|idx| rows.dot(idx, idx)).collect::<Vec<T>>();
+        let mut by_norm = (0..n).collect::<Vec<usize>>();
+        by_norm.sort_by(|a, b| squared_norms[*a].partial_cmp(&squared_norms[*b]).expect("PartialOrd norm"));
+        let mut rank = vec![0; n];
+        for (position, idx) in by_norm.iter().enumerate() {
+            rank[*idx] = position;
+        }
+        let postings = rows.postings();
+        let mut accumulator = DotAccumulator::new(n);
+        let (clusters, point_types) = expand_clusters(n, min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            accumulator.accumulate(rows, row_idx, &postings);
+            let norm = squared_norms[row_idx].sqrt();
+            let close = |other: &usize| squared_norms[row_idx] + squared_norms[*other] - (T::one() + T::one()) * accumulator.dots[*other] <= eps * eps;
+            let near_norm = |other: &&usize| (squared_norms[**other].sqrt() - norm).abs() <= eps;
+            neighbours.extend(by_norm[rank[row_idx]..].iter().take_while(near_norm).filter(|other| close(other)));
+            neighbours.extend(by_norm[..rank[row_idx]].iter().rev().take_while(near_norm).filter(|other| close(other)));
+        });
 
-    #[test]
-    fn test_clusters() {
-        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
-        let model = Dbscan::new(&data, 0.5, 2, false);
-        let clustering = dbg!(model.clusters);
-        assert!(clustering.iter().take(4).all_equal());
-        assert!(clustering.iter().skip(4).take(2).all_equal());
-        assert!(clustering.iter().skip(6).all_equal());
-        assert!(clustering[0] != clustering[4]);
-        assert!(clustering[4] != clustering[6]);
-        assert!(clustering[6] != clustering[0]);
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
     }
 
-    #[test]
-    fn test_border_points() {
-        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+    /// ST-DBSCAN (Birant & Kut, 2007): rows of `data` are positions and `times[i]` is row i's timestamp, and
+    /// neighbours must lie within `eps_spatial` in space and within `eps_temporal` in time. Events at one place
+    /// at distant times stay apart, which no rescaling of a time column can guarantee under one Euclidean eps.
+    /// Neighbours come from the spatial index and are then filtered by time; the model's `eps` is `eps_spatial`.
+    pub fn new_spatiotemporal(data: &Array2<T>, times: &[T], eps_spatial: T, eps_temporal: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_spatiotemporal_with_rng(data, times, eps_spatial, eps_temporal, min_points, borders, &mut default_rng())
+    }
 
-        let with = Dbscan::new(&data, 0.5, 3, true);
-        let without = Dbscan::new(&data, 0.5, 3, false);
-        let with_borders_clustering = dbg!(with.clusters);
-        let without_borders_clustering = dbg!(without.clusters);
-        assert!(with_borders_clustering.iter().all(|x| *x == 1));
-        assert!(without_borders_clustering.iter().take(1).all(|x| *x == 0));
-        assert!(without_borders_clustering.iter().skip(1).take(3).all(|x| *x == 1));
-        assert!(without_borders_clustering.iter().skip(4).all(|x| *x == 0));
+    pub fn new_spatiotemporal_with_rng<R: Rng>(data: &Array2<T>, times: &[T], eps_spatial: T, eps_temporal: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(times.len() == data.rows(), "need one timestamp per row");
+        let index = index_rows(data);
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            region_query(&row_slice(data.row(row_idx)), eps_spatial, &index, neighbours);
+            neighbours.retain(|other| (times[*other] - times[row_idx]).abs() <= eps_temporal);
+        });
+
+        Dbscan::from_clusters(eps_spatial, min_points, clusters, point_types)
     }
 
-    #[test]
-    fn test_prediction() {
-        let data = Array2::from_shape_vec((6, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1]).unwrap();
-        let model = Dbscan::new(&data, 0.5, 2, false);
+    /// Clusters 2D trajectories stored one per row as `points_per_traj` flattened `(x, y)` points, so `data`
+    /// must have `2 * points_per_traj` columns. Shorter paths should be padded by repeating their last point,
+    /// which leaves the distance unchanged. Trajectories are compared by Hausdorff distance, the larger of the
+    /// two directed distances so neighbourhoods stay symmetric. Neighbours are found by brute force, which is
+    /// O(n^2).
+    pub fn new_trajectory(data: &Array2<T>, points_per_traj: usize, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_trajectory_with_rng(data, points_per_traj, eps, min_points, borders, &mut default_rng())
+    }
 
-        let new_data = Array2::from_shape_vec((2, 2), vec![1.0, 2.0, 4.0, 4.0]).unwrap();
-        let classes = dbg!(model.predict(&data, &new_data));
+    pub fn new_trajectory_with_rng<R: Rng>(data: &Array2<T>, points_per_traj: usize, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(data.cols() == 2 * points_per_traj, "trajectories need 2 * points_per_traj columns");
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            brute_force_query(data, row_idx, eps, hausdorff_distance, neighbours)
+        });
 
-        let c0 = classes.get(0).unwrap();
-        assert!(c0.iter().any(|c| *c == model.clusters[0]));
-        assert!(classes[1] == vec![0]);
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Clusters complex-valued rows stored as interleaved `(re, im)` column pairs, so `data` must have an even
+    /// number of columns. The distance between two rows is the euclidean norm of the magnitudes of their
+    /// per-pair complex differences. Neighbours are found by brute force, which is O(n^2).
+    pub fn new_complex(data: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_complex_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_complex_with_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(data.cols() % 2 == 0, "complex data needs an even number of columns");
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            brute_force_query(data, row_idx, eps, complex_distance, neighbours)
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Like `new`, with periodic boundaries: a dimension with `periods[d] = Some(p)` wraps around every `p`,
+    /// as angles do with `2 * PI`, so points either side of the seam are close. Values are first wrapped into
+    /// `[0, p)`, and points within eps of a seam are also indexed shifted by `p` so the KdTree still finds them.
+    /// eps must be under half of every period.
+    pub fn new_periodic(data: &Array2<T>, periods: &[Option<T>], eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_periodic_with_rng(data, periods, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_periodic_with_rng<R: Rng>(data: &Array2<T>, periods: &[Option<T>], eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(periods.len() == data.cols(), "need one period per column");
+        let two = T::one() + T::one();
+        assert!(periods.iter().flatten().all(|p| eps < *p / two), "eps must be under half of every period");
+        let wrapped = data
+            .outer_iter()
+            .map(|row| row.iter().zip(periods.iter()).map(|(x, period)| period.map_or(*x, |p| *x - p * (*x / p).floor())).collect::<Vec<T>>())
+            .collect::<Vec<Vec<T>>>();
+        let mut index = Index::new(data.cols());
+        for (idx, row) in wrapped.iter().enumerate() {
+            let mut images = vec![row.clone()];
+            for (d, period) in periods.iter().enumerate() {
+                if let Some(p) = *period {
+                    for image in images.clone() {
+                        if image[d] < eps {
+                            images.push(image.iter().enumerate().map(|(i, x)| if i == d { *x + p } else { *x }).collect::<Vec<T>>());
+                        }
+                        if image[d] > p - eps {
+                            images.push(image.iter().enumerate().map(|(i, x)| if i == d { *x - p } else { *x }).collect::<Vec<T>>());
+                        }
+                    }
+                }
+            }
+            for image in images {
+                index.add(image, idx);
+            }
+        }
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            index.within(&wrapped[row_idx], eps, neighbours)
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Like `new`, with one eps per column for features in different units, such as meters, seconds and counts:
+    /// points are neighbours when `sum(((a[d] - b[d]) / eps[d])^2) <= 1`, an ellipsoid with semi-axis `eps[d]`
+    /// along each dimension, so a difference of `eps[d]` in a single column is just within reach. The columns
+    /// are divided by their eps before indexing and the model's `eps` is 1; `predict` on both arrays divided
+    /// the same way labels new points.
+    pub fn new_anisotropic(data: &Array2<T>, eps: &[T], min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_anisotropic_with_rng(data, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_anisotropic_with_rng<R: Rng>(data: &Array2<T>, eps: &[T], min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(eps.len() == data.cols(), "need one eps per column");
+        assert!(eps.iter().all(|e| *e > T::zero()), "every eps must be positive");
+        let scaled = Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] / eps[col]);
+        Dbscan::new_with_rng(&scaled, T::one(), min_points, borders, rng)
+    }
+
+    /// Clusters rows of `[latitude, longitude]` in degrees with `eps_meters` measured along the Earth's surface,
+    /// taken as a sphere of radius `EARTH_RADIUS_METERS`. Points are mapped onto the unit sphere, where the
+    /// straight-line distance grows with the great-circle distance, so this uses the KdTree, unlike
+    /// `Metric::Haversine`. `eps` on the result is in meters.
+    pub fn new_geographic(data: &Array2<T>, eps_meters: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_geographic_with_rng(data, eps_meters, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_geographic_with_rng<R: Rng>(data: &Array2<T>, eps_meters: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(data.cols() == 2, "geographic data needs [latitude, longitude] rows");
+        let mut unit = Array2::zeros((data.rows(), 3));
+        for (mut point, row) in unit.outer_iter_mut().zip(data.outer_iter()) {
+            let (latitude, longitude) = (row[0].to_radians(), row[1].to_radians());
+            point[0] = latitude.cos() * longitude.cos();
+            point[1] = latitude.cos() * longitude.sin();
+            point[2] = latitude.sin();
+        }
+        let two = T::one() + T::one();
+        let angle = eps_meters / T::from(EARTH_RADIUS_METERS).expect("T::from(f64)");
+        let chord = two * (angle.min(T::from(std::f64::consts::PI).expect("T::from(f64)")) / two).sin();
+        let mut model = Dbscan::new_with_rng(&unit, chord, min_points, borders, rng);
+        model.eps = eps_meters;
+        model
+    }
+
+    /// Like `new`, measuring neighbourhoods with `metric`. Only `Metric::Euclidean` uses the KdTree; the other
+    /// metrics compare every pair of points.
+    pub fn new_with_metric(data: &Array2<T>, eps: T, min_points: usize, borders: bool, metric: Metric<T>) -> Dbscan<T> {
+        Dbscan::new_with_metric_and_rng(data, eps, min_points, borders, metric, &mut default_rng())
+    }
+
+    pub fn new_with_metric_and_rng<R: Rng>(data: &Array2<T>, eps: T, min_points: usize, borders: bool, metric: Metric<T>, rng: &mut R) -> Dbscan<T> {
+        if metric == Metric::Euclidean {
+            return Dbscan::new_with_rng(data, eps, min_points, borders, rng);
+        }
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            brute_force_query(data, row_idx, eps, |a, b| metric.distance(a, b), neighbours)
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Clusters from a precomputed square matrix of pairwise distances, for data where only a distance function
+    /// is available. The points are never indexed, so every row of the matrix is scanned.
+    pub fn from_distances(distances: &Array2<T>, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::from_distances_with_rng(distances, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn from_distances_with_rng<R: Rng>(distances: &Array2<T>, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(distances.rows() == distances.cols(), "distance matrix must be square");
+        let (clusters, point_types) = expand_clusters(distances.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            neighbours.extend(distances.row(row_idx).iter().enumerate().filter(|(_, d)| **d <= eps).map(|(idx, _)| idx))
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Like `from_distances`, from the condensed upper triangle of the matrix: the distances of pairs `(i, j)`
+    /// with `i < j` in row-major order, `n * (n - 1) / 2` values for `n` points.
+    pub fn from_condensed_distances(condensed: &[T], n: usize, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::from_condensed_distances_with_rng(condensed, n, eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn from_condensed_distances_with_rng<R: Rng>(condensed: &[T], n: usize, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(condensed.len() == n * n.saturating_sub(1) / 2, "condensed distances need n * (n - 1) / 2 values");
+        let position = |i: usize, j: usize| n * i - i * (i + 1) / 2 + j - i - 1;
+        let (clusters, point_types) = expand_clusters(n, min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            neighbours.extend((0..n).filter(|other| match other.cmp(&row_idx) {
+                Ordering::Less => condensed[position(*other, row_idx)] <= eps,
+                Ordering::Equal => true,
+                Ordering::Greater => condensed[position(row_idx, *other)] <= eps,
+            }))
+        });
+
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    /// Shared nearest neighbour DBSCAN (Ertöz, Steinbach & Kumar, 2003): two points are neighbours when each
+    /// is among the other's `k` nearest and their k-nearest lists share at least `min_shared` points, and a
+    /// point is core with at least `min_points` such neighbours, itself included. Counting shared neighbours
+    /// instead of thresholding distance adapts to the local density, so clusters of different densities come
+    /// out together, and it keeps working in high dimensions where distances concentrate and no eps separates
+    /// anything. The model's `eps` is zero and its `neighbourhood` holds `k` and `min_shared`; `predict`,
+    /// `drift_score` and `point_in_cluster_reach` ask the same of new points, while predictions that search a
+    /// radius around them panic.
+    pub fn new_shared_neighbours(data: &Array2<T>, k: usize, min_shared: usize, min_points: usize, borders: bool) -> Dbscan<T> {
+        Dbscan::new_shared_neighbours_with_rng(data, k, min_shared, min_points, borders, &mut default_rng())
+    }
+
+    pub fn new_shared_neighbours_with_rng<R: Rng>(data: &Array2<T>, k: usize, min_shared: usize, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        let index = index_rows(data);
+        let nearest = k_nearest(&index, data, k);
+        let (clusters, point_types) = expand_clusters(data.rows(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            let (own, _) = &nearest[row_idx];
+            neighbours.push(row_idx);
+            neighbours.extend(own.iter().filter(|other| {
+                let (theirs, _) = &nearest[**other];
+                theirs.binary_search(&row_idx).is_ok() && own.iter().filter(|shared| theirs.binary_search(shared).is_ok()).count() >= min_shared
+            }));
+        });
+
+        let mut dbscan = Dbscan::from_clusters(T::zero(), min_points, clusters, point_types);
+        dbscan.neighbourhood = Neighbourhood::SharedNeighbours { k, min_shared };
+        dbscan
+    }
+
+    /// Clusters within eps of each row of `new_data`, or `[0]` for noise. Like `new`, accepts any 2-D arrays.
+    /// Shared nearest neighbour models list the clusters of the row's shared neighbours instead; see
+    /// `shared_neighbours_of`.
+    pub fn predict<S: Data<Elem = T>, N: Data<Elem = T>>(&self, data: &ArrayBase<S, Ix2>, new_data: &ArrayBase<N, Ix2>) -> Vec<Vec<usize>> {
+        let data_copy = row_major_copy(data);
+        let new_copy = row_major_copy(new_data);
+        let data = data_copy.as_ref().map_or_else(|| data.view(), Array2::view);
+        let new_data = new_copy.as_ref().map_or_else(|| new_data.view(), Array2::view);
+        let index = index_rows(&data);
+        match self.neighbourhood {
+            Neighbourhood::Eps => self.predict_from(&index, new_data),
+            Neighbourhood::SharedNeighbours { k, min_shared } => {
+                let nearest = k_nearest(&index, &data, k);
+                self.predict_by(new_data, |row, neighbours| shared_neighbours_of(&index, &nearest, row, k, min_shared, neighbours))
+            }
+        }
+    }
+
+    /// Like `predict`, reusing an index over the training data that can be shared between models fit on it.
+    pub fn predict_with_index(&self, index: &PredictionIndex<T>, new_data: &Array2<T>) -> Vec<Vec<usize>> {
+        self.predict_from(&index.index, new_data.view())
+    }
+
+    /// Like `predict`, for a model fit with `new_with_metric`; `metric` must be the one used to fit.
+    pub fn predict_with_metric(&self, data: &Array2<T>, new_data: &Array2<T>, metric: Metric<T>) -> Vec<Vec<usize>> {
+        if metric == Metric::Euclidean {
+            return self.predict(data, new_data);
+        }
+        self.predict_by(new_data.view(), |row, neighbours| {
+            neighbours.extend(data.outer_iter().enumerate().filter(|(_, other)| metric.distance(row, other) <= self.radius()).map(|(idx, _)| idx))
+        })
+    }
+
+    fn predict_from<U: AsRef<[T]>>(&self, index: &Index<T, U>, new_data: ArrayView2<T>) -> Vec<Vec<usize>> {
+        self.predict_by(new_data, |row, neighbours| index.within(&row_slice(row.view()), self.radius(), neighbours))
+    }
+
+    fn predict_by<F: Fn(&ArrayView1<T>, &mut Vec<usize>)>(&self, new_data: ArrayView2<T>, query: F) -> Vec<Vec<usize>> {
+        let mut neighbours = Vec::with_capacity(self.clusters.len());
+        new_data
+            .outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                query(&row, &mut neighbours);
+                self.neighbour_clusters(&neighbours)
+            })
+            .collect::<Vec<Vec<usize>>>()
+    }
+
+    /// Like `predict`, with the rows of `new_data` split over rayon's thread pool. The model is only read, and
+    /// each thread queries with a buffer of its own, so one fitted model can serve many threads at once.
+    #[cfg(feature = "rayon")]
+    pub fn predict_parallel(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        self.predict_chunked(data, new_data, 1)
+    }
+
+    /// Like `predict_parallel`, handing the rows of `new_data` to the threads at least `chunk_rows` at a time,
+    /// which cuts scheduling overhead on large batches of cheap rows; a thread that finishes early steals work.
+    #[cfg(feature = "rayon")]
+    pub fn predict_chunked(&self, data: &Array2<T>, new_data: &Array2<T>, chunk_rows: usize) -> Vec<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        let index = index_rows(data);
+        self.predict_parallel_by(new_data, chunk_rows, |row, neighbours| index.within(&row_slice(row.view()), self.radius(), neighbours))
+    }
+
+    #[cfg(feature = "rayon")]
+    fn predict_parallel_by<F: Fn(&ArrayView1<T>, &mut Vec<usize>) + Sync>(&self, new_data: &Array2<T>, chunk_rows: usize, query: F) -> Vec<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        parallel_map_chunked(new_data.rows(), chunk_rows, |row_idx, neighbours| {
+            neighbours.clear();
+            query(&new_data.row(row_idx), neighbours);
+            self.neighbour_clusters(neighbours)
+        })
+    }
+
+    fn neighbour_clusters(&self, neighbours: &[usize]) -> Vec<usize> {
+        let neighbour_clusters = neighbours.iter().map(|idx| self.clusters[*idx]).unique().filter(|c| *c > 0).collect::<Vec<usize>>();
+        if !neighbour_clusters.is_empty() {
+            neighbour_clusters
+        } else {
+            vec![0]
+        }
+    }
+
+    /// Like `predict`, also saying whether each new point would be a core point (at least `min_points` training
+    /// points within eps, counting itself), a border point of the listed clusters, or noise. A core point lists
+    /// the clusters of its core neighbours, and an empty list means it would start a cluster of its own.
+    pub fn predict_labeled(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<ClusterPrediction> {
+        let core = self.core_neighbours(data).iter().map(Option::is_some).collect::<Vec<bool>>();
+        let index = index_rows(data);
+        self.predict_labeled_by(&core, new_data, |row, radius, neighbours| index.within(&row_slice(row.view()), radius, neighbours))
+    }
+
+    fn predict_labeled_by<Q: Fn(&ArrayView1<T>, T, &mut Vec<usize>)>(&self, core: &[bool], new_data: &Array2<T>, query: Q) -> Vec<ClusterPrediction> {
+        let mut neighbours = Vec::with_capacity(self.clusters.len());
+        new_data
+            .outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                query(&row, self.radius(), &mut neighbours);
+                let clusters = neighbours.iter().filter(|idx| core[**idx]).map(|idx| self.clusters[*idx]).filter(|c| *c > 0).unique().sorted().collect::<Vec<usize>>();
+                if neighbours.len() + 1 >= self.min_points {
+                    ClusterPrediction::Core(clusters)
+                } else if !clusters.is_empty() {
+                    ClusterPrediction::Border(clusters)
+                } else {
+                    ClusterPrediction::Noise
+                }
+            })
+            .collect::<Vec<ClusterPrediction>>()
+    }
+
+    /// For each row of `new_data`, every cluster with a core point within eps, paired with the distance to its
+    /// nearest such core point, nearest cluster first; empty for points `predict` would call noise. Core points
+    /// are the fit's own `point_types`, so weighted fits use their weighted core condition.
+    pub fn predict_with_distances(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<Vec<(usize, T)>> {
+        let index = index_rows(data);
+        self.predict_with_distances_by(
+            new_data,
+            |row, radius, neighbours| index.within(&row_slice(row.view()), radius, neighbours),
+            |row, idx| squared_distance(row, &data.row(idx)).sqrt(),
+        )
+    }
+
+    /// `predict_with_distances` with the neighbours within a radius of a row found by `query`, and the distance
+    /// from a row to a training point by `distance`, so models fit with another metric can share it.
+    fn predict_with_distances_by<Q, D>(&self, new_data: &Array2<T>, query: Q, distance: D) -> Vec<Vec<(usize, T)>>
+    where
+        Q: Fn(&ArrayView1<T>, T, &mut Vec<usize>),
+        D: Fn(&ArrayView1<T>, usize) -> T,
+    {
+        let mut neighbours = Vec::with_capacity(self.clusters.len());
+        new_data
+            .outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                query(&row, self.radius(), &mut neighbours);
+                let mut nearest: BTreeMap<usize, T> = BTreeMap::new();
+                for idx in neighbours.iter().filter(|idx| self.point_types[**idx] == PointType::Core && self.clusters[**idx] > 0) {
+                    let distance = distance(&row, *idx);
+                    let entry = nearest.entry(self.clusters[*idx]).or_insert(distance);
+                    *entry = entry.min(distance);
+                }
+                let mut nearest = nearest.into_iter().collect::<Vec<(usize, T)>>();
+                nearest.sort_by(|a, b| a.1.partial_cmp(&b.1).expect("PartialOrd distance"));
+                nearest
+            })
+            .collect::<Vec<Vec<(usize, T)>>>()
+    }
+
+    /// One label per row of `new_data`: the cluster of the nearest core point within eps, or `None` for noise.
+    /// Points `predict` places between clusters go to the closer one, and exact ties to the lower cluster.
+    pub fn predict_single(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<Option<usize>> {
+        nearest_clusters(self.predict_with_distances(data, new_data))
+    }
+
+    /// `predict_single`'s label for each row of `new_data` paired with a density score: a Gaussian kernel of
+    /// bandwidth eps summed over the training core points and divided by their number, so it lies in [0, 1]
+    /// and falls smoothly as points leave the clusters. Kernels are cut off at three bandwidths, beyond which
+    /// they'd add under 1.2% each. Low scores flag anomalies even among points that got a cluster.
+    pub fn predict_density(&self, data: &Array2<T>, new_data: &Array2<T>) -> Vec<(Option<usize>, T)> {
+        let index = index_rows(data);
+        self.predict_density_by(
+            new_data,
+            |row, radius, neighbours| index.within(&row_slice(row.view()), radius, neighbours),
+            |row, idx| squared_distance(row, &data.row(idx)).sqrt(),
+        )
+    }
+
+    fn predict_density_by<Q, D>(&self, new_data: &Array2<T>, query: Q, distance: D) -> Vec<(Option<usize>, T)>
+    where
+        Q: Fn(&ArrayView1<T>, T, &mut Vec<usize>),
+        D: Fn(&ArrayView1<T>, usize) -> T,
+    {
+        let cores = T::from(self.point_types.iter().filter(|t| **t == PointType::Core).count().max(1)).expect("T::from(usize)");
+        let two = T::one() + T::one();
+        let cutoff = (two + T::one()) * self.radius();
+        let mut neighbours = Vec::with_capacity(self.clusters.len());
+        let densities = new_data.outer_iter().map(|row| {
+            neighbours.clear();
+            query(&row, cutoff, &mut neighbours);
+            let kernels = neighbours
+                .iter()
+                .filter(|idx| self.point_types[**idx] == PointType::Core)
+                .fold(T::zero(), |acc, idx| acc + (-distance(&row, *idx).powi(2) / (two * self.radius() * self.radius())).exp());
+            kernels / cores
+        });
+        nearest_clusters(self.predict_with_distances_by(new_data, &query, &distance)).into_iter().zip(densities).collect::<Vec<(Option<usize>, T)>>()
+    }
+
+    /// Soft labels for the training points: each cluster with a core point within eps, scored `1 - d / eps`
+    /// for the distance `d` to its nearest such core point, highest first. Core points score 1 in their own
+    /// cluster, a border point between clusters is listed under each of them, and noise gets an empty list.
+    pub fn memberships(&self, data: &Array2<T>) -> Vec<Vec<(usize, T)>> {
+        self.predict_with_distances(data, data)
+            .into_iter()
+            .map(|nearest| nearest.into_iter().map(|(cluster, distance)| (cluster, T::one() - distance / self.radius())).collect::<Vec<(usize, T)>>())
+            .collect::<Vec<Vec<(usize, T)>>>()
+    }
+
+    /// Fraction of `new_batch` that `predict` would call noise, i.e. with no clustered training point within
+    /// eps. A rising score across batches suggests the data has drifted away from the training set.
+    pub fn drift_score(&self, data: &Array2<T>, new_batch: &Array2<T>) -> f64 {
+        if new_batch.rows() == 0 {
+            return 0.0;
+        }
+        let noise = self.predict(data, new_batch).iter().filter(|clusters| clusters[..] == [0]).count();
+        noise as f64 / new_batch.rows() as f64
+    }
+
+    /// Whether `point` lies within eps of a core point of `cluster_id`, i.e. in the region DBSCAN would assign
+    /// to that cluster. Core points are the fit's own `point_types`, so weighted fits use their weighted core
+    /// condition. For shared nearest neighbour models, whether a core point of the cluster is among its shared
+    /// neighbours.
+    pub fn point_in_cluster_reach(&self, data: &Array2<T>, point: &Array1<T>, cluster_id: usize) -> bool {
+        if cluster_id == 0 {
+            return false;
+        }
+        if let Neighbourhood::SharedNeighbours { k, min_shared } = self.neighbourhood {
+            let index = index_rows(data);
+            let mut neighbours = Vec::new();
+            shared_neighbours_of(&index, &k_nearest(&index, data, k), &point.view(), k, min_shared, &mut neighbours);
+            return neighbours.iter().any(|idx| self.point_types[*idx] == PointType::Core && self.clusters[*idx] == cluster_id);
+        }
+        let eps = self.eps.powi(2);
+        data.outer_iter()
+            .zip(self.point_types.iter().zip(self.clusters.iter()))
+            .filter(|(_, (point_type, c))| **point_type == PointType::Core && **c == cluster_id)
+            .any(|(row, _)| squared_distance(&row, &point.view()) <= eps)
+    }
+
+    /// Like `point_in_cluster_reach`, querying an index over the training data instead of scanning every
+    /// core point, for checking many points against one model.
+    pub fn point_in_cluster_reach_with_index(&self, index: &PredictionIndex<T>, point: &Array1<T>, cluster_id: usize) -> bool {
+        if cluster_id == 0 {
+            return false;
+        }
+        let mut neighbours = Vec::new();
+        index.index.within(&row_slice(point.view()), self.radius(), &mut neighbours);
+        neighbours.iter().any(|idx| self.point_types[*idx] == PointType::Core && self.clusters[*idx] == cluster_id)
+    }
+
+    /// Names cluster `id` for reporting; see `named_labels`.
+    pub fn set_cluster_name(&mut self, id: usize, name: &str) {
+        self.labels.insert(id, name.to_string());
+    }
+
+    /// Each point's cluster name, or `None` for noise and clusters that haven't been named.
+    pub fn named_labels(&self) -> Vec<Option<&str>> {
+        self.clusters.iter().map(|c| self.labels.get(c).map(String::as_str).filter(|_| *c > 0)).collect::<Vec<Option<&str>>>()
+    }
+
+    pub fn labels_typed(&self) -> Vec<ClusterLabel> {
+        self.clusters.iter().map(|c| NonZeroUsize::new(*c).map_or(ClusterLabel::Noise, ClusterLabel::Cluster)).collect::<Vec<ClusterLabel>>()
+    }
+
+    /// Labels in scikit-learn's convention, for comparing with `labels_` from Python: noise is -1 and clusters
+    /// are numbered from 0.
+    pub fn labels_sklearn(&self) -> Array1<i64> {
+        self.clusters.iter().map(|c| *c as i64 - 1).collect::<Array1<i64>>()
+    }
+
+    /// Number of clusters, not counting noise.
+    pub fn n_clusters(&self) -> usize {
+        self.cluster_sizes().len()
+    }
+
+    /// Renumbers clusters 1, 2, ... in order of their lowest point index, leaving noise as 0, so fits that find
+    /// the same partition get the same labels whatever order points were visited in. Names follow their clusters.
+    pub fn canonicalize(&mut self) {
+        let mut renumbered = HashMap::new();
+        for c in self.clusters.iter().filter(|c| **c > 0) {
+            let next = renumbered.len() + 1;
+            renumbered.entry(*c).or_insert(next);
+        }
+        for c in self.clusters.iter_mut().filter(|c| **c > 0) {
+            *c = renumbered[c];
+        }
+        self.labels = self.labels.drain().filter_map(|(c, name)| renumbered.get(&c).map(|c| (*c, name))).collect::<HashMap<usize, String>>();
+    }
+
+    /// Indices of the core points, in ascending order.
+    pub fn core_sample_indices(&self) -> Vec<usize> {
+        self.point_types.iter().positions(|t| *t == PointType::Core).collect::<Vec<usize>>()
+    }
+
+    /// Indices of the points labelled noise.
+    pub fn noise_indices(&self) -> Vec<usize> {
+        self.cluster_indices(0)
+    }
+
+    /// Indices of the points in cluster `c`; `cluster_indices(0)` is the noise.
+    pub fn cluster_indices(&self, c: usize) -> Vec<usize> {
+        self.clusters.iter().enumerate().filter(|(_, label)| **label == c).map(|(idx, _)| idx).collect::<Vec<usize>>()
+    }
+
+    /// Merges clusters that share an edge between two core points within eps, then renumbers the clusters
+    /// from 1 in order of their previous ids.
+    pub fn repair_clusters(&mut self, data: &Array2<T>) {
+        let core_neighbours = self.core_neighbours(data);
+        let mut merged = UnionFind::new(self.clusters.iter().cloned().max().unwrap_or(0) + 1);
+        for (idx, neighbours) in core_neighbours.iter().enumerate() {
+            if let Some(neighbours) = neighbours {
+                for neighbour_idx in neighbours.iter().filter(|n| core_neighbours[**n].is_some()) {
+                    if self.clusters[idx] > 0 && self.clusters[*neighbour_idx] > 0 {
+                        merged.union(self.clusters[idx], self.clusters[*neighbour_idx]);
+                    }
+                }
+            }
+        }
+
+        let roots = self.clusters.iter().map(|c| if *c > 0 { merged.find(*c) } else { 0 }).collect::<Vec<usize>>();
+        let mut ids = BTreeMap::new();
+        for (root, c) in roots.iter().zip(self.clusters.iter()).filter(|(root, _)| **root > 0) {
+            let id = ids.entry(*root).or_insert(*c);
+            *id = (*id).min(*c);
+        }
+        let ordered = ids.values().cloned().sorted().collect::<Vec<usize>>();
+        for (c, root) in self.clusters.iter_mut().zip(roots.iter()) {
+            if *root > 0 {
+                *c = ordered.binary_search(&ids[root]).expect("merged cluster id") + 1;
+            }
+        }
+    }
+
+    /// Whether the convex hulls of clusters `a` and `b` intersect, tested with the separating axis theorem.
+    /// Only defined for two-dimensional data.
+    pub fn clusters_overlap(&self, data: &Array2<T>, a: usize, b: usize) -> Result<bool, Error> {
+        if data.cols() != 2 {
+            return Err(Error::DimensionMismatch { expected: 2, found: data.cols() });
+        }
+        let hull_a = self.convex_hull(data, a);
+        let hull_b = self.convex_hull(data, b);
+        if hull_a.is_empty() || hull_b.is_empty() {
+            return Ok(false);
+        }
+        let mut axes = vec![(T::one(), T::zero()), (T::zero(), T::one())];
+        for hull in [&hull_a, &hull_b].iter() {
+            for (p, q) in hull.iter().zip(hull.iter().skip(1).chain(hull.first())) {
+                let edge = (q.0 - p.0, q.1 - p.1);
+                axes.push(edge);
+                axes.push((-edge.1, edge.0));
+            }
+        }
+        let project = |hull: &[(T, T)], axis: (T, T)| {
+            hull.iter().map(|p| p.0 * axis.0 + p.1 * axis.1).fold((T::infinity(), T::neg_infinity()), |(lo, hi), x| (lo.min(x), hi.max(x)))
+        };
+        Ok(axes.into_iter().all(|axis| {
+            let (lo_a, hi_a) = project(&hull_a, axis);
+            let (lo_b, hi_b) = project(&hull_b, axis);
+            lo_a <= hi_b && lo_b <= hi_a
+        }))
+    }
+
+    fn convex_hull(&self, data: &Array2<T>, cluster: usize) -> Vec<(T, T)> {
+        convex_hull(data.outer_iter().zip(self.clusters.iter()).filter(|(_, c)| **c == cluster).map(|(row, _)| (row[0], row[1])).collect::<Vec<(T, T)>>())
+    }
+
+    /// Size, centroid, medoid and diameter of each cluster, keyed by cluster id. Medoids and diameters compare
+    /// every pair of members, so this is O(m^2) in the size of the largest cluster.
+    pub fn summaries(&self, data: &Array2<T>) -> BTreeMap<usize, ClusterSummary<T>> {
+        let sizes = self.cluster_sizes();
+        self.centroids(data)
+            .into_iter()
+            .map(|(c, centroid)| {
+                let members = self.cluster_indices(c);
+                let (mut medoid, mut diameter) = ((T::infinity(), members[0]), T::zero());
+                for a in members.iter() {
+                    let distances = members.iter().map(|b| squared_distance(&data.row(*a), &data.row(*b)).sqrt()).collect::<Vec<T>>();
+                    let total = distances.iter().fold(T::zero(), |acc, d| acc + *d);
+                    if total < medoid.0 {
+                        medoid = (total, *a);
+                    }
+                    diameter = distances.into_iter().fold(diameter, T::max);
+                }
+                let summary = ClusterSummary {
+                    size: sizes[&c],
+                    centroid,
+                    medoid: medoid.1,
+                    diameter,
+                };
+                (c, summary)
+            })
+            .collect()
+    }
+
+    /// Approximate minimum enclosing ball `(center, radius)` of each cluster, from Badoiu-Clarkson iterations
+    /// that repeatedly step the center towards the farthest member. Works in any dimension; the radius is
+    /// within about 3% of optimal.
+    pub fn bounding_spheres(&self, data: &Array2<T>) -> BTreeMap<usize, (Array1<T>, T)> {
+        self.centroids(data)
+            .into_iter()
+            .map(|(c, mut center)| {
+                let members = data.outer_iter().zip(self.clusters.iter()).filter(|(_, cluster)| **cluster == c).map(|(row, _)| row).collect::<Vec<_>>();
+                let farthest = |center: &Array1<T>| {
+                    members
+                        .iter()
+                        .map(|row| (row, squared_distance(row, &center.view())))
+                        .max_by(|a, b| a.1.partial_cmp(&b.1).expect("PartialOrd distance"))
+                        .expect("cluster member")
+                };
+                for i in 1..=1000 {
+                    let (row, _) = farthest(&center);
+                    let step = T::one() / T::from(i + 1).expect("T::from(usize)");
+                    center = &center + &(row - &center).mapv(|x| x * step);
+                }
+                let radius = farthest(&center).1.sqrt();
+                (c, (center, radius))
+            })
+            .collect()
+    }
+
+    /// One-hot `points x clusters` membership matrix where column `j` is cluster `j + 1`; noise rows are all zero.
+    pub fn one_hot(&self) -> Array2<u8> {
+        let nclust = self.clusters.iter().cloned().max().unwrap_or(0);
+        let mut encoded = Array2::zeros((self.clusters.len(), nclust));
+        for (idx, c) in self.clusters.iter().enumerate().filter(|(_, c)| **c > 0) {
+            encoded[[idx, *c - 1]] = 1;
+        }
+        encoded
+    }
+
+    /// The `k` members of `cluster_id` nearest to `point`, as `(index, distance)` in ascending distance.
+    pub fn k_nearest_in_cluster(&self, data: &Array2<T>, point: &Array1<T>, cluster_id: usize, k: usize) -> Vec<(usize, T)> {
+        let index = index_rows(data);
+        index
+            .nearest_matching(&row_slice(point.view()), k, |idx| self.clusters[idx] == cluster_id)
+            .into_iter()
+            .map(|(distance, idx)| (idx, distance))
+            .collect::<Vec<(usize, T)>>()
+    }
+
+    /// GraphViz DOT rendering of the core graph: one node per point, filled by cluster (noise is gray), and an
+    /// edge between every pair of core points within eps.
+    pub fn to_dot(&self, data: &Array2<T>) -> String {
+        const PALETTE: [&str; 8] = ["red", "blue", "green", "orange", "purple", "cyan", "magenta", "brown"];
+        let mut dot = String::from("graph dbscan {\n");
+        for (idx, c) in self.clusters.iter().enumerate() {
+            let color = if *c > 0 { PALETTE[(*c - 1) % PALETTE.len()] } else { "gray" };
+            dot.push_str(&format!("    {} [label=\"{}\", style=filled, fillcolor={}];\n", idx, c, color));
+        }
+        let core_neighbours = self.core_neighbours(data);
+        for (idx, neighbours) in core_neighbours.iter().enumerate() {
+            if let Some(neighbours) = neighbours {
+                for neighbour_idx in neighbours.iter().filter(|n| **n > idx && core_neighbours[**n].is_some()) {
+                    dot.push_str(&format!("    {} -- {};\n", idx, neighbour_idx));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Normalized entropy of the cluster sizes, ignoring noise: 1.0 when every cluster is the same size, falling
+    /// towards 0 as one cluster dominates. A single cluster scores 1.0 and no clusters at all score 0.0.
+    pub fn size_balance(&self) -> f64 {
+        let sizes = self.cluster_sizes();
+        let total = sizes.values().sum::<usize>() as f64;
+        match sizes.len() {
+            0 => 0.0,
+            1 => 1.0,
+            k => -sizes.values().map(|size| *size as f64 / total).map(|p| p * p.ln()).sum::<f64>() / (k as f64).ln(),
+        }
+    }
+
+    /// Per cluster, the fraction of member pairs that lie within eps of each other: 1.0 for a cluster where
+    /// every point neighbours every other, lower for chained clusters. Single-point clusters score 1.0.
+    pub fn cluster_connectivity(&self, data: &Array2<T>) -> BTreeMap<usize, f64> {
+        let mut neighbours = Vec::with_capacity(data.rows());
+        let index = index_rows(data);
+        let mut edges: BTreeMap<usize, usize> = BTreeMap::new();
+        for (idx, (row, c)) in data.outer_iter().zip(self.clusters.iter()).enumerate().filter(|(_, (_, c))| **c > 0) {
+            neighbours.clear();
+            region_query(&row_slice(row.view()), self.radius(), &index, &mut neighbours);
+            *edges.entry(*c).or_insert(0) += neighbours.iter().filter(|n| **n > idx && self.clusters[**n] == *c).count();
+        }
+        self.cluster_sizes()
+            .into_iter()
+            .map(|(c, size)| {
+                let pairs = size * (size - 1) / 2;
+                (c, if pairs > 0 { edges[&c] as f64 / pairs as f64 } else { 1.0 })
+            })
+            .collect()
+    }
+
+    /// Fraction of the total sum of squares captured by the clustering, `1 - within / total`.
+    /// Noise points are excluded from both sums, so this scores only the points that were clustered.
+    pub fn variance_explained(&self, data: &Array2<T>) -> T {
+        let centroids = self.centroids(data);
+        let clustered = self.clusters.iter().enumerate().filter(|(_, c)| **c > 0).map(|(idx, _)| idx).collect::<Vec<usize>>();
+        if clustered.is_empty() {
+            return T::zero();
+        }
+        let mut grand_mean = Array1::zeros(data.cols());
+        for idx in clustered.iter() {
+            grand_mean = grand_mean + data.row(*idx);
+        }
+        let n = T::from(clustered.len()).expect("T::from(usize)");
+        let grand_mean = grand_mean.mapv(|x| x / n);
+        let (within_ss, total_ss) = clustered.iter().fold((T::zero(), T::zero()), |(within, total), idx| {
+            let row = data.row(*idx);
+            let centroid = &centroids[&self.clusters[*idx]];
+            (within + squared_distance(&row, &centroid.view()), total + squared_distance(&row, &grand_mean.view()))
+        });
+        if total_ss > T::zero() {
+            T::one() - within_ss / total_ss
+        } else {
+            T::zero()
+        }
+    }
+
+    /// One-way ANOVA F-statistic of each dimension across the clusters, ignoring noise: between-cluster variance
+    /// over within-cluster variance. Zero when there are fewer than two clusters or no within-cluster freedom.
+    pub fn dimension_separation(&self, data: &Array2<T>) -> Array1<T> {
+        let centroids = self.centroids(data);
+        let sizes = self.cluster_sizes();
+        let clustered = self.clusters.iter().filter(|c| **c > 0).count();
+        if centroids.len() < 2 || clustered <= centroids.len() {
+            return Array1::zeros(data.cols());
+        }
+        let mut grand_mean = Array1::zeros(data.cols());
+        for (row, _) in data.outer_iter().zip(self.clusters.iter()).filter(|(_, c)| **c > 0) {
+            grand_mean = grand_mean + row;
+        }
+        let grand_mean = grand_mean.mapv(|x| x / T::from(clustered).expect("T::from(usize)"));
+        let mut between = Array1::zeros(data.cols());
+        for (c, centroid) in centroids.iter() {
+            between = between + (centroid - &grand_mean).mapv(|x| x.powi(2) * T::from(sizes[c]).expect("T::from(usize)"));
+        }
+        let mut within = Array1::zeros(data.cols());
+        for (row, c) in data.outer_iter().zip(self.clusters.iter()).filter(|(_, c)| **c > 0) {
+            within = within + (&row - &centroids[c]).mapv(|x| x.powi(2));
+        }
+        let between_df = T::from(centroids.len() - 1).expect("T::from(usize)");
+        let within_df = T::from(clustered - centroids.len()).expect("T::from(usize)");
+        let mut f = Array1::zeros(data.cols());
+        for ((f, between), within) in f.iter_mut().zip(between.iter()).zip(within.iter()) {
+            *f = if *within > T::zero() {
+                (*between / between_df) / (*within / within_df)
+            } else if *between > T::zero() {
+                T::infinity()
+            } else {
+                T::zero()
+            };
+        }
+        f
+    }
+
+    /// Per-point eps-neighbour count divided by `min_points`, capped at one, so core points score exactly one
+    /// and sparser points fall off smoothly towards zero.
+    pub fn density_scores(&self, data: &Array2<T>) -> Vec<T> {
+        let mut neighbours = Vec::with_capacity(data.rows());
+        let index = index_rows(data);
+        let min_points = T::from(self.min_points).expect("T::from(usize)");
+        data.outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                region_query(&row_slice(row.view()), self.radius(), &index, &mut neighbours);
+                (T::from(neighbours.len()).expect("T::from(usize)") / min_points).min(T::one())
+            })
+            .collect::<Vec<T>>()
+    }
+
+    /// Per point, distance to its own cluster centroid over distance to the nearest other centroid. Values
+    /// above one flag points closer to another cluster's centroid than their own. Noise points are infinite.
+    pub fn assignment_confidence(&self, data: &Array2<T>) -> Vec<T> {
+        let centroids = self.centroids(data);
+        data.outer_iter()
+            .zip(self.clusters.iter())
+            .map(|(row, c)| {
+                if *c == 0 {
+                    return T::infinity();
+                }
+                let own = squared_distance(&row, &centroids[c].view()).sqrt();
+                let other = centroids
+                    .iter()
+                    .filter(|(other, _)| *other != c)
+                    .map(|(_, centroid)| squared_distance(&row, &centroid.view()).sqrt())
+                    .fold(T::infinity(), T::min);
+                own / other
+            })
+            .collect::<Vec<T>>()
+    }
+
+    fn from_clusters(eps: T, min_points: usize, clusters: Vec<usize>, point_types: Vec<PointType>) -> Dbscan<T> {
+        Dbscan {
+            eps,
+            min_points,
+            clusters,
+            point_types,
+            labels: HashMap::new(),
+            neighbourhood: Neighbourhood::Eps,
+        }
+    }
+
+    /// `eps`, for the queries that search a radius around each point, which shared nearest neighbour fits don't
+    /// have.
+    fn radius(&self) -> T {
+        assert!(self.neighbourhood == Neighbourhood::Eps, "shared nearest neighbour models have no eps to search; predict them with `predict`");
+        self.eps
+    }
+
+    fn cluster_sizes(&self) -> BTreeMap<usize, usize> {
+        let mut sizes = BTreeMap::new();
+        for c in self.clusters.iter().filter(|c| **c > 0) {
+            *sizes.entry(*c).or_insert(0) += 1;
+        }
+        sizes
+    }
+
+    fn core_neighbours(&self, data: &Array2<T>) -> Vec<Option<Vec<usize>>> {
+        let mut neighbours = Vec::with_capacity(data.rows());
+        let index = index_rows(data);
+        data.outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                region_query(&row_slice(row.view()), self.radius(), &index, &mut neighbours);
+                if neighbours.len() >= self.min_points {
+                    Some(neighbours.clone())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Option<Vec<usize>>>>()
+    }
+
+    fn centroids(&self, data: &Array2<T>) -> BTreeMap<usize, Array1<T>> {
+        let mut sums: BTreeMap<usize, (Array1<T>, usize)> = BTreeMap::new();
+        for (row, c) in data.outer_iter().zip(self.clusters.iter()).filter(|(_, c)| **c > 0) {
+            let entry = sums.entry(*c).or_insert_with(|| (Array1::zeros(data.cols()), 0));
+            entry.0 = &entry.0 + &row;
+            entry.1 += 1;
+        }
+        sums.into_iter()
+            .map(|(c, (sum, count))| {
+                let count = T::from(count).expect("T::from(usize)");
+                (c, sum.mapv(|x| x / count))
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn validate<T: Float, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>) -> Result<(), Error> {
+    if data.rows() == 0 || data.cols() == 0 {
+        return Err(Error::EmptyData);
+    }
+    match data.indexed_iter().find(|(_, x)| !x.is_finite()) {
+        Some(((row, col), _)) => Err(Error::NonFiniteValue { row, col }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "model-io")]
+fn invalid_model(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// The rest of the next line of a saved model, which must start with `key` and a space.
+#[cfg(feature = "model-io")]
+fn model_field<'a>(lines: &mut impl Iterator<Item = &'a str>, key: &str) -> io::Result<&'a str> {
+    lines
+        .next()
+        .and_then(|line| line.strip_prefix(key))
+        .and_then(|rest| rest.strip_prefix(' '))
+        .ok_or_else(|| invalid_model(&format!("expected {}", key)))
+}
+
+fn squared_distance<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    a.iter().zip(b.iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y).powi(2))
+}
+
+/// `f` applied to every row index on rayon's thread pool, in row order. Each task passes `f` a scratch buffer
+/// for neighbours that it reuses across the rows it takes.
+#[cfg(feature = "rayon")]
+fn parallel_map<X: Send, F: Fn(usize, &mut Vec<usize>) -> X + Sync>(rows: usize, f: F) -> Vec<X> {
+    parallel_map_chunked(rows, 1, f)
+}
+
+/// Like `parallel_map`, splitting the rows into tasks of at least `chunk` rows.
+#[cfg(feature = "rayon")]
+fn parallel_map_chunked<X: Send, F: Fn(usize, &mut Vec<usize>) -> X + Sync>(rows: usize, chunk: usize, f: F) -> Vec<X> {
+    (0..rows).into_par_iter().with_min_len(chunk.max(1)).map_init(Vec::new, |neighbours, row_idx| f(row_idx, neighbours)).collect::<Vec<X>>()
+}
+
+/// `f` applied to every row index in turn, sharing one scratch buffer, for builds without rayon.
+#[cfg(not(feature = "rayon"))]
+fn parallel_map<X, F: Fn(usize, &mut Vec<usize>) -> X>(rows: usize, f: F) -> Vec<X> {
+    let mut neighbours = Vec::new();
+    (0..rows).map(|row_idx| f(row_idx, &mut neighbours)).collect::<Vec<X>>()
+}
+
+fn expand_clusters<R: Rng, F: FnMut(usize, &mut Vec<usize>)>(
+    rows: usize,
+    min_points: usize,
+    borders: bool,
+    expansion: ExpansionOrder,
+    rng: &mut R,
+    region_query: F,
+) -> (Vec<usize>, Vec<PointType>) {
+    expand_clusters_by(rows, |neighbours| neighbours.len() >= min_points, borders, expansion, rng, region_query, |_, _| true)
+}
+
+/// Like `expand_clusters`, with `is_core` deciding from a point's neighbourhood whether it's a core point.
+/// `progress` is called before each region query with the points visited and clusters found so far, and the
+/// fit stops where it is when it returns false.
+fn expand_clusters_by<R: Rng, C: Fn(&[usize]) -> bool, F: FnMut(usize, &mut Vec<usize>), P: FnMut(usize, usize) -> bool>(
+    rows: usize,
+    is_core: C,
+    borders: bool,
+    expansion: ExpansionOrder,
+    rng: &mut R,
+    mut region_query: F,
+    mut progress: P,
+) -> (Vec<usize>, Vec<PointType>) {
+    let mut c = 1;
+    let mut queried = 0;
+    let mut neighbours = Vec::with_capacity(rows);
+    let mut sub_neighbours = Vec::with_capacity(rows);
+    let mut visited = vec![false; rows];
+    let mut clusters = vec![0; rows];
+    let mut point_types = vec![PointType::Noise; rows];
+    let mut mark_core = |core: usize, neighbours: &[usize]| {
+        for neighbour in neighbours.iter() {
+            if point_types[*neighbour] == PointType::Noise {
+                point_types[*neighbour] = PointType::Border;
+            }
+        }
+        point_types[core] = PointType::Core;
+    };
+
+    let indices = sample(rng, rows, rows);
+    'fit: for row_idx in indices.iter() {
+        if !visited[row_idx] {
+            visited[row_idx] = true;
+
+            if !progress(queried, c - 1) {
+                break 'fit;
+            }
+            queried += 1;
+            neighbours.clear();
+            region_query(row_idx, &mut neighbours);
+            neighbours.sort_unstable();
+            neighbours.dedup();
+
+            if is_core(&neighbours) {
+                mark_core(row_idx, &neighbours);
+                clusters[row_idx] = c;
+                match expansion {
+                    ExpansionOrder::DepthFirst => {
+                        while let Some(neighbour_idx) = neighbours.pop() {
+                            if borders {
+                                clusters[neighbour_idx] = c;
+                            }
+                            if !visited[neighbour_idx] {
+                                visited[neighbour_idx] = true;
+                                if !progress(queried, c) {
+                                    break 'fit;
+                                }
+                                queried += 1;
+                                sub_neighbours.clear();
+                                region_query(neighbour_idx, &mut sub_neighbours);
+
+                                if is_core(&sub_neighbours) {
+                                    mark_core(neighbour_idx, &sub_neighbours);
+                                    if !borders {
+                                        clusters[neighbour_idx] = c;
+                                    }
+                                    neighbours.extend_from_slice(&sub_neighbours);
+                                    neighbours.sort_unstable();
+                                    neighbours.dedup();
+                                }
+                            }
+                        }
+                    }
+                    ExpansionOrder::BreadthFirst => {
+                        let mut queue = neighbours.drain(..).collect::<VecDeque<usize>>();
+                        while let Some(neighbour_idx) = queue.pop_front() {
+                            if borders {
+                                clusters[neighbour_idx] = c;
+                            }
+                            if !visited[neighbour_idx] {
+                                visited[neighbour_idx] = true;
+                                if !progress(queried, c) {
+                                    break 'fit;
+                                }
+                                queried += 1;
+                                sub_neighbours.clear();
+                                region_query(neighbour_idx, &mut sub_neighbours);
+
+                                if is_core(&sub_neighbours) {
+                                    mark_core(neighbour_idx, &sub_neighbours);
+                                    if !borders {
+                                        clusters[neighbour_idx] = c;
+                                    }
+                                    queue.extend(sub_neighbours.iter().filter(|n| !visited[**n] || (borders && clusters[**n] != c)));
+                                }
+                            }
+                        }
+                    }
+                }
+                c += 1;
+            }
+        }
+    }
+    (clusters, point_types)
+}
+
+fn brute_force_query<T: Float, D: Fn(&ArrayView1<T>, &ArrayView1<T>) -> T>(data: &Array2<T>, row_idx: usize, eps: T, distance: D, neighbours: &mut Vec<usize>) {
+    let row = data.row(row_idx);
+    for (neighbour_idx, other) in data.outer_iter().enumerate() {
+        if distance(&row, &other) <= eps {
+            neighbours.push(neighbour_idx);
+        }
+    }
+}
+
+fn angular_distance<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    let (dot, norm_a, norm_b) = a.iter().zip(b.iter()).fold((T::zero(), T::zero(), T::zero()), |(dot, norm_a, norm_b), (x, y)| {
+        (dot + *x * *y, norm_a + *x * *x, norm_b + *y * *y)
+    });
+    if norm_a > T::zero() && norm_b > T::zero() {
+        (dot / (norm_a.sqrt() * norm_b.sqrt())).max(-T::one()).min(T::one()).acos()
+    } else {
+        T::nan()
+    }
+}
+
+fn hausdorff_distance<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    let directed = |from: &ArrayView1<T>, to: &ArrayView1<T>| {
+        from.iter()
+            .tuples()
+            .map(|(x, y)| to.iter().tuples().map(|(u, v)| (*x - *u).hypot(*y - *v)).fold(T::infinity(), T::min))
+            .fold(T::zero(), T::max)
+    };
+    directed(a, b).max(directed(b, a))
+}
+
+fn complex_distance<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    a.iter()
+        .zip(b.iter())
+        .tuples()
+        .fold(T::zero(), |acc, ((a_re, b_re), (a_im, b_im))| acc + (*a_re - *b_re).hypot(*a_im - *b_im).powi(2))
+        .sqrt()
+}
+
+/// Each row's `k` nearest other rows in ascending order of index, paired with the distance to the
+/// farthest of them, or infinity when there are fewer than `k` others.
+fn k_nearest<T: Float + One + Zero, U: AsRef<[T]>, S: Data<Elem = T>>(index: &Index<T, U>, data: &ArrayBase<S, Ix2>, k: usize) -> Vec<(Vec<usize>, T)> {
+    data.outer_iter()
+        .enumerate()
+        .map(|(row_idx, row)| {
+            let nearest = index.nearest_matching(&row_slice(row.view()), k, |other| other != row_idx);
+            let farthest = if nearest.len() < k { T::infinity() } else { nearest.iter().fold(T::zero(), |acc, (distance, _)| acc.max(*distance)) };
+            (nearest.into_iter().map(|(_, other)| other).sorted().collect::<Vec<usize>>(), farthest)
+        })
+        .collect::<Vec<(Vec<usize>, T)>>()
+}
+
+/// The training points that would be shared nearest neighbours of a new `row`, given the training rows'
+/// `k_nearest`: those among the row's `k` nearest that it is closer to than to their own `k`th nearest, so
+/// that it would join their lists, and whose lists share at least `min_shared` points with the row's.
+fn shared_neighbours_of<T: Float + One + Zero, U: AsRef<[T]>>(index: &Index<T, U>, nearest: &[(Vec<usize>, T)], row: &ArrayView1<T>, k: usize, min_shared: usize, neighbours: &mut Vec<usize>) {
+    let own = index.nearest(&row_slice(row.view()), k);
+    let own_ids = own.iter().map(|(_, idx)| *idx).sorted().collect::<Vec<usize>>();
+    neighbours.extend(own.iter().filter(|(distance, other)| {
+        let (theirs, farthest) = &nearest[*other];
+        *distance < *farthest && own_ids.iter().filter(|shared| theirs.binary_search(shared).is_ok()).count() >= min_shared
+    }).map(|(_, other)| *other));
+}
+
+fn region_query<T: Float + One + Zero, U: AsRef<[T]>>(row: &[T], eps: T, index: &Index<T, U>, neighbours: &mut Vec<usize>) {
+    index.within(row, eps, neighbours);
+}
+
+/// Each point's distance to its k-th nearest other point, in ascending order. Plotted, this is the k-distance
+/// graph used to choose eps for `min_points = k + 1`: eps sits near the knee where the curve turns upwards.
+pub fn kdist<T: Float + One + Zero>(data: &Array2<T>, k: usize) -> Vec<T> {
+    let mut distances = point_kdist(data, k);
+    distances.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd distance"));
+    distances
+}
+
+/// `kdist` in row order.
+fn point_kdist<T: Float + One + Zero>(data: &Array2<T>, k: usize) -> Vec<T> {
+    let index = index_rows(data);
+    data.outer_iter().map(|row| index.nearest(&row_slice(row.view()), k + 1).last().map_or(T::zero(), |(distance, _)| *distance)).collect::<Vec<T>>()
+}
+
+/// Position of the knee of an ascending curve, by the Kneedle method (Satopaa et al., 2011): with both axes
+/// scaled to [0, 1], the point furthest below the chord from the first value to the last. `None` for fewer
+/// than three values or a flat curve.
+pub fn knee<T: Float>(values: &[T]) -> Option<usize> {
+    let (first, last) = (*values.first()?, *values.last()?);
+    if values.len() < 3 || last <= first {
+        return None;
+    }
+    let steps = T::from(values.len() - 1).expect("T::from(usize)");
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (i, T::from(i).expect("T::from(usize)") / steps - (*value - first) / (last - first)))
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance from chord"))
+        .map(|(i, _)| i)
+}
+
+/// An eps for `min_points`: the k-distance at the knee of `kdist(data, min_points - 1)`, or the largest
+/// k-distance when the curve has no knee.
+pub fn estimate_eps<T: Float + One + Zero>(data: &Array2<T>, min_points: usize) -> T {
+    let distances = kdist(data, min_points.saturating_sub(1).max(1));
+    knee(&distances).or_else(|| distances.len().checked_sub(1)).map_or(T::zero(), |i| distances[i])
+}
+
+/// An eps for `min_points` under which all but `noise_fraction` of the points are core: that quantile of
+/// `kdist(data, min_points - 1)`, since a point is core exactly when its k-distance is within eps.
+pub fn estimate_eps_for_noise<T: Float + One + Zero>(data: &Array2<T>, min_points: usize, noise_fraction: f64) -> T {
+    assert!((0.0..1.0).contains(&noise_fraction), "noise_fraction must be in [0, 1)");
+    let distances = kdist(data, min_points.saturating_sub(1).max(1));
+    let core = ((1.0 - noise_fraction) * distances.len() as f64).ceil() as usize;
+    core.checked_sub(1).map_or(T::zero(), |i| distances[i.min(distances.len() - 1)])
+}
+
+/// Per-cluster statistics from `Dbscan::summaries`: `medoid` is the index of the member with the smallest
+/// total distance to the others, and `diameter` the largest distance between two members.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterSummary<T: Float> {
+    pub size: usize,
+    pub centroid: Array1<T>,
+    pub medoid: usize,
+    pub diameter: T,
+}
+
+/// Internal validity index ranking the fits of `Dbscan::grid_search`, with noise left out. Higher silhouette
+/// and Calinski-Harabasz scores are better, and lower Davies-Bouldin scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InternalScore {
+    Silhouette,
+    DaviesBouldin,
+    CalinskiHarabasz,
+}
+
+/// One configuration tried by `Dbscan::grid_search`, with the size of its fit and its score, if it had at
+/// least two clusters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSearchResult<T: Float> {
+    pub eps: T,
+    pub min_points: usize,
+    pub n_clusters: usize,
+    pub n_noise: usize,
+    pub score: Option<T>,
+}
+
+impl<T: Float + One + Zero> GridSearchResult<T> {
+    fn fit(data: &Array2<T>, eps: T, min_points: usize, borders: bool, score: InternalScore) -> GridSearchResult<T> {
+        let model = Dbscan::new(data, eps, min_points, borders);
+        let n_clusters = model.n_clusters();
+        let score = if n_clusters < 2 {
+            None
+        } else {
+            Some(match score {
+                InternalScore::Silhouette => silhouette_score(data, &model.clusters),
+                InternalScore::DaviesBouldin => davies_bouldin(data, &model.clusters),
+                InternalScore::CalinskiHarabasz => calinski_harabasz(data, &model.clusters),
+            })
+        };
+        GridSearchResult {
+            eps,
+            min_points,
+            n_clusters,
+            n_noise: model.noise_indices().len(),
+            score,
+        }
+    }
+}
+
+fn rank_grid<T: Float>(mut results: Vec<GridSearchResult<T>>, score: InternalScore) -> Vec<GridSearchResult<T>> {
+    results.sort_by(|a, b| match (a.score, b.score) {
+        (Some(x), Some(y)) if score == InternalScore::DaviesBouldin => x.partial_cmp(&y).expect("PartialOrd score"),
+        (Some(x), Some(y)) => y.partial_cmp(&x).expect("PartialOrd score"),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    results
+}
+
+/// Mean radius of the Earth, used by `Dbscan::new_geographic`.
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// How `Dbscan::predict_labeled` would place a new point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClusterPrediction {
+    Core(Vec<usize>),
+    Border(Vec<usize>),
+    Noise,
+}
+
+/// The eps-neighbourhood graph in compressed sparse row form: point `i`'s neighbours, itself included, are
+/// `indices[offsets[i]..offsets[i + 1]]` in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeighbourGraph {
+    pub offsets: Vec<usize>,
+    pub indices: Vec<usize>,
+}
+
+impl NeighbourGraph {
+    pub fn neighbours(&self, point: usize) -> &[usize] {
+        &self.indices[self.offsets[point]..self.offsets[point + 1]]
+    }
+
+    /// Number of points in the graph.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Every point's neighbours within `eps` with their squared distances, found once so that `fit` can refit
+/// DBSCAN for any `min_points` and any eps up to the snapshot's without querying an index again. Tuning loops
+/// then pay for the region queries once; the snapshot holds every pair within `eps`, so keep `eps` to the
+/// largest value actually tried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighbourSnapshot<T: Float> {
+    pub eps: T,
+    graph: NeighbourGraph,
+    squared_distances: Vec<T>,
+}
+
+impl<T: Float + One + Zero> NeighbourSnapshot<T> {
+    pub fn new(data: &Array2<T>, eps: T) -> NeighbourSnapshot<T> {
+        let index = index_rows(data);
+        let mut graph = NeighbourGraph { offsets: vec![0], indices: Vec::new() };
+        let mut squared_distances = Vec::new();
+        let mut neighbours = Vec::new();
+        for row_idx in 0..data.rows() {
+            neighbours.clear();
+            region_query(&row_slice(data.row(row_idx)), eps, &index, &mut neighbours);
+            neighbours.sort_unstable();
+            squared_distances.extend(neighbours.iter().map(|other| squared_distance(&data.row(row_idx), &data.row(*other))));
+            graph.indices.extend_from_slice(&neighbours);
+            graph.offsets.push(graph.indices.len());
+        }
+        NeighbourSnapshot { eps, graph, squared_distances }
+    }
+
+    /// The neighbourhoods at the snapshot's own eps.
+    pub fn graph(&self) -> &NeighbourGraph {
+        &self.graph
+    }
+
+    /// DBSCAN with `eps` no larger than the snapshot's, as `Dbscan::new` would fit it on the same data.
+    pub fn fit(&self, eps: T, min_points: usize, borders: bool) -> Dbscan<T> {
+        self.fit_with_rng(eps, min_points, borders, &mut default_rng())
+    }
+
+    pub fn fit_with_rng<R: Rng>(&self, eps: T, min_points: usize, borders: bool, rng: &mut R) -> Dbscan<T> {
+        assert!(eps <= self.eps, "eps must not exceed the snapshot's eps");
+        let radius = eps.powi(2);
+        let (clusters, point_types) = expand_clusters(self.graph.len(), min_points, borders, ExpansionOrder::DepthFirst, rng, |row_idx, neighbours| {
+            let span = self.graph.offsets[row_idx]..self.graph.offsets[row_idx + 1];
+            neighbours.extend(self.graph.indices[span.clone()].iter().zip(self.squared_distances[span].iter()).filter(|(_, d)| **d <= radius).map(|(other, _)| *other));
+        });
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+}
+
+/// Version of the text format `DbscanModel::save` writes, raised whenever the format changes.
+#[cfg(feature = "model-io")]
+pub const MODEL_FORMAT_VERSION: u32 = 2;
+
+/// Neighbour index over a set of training data, built once and shared by every model fit on that data.
+#[derive(Debug)]
+pub struct PredictionIndex<T: Float + One + Zero> {
+    index: Index<T, Vec<T>>,
+}
+
+impl<T: Float + One + Zero> PredictionIndex<T> {
+    pub fn new(data: &Array2<T>) -> PredictionIndex<T> {
+        PredictionIndex { index: index_owned_rows(data) }
+    }
+}
+
+/// How a Euclidean fit finds each point's neighbours. `Index` queries the neighbour index the crate was built
+/// with. `BallTree` builds a ball tree, which keeps pruning in more dimensions than a KdTree's axis-aligned
+/// splits, and `Auto` picks it over `Index` above `BALL_TREE_COLUMNS` columns. `BruteForce` checks every pair
+/// of points in SIMD lanes, as `Dbscan::new_brute_force` does, and `Gpu` compares every pair on the GPU; on
+/// dense high-dimensional data both beat an index that prunes almost nothing. `Gpu` needs the `gpu` feature
+/// and an adapter, and without either falls back to `Index`. All of these give the same neighbourhoods.
+///
+/// `Lsh` is approximate: it hashes the points into a grid of random projections sized for eps and only
+/// compares points sharing a cell, which on large high-dimensional data is much faster than an exact search.
+/// It never finds a point outside eps but misses some inside it, so clusters can split or shrink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NeighbourSearch {
+    #[default]
+    Index,
+    BruteForce,
+    BallTree,
+    Gpu,
+    Auto,
+    Lsh,
+}
+
+/// Columns above which `NeighbourSearch::Auto` searches with a ball tree rather than the neighbour index.
+pub const BALL_TREE_COLUMNS: usize = 20;
+
+/// Collects DBSCAN parameters, then fits them with `fit`:
+/// `DbscanBuilder::new(0.5, 2).borders(true).seed(7).fit(&data)`.
+#[derive(Debug, Clone, Copy)]
+pub struct DbscanBuilder<T: Float + One + Zero> {
+    eps: T,
+    min_points: usize,
+    borders: bool,
+    metric: Metric<T>,
+    search: NeighbourSearch,
+    seed: Option<u64>,
+}
+
+impl<T: Float + One + Zero> DbscanBuilder<T> {
+    pub fn new(eps: T, min_points: usize) -> DbscanBuilder<T> {
+        DbscanBuilder {
+            eps,
+            min_points,
+            borders: false,
+            metric: Metric::Euclidean,
+            search: NeighbourSearch::Index,
+            seed: None,
+        }
+    }
+
+    pub fn borders(mut self, borders: bool) -> DbscanBuilder<T> {
+        self.borders = borders;
+        self
+    }
+
+    pub fn metric(mut self, metric: Metric<T>) -> DbscanBuilder<T> {
+        self.metric = metric;
+        self
+    }
+
+    /// How a Euclidean fit and its model's predictions find neighbours. Other metrics always compare every pair
+    /// of points.
+    pub fn search(mut self, search: NeighbourSearch) -> DbscanBuilder<T> {
+        self.search = search;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> DbscanBuilder<T> {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn fit(&self, data: &Array2<T>) -> DbscanModel<T> {
+        let dbscan = match self.seed {
+            Some(seed) => self.fit_with_rng(data, &mut StdRng::seed_from_u64(seed)),
+            None => self.fit_with_rng(data, &mut default_rng()),
+        };
+        DbscanModel::from_parts(dbscan, self.metric, self.search, data.to_owned())
+    }
+
+    fn fit_with_rng<R: Rng>(&self, data: &Array2<T>, rng: &mut R) -> Dbscan<T> {
+        match self.metric {
+            Metric::Euclidean => Dbscan::new_with_search_and_rng(data, self.eps, self.min_points, self.borders, self.search, rng),
+            metric => Dbscan::new_with_metric_and_rng(data, self.eps, self.min_points, self.borders, metric, rng),
+        }
+    }
+}
+
+/// A fitted `Dbscan` that keeps its training data, so `predict` doesn't need it passed back in, and which
+/// training points have a core neighbourhood. Euclidean models not searching with `NeighbourSearch::BruteForce`
+/// also keep a neighbour index built at fit time, so each prediction only costs its queries; other models
+/// compare against every training point. With the `serde` feature a model serializes its fit, metric, search
+/// and training data, and deserializing rebuilds the index and core flags from them.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(try_from = "SavedModel<T>", bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")))]
+pub struct DbscanModel<T: Float + One + Zero> {
+    pub dbscan: Dbscan<T>,
+    metric: Metric<T>,
+    search: NeighbourSearch,
+    data: Array2<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index: Option<PredictionIndex<T>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    core: Vec<bool>,
+}
+
+/// The serialized fields of a `DbscanModel`, which deserializing checks and rebuilds the model from.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound = "T: Deserialize<'de>")]
+struct SavedModel<T: Float + One + Zero> {
+    dbscan: Dbscan<T>,
+    metric: Metric<T>,
+    search: NeighbourSearch,
+    data: Array2<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Float + One + Zero> TryFrom<SavedModel<T>> for DbscanModel<T> {
+    type Error = String;
+
+    fn try_from(saved: SavedModel<T>) -> Result<DbscanModel<T>, String> {
+        let rows = saved.data.rows();
+        if saved.dbscan.clusters.len() != rows || saved.dbscan.point_types.len() != rows {
+            return Err("cluster count doesn't match data".to_string());
+        }
+        Ok(DbscanModel::from_parts(saved.dbscan, saved.metric, saved.search, saved.data))
+    }
+}
+
+impl<T: Float + One + Zero> DbscanModel<T> {
+    /// Keeps `data` in row-major order, copying it if need be, so that queries can borrow its rows.
+    fn from_parts(dbscan: Dbscan<T>, metric: Metric<T>, search: NeighbourSearch, data: Array2<T>) -> DbscanModel<T> {
+        let data = row_major_copy(&data).unwrap_or(data);
+        let index = if metric == Metric::Euclidean && search != NeighbourSearch::BruteForce { Some(PredictionIndex::new(&data)) } else { None };
+        let mut model = DbscanModel { dbscan, metric, search, data, index, core: Vec::new() };
+        let mut neighbours = Vec::with_capacity(model.data.rows());
+        model.core = model
+            .data
+            .outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                model.within(&row, model.dbscan.eps, &mut neighbours);
+                neighbours.len() >= model.dbscan.min_points
+            })
+            .collect::<Vec<bool>>();
+        model
+    }
+
+    /// Training points within `radius` of `row` under the model's metric.
+    fn within(&self, row: &ArrayView1<T>, radius: T, neighbours: &mut Vec<usize>) {
+        match self.index {
+            Some(ref index) => index.index.within(&row_slice(row.view()), radius, neighbours),
+            None => neighbours.extend(self.data.outer_iter().enumerate().filter(|(_, other)| self.metric.distance(row, other) <= radius).map(|(idx, _)| idx)),
+        }
+    }
+
+    fn distance(&self, row: &ArrayView1<T>, idx: usize) -> T {
+        self.metric.distance(row, &self.data.row(idx))
+    }
+
+    pub fn clusters(&self) -> &[usize] {
+        &self.dbscan.clusters
+    }
+
+    /// The training data the model was fit on.
+    pub fn data(&self) -> &Array2<T> {
+        &self.data
+    }
+
+    /// How the model was fit and how its predictions find neighbours.
+    pub fn search(&self) -> NeighbourSearch {
+        self.search
+    }
+
+    pub fn predict(&self, new_data: &Array2<T>) -> Vec<Vec<usize>> {
+        match self.index {
+            Some(ref index) => self.dbscan.predict_with_index(index, new_data),
+            None => self.dbscan.predict_with_metric(&self.data, new_data, self.metric),
+        }
+    }
+
+    /// `predict` on rayon's thread pool; see `Dbscan::predict_parallel`.
+    #[cfg(feature = "rayon")]
+    pub fn predict_parallel(&self, new_data: &Array2<T>) -> Vec<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        self.predict_chunked(new_data, 1)
+    }
+
+    /// `predict` on rayon's thread pool at least `chunk_rows` at a time; see `Dbscan::predict_chunked`.
+    #[cfg(feature = "rayon")]
+    pub fn predict_chunked(&self, new_data: &Array2<T>, chunk_rows: usize) -> Vec<Vec<usize>>
+    where
+        T: Send + Sync,
+    {
+        self.dbscan.predict_parallel_by(new_data, chunk_rows, |row, neighbours| self.within(row, self.dbscan.eps, neighbours))
+    }
+
+    /// `Dbscan::predict_labeled` against the training data, with neighbourhoods under the model's metric.
+    pub fn predict_labeled(&self, new_data: &Array2<T>) -> Vec<ClusterPrediction> {
+        self.dbscan.predict_labeled_by(&self.core, new_data, |row, radius, neighbours| self.within(row, radius, neighbours))
+    }
+
+    /// `Dbscan::predict_with_distances` against the training data, with distances under the model's metric.
+    pub fn predict_with_distances(&self, new_data: &Array2<T>) -> Vec<Vec<(usize, T)>> {
+        self.dbscan.predict_with_distances_by(new_data, |row, radius, neighbours| self.within(row, radius, neighbours), |row, idx| self.distance(row, idx))
+    }
+
+    /// `Dbscan::predict_single` against the training data, with the nearest core point under the model's metric.
+    pub fn predict_single(&self, new_data: &Array2<T>) -> Vec<Option<usize>> {
+        nearest_clusters(self.predict_with_distances(new_data))
+    }
+
+    /// `Dbscan::predict_density` against the training data, with the kernel over distances under the model's metric.
+    pub fn predict_density(&self, new_data: &Array2<T>) -> Vec<(Option<usize>, T)> {
+        self.dbscan.predict_density_by(new_data, |row, radius, neighbours| self.within(row, radius, neighbours), |row, idx| self.distance(row, idx))
+    }
+
+    /// `local_outlier_factor` of the training points, reusing the model's neighbour index when it has one.
+    pub fn local_outlier_factor(&self, k: usize) -> Vec<T> {
+        match self.index {
+            Some(ref index) => lof_from_index(&index.index, &self.data, k),
+            None => local_outlier_factor(&self.data, k),
+        }
+    }
+
+    /// Writes the model, training data included, as line-based text that `load` reads back. This is for builds
+    /// without the `serde` feature, not a stable interchange format: the first line names the format version,
+    /// `MODEL_FORMAT_VERSION`. Then come one `key value` line each for eps, min_points, the metric, the
+    /// neighbour search, the cluster of every point and their point types, a `name`
+    /// line per named cluster, and the data's shape followed by one line per row. Values go through `f64`, so
+    /// `f32` and `f64` models round-trip exactly.
+    #[cfg(feature = "model-io")]
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let float = |x: T| format!("{:?}", x.to_f64().expect("T::to_f64"));
+        writeln!(writer, "clust-rs dbscan {}", MODEL_FORMAT_VERSION)?;
+        writeln!(writer, "eps {}", float(self.dbscan.eps))?;
+        writeln!(writer, "min_points {}", self.dbscan.min_points)?;
+        match self.metric {
+            Metric::Euclidean => writeln!(writer, "metric euclidean")?,
+            Metric::Manhattan => writeln!(writer, "metric manhattan")?,
+            Metric::Chebyshev => writeln!(writer, "metric chebyshev")?,
+            Metric::Cosine => writeln!(writer, "metric cosine")?,
+            Metric::Minkowski(p) => writeln!(writer, "metric minkowski {}", float(p))?,
+            Metric::Haversine => writeln!(writer, "metric haversine")?,
+        }
+        let search = match self.search {
+            NeighbourSearch::Index => "index",
+            NeighbourSearch::BruteForce => "brute_force",
+            NeighbourSearch::BallTree => "ball_tree",
+            NeighbourSearch::Gpu => "gpu",
+            NeighbourSearch::Auto => "auto",
+            NeighbourSearch::Lsh => "lsh",
+        };
+        writeln!(writer, "search {}", search)?;
+        writeln!(writer, "clusters {}", self.dbscan.clusters.iter().join(" "))?;
+        let point_type = |t: &PointType| match t {
+            PointType::Core => "c",
+            PointType::Border => "b",
+            PointType::Noise => "n",
+        };
+        writeln!(writer, "point_types {}", self.dbscan.point_types.iter().map(point_type).join(" "))?;
+        for (id, name) in self.dbscan.labels.iter().sorted() {
+            writeln!(writer, "name {} {}", id, name.replace('\\', "\\\\").replace('\n', "\\n"))?;
+        }
+        writeln!(writer, "data {} {}", self.data.rows(), self.data.cols())?;
+        for row in self.data.outer_iter() {
+            writeln!(writer, "{}", row.iter().map(|x| float(*x)).join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a model written by `save` and rebuilds its neighbour index for the search it was fit with. Version 1
+    /// models, saved before the search was recorded, load with `NeighbourSearch::Index`; other format versions
+    /// are an `InvalidData` error.
+    #[cfg(feature = "model-io")]
+    pub fn load<R: BufRead>(reader: &mut R) -> io::Result<DbscanModel<T>> {
+        let float = |word: &str| word.parse::<f64>().ok().and_then(T::from).ok_or_else(|| invalid_model("bad number"));
+        let count = |word: &str| word.parse::<usize>().map_err(|_| invalid_model("bad count"));
+        let lines = reader.lines().collect::<io::Result<Vec<String>>>()?;
+        let mut lines = lines.iter().map(String::as_str).peekable();
+
+        let version = model_field(&mut lines, "clust-rs")?.strip_prefix("dbscan ").ok_or_else(|| invalid_model("not a clust-rs dbscan model"))?;
+        if version != "1" && version != MODEL_FORMAT_VERSION.to_string() {
+            return Err(invalid_model(&format!("unsupported model format version {}", version)));
+        }
+        let eps = float(model_field(&mut lines, "eps")?)?;
+        let min_points = count(model_field(&mut lines, "min_points")?)?;
+        let metric = match model_field(&mut lines, "metric")?.split(' ').collect::<Vec<&str>>().as_slice() {
+            ["euclidean"] => Metric::Euclidean,
+            ["manhattan"] => Metric::Manhattan,
+            ["chebyshev"] => Metric::Chebyshev,
+            ["cosine"] => Metric::Cosine,
+            ["minkowski", p] => Metric::Minkowski(float(p)?),
+            ["haversine"] => Metric::Haversine,
+            _ => return Err(invalid_model("unknown metric")),
+        };
+        let search = match version {
+            "1" => NeighbourSearch::Index,
+            _ => match model_field(&mut lines, "search")? {
+                "index" => NeighbourSearch::Index,
+                "brute_force" => NeighbourSearch::BruteForce,
+                "ball_tree" => NeighbourSearch::BallTree,
+                "gpu" => NeighbourSearch::Gpu,
+                "auto" => NeighbourSearch::Auto,
+                "lsh" => NeighbourSearch::Lsh,
+                _ => return Err(invalid_model("unknown neighbour search")),
+            },
+        };
+        let clusters = model_field(&mut lines, "clusters")?.split_whitespace().map(count).collect::<io::Result<Vec<usize>>>()?;
+        let point_types = model_field(&mut lines, "point_types")?
+            .split_whitespace()
+            .map(|word| match word {
+                "c" => Ok(PointType::Core),
+                "b" => Ok(PointType::Border),
+                "n" => Ok(PointType::Noise),
+                _ => Err(invalid_model("bad point type")),
+            })
+            .collect::<io::Result<Vec<PointType>>>()?;
+        let mut labels = HashMap::new();
+        while lines.peek().is_some_and(|line| line.starts_with("name ")) {
+            let (id, name) = model_field(&mut lines, "name")?.split_once(' ').ok_or_else(|| invalid_model("bad cluster name"))?;
+            let mut unescaped = String::with_capacity(name.len());
+            let mut chars = name.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' if chars.next() == Some('n') => unescaped.push('\n'),
+                    '\\' => unescaped.push('\\'),
+                    c => unescaped.push(c),
+                }
+            }
+            labels.insert(count(id)?, unescaped);
+        }
+        let (rows, cols) = model_field(&mut lines, "data")?.split_once(' ').ok_or_else(|| invalid_model("bad data shape"))?;
+        let (rows, cols) = (count(rows)?, count(cols)?);
+        let mut values = Vec::with_capacity(rows * cols);
+        for _ in 0..rows {
+            let line = lines.next().ok_or_else(|| invalid_model("missing data row"))?;
+            values.extend(line.split_whitespace().map(float).collect::<io::Result<Vec<T>>>()?);
+        }
+        let data = Array2::from_shape_vec((rows, cols), values).map_err(|_| invalid_model("bad data shape"))?;
+        if clusters.len() != rows || point_types.len() != rows {
+            return Err(invalid_model("cluster count doesn't match data"));
+        }
+        Ok(DbscanModel::from_parts(Dbscan { eps, min_points, clusters, point_types, labels, neighbourhood: Neighbourhood::Eps }, metric, search, data))
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for Dbscan<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+impl<T: Float + One + Zero> Fit<T> for DbscanBuilder<T> {
+    type Model = DbscanModel<T>;
+
+    fn fit(&self, data: &Array2<T>) -> DbscanModel<T> {
+        DbscanBuilder::fit(self, data)
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for DbscanModel<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.dbscan.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.dbscan.clusters)
+    }
+}
+
+/// Labels each new point with the cluster of its nearest core point within eps under the model's metric, or 0 for noise.
+impl<T: Float + One + Zero> Predict<T> for DbscanModel<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        self.predict_single(data).into_iter().map(|cluster| cluster.unwrap_or(0)).collect::<Vec<usize>>()
+    }
+}
+
+fn nearest_clusters<T>(distances: Vec<Vec<(usize, T)>>) -> Vec<Option<usize>> {
+    distances.into_iter().map(|nearest| nearest.first().map(|(cluster, _)| *cluster)).collect::<Vec<Option<usize>>>()
+}
+
+/// How `Dbscan::new_plus_plus` picks the points it tests for being core: `Uniform` at random, or `KCenter` by
+/// greedily taking the point farthest from those already picked, which covers sparse regions a uniform
+/// sample of the same size can miss at the cost of O(nm) distance computations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreSampling {
+    Uniform,
+    KCenter,
+}
+
+/// Order in which a cluster grows from its seed: `DepthFirst` follows the most recently found neighbours,
+/// `BreadthFirst` grows outwards ring by ring.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpansionOrder {
+    #[default]
+    DepthFirst,
+    BreadthFirst,
+}
+
+/// Limit on the work `new_with_budget` may do: wall-clock time, which needs a clock and so isn't available on
+/// wasm32-unknown-unknown, or a number of region queries, which gives the same partial labels on every run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    Time(Duration),
+    Queries(usize),
+}
+
+/// Distance used by `new_with_metric`. `Cosine` is one minus the cosine similarity, `Minkowski(p)` is the
+/// p-norm of the difference, and `Haversine` is the great-circle distance on the unit sphere between rows of
+/// `[latitude, longitude]` in radians (multiply eps by the sphere's radius to work in other units).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Metric<T: Float> {
+    Euclidean,
+    Manhattan,
+    Chebyshev,
+    Cosine,
+    Minkowski(T),
+    Haversine,
+}
+
+impl<T: Float> Metric<T> {
+    pub fn distance(&self, a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+        let differences = a.iter().zip(b.iter()).map(|(x, y)| (*x - *y).abs());
+        match *self {
+            Metric::Euclidean => squared_distance(a, b).sqrt(),
+            Metric::Manhattan => differences.fold(T::zero(), |acc, d| acc + d),
+            Metric::Chebyshev => differences.fold(T::zero(), T::max),
+            Metric::Cosine => {
+                let (dot, norm_a, norm_b) = a.iter().zip(b.iter()).fold((T::zero(), T::zero(), T::zero()), |(dot, norm_a, norm_b), (x, y)| {
+                    (dot + *x * *y, norm_a + *x * *x, norm_b + *y * *y)
+                });
+                T::one() - dot / (norm_a.sqrt() * norm_b.sqrt())
+            }
+            Metric::Minkowski(p) => differences.fold(T::zero(), |acc, d| acc + d.powf(p)).powf(p.recip()),
+            Metric::Haversine => {
+                assert!(a.len() == 2 && b.len() == 2, "haversine needs [latitude, longitude] rows");
+                let two = T::one() + T::one();
+                let h = ((b[0] - a[0]) / two).sin().powi(2) + a[0].cos() * b[0].cos() * ((b[1] - a[1]) / two).sin().powi(2);
+                two * h.sqrt().min(T::one()).asin()
+            }
+        }
+    }
+}
+
+/// The DBSCAN role of a training point: core points have at least `min_points` neighbours within eps,
+/// border points are within eps of a core point without being one, and everything else is noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PointType {
+    Core,
+    Border,
+    Noise,
+}
+
+/// Which points a DBSCAN fit counted as neighbours of each other. Almost every fit uses those within `eps`;
+/// `Dbscan::new_shared_neighbours` uses pairs that are each among the other's `k` nearest and share at least
+/// `min_shared` of their `k` nearest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Neighbourhood {
+    #[default]
+    Eps,
+    SharedNeighbours { k: usize, min_shared: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterLabel {
+    Noise,
+    Cluster(NonZeroUsize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::labels::same_partition;
+    use crate::metrics::adjusted_rand_index;
+    use ndarray::s;
+    use rand::rngs::mock::StepRng;
+    use rand::rngs::SmallRng;
+
+    /// A fit with the given labels, taking every clustered point as core.
+    fn labelled(eps: f64, min_points: usize, clusters: Vec<usize>) -> Dbscan<f64> {
+        let point_types = clusters.iter().map(|c| if *c > 0 { PointType::Core } else { PointType::Noise }).collect::<Vec<PointType>>();
+        Dbscan::from_clusters(eps, min_points, clusters, point_types)
+    }
+
+    #[test]
+    fn test_gpu_search() {
+        // Runs on the GPU with the gpu feature and an adapter, and through the fallback otherwise; either way
+        // the labels are those of the index. The grid puts many pairs exactly eps apart.
+        let mut rng = StdRng::seed_from_u64(4);
+        let grid = Array2::from_shape_fn((200, 2), |(row, col)| if col == 0 { (row % 20) as f64 * 0.5 } else { (row / 20) as f64 * 0.5 });
+        let noisy = Array2::from_shape_fn((300, 24), |(row, _)| (row % 3) as f64 + rng.gen_range(0.0, 0.3));
+        for (data, eps) in [(grid, 0.5), (noisy, 0.8)].iter() {
+            let indexed = Dbscan::new_with_search_and_rng(data, *eps, 4, true, NeighbourSearch::Index, &mut StdRng::seed_from_u64(5));
+            let gpu = Dbscan::new_with_search_and_rng(data, *eps, 4, true, NeighbourSearch::Gpu, &mut StdRng::seed_from_u64(5));
+            assert!(gpu.clusters == indexed.clusters && gpu.point_types == indexed.point_types);
+        }
+        let model = DbscanBuilder::new(0.5, 4).search(NeighbourSearch::Gpu).seed(5).fit(&arr2(&[[0.0, 0.0], [0.3, 0.0], [0.0, 0.3], [0.3, 0.3], [5.0, 5.0]]));
+        assert!(model.clusters() == [1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_clusters() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let clustering = dbg!(model.clusters);
+        assert!(clustering.iter().take(4).all_equal());
+        assert!(clustering.iter().skip(4).take(2).all_equal());
+        assert!(clustering.iter().skip(6).all_equal());
+        assert!(clustering[0] != clustering[4]);
+        assert!(clustering[4] != clustering[6]);
+        assert!(clustering[6] != clustering[0]);
+    }
+
+    #[test]
+    fn test_border_points() {
+        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+
+        let with = Dbscan::new(&data, 0.5, 3, true);
+        let without = Dbscan::new(&data, 0.5, 3, false);
+        let with_borders_clustering = dbg!(with.clusters);
+        let without_borders_clustering = dbg!(without.clusters);
+        assert!(with_borders_clustering.iter().all(|x| *x == 1));
+        assert!(without_borders_clustering.iter().take(1).all(|x| *x == 0));
+        assert!(without_borders_clustering.iter().skip(1).take(3).all(|x| *x == 1));
+        assert!(without_borders_clustering.iter().skip(4).all(|x| *x == 0));
+    }
+
+    #[test]
+    fn test_prediction() {
+        let data = Array2::from_shape_vec((6, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+
+        let new_data = Array2::from_shape_vec((2, 2), vec![1.0, 2.0, 4.0, 4.0]).unwrap();
+        let classes = dbg!(model.predict(&data, &new_data));
+
+        let c0 = classes.first().unwrap();
+        assert!(c0.iter().any(|c| *c == model.clusters[0]));
+        assert!(classes[1] == vec![0]);
+    }
+
+    #[test]
+    fn test_variance_explained() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, 10.0, 3.0, 10.2, 3.1, -8.0, -9.0, -8.1, -9.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let explained = model.variance_explained(&data);
+        assert!(explained > 0.99 && explained <= 1.0);
+    }
+
+    #[test]
+    fn test_complex() {
+        let data = Array2::from_shape_vec(
+            (4, 4),
+            vec![1.0, 0.0, 0.0, 1.0, 0.98, 0.05, 0.02, 1.01, 1.02, -0.03, -0.01, 0.97, -1.0, 0.0, 0.0, -1.0],
+        )
+        .unwrap();
+        let model = Dbscan::new_complex(&data, 0.2, 2, false);
+        assert!(model.clusters.iter().take(3).all(|c| *c == 1));
+        assert!(model.clusters[3] == 0);
+    }
+
+    #[test]
+    fn test_density_scores() {
+        let data = Array2::from_shape_vec((11, 1), vec![1.0, 1.01, 1.02, 1.03, 1.04, 1.05, 1.06, 1.07, 1.08, 1.09, 5.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 10, false);
+        let scores = model.density_scores(&data);
+        assert!(scores.iter().take(10).all(|s| *s == 1.0));
+        assert!(scores[10] <= 0.1);
+    }
+
+    #[test]
+    fn test_transposed() {
+        let data = Array2::from_shape_vec((3, 3), vec![1.0, 1.0, 9.0, 2.0, 2.0, -4.0, 3.0, 3.0, 7.0]).unwrap();
+        let model = Dbscan::new_transposed(&data, 0.5, 2, false);
+        assert!(model.clusters.len() == 3);
+        assert!(model.clusters[0] == model.clusters[1] && model.clusters[0] > 0);
+        assert!(model.clusters[2] == 0);
+    }
+
+    #[test]
+    fn test_labels_typed() {
+        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 3, false);
+        let labels = model.labels_typed();
+        assert!(labels[0] == ClusterLabel::Noise && labels[4] == ClusterLabel::Noise);
+        assert!(labels.iter().skip(1).take(3).all(|l| match l {
+            ClusterLabel::Cluster(id) => id.get() == 1,
+            ClusterLabel::Noise => false,
+        }));
+    }
+
+    #[test]
+    fn test_repair_clusters() {
+        let data = Array2::from_shape_vec((7, 1), vec![0.0, 0.1, 0.2, 0.3, 0.4, 5.0, 5.1]).unwrap();
+        let mut model = labelled(0.15, 2, vec![1, 1, 3, 3, 3, 2, 2]);
+        model.repair_clusters(&data);
+        assert!(model.clusters == vec![1, 1, 1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_clusters_overlap() {
+        let mut points = (0..24)
+            .map(|i| f64::from(i) * std::f64::consts::PI / 12.0)
+            .flat_map(|angle| vec![3.0 * angle.cos(), 3.0 * angle.sin()])
+            .collect::<Vec<f64>>();
+        points.extend_from_slice(&[0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 10.0, 10.0, 10.1, 10.0, 10.0, 10.1]);
+        let data = Array2::from_shape_vec((30, 2), points).unwrap();
+        let model = Dbscan::new(&data, 1.0, 2, false);
+        let (ring, blob, far) = (model.clusters[0], model.clusters[24], model.clusters[27]);
+        assert!(model.clusters_overlap(&data, ring, blob).unwrap());
+        assert!(!model.clusters_overlap(&data, ring, far).unwrap());
+        assert!(!model.clusters_overlap(&data, blob, far).unwrap());
+        let flat = Array2::from_shape_vec((2, 3), vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0]).unwrap();
+        assert!(model.clusters_overlap(&flat, 1, 2) == Err(Error::DimensionMismatch { expected: 2, found: 3 }));
+    }
+
+    #[test]
+    fn test_reproducible_with_rng() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let first = Dbscan::new_with_rng(&data, 0.5, 2, false, &mut StepRng::new(0, 1));
+        let second = Dbscan::new_with_rng(&data, 0.5, 2, false, &mut StepRng::new(0, 1));
+        assert!(first.clusters == second.clusters);
+        assert!(Dbscan::with_seed(&data, 0.5, 2, false, 3).clusters == Dbscan::with_seed(&data, 0.5, 2, false, 3).clusters);
+    }
+
+    #[test]
+    fn test_bounding_spheres() {
+        let points = (0..16)
+            .map(|i| f64::from(i) * std::f64::consts::PI / 8.0)
+            .flat_map(|angle| vec![1.0 + 2.0 * angle.cos(), 1.0 + 2.0 * angle.sin()])
+            .collect::<Vec<f64>>();
+        let data = Array2::from_shape_vec((16, 2), points).unwrap();
+        let model = Dbscan::new(&data, 1.0, 2, false);
+        let spheres = model.bounding_spheres(&data);
+        assert!(spheres.len() == 1);
+        let (center, radius) = &spheres[&1];
+        assert!((radius - 2.0).abs() < 0.1);
+        assert!((center[0] - 1.0).abs() < 0.1 && (center[1] - 1.0).abs() < 0.1);
+        let max_distance = data.outer_iter().map(|row| squared_distance(&row, &center.view()).sqrt()).fold(0.0, f64::max);
+        assert!((radius - max_distance).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_angular() {
+        let data = Array2::from_shape_vec((4, 2), vec![1.0, 0.0, 10.0, 1.0, 1.0, 0.4, 0.0, 3.0]).unwrap();
+        let model = Dbscan::new_angular(&data, 0.4, 2, false);
+        assert!(model.clusters.iter().take(3).all(|c| *c == 1));
+        assert!(model.clusters[3] == 0);
+        let tight = Dbscan::new_angular(&data, 0.15, 2, false);
+        assert!(tight.clusters[0] == tight.clusters[1] && tight.clusters[0] > 0);
+        assert!(tight.clusters[2] == 0 && tight.clusters[3] == 0);
+    }
+
+    #[test]
+    fn test_one_hot() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let encoded = model.one_hot();
+        assert!(encoded.dim() == (8, 2));
+        for (row, c) in encoded.outer_iter().zip(model.clusters.iter()) {
+            if *c > 0 {
+                assert!(row.iter().map(|x| u32::from(*x)).sum::<u32>() == 1 && row[*c - 1] == 1);
+            } else {
+                assert!(row.iter().all(|x| *x == 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_expansion_order() {
+        let data = Array2::from_shape_vec((10, 1), vec![0.0, 0.3, 0.6, 0.9, 1.2, 3.0, 3.3, 3.6, 5.0, 5.45]).unwrap();
+        for borders in [false, true].iter() {
+            let depth = Dbscan::new_with_expansion(&data, 0.35, 3, *borders, ExpansionOrder::DepthFirst, &mut StepRng::new(3, 7));
+            let breadth = Dbscan::new_with_expansion(&data, 0.35, 3, *borders, ExpansionOrder::BreadthFirst, &mut StepRng::new(3, 7));
+            assert!(depth.clusters == breadth.clusters);
+        }
+    }
+
+    #[test]
+    fn test_fit_best_silhouette() {
+        let data = Array2::from_shape_vec(
+            (9, 2),
+            vec![0.0, 0.0, 0.2, 0.0, 0.0, 0.2, 4.0, 0.0, 4.2, 0.0, 4.0, 0.2, 0.0, 9.0, 0.2, 9.0, 0.0, 9.2],
+        )
+        .unwrap();
+        let model = Dbscan::fit_best_silhouette(&data, 2, false, &[0.05, 0.5, 4.5, 20.0]);
+        assert!(model.eps == 0.5);
+        assert!(model.clusters.iter().unique().count() == 3 && model.clusters.iter().all(|c| *c > 0));
+    }
+
+    #[test]
+    fn test_k_nearest_in_cluster() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let cluster = model.clusters[0];
+        let nearest = model.k_nearest_in_cluster(&data, &arr1(&[-1.0, 2.5]), cluster, 3);
+        assert!(nearest.len() == 3);
+        assert!(nearest.iter().all(|(idx, _)| model.clusters[*idx] == cluster));
+        assert!(nearest.windows(2).all(|w| w[0].1 <= w[1].1));
+        assert!(model.k_nearest_in_cluster(&data, &arr1(&[-1.0, 2.5]), cluster, 10).len() == 4);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 3, true);
+        let dot = model.to_dot(&data);
+        assert!(dot.starts_with("graph dbscan {\n") && dot.ends_with("}\n"));
+        assert!(dot.matches('{').count() == 1 && dot.matches('}').count() == 1);
+        assert!(dot.lines().filter(|line| line.contains("[label=")).count() == 5);
+        assert!(dot.lines().filter(|line| line.contains(" -- ")).count() == 3);
+        assert!(dot.lines().skip(1).all(|line| line == "}" || line.ends_with(';')));
+    }
+
+    #[test]
+    fn test_sorted_1d() {
+        let timestamps = vec![0.0, 1.0, 1.5, 2.0, 3.8, 10.0, 10.5, 11.0, 11.2, 20.0, 30.0, 30.4];
+        let data = Array2::from_shape_vec((timestamps.len(), 1), timestamps.clone()).unwrap();
+        for borders in [false, true].iter() {
+            let sorted = Dbscan::new_sorted_1d(&timestamps, 1.0, 3, *borders);
+            let general = Dbscan::new(&data, 1.0, 3, *borders);
+            assert!(same_partition(&sorted.clusters, &general.clusters), "{:?} vs {:?}", sorted.clusters, general.clusters);
+        }
+        assert!(Dbscan::new_sorted_1d(&timestamps, 1.0, 3, true).clusters == vec![1, 1, 1, 1, 0, 2, 2, 2, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_assignment_confidence() {
+        let data = Array2::from_shape_vec((15, 1), vec![0.0, 0.4, 0.8, 1.2, 1.6, 2.0, 2.4, 2.8, 3.2, 3.6, 4.0, 5.0, 5.1, 5.2, 9.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let confidence = model.assignment_confidence(&data);
+        assert!(confidence[10] > 1.0);
+        assert!(confidence[5] < 0.1 && confidence[12] < 0.1);
+        assert!(confidence[14].is_infinite());
+    }
+
+    #[test]
+    fn test_sparse_cosine() {
+        let mut rows = SparseRows::new(8);
+        rows.push_row(&[(1, 2.0), (0, 1.0)]);
+        rows.push_row(&[(0, 1.0), (1, 1.8), (2, 0.1)]);
+        rows.push_row(&[(5, 3.0), (7, 1.0)]);
+        let model = Dbscan::from_sparse_cosine(rows, 0.1, 2, false);
+        assert!(model.clusters[0] == model.clusters[1] && model.clusters[0] > 0);
+        assert!(model.clusters[2] == 0);
+    }
+
+    #[test]
+    fn test_sparse_euclidean() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut rows = SparseRows::new(200);
+        let mut dense = Array2::zeros((60, 200));
+        for idx in 0..60 {
+            // Three groups on their own columns, plus empty rows that sit together at the origin.
+            let entries = match idx % 4 {
+                3 => Vec::new(),
+                group => (0..5).map(|j| (group * 50 + j * 3, 1.0 + rng.gen_range(0.0, 0.2))).collect::<Vec<(usize, f64)>>(),
+            };
+            for (col, value) in entries.iter() {
+                dense[[idx, *col]] = *value;
+            }
+            rows.push_row(&entries);
+        }
+        let sparse = Dbscan::from_sparse_euclidean(&rows, 0.3, 3, true);
+        assert!(same_partition(&sparse.clusters, &Dbscan::new(&dense, 0.3, 3, true).clusters));
+        assert!(sparse.clusters.iter().unique().count() == 4);
+        let cosine = Dbscan::from_sparse_cosine(rows, 0.01, 3, true);
+        assert!((0..60).all(|idx| (cosine.clusters[idx] == 0) == (idx % 4 == 3)));
+    }
+
+    #[test]
+    fn test_size_balance() {
+        let clustering = |sizes: &[usize]| {
+            labelled(0.5, 2, sizes.iter().enumerate().flat_map(|(c, size)| vec![c + 1; *size]).chain(vec![0; 7]).collect())
+        };
+        assert!((clustering(&[10, 10, 10]).size_balance() - 1.0).abs() < 1e-12);
+        assert!(clustering(&[90, 5, 5]).size_balance() < 0.4);
+    }
+
+    #[test]
+    fn test_new_with_counts() {
+        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+        let (model, counts) = Dbscan::new_with_counts(&data, 0.5, 3, false);
+        assert!(counts == vec![2, 4, 3, 4, 2]);
+        for (c, count) in model.clusters.iter().zip(counts.iter()) {
+            assert!((*c > 0) == (*count >= 3));
+        }
+    }
+
+    #[test]
+    fn test_named_labels() {
+        let data = Array2::from_shape_vec((5, 1), vec![1.55, 2.0, 2.1, 2.2, 2.65]).unwrap();
+        let mut model = Dbscan::new(&data, 0.5, 3, false);
+        model.set_cluster_name(1, "dense");
+        assert!(model.named_labels() == vec![None, Some("dense"), Some("dense"), Some("dense"), None]);
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let data = Array2::from_shape_vec(
+            (3, 8),
+            vec![
+                0.0, 0.0, 1.0, 0.0, 2.0, 0.0, 3.0, 0.0,
+                0.0, 0.1, 1.0, 0.1, 2.0, 0.2, 2.0, 0.2,
+                0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0,
+            ],
+        )
+        .unwrap();
+        let model = Dbscan::new_trajectory(&data, 4, 1.1, 2, false);
+        assert!(model.clusters[0] == model.clusters[1] && model.clusters[0] > 0);
+        assert!(model.clusters[2] == 0);
+    }
+
+    #[cfg(not(feature = "kdtree"))]
+    #[test]
+    fn test_brute_force_fixture() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 1, 2, 2, 0, 0]));
+    }
+
+    #[test]
+    fn test_cluster_connectivity() {
+        let data = Array2::from_shape_vec((9, 1), vec![0.0, 0.1, 0.2, 0.3, 5.0, 5.4, 5.8, 6.2, 6.6]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let connectivity = model.cluster_connectivity(&data);
+        let (compact, chained) = (connectivity[&model.clusters[0]], connectivity[&model.clusters[4]]);
+        assert!((compact - 1.0).abs() < 1e-12);
+        assert!((chained - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_predict_with_index() {
+        let data = Array2::from_shape_vec((6, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1]).unwrap();
+        let new_data = Array2::from_shape_vec((3, 2), vec![1.0, 2.0, 4.0, 4.0, -2.1, 2.5]).unwrap();
+        let index = PredictionIndex::new(&data);
+        for eps in [0.3, 0.7].iter() {
+            let model = Dbscan::new(&data, *eps, 2, false);
+            assert!(model.predict_with_index(&index, &new_data) == model.predict(&data, &new_data));
+        }
+    }
+
+    #[test]
+    fn test_drift_score() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let in_distribution = Array2::from_shape_vec((4, 2), vec![1.0, 2.05, 0.95, 2.0, -2.1, 3.0, -2.0, 3.1]).unwrap();
+        let shifted = in_distribution.mapv(|x| x + 3.0);
+        assert!(model.drift_score(&data, &in_distribution) == 0.0);
+        assert!(model.drift_score(&data, &shifted) == 1.0);
+    }
+
+    #[test]
+    fn test_point_in_cluster_reach() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, false);
+        let cluster = model.clusters[0];
+        assert!(model.point_in_cluster_reach(&data, &arr1(&[1.0, 2.4]), cluster));
+        assert!(!model.point_in_cluster_reach(&data, &arr1(&[1.0, 2.8]), cluster));
+        assert!(!model.point_in_cluster_reach(&data, &arr1(&[1.0, 2.4]), model.clusters[4]));
+        assert!(!model.point_in_cluster_reach(&data, &arr1(&[-1.0, -2.0]), 0));
+        let index = PredictionIndex::new(&data);
+        for (point, cluster) in [([1.0, 2.4], cluster), ([1.0, 2.8], cluster), ([1.0, 2.4], model.clusters[4]), ([-1.0, -2.0], 0)].iter() {
+            assert!(model.point_in_cluster_reach_with_index(&index, &arr1(point), *cluster) == model.point_in_cluster_reach(&data, &arr1(point), *cluster));
+        }
+
+        // The heavy point at 10 is core by weight alone, with no other point within eps.
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.3, 5.0, 5.3, 10.0, 20.0]).unwrap();
+        let weighted = Dbscan::new_weighted(&data, &[1.0, 1.0, 2.0, 1.0, 3.0, 2.9], 0.5, 3, false);
+        assert!(weighted.point_in_cluster_reach(&data, &arr1(&[10.2]), weighted.clusters[4]));
+        assert!(weighted.point_in_cluster_reach_with_index(&PredictionIndex::new(&data), &arr1(&[10.2]), weighted.clusters[4]));
+    }
+
+    #[test]
+    fn test_dimension_separation() {
+        let data = Array2::from_shape_vec((8, 2), vec![0.0, 0.0, 0.1, 1.0, 0.2, 2.0, 0.1, 3.0, 5.0, 0.5, 5.1, 1.5, 5.2, 2.5, 5.1, 3.5]).unwrap();
+        let model = labelled(0.5, 2, vec![1, 1, 1, 1, 2, 2, 2, 2]);
+        let f = model.dimension_separation(&data);
+        assert!(f[0] > 1000.0);
+        assert!(f[1] < 1.0);
+        assert!(labelled(0.5, 2, vec![1; 8]).dimension_separation(&data) == arr1(&[0.0, 0.0]));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let data = Array2::from_shape_vec((4, 2), vec![0.0, 0.0, 0.3, 0.3, 5.0, 5.0, 5.3, 5.3]).unwrap();
+        let euclidean = Dbscan::new_with_metric(&data, 0.5, 2, false, Metric::Euclidean);
+        assert!(same_partition(&euclidean.clusters, &[1, 1, 2, 2]));
+        assert!(Dbscan::new_with_metric(&data, 0.5, 2, false, Metric::Manhattan).clusters == vec![0; 4]);
+        assert!(same_partition(&Dbscan::new_with_metric(&data, 0.5, 2, false, Metric::Chebyshev).clusters, &[1, 1, 2, 2]));
+        assert!(same_partition(&Dbscan::new_with_metric(&data, 0.5, 2, false, Metric::Minkowski(3.0)).clusters, &[1, 1, 2, 2]));
+        let manhattan = Dbscan::new_with_metric(&data, 0.7, 2, false, Metric::Manhattan);
+        let new_data = Array2::from_shape_vec((2, 2), vec![0.2, 0.2, 5.0, 5.8]).unwrap();
+        assert!(manhattan.predict_with_metric(&data, &new_data, Metric::Manhattan) == vec![vec![manhattan.clusters[0]], vec![0]]);
+
+        // London, Paris and Sydney; eps of 400km on the Earth's surface.
+        let cities = Array2::from_shape_vec((3, 2), vec![51.5074, -0.1278, 48.8566, 2.3522, -33.8688, 151.2093]).unwrap().mapv(f64::to_radians);
+        let haversine = Dbscan::new_with_metric(&cities, 400.0 / 6371.0, 2, false, Metric::Haversine);
+        assert!(haversine.clusters[0] == haversine.clusters[1] && haversine.clusters[0] > 0 && haversine.clusters[2] == 0);
+        assert!(Metric::Cosine.distance(&arr1(&[1.0, 0.0]).view(), &arr1(&[2.0, 0.0]).view()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_builder() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let new_data = Array2::from_shape_vec((3, 2), vec![1.05, 2.05, -2.1, 3.05, 10.0, 10.0]).unwrap();
+        let model = DbscanBuilder::new(0.5, 2).seed(7).fit(&data);
+        assert!(model.clusters() == &Dbscan::with_seed(&data, 0.5, 2, false, 7).clusters[..]);
+        assert!(model.predict(&new_data) == model.dbscan.predict(&data, &new_data));
+        let manhattan = DbscanBuilder::new(0.5, 2).borders(true).metric(Metric::Manhattan).fit(&data);
+        assert!(manhattan.predict(&new_data) == manhattan.dbscan.predict_with_metric(&data, &new_data, Metric::Manhattan));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_predict_parallel() {
+        fn shareable<M: Send + Sync>(_: &M) {}
+        let (data, _) = blobs(200, &Array2::from_shape_vec((2, 2), vec![0.0, 0.0, 5.0, 5.0]).unwrap(), 0.5, 4);
+        let new_data = Array2::from_shape_fn((50, 2), |(row, _)| row as f64 * 0.12);
+        let dbscan = Dbscan::new(&data, 0.5, 4, true);
+        shareable(&dbscan);
+        assert!(dbscan.predict_parallel(&data, &new_data) == dbscan.predict(&data, &new_data));
+        for chunk_rows in [1, 7, 1000] {
+            assert!(dbscan.predict_chunked(&data, &new_data, chunk_rows) == dbscan.predict(&data, &new_data));
+        }
+        assert!(dbscan.predict_chunked(&data, &Array2::zeros((0, 2)), 8).is_empty());
+
+        // One model answering from several threads at once.
+        for metric in [Metric::Euclidean, Metric::Manhattan] {
+            let model = DbscanBuilder::new(0.5, 4).borders(true).metric(metric).fit(&data);
+            shareable(&model);
+            let expected = model.predict(&new_data);
+            assert!(model.predict_chunked(&new_data, 3) == expected);
+            std::thread::scope(|scope| {
+                let handles = (0..4).map(|_| scope.spawn(|| model.predict_parallel(&new_data))).collect::<Vec<_>>();
+                assert!(handles.into_iter().all(|handle| handle.join().unwrap() == expected));
+            });
+        }
+    }
+
+    #[test]
+    fn test_model_reuses_index() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let new_data = Array2::from_shape_vec((4, 2), vec![1.05, 2.05, -2.1, 3.05, -1.5, -1.5, 1.1, 2.6]).unwrap();
+        let model = DbscanBuilder::new(0.5, 3).borders(true).seed(1).fit(&data);
+        assert!(model.predict_labeled(&new_data) == model.dbscan.predict_labeled(&data, &new_data));
+        assert!(model.predict_with_distances(&new_data) == model.dbscan.predict_with_distances(&data, &new_data));
+    }
+
+    #[test]
+    fn test_model_predicts_with_its_metric() {
+        // (0.4, 0.1) is within 0.15 of the end of the first cluster in Euclidean distance but not in Manhattan.
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.2, 0.0, 0.3, 0.0, 2.0, 0.0, 2.1, 0.0, 2.2, 0.0]).unwrap();
+        let new_data = Array2::from_shape_vec((2, 2), vec![0.4, 0.1, 0.35, 0.05]).unwrap();
+        let model = DbscanBuilder::new(0.15, 2).borders(true).metric(Metric::Manhattan).seed(1).fit(&data);
+        let euclidean = DbscanBuilder::new(0.15, 2).borders(true).seed(1).fit(&data);
+        let first = model.clusters()[0];
+        assert!(model.predict(&new_data) == vec![vec![0], vec![first]] && euclidean.predict(&new_data) == vec![vec![first], vec![first]]);
+        assert!(model.predict_labeled(&new_data) == vec![ClusterPrediction::Noise, ClusterPrediction::Core(vec![first])]);
+        assert!(euclidean.predict_labeled(&new_data)[0] == ClusterPrediction::Core(vec![first]));
+        let distances = model.predict_with_distances(&new_data);
+        assert!(distances[0].is_empty() && distances[1].len() == 1 && distances[1][0].0 == first && (distances[1][0].1 - 0.1).abs() < 1e-12);
+        assert!(model.predict_single(&new_data) == vec![None, Some(first)] && euclidean.predict_single(&new_data) == vec![Some(first), Some(first)]);
+        assert!(Predict::predict(&model, &new_data) == vec![0, first] && Predict::predict(&euclidean, &new_data) == vec![first, first]);
+
+        // The kernel at (0.35, 0.05) sums over the Manhattan distances 0.4, 0.3, 0.2 and 0.1 to the first
+        // cluster's core points, the second cluster lying beyond the three-eps cutoff.
+        let density = model.predict_density(&new_data);
+        let expected = [0.4, 0.3, 0.2, 0.1].iter().map(|d: &f64| (-d * d / (2.0 * 0.15 * 0.15)).exp()).sum::<f64>() / 7.0;
+        assert!(density[1].0 == Some(first) && (density[1].1 - expected).abs() < 1e-9 && density[0].0.is_none());
+        assert!(euclidean.predict_density(&new_data)[1].1 > density[1].1);
+    }
+
+    #[test]
+    fn test_predict_labeled() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 3, false);
+        let new_data = Array2::from_shape_vec((4, 2), vec![1.05, 2.05, -2.1, 3.05, -1.5, -1.5, 1.1, 2.6]).unwrap();
+        let predictions = model.predict_labeled(&data, &new_data);
+        assert!(predictions[0] == ClusterPrediction::Core(vec![model.clusters[0]]));
+        assert!(predictions[1] == ClusterPrediction::Core(vec![]));
+        assert!(predictions[2] == ClusterPrediction::Noise);
+        assert!(predictions[3] == ClusterPrediction::Border(vec![model.clusters[0]]));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let parallel = Dbscan::new_parallel_with_rng(&data, 0.5, 2, true, &mut StepRng::new(0, 1));
+        assert!(parallel.clusters == Dbscan::new_with_rng(&data, 0.5, 2, true, &mut StepRng::new(0, 1)).clusters);
+    }
+
+    #[test]
+    fn test_label_accessors() {
+        let model = labelled(0.5, 2, vec![1, 1, 0, 2, 2, 0, 1]);
+        assert!(model.n_clusters() == 2);
+        assert!(model.noise_indices() == vec![2, 5]);
+        assert!(model.cluster_indices(1) == vec![0, 1, 6] && model.cluster_indices(3).is_empty());
+    }
+
+    #[test]
+    fn test_try_new() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let model = Dbscan::try_new(&data, 0.5, 2, false).unwrap();
+        assert!(model.clusters.len() == 8);
+        assert!(Dbscan::try_new(&Array2::<f64>::zeros((0, 2)), 0.5, 2, false).unwrap_err() == Error::EmptyData);
+        let mut nan = data.clone();
+        nan[[3, 1]] = f64::NAN;
+        assert!(Dbscan::try_new(&nan, 0.5, 2, false).unwrap_err() == Error::NonFiniteValue { row: 3, col: 1 });
+        let wide = Array2::zeros((1, 3));
+        assert!(model.try_predict(&data, &wide).unwrap_err() == Error::DimensionMismatch { expected: 2, found: 3 });
+        assert!(model.try_predict(&data, &data).unwrap() == model.predict(&data, &data));
+    }
+
+    #[test]
+    fn test_views_and_column_major() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let expected = Dbscan::new_with_rng(&data, 0.5, 2, false, &mut StepRng::new(0, 1)).clusters;
+        let column_major = Array2::from_shape_vec((8, 2).f(), data.t().iter().cloned().collect()).unwrap();
+        assert!(column_major == data && column_major.as_slice().is_none());
+        assert!(Dbscan::new_with_rng(&column_major, 0.5, 2, false, &mut StepRng::new(0, 1)).clusters == expected);
+        let transposed = data.t().to_owned();
+        assert!(Dbscan::new_with_rng(&transposed.t(), 0.5, 2, false, &mut StepRng::new(0, 1)).clusters == expected);
+        let model = Dbscan::new(&data.view(), 0.5, 2, false);
+        assert!(model.predict(&column_major, &transposed.t()) == model.predict(&data, &data));
+        assert!(model.clusters.len() == 8 && Dbscan::new(&data.slice(s![..4, ..]), 0.5, 2, false).clusters == vec![1; 4]);
+    }
+
+    #[test]
+    fn test_column_major_entry_points() {
+        let (data, _) = blobs(60, &arr2(&[[0.0, 0.0], [4.0, 0.0]]), 0.4, 3);
+        let column_major = Array2::from_shape_vec(data.dim().f(), data.t().iter().cloned().collect()).unwrap();
+        assert!(column_major == data && column_major.as_slice().is_none());
+        let rng = || StepRng::new(0, 1);
+        let fits = |data: &Array2<f64>| {
+            vec![
+                Dbscan::new_union_find(data, 0.5, 4, true).clusters,
+                Dbscan::new_with_graph_and_rng(data, 0.5, 4, true, &mut rng()).0.clusters,
+                Dbscan::new_with_progress_and_rng(data, 0.5, 4, true, 10, &mut |_, _| true, &mut rng()).unwrap().clusters,
+                Dbscan::new_with_budget_and_rng(data, 0.5, 4, true, Budget::Queries(1000), &mut rng()).0.clusters,
+                Dbscan::new_weighted_with_rng(data, &[1.0; 60], 0.5, 4, true, &mut rng()).clusters,
+                Dbscan::new_sklearn(data, 0.5, 4).clusters,
+                Dbscan::new_constrained(data, 0.5, 4, true, &[], &[]).unwrap().clusters,
+                Dbscan::fit_sweep_with_rng(data, &[0.3, 0.5], 4, true, &mut rng()).pop().unwrap().clusters,
+                Dbscan::new_multi_density(data, 4, true, 3).clusters,
+                #[cfg(feature = "rayon")]
+                Dbscan::new_parallel_with_rng(data, 0.5, 4, true, &mut rng()).clusters,
+            ]
+        };
+        for (expected, found) in fits(&data).iter().zip(fits(&column_major).iter()) {
+            assert!(same_partition(expected, found));
+        }
+
+        let model = DbscanBuilder::new(0.5, 4).borders(true).seed(1).fit(&column_major);
+        let expected = DbscanBuilder::new(0.5, 4).borders(true).seed(1).fit(&data);
+        assert!(model.clusters() == expected.clusters() && model.data() == &data && model.data().as_slice().is_some());
+        let new_data = Array2::from_shape_vec((3, 2).f(), vec![0.1, 3.9, 10.0, 0.1, -0.1, 10.0]).unwrap();
+        assert!(model.predict(&new_data) == expected.predict(&new_data.to_owned()) && model.predict(&new_data)[2] == vec![0]);
+        assert!(model.predict_single(&new_data) == expected.predict_single(&new_data));
+        #[cfg(feature = "rayon")]
+        assert!(model.predict_parallel(&new_data) == model.predict(&new_data));
+        assert!(Predict::predict(&model, &new_data) == Predict::predict(&expected, &new_data));
+        assert!(model.dbscan.predict_single(&column_major, &new_data) == expected.predict_single(&new_data));
+    }
+
+    #[test]
+    fn test_from_distances() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let distances = Array2::from_shape_fn((8, 8), |(a, b)| squared_distance(&data.row(a), &data.row(b)).sqrt());
+        let condensed = (0..8).flat_map(|a| (a + 1..8).map(move |b| (a, b))).map(|(a, b)| distances[[a, b]]).collect::<Vec<f64>>();
+        let expected = Dbscan::new(&data, 0.5, 2, false).clusters;
+        assert!(same_partition(&Dbscan::from_distances(&distances, 0.5, 2, false).clusters, &expected));
+        assert!(same_partition(&Dbscan::from_condensed_distances(&condensed, 8, 0.5, 2, false).clusters, &expected));
+    }
+
+    #[test]
+    #[cfg(feature = "model-io")]
+    fn test_save_load() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let new_data = Array2::from_shape_vec((2, 2), vec![1.0, 2.05, 5.0, 5.0]).unwrap();
+        for metric in [Metric::Euclidean, Metric::Minkowski(3.0)].iter() {
+            let mut model = DbscanBuilder::new(0.5, 2).metric(*metric).seed(3).fit(&data);
+            model.dbscan.set_cluster_name(1, "line\nbreak \\ slash");
+            let mut saved = Vec::new();
+            model.save(&mut saved).unwrap();
+            let loaded = DbscanModel::<f64>::load(&mut saved.as_slice()).unwrap();
+            assert!(loaded.clusters() == model.clusters() && loaded.dbscan.point_types == model.dbscan.point_types && loaded.dbscan.labels == model.dbscan.labels);
+            assert!(loaded.metric == model.metric && loaded.data == model.data && loaded.index.is_some() == model.index.is_some());
+            assert!(loaded.predict(&new_data) == model.predict(&new_data));
+            assert!(DbscanModel::<f64>::load(&mut &saved[..saved.len() / 2]).is_err());
+        }
+        let newer = "clust-rs dbscan 3\neps 0.5\n";
+        assert!(DbscanModel::<f64>::load(&mut newer.as_bytes()).unwrap_err().to_string() == "unsupported model format version 3");
+
+        let model = DbscanBuilder::new(0.5, 2).search(NeighbourSearch::BruteForce).seed(3).fit(&data);
+        let mut saved = Vec::new();
+        model.save(&mut saved).unwrap();
+        let loaded = DbscanModel::<f64>::load(&mut saved.as_slice()).unwrap();
+        assert!(loaded.search() == NeighbourSearch::BruteForce && loaded.index.is_none() && loaded.predict(&new_data) == model.predict(&new_data));
+        let version_1 = String::from_utf8(saved).unwrap().replacen("dbscan 2", "dbscan 1", 1).replacen("search brute_force\n", "", 1);
+        let loaded = DbscanModel::<f64>::load(&mut version_1.as_bytes()).unwrap();
+        assert!(loaded.search() == NeighbourSearch::Index && loaded.index.is_some() && loaded.clusters() == model.clusters());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let data = arr2(&[[1.0, 2.0], [1.1, 2.2], [0.9, 1.9], [1.0, 2.1], [-2.0, 3.0], [-2.2, 3.1], [-1.0, -2.0], [-2.0, -1.0]]);
+        let new_data = arr2(&[[1.0, 2.05], [5.0, 5.0]]);
+        for (metric, search) in [(Metric::Euclidean, NeighbourSearch::BallTree), (Metric::Euclidean, NeighbourSearch::BruteForce), (Metric::Minkowski(3.0), NeighbourSearch::Index)].iter() {
+            let mut model = DbscanBuilder::new(0.5, 2).metric(*metric).search(*search).seed(3).fit(&data);
+            model.dbscan.set_cluster_name(1, "named");
+            let json = serde_json::to_string(&model).unwrap();
+            let loaded = serde_json::from_str::<DbscanModel<f64>>(&json).unwrap();
+            assert!(loaded.clusters() == model.clusters() && loaded.dbscan.point_types == model.dbscan.point_types && loaded.dbscan.labels == model.dbscan.labels);
+            assert!(loaded.metric == *metric && loaded.search() == *search && loaded.data == data && loaded.index.is_some() == model.index.is_some());
+            assert!(loaded.core == model.core && loaded.predict(&new_data) == model.predict(&new_data));
+        }
+        let mut value = serde_json::to_value(DbscanBuilder::new(0.5, 2).seed(3).fit(&data)).unwrap();
+        value["dbscan"]["clusters"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<DbscanModel<f64>>(value).unwrap_err().to_string() == "cluster count doesn't match data");
+        assert!(serde_json::from_str::<PointType>(&serde_json::to_string(&PointType::Border).unwrap()).unwrap() == PointType::Border);
+    }
+
+    #[test]
+    fn test_estimate_eps() {
+        let mut points = Vec::new();
+        for center in [0.0, 10.0].iter() {
+            points.extend((0..20).flat_map(|i| vec![center + 0.1 * f64::from(i % 5), 0.1 * f64::from(i / 5)]));
+        }
+        points.extend_from_slice(&[5.0, 5.0, -5.0, 4.0, 15.0, -4.0]);
+        let data = Array2::from_shape_vec((43, 2), points).unwrap();
+        let distances = kdist(&data, 3);
+        assert!(distances.len() == 43 && distances.windows(2).all(|w| w[0] <= w[1]));
+        assert!((distances[0] - 0.1).abs() < 1e-9 && distances[42] > 5.0);
+        assert!(knee(&[1.0, 1.0]).is_none() && knee(&[2.0, 2.0, 2.0]).is_none());
+        assert!(knee(&[1.0, 1.1, 1.2, 1.3, 5.0, 9.0]) == Some(3));
+
+        let eps = estimate_eps(&data, 4);
+        assert!((0.1..1.0).contains(&eps));
+        let model = Dbscan::new(&data, eps, 4, true);
+        assert!(model.n_clusters() == 2 && model.noise_indices() == vec![40, 41, 42]);
+        assert!(Dbscan::fit_auto(&data, 4).eps == eps && Dbscan::fit_auto(&data, 4).noise_indices() == vec![40, 41, 42]);
+
+        // Three outliers are 7% of the points, so allowing 10% noise keeps them out and allowing 5% makes one of them core.
+        let eps = estimate_eps_for_noise(&data, 4, 0.1);
+        assert!(eps == distances[38] && Dbscan::fit_auto_with_noise(&data, 4, 0.1).noise_indices() == vec![40, 41, 42]);
+        assert!(estimate_eps_for_noise(&data, 4, 0.05) == distances[40] && Dbscan::fit_auto_with_noise(&data, 4, 0.05).noise_indices().len() < 3);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.3, 5.0, 5.3, 10.0, 20.0]).unwrap();
+        let model = Dbscan::new_weighted(&data, &[1.0, 1.0, 2.0, 1.0, 3.0, 2.9], 0.5, 3, false);
+        assert!(same_partition(&model.clusters, &[0, 0, 1, 1, 2, 0]));
+        let unit = Dbscan::new_weighted(&data, &[1.0; 6], 0.5, 2, false).clusters;
+        assert!(same_partition(&unit, &Dbscan::new(&data, 0.5, 2, false).clusters));
+    }
+
+    #[test]
+    fn test_point_types() {
+        let data = Array2::from_shape_vec((7, 1), vec![0.0, 0.4, 0.8, 1.2, 1.7, 5.0, 5.3]).unwrap();
+        let expected = vec![PointType::Border, PointType::Core, PointType::Core, PointType::Core, PointType::Border, PointType::Noise, PointType::Noise];
+        for borders in [false, true].iter() {
+            for expansion in [ExpansionOrder::DepthFirst, ExpansionOrder::BreadthFirst].iter() {
+                let model = Dbscan::new_with_expansion(&data, 0.5, 3, *borders, *expansion, &mut StdRng::seed_from_u64(1));
+                assert!(model.point_types == expected && model.core_sample_indices() == vec![1, 2, 3]);
+            }
+            let sorted = Dbscan::new_sorted_1d(data.as_slice().unwrap(), 0.5, 3, *borders);
+            assert!(sorted.point_types == expected);
+        }
+    }
+
+    #[test]
+    fn test_summaries() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.3, 0.0, 0.4, 0.0, 5.0, 5.0, 5.0, 5.4, 5.0, 5.2, 20.0, 20.0]).unwrap();
+        let model = labelled(0.5, 2, vec![1, 1, 1, 2, 2, 2, 0]);
+        let summaries = model.summaries(&data);
+        assert!(summaries.keys().cloned().collect::<Vec<usize>>() == vec![1, 2]);
+        let (first, second) = (&summaries[&1], &summaries[&2]);
+        assert!(first.size == 3 && first.medoid == 1 && (first.diameter - 0.4).abs() < 1e-12);
+        assert!((first.centroid[0] - 0.7 / 3.0).abs() < 1e-12 && first.centroid[1] == 0.0);
+        assert!(second.size == 3 && second.medoid == 5 && (second.diameter - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_periodic() {
+        let tau = 2.0 * std::f64::consts::PI;
+        let data = Array2::from_shape_vec((8, 2), vec![0.05, 1.0, 6.2, 1.0, 6.25, 1.1, 0.1, 1.1, 3.0, 1.0, 3.1, 1.0, 3.2, 1.1, -0.02, 1.05]).unwrap();
+        let model = Dbscan::new_periodic(&data, &[Some(tau), None], 0.3, 3, false);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 1, 2, 2, 2, 1]));
+        let flat = Dbscan::new(&data, 0.3, 3, false);
+        assert!(flat.clusters[0] != flat.clusters[1]);
+    }
+
+    #[test]
+    fn test_geographic() {
+        // Three points within a few hundred meters in central London, two in Paris, one in Berlin, and two
+        // either side of the antimeridian about 1.1 km apart.
+        let data = Array2::from_shape_vec(
+            (8, 2),
+            vec![51.5007, -0.1246, 51.5014, -0.1419, 51.5033, -0.1195, 48.8584, 2.2945, 48.8606, 2.3376, 52.5163, 13.3777, 0.0, 179.995, 0.0, -179.995],
+        )
+        .unwrap();
+        let model = Dbscan::new_geographic(&data, 1500.0, 2, false);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 0, 0, 0, 2, 2]) && model.eps == 1500.0);
+        let paris = Dbscan::new_geographic(&data, 3500.0, 2, false);
+        assert!(paris.clusters[3] > 0 && paris.clusters[3] == paris.clusters[4] && paris.clusters[5] == 0);
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        for score in [InternalScore::Silhouette, InternalScore::DaviesBouldin, InternalScore::CalinskiHarabasz].iter() {
+            let results = Dbscan::grid_search(&data, &[0.05, 0.5, 10.0], &[2, 3], false, *score);
+            assert!(results.len() == 6 && results[0].eps == 0.5 && results[0].min_points == 2 && results[0].n_clusters == 2);
+            assert!(results[0].n_noise == 2 && results[1].score.is_none() && results[5].score.is_none());
+            #[cfg(feature = "rayon")]
+            assert!(Dbscan::grid_search_parallel(&data, &[0.05, 0.5, 10.0], &[2, 3], false, *score) == results);
+        }
+    }
+
+    #[test]
+    fn test_predict_with_distances() {
+        let data = Array2::from_shape_vec((7, 1), vec![0.0, 0.4, 0.8, 1.6, 2.0, 2.4, 1.2]).unwrap();
+        let model = Dbscan::new_weighted(&data, &[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.1], 0.45, 3, true);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 2, 2, 2, 0]) && model.point_types[6] == PointType::Noise);
+        let new_data = Array2::from_shape_vec((3, 1), vec![1.2, 0.3, 5.0]).unwrap();
+        let predictions = model.predict_with_distances(&data, &new_data);
+        assert!(predictions[0].is_empty() && predictions[2].is_empty());
+        assert!(predictions[1].len() == 1 && predictions[1][0].0 == model.clusters[0] && (predictions[1][0].1 - 0.1).abs() < 1e-12);
+
+        let wide = Dbscan::new(&data, 0.5, 3, true);
+        let between = wide.predict_with_distances(&data, &Array2::from_shape_vec((1, 1), vec![1.1]).unwrap());
+        assert!(between[0].iter().map(|(c, _)| *c).collect::<Vec<usize>>() == vec![wide.clusters[6]]);
+    }
+
+    #[test]
+    fn test_memberships() {
+        let data = Array2::from_shape_vec((7, 1), vec![0.0, 0.2, 0.4, 1.0, 1.6, 1.8, 2.0]).unwrap();
+        let model = Dbscan::new(&data, 0.45, 3, true);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 0, 2, 2, 2]));
+        let memberships = model.memberships(&data);
+        assert!(memberships[0] == vec![(model.clusters[0], 1.0)]);
+        assert!(memberships[3].is_empty());
+
+        let data = Array2::from_shape_vec((9, 1), vec![0.0, 0.1, 0.2, 0.3, 0.7, 1.05, 1.2, 1.3, 1.4]).unwrap();
+        let model = Dbscan::new(&data, 0.45, 4, true);
+        let between = &model.memberships(&data)[4];
+        assert!(between.len() == 2 && between[0].0 == model.clusters[5] && between[1].0 == model.clusters[0]);
+        assert!((between[0].1 - (1.0 - 0.35 / 0.45)).abs() < 1e-12 && (between[1].1 - (1.0 - 0.4 / 0.45)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let data = Array2::from_shape_vec((8, 2), vec![-2.0, 3.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, 1.0, 2.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let mut fits = (0..8).map(|seed| Dbscan::with_seed(&data, 0.5, 2, false, seed)).collect::<Vec<Dbscan<f64>>>();
+        for fit in fits.iter_mut() {
+            fit.canonicalize();
+        }
+        assert!(fits.iter().all(|fit| fit.clusters == vec![1, 2, 2, 2, 2, 1, 0, 0]));
+
+        let mut named = labelled(0.5, 2, vec![0, 5, 5, 3]);
+        named.labels.insert(3, "late".to_string());
+        named.labels.insert(9, "gone".to_string());
+        named.canonicalize();
+        assert!(named.clusters == vec![0, 1, 1, 2] && named.labels.len() == 1 && named.labels[&2] == "late");
+    }
+
+    #[test]
+    fn test_progress() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let mut reports = Vec::new();
+        let mut record = |fraction, clusters| {
+            reports.push((fraction, clusters));
+            true
+        };
+        let model = Dbscan::new_with_progress_and_rng(&data, 0.5, 2, false, 2, &mut record, &mut StdRng::seed_from_u64(1)).unwrap();
+        assert!(model.clusters == Dbscan::with_seed(&data, 0.5, 2, false, 1).clusters);
+        assert!(reports.iter().map(|(fraction, _)| *fraction).collect::<Vec<f64>>() == vec![0.25, 0.5, 0.75, 1.0] && reports[3].1 == 2);
+        assert!(reports.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        let mut calls = 0;
+        let cancelled = Dbscan::new_with_progress(&data, 0.5, 2, false, 3, &mut |_, _| {
+            calls += 1;
+            false
+        });
+        assert!(cancelled.err() == Some(Error::Cancelled) && calls == 1);
+    }
+
+    #[test]
+    fn test_fit_sweep() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let data = Array2::from_shape_fn((120, 2), |(row, _)| (row % 3) as f64 * 4.0 + rng.gen_range(0.0, 1.5));
+        let eps_values = [0.3, 1.0, 0.05, 6.0];
+        let sweep = Dbscan::fit_sweep(&data, &eps_values, 4, false);
+        assert!(sweep.len() == 4);
+        for (fit, eps) in sweep.iter().zip(eps_values.iter()) {
+            let single = Dbscan::new(&data, *eps, 4, false);
+            assert!(fit.eps == *eps && same_partition(&fit.clusters, &single.clusters) && fit.point_types == single.point_types);
+        }
+        assert!(Dbscan::<f64>::fit_sweep(&data, &[], 4, true).is_empty());
+    }
+
+    #[test]
+    fn test_budget() {
+        let (data, _) = blobs(300, &arr2(&[[0.0, 0.0], [4.0, 0.0], [0.0, 4.0]]), 0.3, 3);
+        let exact = Dbscan::new_with_rng(&data, 0.5, 4, true, &mut StdRng::seed_from_u64(2));
+        let (full, completed) = Dbscan::new_with_budget_and_rng(&data, 0.5, 4, true, Budget::Queries(300), &mut StdRng::seed_from_u64(2));
+        assert!(completed && full.clusters == exact.clusters && full.point_types == exact.point_types);
+        assert!(Dbscan::new_with_budget(&data, 0.5, 4, true, Budget::Time(Duration::from_secs(60))).1);
+
+        // Part way through, every point labelled so far has the label the full fit gives it.
+        let (partial, completed) = Dbscan::new_with_budget_and_rng(&data, 0.5, 4, true, Budget::Queries(150), &mut StdRng::seed_from_u64(2));
+        assert!(!completed && partial.n_clusters() > 0 && partial.clusters.iter().filter(|c| **c == 0).count() > exact.clusters.iter().filter(|c| **c == 0).count());
+        assert!(partial.clusters.iter().zip(exact.clusters.iter()).all(|(p, e)| *p == 0 || p == e));
+        let (none, completed) = Dbscan::new_with_budget(&data, 0.5, 4, true, Budget::Queries(0));
+        assert!(!completed && none.clusters == vec![0; 300]);
+    }
+
+    #[test]
+    fn test_shared_neighbours() {
+        // A tight cluster beside a diffuse one: an eps small enough to keep the tight one apart leaves most of
+        // the diffuse one as noise, while shared neighbour counts find both.
+        let (tight, _) = blobs(100, &arr2(&[[0.0, 0.0]]), 0.1, 1);
+        let (diffuse, _) = blobs(100, &arr2(&[[6.0, 0.0]]), 1.0, 2);
+        let data = ndarray::stack(Axis(0), &[tight.view(), diffuse.view()]).unwrap();
+        let truth = (0..200).map(|row| row / 100).collect::<Vec<usize>>();
+        let model = Dbscan::new_shared_neighbours(&data, 15, 5, 8, true);
+        assert!(model.neighbourhood == Neighbourhood::SharedNeighbours { k: 15, min_shared: 5 } && model.n_clusters() == 2 && adjusted_rand_index(&model.clusters, &truth) > 0.9);
+        assert!(Dbscan::new(&data, 0.15, 8, true).clusters[100..].iter().filter(|c| **c == 0).count() > 80);
+
+        // New points are placed by their shared neighbours too, however different the two densities.
+        let (tight_cluster, diffuse_cluster) = (model.clusters[0], model.clusters[150]);
+        let new_data = arr2(&[[0.02, 0.0], [6.3, 0.2], [3.0, 40.0]]);
+        assert!(model.predict(&data, &new_data) == vec![vec![tight_cluster], vec![diffuse_cluster], vec![0]]);
+        assert!(model.point_in_cluster_reach(&data, &arr1(&[0.02, 0.0]), tight_cluster) && !model.point_in_cluster_reach(&data, &arr1(&[0.02, 0.0]), diffuse_cluster));
+        assert!(model.point_in_cluster_reach(&data, &arr1(&[6.3, 0.2]), diffuse_cluster) && !model.point_in_cluster_reach(&data, &arr1(&[3.0, 40.0]), diffuse_cluster));
+        assert!(model.drift_score(&data, &new_data) == 1.0 / 3.0);
+        assert!(std::panic::catch_unwind(|| model.predict_density(&data, &new_data)).is_err());
+    }
+
+    #[test]
+    fn test_anisotropic() {
+        // Positions in meters and times in seconds: two visits to the same place an hour apart, and a third
+        // group at another place during the first visit.
+        let data = Array2::from_shape_vec(
+            (9, 2),
+            vec![0.0, 0.0, 20.0, 60.0, 40.0, 120.0, 10.0, 3600.0, 30.0, 3660.0, 0.0, 3720.0, 900.0, 0.0, 920.0, 60.0, 880.0, 120.0],
+        )
+        .unwrap();
+        let model = Dbscan::new_anisotropic(&data, &[50.0, 300.0], 3, true);
+        assert!(model.eps == 1.0 && same_partition(&model.clusters, &[1, 1, 1, 2, 2, 2, 3, 3, 3]));
+        // A single eps either finds nothing or joins the two places.
+        assert!(Dbscan::new(&data, 50.0, 3, true).n_clusters() == 0 && Dbscan::new(&data, 1000.0, 3, true).n_clusters() == 2);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let data = Array2::from_shape_fn((120, 2), |(row, _)| (row % 3) as f64 * 4.0 + rng.gen_range(0.0, 1.5));
+        let snapshot = NeighbourSnapshot::new(&data, 1.0);
+        assert!(snapshot.graph().len() == 120 && snapshot.graph() == &Dbscan::new_with_graph(&data, 1.0, 4, false).1);
+        for (eps, min_points) in [(1.0, 4), (1.0, 12), (0.3, 4), (0.05, 2)].iter() {
+            let refit = snapshot.fit(*eps, *min_points, false);
+            let single = Dbscan::new(&data, *eps, *min_points, false);
+            assert!(refit.eps == *eps && same_partition(&refit.clusters, &single.clusters) && refit.point_types == single.point_types);
+        }
+    }
+
+    #[test]
+    fn test_sklearn() {
+        // The border point at 2.0 is reached first by the cluster seeded at row 0, although its lowest-indexed
+        // core neighbour, row 1, belongs to the other cluster.
+        let data = Array2::from_shape_vec((10, 1), vec![0.0, 3.0, 2.0, 0.3, 0.6, 1.0, 3.4, 3.7, 4.0, 10.0]).unwrap();
+        let model = Dbscan::new_sklearn(&data, 1.0, 4);
+        assert!(model.clusters == vec![1, 2, 1, 1, 1, 1, 2, 2, 2, 0] && model.point_types[2] == PointType::Border);
+        assert!(model.labels_sklearn().to_vec() == vec![0, 1, 0, 0, 0, 0, 1, 1, 1, -1]);
+        assert!(Dbscan::new_union_find(&data, 1.0, 4, true).clusters == vec![1, 2, 2, 1, 1, 1, 2, 2, 2, 0]);
+    }
+
+    #[test]
+    fn test_quantized() {
+        // 0.1 + 0.2 is just over 0.3 in f64 but rounds to exactly 0.3 in f32, so only the exact re-check keeps
+        // the pair apart.
+        let pair = Array2::from_shape_vec((2, 1), vec![0.0, 0.1 + 0.2]).unwrap();
+        assert!(Dbscan::new_quantized(&pair, 0.3, 2, true).clusters == vec![0, 0] && Dbscan::new(&pair.mapv(|x| x as f32), 0.3, 2, true).clusters == vec![1, 1]);
+
+        let mut rng = SmallRng::seed_from_u64(5);
+        let data = Array2::from_shape_fn((300, 3), |_| rng.gen_range(-1.0e3, 1.0e3) + 1.0e4);
+        for eps in [150.0, 250.0] {
+            let expected = Dbscan::new_with_rng(&data, eps, 4, true, &mut StdRng::seed_from_u64(6));
+            let quantized = Dbscan::new_quantized_with_rng(&data.t().t(), eps, 4, true, &mut StdRng::seed_from_u64(6));
+            assert!(quantized.clusters == expected.clusters && quantized.point_types == expected.point_types);
+        }
+    }
+
+    #[test]
+    fn test_multi_density() {
+        // A tight blob, a diffuse one ten times wider and a few outliers far from both.
+        let mut rng = SmallRng::seed_from_u64(8);
+        let data = Array2::from_shape_fn((405, 2), |(row, col)| match row {
+            0..=199 => crate::datasets::normal::<f64, _>(&mut rng) * 0.1,
+            200..=399 => crate::datasets::normal::<f64, _>(&mut rng) + if col == 0 { 10.0 } else { 0.0 },
+            _ => 30.0 + 10.0 * (row - 400) as f64 * if col == 0 { 1.0 } else { -1.0 },
+        });
+        let truth = (0..405).map(|row| if row < 400 { 1 + row / 200 } else { 0 }).collect::<Vec<usize>>();
+        let model = Dbscan::new_multi_density(&data, 5, true, 2);
+        assert!(model.n_clusters() == 2 && model.clusters[400..] == [0; 5] && adjusted_rand_index(&model.clusters, &truth) > 0.95);
+        // The tight blob's eps leaves most of the diffuse one as noise.
+        let tight = Dbscan::new_union_find(&data, 0.1, 5, true);
+        assert!(tight.clusters[200..400].iter().filter(|c| **c == 0).count() > 150);
+        assert!(Dbscan::new_multi_density(&Array2::<f64>::zeros((0, 2)), 5, true, 2).clusters.is_empty());
+    }
+
+    #[test]
+    fn test_brute_force() {
+        let mut rng = SmallRng::seed_from_u64(12);
+        let data = Array2::from_shape_fn((300, 37), |(row, _)| (row % 3) as f64 + rng.gen_range(0.0, 0.4));
+        for eps in [1.0, 1.4] {
+            let indexed = Dbscan::new_with_rng(&data, eps, 5, true, &mut StdRng::seed_from_u64(13));
+            let brute = Dbscan::new_brute_force_with_rng(&data.t().t(), eps, 5, true, &mut StdRng::seed_from_u64(13));
+            assert!(brute.clusters == indexed.clusters && brute.point_types == indexed.point_types);
+        }
+        assert!(Dbscan::new_brute_force(&data.t(), 1.0, 5, true).clusters.len() == 37);
+    }
+
+    #[test]
+    fn test_neighbour_search() {
+        let mut rng = SmallRng::seed_from_u64(12);
+        let data = Array2::from_shape_fn((300, 37), |(row, _)| (row % 3) as f64 + rng.gen_range(0.0, 0.4));
+        let new_data = Array2::from_shape_fn((20, 37), |(row, _)| (row % 4) as f64 + rng.gen_range(0.0, 0.4));
+        let indexed = Dbscan::new_with_search_and_rng(&data, 1.0, 5, true, NeighbourSearch::Index, &mut StdRng::seed_from_u64(13));
+        let brute = Dbscan::new_with_search_and_rng(&data, 1.0, 5, true, NeighbourSearch::BruteForce, &mut StdRng::seed_from_u64(13));
+        assert!(brute.clusters == indexed.clusters && brute.point_types == indexed.point_types);
+        for search in [NeighbourSearch::BallTree, NeighbourSearch::Auto] {
+            let fit = Dbscan::new_with_search_and_rng(&data.t(), 1.0, 5, true, search, &mut StdRng::seed_from_u64(13));
+            assert!(fit.clusters == Dbscan::new_with_rng(&data.t(), 1.0, 5, true, &mut StdRng::seed_from_u64(13)).clusters);
+            let fit = Dbscan::new_with_search_and_rng(&data, 1.0, 5, true, search, &mut StdRng::seed_from_u64(13));
+            assert!(fit.clusters == indexed.clusters && fit.point_types == indexed.point_types);
+        }
+        // The three blobs are dense enough that LSH misses no core point; its neighbourhoods are never larger
+        // than exact ones.
+        let lsh = Dbscan::new_with_search_and_rng(&data, 1.0, 5, true, NeighbourSearch::Lsh, &mut StdRng::seed_from_u64(13));
+        assert!(lsh.clusters.iter().max() == indexed.clusters.iter().max() && lsh.point_types.iter().zip(indexed.point_types.iter()).all(|(a, b)| *a != PointType::Core || *b == PointType::Core));
+
+        let model = DbscanBuilder::new(1.0, 5).borders(true).search(NeighbourSearch::BruteForce).seed(13).fit(&data);
+        let indexed_model = DbscanBuilder::new(1.0, 5).borders(true).seed(13).fit(&data);
+        assert!(model.index.is_none() && indexed_model.index.is_some() && model.clusters() == indexed_model.clusters());
+        assert!(model.predict(&new_data) == indexed_model.predict(&new_data) && model.predict_labeled(&new_data) == indexed_model.predict_labeled(&new_data));
+        let manhattan = DbscanBuilder::new(1.0, 5).metric(Metric::Manhattan).search(NeighbourSearch::BruteForce).seed(13).fit(&data);
+        assert!(manhattan.clusters() == DbscanBuilder::new(1.0, 5).metric(Metric::Manhattan).seed(13).fit(&data).clusters());
+    }
+
+    #[test]
+    fn test_union_find() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let data = Array2::from_shape_fn((300, 2), |(row, _)| (row % 4) as f64 * 3.0 + rng.gen_range(0.0, 2.0));
+        for borders in [false, true] {
+            let fit = Dbscan::new_union_find(&data, 0.25, 5, borders);
+            let sequential = Dbscan::new(&data, 0.25, 5, borders);
+            assert!(fit.point_types == sequential.point_types);
+            assert!(fit.clusters.iter().zip(sequential.clusters.iter()).all(|(a, b)| (*a == 0) == (*b == 0)));
+            let cores = fit.core_sample_indices();
+            assert!(same_partition(&cores.iter().map(|idx| fit.clusters[*idx]).collect::<Vec<usize>>(), &cores.iter().map(|idx| sequential.clusters[*idx]).collect::<Vec<usize>>()));
+            assert!(fit.clusters == Dbscan::new_union_find(&data, 0.25, 5, borders).clusters);
+            let mut canonical = Dbscan::new_union_find(&data, 0.25, 5, borders);
+            canonical.canonicalize();
+            assert!(canonical.clusters == fit.clusters);
+        }
+    }
+
+    #[test]
+    fn test_spatiotemporal() {
+        // The same street corner busy on two mornings, and a one-off event nearby in between.
+        let data = Array2::from_shape_vec((9, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.05, 0.05, 0.1, 0.1, 0.05, 0.0]).unwrap();
+        let times = [0.0, 0.5, 1.0, 24.0, 24.5, 25.0, 12.0, 12.2, 12.4];
+        let model = Dbscan::new_spatiotemporal(&data, &times, 0.2, 2.0, 3, false);
+        assert!(same_partition(&model.clusters, &[1, 1, 1, 2, 2, 2, 3, 3, 3]));
+        // Half an hour apart is too long under a tighter time threshold; the event's points chain together.
+        let tight = Dbscan::new_spatiotemporal(&data, &times, 0.2, 0.3, 2, false);
+        assert!(tight.clusters == vec![0, 0, 0, 0, 0, 0, 1, 1, 1]);
+        assert!(Dbscan::new(&data, 0.2, 3, false).clusters.iter().all_equal());
+    }
+
+    #[test]
+    fn test_predict_single() {
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.1, 0.2, 1.0, 1.1, 1.2]).unwrap();
+        let model = Dbscan::new(&data, 0.15, 2, false);
+        let new_data = Array2::from_shape_vec((5, 1), vec![0.05, 0.5, 0.62, 0.7, 5.0]).unwrap();
+        assert!(model.predict_single(&data, &new_data) == vec![Some(model.clusters[0]), None, None, None, None]);
+
+        // Under a wider eps 0.62 is near both clusters, and goes to the closer.
+        let wide = Dbscan::new(&data, 0.45, 2, false);
+        assert!(wide.predict(&data, &new_data)[2].len() == 2);
+        let labels = wide.predict_single(&data, &new_data);
+        assert!(labels == vec![Some(wide.clusters[0]), Some(wide.clusters[0]), Some(wide.clusters[3]), Some(wide.clusters[3]), None]);
+    }
+
+    #[test]
+    fn test_chunked() {
+        let data = Array2::from_shape_vec((9, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0, -2.0, 3.4]).unwrap();
+        let chunks = vec![data.slice(s![..4, ..]).to_owned(), data.slice(s![4..5, ..]).to_owned(), data.slice(s![5.., ..]).to_owned()];
+        let model = Dbscan::new_chunked_with_rng(chunks, 0.5, 3, true, &mut StdRng::seed_from_u64(1));
+        assert!(model.clusters == Dbscan::with_seed(&data, 0.5, 3, true, 1).clusters);
+        assert!(Dbscan::new_chunked(Vec::<Array2<f64>>::new(), 0.5, 3, true).clusters.is_empty());
+    }
+
+    #[test]
+    fn test_plus_plus() {
+        let (data, truth) = crate::datasets::moons(1000, 0.05, 3);
+        let exact = Dbscan::new(&data, 0.2, 4, true);
+        let mut rng = SmallRng::seed_from_u64(1);
+        let all = Dbscan::new_plus_plus_with_rng(&data, 0.2, 4, true, 1000, CoreSampling::Uniform, &mut rng);
+        assert!(all.point_types == exact.point_types && adjusted_rand_index(&all.clusters, &exact.clusters) == 1.0);
+        // A tenth of the points covers both moons picked by k-center, but a uniform sample that small leaves gaps wider than eps.
+        for (sampling, m) in [(CoreSampling::KCenter, 100), (CoreSampling::Uniform, 300)] {
+            let model = Dbscan::new_plus_plus_with_rng(&data, 0.2, 4, true, m, sampling, &mut rng);
+            assert!(model.point_types.iter().filter(|t| **t == PointType::Core).count() <= m);
+            assert!(adjusted_rand_index(&model.clusters, &truth) > 0.95);
+        }
+        assert!(adjusted_rand_index(&Dbscan::new_plus_plus_with_rng(&data, 0.2, 4, true, 100, CoreSampling::Uniform, &mut rng).clusters, &truth) < 0.95);
+        assert!(Dbscan::new_plus_plus(&data, 0.2, 4, true, 0, CoreSampling::KCenter).clusters.iter().all(|c| *c == 0));
+    }
+
+    #[test]
+    fn test_graph() {
+        let data = Array2::from_shape_vec((9, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0, -2.0, 3.4]).unwrap();
+        let (model, graph) = Dbscan::new_with_graph_and_rng(&data, 0.5, 3, true, &mut StdRng::seed_from_u64(1));
+        assert!(model.clusters == Dbscan::with_seed(&data, 0.5, 3, true, 1).clusters && graph.len() == 9 && graph.offsets[9] == graph.indices.len());
+        for a in 0..9 {
+            let expected = (0..9).filter(|b| squared_distance(&data.row(a), &data.row(*b)) <= 0.25).collect::<Vec<usize>>();
+            assert!(graph.neighbours(a) == &expected[..]);
+        }
+        assert!(graph.neighbours(6) == [6] && !graph.is_empty());
+    }
+
+    #[test]
+    fn test_predict_density() {
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.1, 0.2, 1.0, 1.1, 1.2]).unwrap();
+        let model = Dbscan::new(&data, 0.15, 2, false);
+        let new_data = Array2::from_shape_vec((4, 1), vec![0.1, 0.25, 0.4, 5.0]).unwrap();
+        let scores = model.predict_density(&data, &new_data);
+        assert!(scores.iter().map(|(cluster, _)| *cluster).collect::<Vec<Option<usize>>>() == model.predict_single(&data, &new_data));
+        assert!(scores[0].1 > scores[1].1 && scores[1].1 > scores[2].1 && scores[2].1 > 0.0 && scores[3].1 == 0.0);
+        let expected = [-0.01, 0.0, -0.01].iter().map(|d: &f64| (d / (2.0 * 0.15 * 0.15)).exp()).sum::<f64>() / 6.0;
+        assert!((scores[0].1 - expected).abs() < 1e-12);
+        let fitted = DbscanBuilder::new(0.15, 2).seed(1).fit(&data);
+        assert!(fitted.predict_density(&new_data).iter().zip(scores.iter()).all(|(a, b)| a.0.is_some() == b.0.is_some() && (a.1 - b.1).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_constrained() {
+        // A chain of points, which plain DBSCAN keeps as one cluster, and an isolated point.
+        let data = Array2::from_shape_vec((11, 1), vec![0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 3.0]).unwrap();
+        assert!(Dbscan::new(&data, 0.15, 3, true).n_clusters() == 1);
+        assert!(Dbscan::new_constrained(&data, 0.15, 3, true, &[], &[]).unwrap().clusters == Dbscan::new_union_find(&data, 0.15, 3, true).clusters);
+        let cut = Dbscan::new_constrained(&data, 0.15, 3, true, &[], &[(1, 8)]).unwrap();
+        assert!(cut.clusters == vec![1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 0]);
+
+        // A must-link pulls the isolated point in, and must-links that contradict a cannot-link are refused.
+        let joined = Dbscan::new_constrained(&data, 0.15, 3, true, &[(10, 9)], &[(1, 8)]).unwrap();
+        assert!(joined.clusters == vec![1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2] && joined.point_types[10] == PointType::Noise);
+        let conflict = Dbscan::new_constrained(&data, 0.15, 3, true, &[(1, 10), (10, 8)], &[(1, 8)]);
+        assert!(conflict.err() == Some(Error::ConflictingConstraints { a: 10, b: 8 }));
     }
 }
@@ -0,0 +1,161 @@
+use crate::index::{index_rows, row_slice, squared_euclidean};
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+
+/// Density peaks clustering (Rodriguez & Laio, 2014): cluster centers are points denser than their
+/// neighbours and far from any denser point. Each point gets a `density`, the number of other points within
+/// the cutoff distance `dc`, and a `delta`, its distance to the nearest denser point (`nearest_higher`), or
+/// for the densest point its distance to the furthest. Plotting delta against density is the decision graph,
+/// where centers stand out at the top right; every other point joins the cluster of its nearest denser
+/// point. Density ties go to the lower row. There is no noise, and `clusters` indexes into `centers` from 0,
+/// as `Kmeans` labels index into its centers.
+#[derive(Debug, Clone)]
+pub struct DensityPeaks<T: Float> {
+    pub dc: T,
+    pub density: Vec<T>,
+    pub delta: Vec<T>,
+    pub nearest_higher: Vec<Option<usize>>,
+    pub centers: Vec<usize>,
+    pub clusters: Vec<usize>,
+}
+
+impl<T: Float + One + Zero> DensityPeaks<T> {
+    /// Density peaks with the `n_centers` points of largest `gamma` as centers, or with `None`, as many as
+    /// precede the largest relative drop among the top `sqrt(n)` gammas, sorted in descending order.
+    pub fn new(data: &Array2<T>, dc: T, n_centers: Option<usize>) -> DensityPeaks<T> {
+        let n = data.rows();
+        assert!(n > 0, "need at least one point");
+        let index = index_rows(data);
+        let mut neighbours = Vec::new();
+        let density = data
+            .outer_iter()
+            .map(|row| {
+                neighbours.clear();
+                index.within(&row_slice(row.view()), dc, &mut neighbours);
+                T::from(neighbours.len() - 1).expect("T::from(usize)")
+            })
+            .collect::<Vec<T>>();
+        let order = density_order(&density);
+        let mut rank = vec![0; n];
+        for (position, point) in order.iter().enumerate() {
+            rank[*point] = position;
+        }
+
+        let mut delta = vec![T::zero(); n];
+        let mut nearest_higher = vec![None; n];
+        for (point, row) in data.outer_iter().enumerate() {
+            match index.nearest_matching(&row_slice(row.view()), 1, |other| rank[other] < rank[point]).first() {
+                Some((distance, other)) => {
+                    delta[point] = *distance;
+                    nearest_higher[point] = Some(*other);
+                }
+                None => delta[point] = data.outer_iter().map(|other| squared_euclidean(&row_slice(row.view()), &row_slice(other.view()))).fold(T::zero(), T::max).sqrt(),
+            }
+        }
+
+        let mut peaks = DensityPeaks { dc, density, delta, nearest_higher, centers: Vec::new(), clusters: Vec::new() };
+        let mut by_gamma = peaks.gamma().into_iter().enumerate().collect::<Vec<(usize, T)>>();
+        by_gamma.sort_by(|(a, x), (b, y)| y.partial_cmp(x).expect("PartialOrd gamma").then(a.cmp(b)));
+        let n_centers = n_centers.unwrap_or_else(|| {
+            let top = ((n as f64).sqrt().ceil() as usize).clamp(1, n);
+            (1..top)
+                .map(|k| (k, by_gamma[k - 1].1 / by_gamma[k].1))
+                .filter(|(_, drop)| !drop.is_nan())
+                .fold((1, T::zero()), |best, (k, drop)| if drop > best.1 { (k, drop) } else { best })
+                .0
+        });
+        let centers = by_gamma.iter().take(n_centers).map(|(point, _)| *point).collect::<Vec<usize>>();
+        peaks.assign(data, &centers);
+        peaks
+    }
+
+    /// `density * delta` for each point, large for the centers the decision graph singles out.
+    pub fn gamma(&self) -> Vec<T> {
+        self.density.iter().zip(self.delta.iter()).map(|(rho, delta)| *rho * *delta).collect::<Vec<T>>()
+    }
+
+    /// The same decision graph with `centers` picked by hand from it. If the densest point isn't among them,
+    /// it joins its nearest center, and the points following it with it.
+    pub fn with_centers(&self, data: &Array2<T>, centers: &[usize]) -> DensityPeaks<T> {
+        let mut peaks = self.clone();
+        peaks.assign(data, centers);
+        peaks
+    }
+
+    fn assign(&mut self, data: &Array2<T>, centers: &[usize]) {
+        assert!(!centers.is_empty(), "need at least one center");
+        let n = self.density.len();
+        let mut clusters = vec![usize::MAX; n];
+        for (cluster, center) in centers.iter().enumerate() {
+            clusters[*center] = cluster;
+        }
+        for point in density_order(&self.density) {
+            if clusters[point] != usize::MAX {
+                continue;
+            }
+            clusters[point] = match self.nearest_higher[point] {
+                Some(higher) => clusters[higher],
+                None => {
+                    let distance = |center: &usize| squared_euclidean(&row_slice(data.row(point)), &row_slice(data.row(*center)));
+                    (0..centers.len()).min_by(|a, b| distance(&centers[*a]).partial_cmp(&distance(&centers[*b])).expect("PartialOrd distance")).unwrap()
+                }
+            };
+        }
+        self.centers = centers.to_vec();
+        self.clusters = clusters;
+    }
+}
+
+impl<T: Float> Clustering for DensityPeaks<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+/// Points from densest to sparsest, ties to the lower row.
+fn density_order<T: Float>(density: &[T]) -> Vec<usize> {
+    let mut order = (0..density.len()).collect::<Vec<usize>>();
+    order.sort_by(|a, b| density[*b].partial_cmp(&density[*a]).expect("PartialOrd density").then(a.cmp(b)));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::Dbscan;
+    use crate::index::column_major;
+    use crate::metrics::adjusted_rand_index;
+
+    #[test]
+    fn test_density_peaks() {
+        // Blobs of three different spreads, which no single DBSCAN eps fits.
+        let (tight, _) = blobs(100, &arr2(&[[0.0, 0.0]]), 0.2, 1);
+        let (medium, _) = blobs(100, &arr2(&[[8.0, 0.0]]), 0.6, 2);
+        let (wide, _) = blobs(100, &arr2(&[[0.0, 10.0]]), 1.2, 3);
+        let data = ndarray::stack(Axis(0), &[tight.view(), medium.view(), wide.view()]).unwrap();
+        let truth = (0..300).map(|row| row / 100).collect::<Vec<usize>>();
+
+        let peaks = DensityPeaks::new(&data, 0.5, None);
+        assert!(peaks.centers.len() == 3 && adjusted_rand_index(&peaks.clusters, &truth) > 0.98);
+        let mut blobs_of_centers = peaks.centers.iter().map(|center| center / 100).collect::<Vec<usize>>();
+        blobs_of_centers.sort_unstable();
+        assert!(blobs_of_centers == vec![0, 1, 2]);
+        assert!(peaks.nearest_higher.iter().filter(|higher| higher.is_none()).count() == 1 && peaks.gamma().len() == 300);
+        assert!(adjusted_rand_index(&Dbscan::new(&data, 0.5, 5, true).clusters, &truth) < adjusted_rand_index(&peaks.clusters, &truth));
+
+        // Picking two centers by hand merges the blob whose center was left out into another.
+        let two = peaks.with_centers(&data, &peaks.centers[..2]);
+        assert!(two.centers.len() == 2 && two.clusters.iter().all(|c| *c < 2));
+        assert!(DensityPeaks::new(&data, 0.5, Some(4)).centers.len() == 4);
+        let columns = DensityPeaks::new(&column_major(&data), 0.5, None);
+        assert!(columns.clusters == peaks.clusters && columns.delta == peaks.delta && columns.with_centers(&column_major(&data), &peaks.centers[..2]).clusters == two.clusters);
+    }
+}
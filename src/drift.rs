@@ -0,0 +1,169 @@
+//! Monitoring a fitted DBSCAN model as new data arrives. Each batch is predicted against the model and
+//! compared with a reference batch on three signals: the share of points called noise, how the points spread
+//! over the clusters, and how far they lie from the nearest core point. `Dbscan::drift_score` is the first
+//! of these for a single batch.
+
+use crate::dbscan::DbscanModel;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How far a batch may move from the reference before `DriftMonitor` flags it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftThresholds {
+    /// Largest rise in the noise fraction.
+    pub noise_increase: f64,
+    /// Largest total variation distance between the batch's cluster hit rates and the reference's.
+    pub hit_rate_shift: f64,
+    /// Largest ratio of the batch's mean distance to the nearest core point over the reference's.
+    pub core_distance_ratio: f64,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> DriftThresholds {
+        DriftThresholds { noise_increase: 0.1, hit_rate_shift: 0.1, core_distance_ratio: 1.5 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftSignal {
+    Noise,
+    HitRates,
+    CoreDistance,
+}
+
+/// What one batch looked like to the model. `hit_rates` are the shares of the batch's clustered points that
+/// each cluster took, and `mean_core_distance`, under the model's metric, is over those points too, `None`
+/// when there were none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchStatistics<T: Float> {
+    pub points: usize,
+    pub noise_fraction: f64,
+    pub hit_rates: BTreeMap<usize, f64>,
+    pub mean_core_distance: Option<T>,
+}
+
+impl<T: Float + One + Zero> BatchStatistics<T> {
+    pub fn new(model: &DbscanModel<T>, batch: &Array2<T>) -> BatchStatistics<T> {
+        let nearest = model.predict_with_distances(batch).into_iter().filter_map(|clusters| clusters.first().cloned()).collect::<Vec<(usize, T)>>();
+        let mut hit_rates = BTreeMap::new();
+        for (cluster, _) in nearest.iter() {
+            *hit_rates.entry(*cluster).or_insert(0.0) += 1.0 / nearest.len() as f64;
+        }
+        let total = nearest.iter().fold(T::zero(), |acc, (_, distance)| acc + *distance);
+        BatchStatistics {
+            points: batch.rows(),
+            noise_fraction: if batch.rows() == 0 { 0.0 } else { (batch.rows() - nearest.len()) as f64 / batch.rows() as f64 },
+            hit_rates,
+            mean_core_distance: T::from(nearest.len()).filter(|count| !count.is_zero()).map(|count| total / count),
+        }
+    }
+
+    /// Total variation distance between the two sets of hit rates: half the summed absolute differences, from
+    /// 0 for the same spread over the clusters to 1 for none in common.
+    pub fn hit_rate_shift(&self, other: &BatchStatistics<T>) -> f64 {
+        let rate = |rates: &BTreeMap<usize, f64>, cluster: &usize| rates.get(cluster).cloned().unwrap_or(0.0);
+        self.hit_rates.keys().chain(other.hit_rates.keys()).collect::<BTreeSet<&usize>>().into_iter().map(|c| (rate(&self.hit_rates, c) - rate(&other.hit_rates, c)).abs()).sum::<f64>() / 2.0
+    }
+}
+
+/// A batch's statistics and the signals it raised against the reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriftReport<T: Float> {
+    pub statistics: BatchStatistics<T>,
+    pub hit_rate_shift: f64,
+    pub signals: Vec<DriftSignal>,
+}
+
+impl<T: Float> DriftReport<T> {
+    pub fn drifted(&self) -> bool {
+        !self.signals.is_empty()
+    }
+}
+
+/// Watches a stream of batches for drift from `reference`, a sample known to match the training data such as
+/// a held-out split. The training data itself makes a poor reference, since its core points lie at distance
+/// 0 from themselves. Every batch's report is kept in `reports`, oldest first.
+#[derive(Debug)]
+pub struct DriftMonitor<T: Float + One + Zero> {
+    pub model: DbscanModel<T>,
+    pub thresholds: DriftThresholds,
+    pub reference: BatchStatistics<T>,
+    pub reports: Vec<DriftReport<T>>,
+}
+
+impl<T: Float + One + Zero> DriftMonitor<T> {
+    pub fn new(model: DbscanModel<T>, reference: &Array2<T>, thresholds: DriftThresholds) -> DriftMonitor<T> {
+        let reference = BatchStatistics::new(&model, reference);
+        DriftMonitor { model, thresholds, reference, reports: Vec::new() }
+    }
+
+    /// Predicts `batch`, records its report and returns it.
+    pub fn observe(&mut self, batch: &Array2<T>) -> &DriftReport<T> {
+        let statistics = BatchStatistics::new(&self.model, batch);
+        let hit_rate_shift = statistics.hit_rate_shift(&self.reference);
+        let mut signals = Vec::new();
+        if statistics.noise_fraction - self.reference.noise_fraction > self.thresholds.noise_increase {
+            signals.push(DriftSignal::Noise);
+        }
+        if hit_rate_shift > self.thresholds.hit_rate_shift {
+            signals.push(DriftSignal::HitRates);
+        }
+        let limit = self.reference.mean_core_distance.map(|distance| distance * T::from(self.thresholds.core_distance_ratio).expect("T::from(f64)"));
+        if let (Some(mean), Some(limit)) = (statistics.mean_core_distance, limit) {
+            if mean > limit {
+                signals.push(DriftSignal::CoreDistance);
+            }
+        }
+        self.reports.push(DriftReport { statistics, hit_rate_shift, signals });
+        self.reports.last().unwrap()
+    }
+
+    /// Whether any batch so far has drifted.
+    pub fn drifted(&self) -> bool {
+        self.reports.iter().any(DriftReport::drifted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::{DbscanBuilder, Metric};
+
+    #[test]
+    fn test_drift_monitor() {
+        let centers = arr2(&[[0.0, 0.0], [6.0, 0.0]]);
+        let (train, _) = blobs(400, &centers, 0.5, 1);
+        let (reference, _) = blobs(200, &centers, 0.5, 2);
+        let model = DbscanBuilder::new(0.5, 5).borders(true).fit(&train);
+        let mut monitor = DriftMonitor::new(model, &reference, DriftThresholds::default());
+        assert!(monitor.reference.points == 200 && monitor.reference.noise_fraction < 0.05 && (monitor.reference.hit_rates[&1] - 0.5).abs() < 0.05);
+
+        // A fresh sample from the same blobs, then one that all falls in the first blob, then one spread wider.
+        assert!(!monitor.observe(&blobs(200, &centers, 0.5, 3).0).drifted());
+        let (one_sided, _) = blobs(200, &arr2(&[[0.0, 0.0]]), 0.5, 4);
+        assert!(monitor.observe(&one_sided).signals == vec![DriftSignal::HitRates]);
+        let (spread, _) = blobs(200, &centers, 1.0, 5);
+        let report = monitor.observe(&spread);
+        assert!(report.signals.contains(&DriftSignal::Noise) && report.signals.contains(&DriftSignal::CoreDistance));
+        assert!(monitor.drifted() && monitor.reports.len() == 3 && !monitor.reports[0].drifted());
+
+        let empty = BatchStatistics::new(&monitor.model, &Array2::zeros((0, 2)));
+        assert!(empty.noise_fraction == 0.0 && empty.mean_core_distance.is_none() && empty.hit_rate_shift(&empty) == 0.0);
+    }
+
+    #[test]
+    fn test_batch_statistics_use_model_metric() {
+        // (0.4, 0.1) is within 0.15 of the first cluster in Euclidean distance but not in Manhattan, and
+        // (0.35, 0.05) is 0.1 from it in Manhattan against 0.07 in Euclidean.
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.2, 0.0, 0.3, 0.0, 2.0, 0.0, 2.1, 0.0, 2.2, 0.0]).unwrap();
+        let batch = Array2::from_shape_vec((2, 2), vec![0.4, 0.1, 0.35, 0.05]).unwrap();
+        let model = DbscanBuilder::new(0.15, 2).metric(Metric::Manhattan).seed(1).fit(&data);
+        let statistics = BatchStatistics::new(&model, &batch);
+        assert!(statistics.noise_fraction == 0.5 && (statistics.mean_core_distance.unwrap() - 0.1).abs() < 1e-12);
+        let euclidean = BatchStatistics::new(&DbscanBuilder::new(0.15, 2).seed(1).fit(&data), &batch);
+        assert!(euclidean.noise_fraction == 0.0);
+    }
+}
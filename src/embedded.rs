@@ -0,0 +1,94 @@
+//! DBSCAN for targets without an allocator. Everything here uses only `core`: points come in as one flat
+//! row-major slice, the caller provides the label and work buffers, and points are visited in index order
+//! rather than sampled, so the same input always gives the same labels. Neighbours are found by a linear scan,
+//! which makes a fit O(n²) distance computations; that suits the few hundred points a sensor frame holds.
+//!
+//! The rest of the crate needs `std` through `ndarray`, `rand` and `itertools`, so a build with
+//! default-features = false holds just this module and is `no_std`. The `alloc` feature adds `dbscan`, which
+//! allocates the buffers itself.
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use num_traits::float::Float;
+
+/// Temporary label for points not yet visited.
+const UNCLASSIFIED: usize = usize::MAX;
+
+/// Clusters the `points.len() / dims` rows of `points` into `labels`, numbering clusters from 1 with 0 for
+/// noise, and returns the number of clusters. Border points join the first cluster that reaches them, and
+/// `queue` is scratch space with at least one slot per point.
+pub fn dbscan_into<T: Float>(points: &[T], dims: usize, eps: T, min_points: usize, labels: &mut [usize], queue: &mut [usize]) -> usize {
+    assert!(dims > 0 && points.len() % dims == 0, "points must hold whole rows of dims values");
+    let n = points.len() / dims;
+    assert!(labels.len() >= n && queue.len() >= n, "labels and queue need a slot per point");
+    let row = |i: usize| &points[i * dims..(i + 1) * dims];
+    let near = |a: usize, b: usize| row(a).iter().zip(row(b).iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y) * (*x - *y)) <= eps * eps;
+    let is_core = |i: usize| (0..n).filter(|j| near(i, *j)).take(min_points).count() >= min_points;
+
+    for label in labels[..n].iter_mut() {
+        *label = UNCLASSIFIED;
+    }
+    let mut cluster = 0;
+    for seed in 0..n {
+        if labels[seed] != UNCLASSIFIED {
+            continue;
+        }
+        if !is_core(seed) {
+            labels[seed] = 0;
+            continue;
+        }
+        cluster += 1;
+        labels[seed] = cluster;
+        // Every point is queued at most once, when it is first claimed, so `queue` never overflows.
+        let (mut head, mut tail) = (0, 1);
+        queue[0] = seed;
+        while head < tail {
+            let point = queue[head];
+            head += 1;
+            if point != seed && !is_core(point) {
+                continue;
+            }
+            for other in (0..n).filter(|other| near(point, *other)) {
+                if labels[other] == UNCLASSIFIED {
+                    queue[tail] = other;
+                    tail += 1;
+                    labels[other] = cluster;
+                } else if labels[other] == 0 {
+                    labels[other] = cluster;
+                }
+            }
+        }
+    }
+    cluster
+}
+
+/// Like `dbscan_into`, allocating the buffers: the label of each of the `points.len() / dims` rows, paired
+/// with the number of clusters.
+#[cfg(feature = "alloc")]
+pub fn dbscan<T: Float>(points: &[T], dims: usize, eps: T, min_points: usize) -> (Vec<usize>, usize) {
+    let n = points.len().checked_div(dims).unwrap_or(0);
+    let (mut labels, mut queue) = (vec![0; n], vec![0; n]);
+    let clusters = dbscan_into(points, dims, eps, min_points, &mut labels, &mut queue);
+    (labels, clusters)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_dbscan_into() {
+        let points = [1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0, -2.0, 3.4];
+        let (mut labels, mut queue) = ([7; 9], [0; 9]);
+        assert!(dbscan_into(&points, 2, 0.5, 3, &mut labels, &mut queue) == 2);
+        assert!(labels == [1, 1, 1, 1, 2, 2, 0, 0, 2]);
+        assert!(dbscan(&points, 2, 0.5, 3) == (labels.to_vec(), 2));
+        let fit = Dbscan::new(&Array2::from_shape_vec((9, 2), points.to_vec()).unwrap(), 0.5, 3, true);
+        assert!(fit.clusters.iter().zip(labels.iter()).all(|(a, b)| (*a == 0) == (*b == 0)));
+        assert!(dbscan_into(&points, 2, 0.5, 10, &mut labels, &mut queue) == 0 && labels == [0; 9]);
+    }
+}
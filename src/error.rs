@@ -0,0 +1,30 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    DimensionMismatch { expected: usize, found: usize },
+    NonFiniteValue { row: usize, col: usize },
+    /// No rows or no columns.
+    EmptyData,
+    /// A progress callback asked the fit to stop.
+    Cancelled,
+    /// Must-links joining `a` and `b`, directly or through other points, contradict a cannot-link.
+    ConflictingConstraints { a: usize, b: usize },
+    /// No cluster could take `point` without breaking one of its constraints.
+    Unsatisfiable { point: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::DimensionMismatch { expected, found } => write!(f, "expected {} dimensions, found {}", expected, found),
+            Error::NonFiniteValue { row, col } => write!(f, "non-finite value at row {}, column {}", row, col),
+            Error::EmptyData => write!(f, "input has no rows or no columns"),
+            Error::Cancelled => write!(f, "fit cancelled"),
+            Error::ConflictingConstraints { a, b } => write!(f, "must-link between points {} and {} contradicts a cannot-link", a, b),
+            Error::Unsatisfiable { point } => write!(f, "no cluster for point {} satisfies its constraints", point),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
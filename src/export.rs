@@ -0,0 +1,163 @@
+//! Clustering results as JSON for maps and dashboards, written by hand so the crate needs no serialization
+//! dependency. `to_geojson` draws each cluster of 2-D or geographic data as its convex hull and its points,
+//! and `to_json_summary` gives the labels with each cluster's size and centroid for any number of dimensions.
+//! Labels are taken as the model gives them, with the `noise` label, 0 for the density-based models, left
+//! out of the clusters.
+
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::collections::BTreeMap;
+
+/// How the two columns of the data map onto GeoJSON's `[x, y]`, which for geographic data is
+/// `[longitude, latitude]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateOrder {
+    /// Rows of `[latitude, longitude]`, as `Dbscan::new_geographic` takes them; written swapped.
+    LatitudeLongitude,
+    /// Rows of `[x, y]` or `[longitude, latitude]`; written as they are.
+    XY,
+}
+
+/// One GeoJSON FeatureCollection per cluster, keyed by label, each holding the cluster's convex hull and its
+/// points as two features with `cluster` and `size` properties. Hulls of one or two distinct points come out
+/// as a Point or LineString.
+pub fn cluster_geojson<T: Float>(data: &Array2<T>, labels: &[usize], noise: Option<usize>, order: CoordinateOrder) -> BTreeMap<usize, String> {
+    cluster_features(data, labels, noise, order).into_iter().map(|(label, features)| (label, feature_collection(&features))).collect::<BTreeMap<usize, String>>()
+}
+
+/// Every cluster's features from `cluster_geojson` in a single FeatureCollection, with the noise points as
+/// one more MultiPoint feature whose `cluster` is null.
+pub fn to_geojson<T: Float>(data: &Array2<T>, labels: &[usize], noise: Option<usize>, order: CoordinateOrder) -> String {
+    let mut features = cluster_features(data, labels, noise, order).into_values().flatten().collect::<Vec<String>>();
+    let noise_points = (0..data.rows()).filter(|row| Some(labels[*row]) == noise).map(|row| position(data, row, order)).collect::<Vec<(T, T)>>();
+    if !noise_points.is_empty() {
+        features.push(feature(&geometry("MultiPoint", &noise_points), &format!("\"cluster\":null,\"size\":{}", noise_points.len())));
+    }
+    feature_collection(&features)
+}
+
+/// A JSON object with the labels in row order, the noise label (or null) and, for each cluster in label order,
+/// its `id`, `size` and `centroid`.
+pub fn to_json_summary<T: Float>(data: &Array2<T>, labels: &[usize], noise: Option<usize>) -> String {
+    let clusters = members(data, labels, noise)
+        .into_iter()
+        .map(|(label, rows)| {
+            let count = T::from(rows.len()).expect("T::from(usize)");
+            let centroid = (0..data.cols()).map(|col| number(rows.iter().fold(T::zero(), |acc, row| acc + data[[*row, col]]) / count)).collect::<Vec<String>>();
+            format!("{{\"id\":{},\"size\":{},\"centroid\":[{}]}}", label, rows.len(), centroid.join(","))
+        })
+        .collect::<Vec<String>>();
+    let labels = labels.iter().map(|label| label.to_string()).collect::<Vec<String>>();
+    let noise = noise.map_or("null".to_string(), |label| label.to_string());
+    format!("{{\"labels\":[{}],\"noise\":{},\"clusters\":[{}]}}", labels.join(","), noise, clusters.join(","))
+}
+
+/// Andrew's monotone chain: the hull of `points` counterclockwise from the lowest, leftmost point, without
+/// repeating it. Fewer than three distinct points come back as they are, sorted.
+pub(crate) fn convex_hull<T: Float>(mut points: Vec<(T, T)>) -> Vec<(T, T)> {
+    points.sort_by(|p, q| p.partial_cmp(q).expect("PartialOrd point"));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: (T, T), a: (T, T), b: (T, T)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<(T, T)> = Vec::with_capacity(2 * points.len());
+    for pass in [points.clone(), points.into_iter().rev().collect()].iter() {
+        let start = hull.len();
+        for p in pass.iter() {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], *p) <= T::zero() {
+                hull.pop();
+            }
+            hull.push(*p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+fn members<T: Float>(data: &Array2<T>, labels: &[usize], noise: Option<usize>) -> BTreeMap<usize, Vec<usize>> {
+    assert!(labels.len() == data.rows(), "need one label per row");
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (row, label) in labels.iter().enumerate().filter(|(_, l)| Some(**l) != noise) {
+        members.entry(*label).or_default().push(row);
+    }
+    members
+}
+
+fn cluster_features<T: Float>(data: &Array2<T>, labels: &[usize], noise: Option<usize>, order: CoordinateOrder) -> BTreeMap<usize, Vec<String>> {
+    assert!(data.cols() == 2, "GeoJSON export needs two columns");
+    members(data, labels, noise)
+        .into_iter()
+        .map(|(label, rows)| {
+            let points = rows.iter().map(|row| position(data, *row, order)).collect::<Vec<(T, T)>>();
+            let properties = format!("\"cluster\":{},\"size\":{}", label, rows.len());
+            let mut hull = convex_hull(points.clone());
+            let hull = match hull.len() {
+                1 => geometry("Point", &hull),
+                2 => geometry("LineString", &hull),
+                _ => {
+                    // GeoJSON rings are closed, ending on their first position.
+                    hull.push(hull[0]);
+                    geometry("Polygon", &hull)
+                }
+            };
+            (label, vec![feature(&hull, &properties), feature(&geometry("MultiPoint", &points), &properties)])
+        })
+        .collect::<BTreeMap<usize, Vec<String>>>()
+}
+
+fn position<T: Float>(data: &Array2<T>, row: usize, order: CoordinateOrder) -> (T, T) {
+    match order {
+        CoordinateOrder::LatitudeLongitude => (data[[row, 1]], data[[row, 0]]),
+        CoordinateOrder::XY => (data[[row, 0]], data[[row, 1]]),
+    }
+}
+
+fn geometry<T: Float>(kind: &str, points: &[(T, T)]) -> String {
+    let positions = points.iter().map(|(x, y)| format!("[{},{}]", number(*x), number(*y))).collect::<Vec<String>>().join(",");
+    match kind {
+        "Point" => format!("{{\"type\":\"Point\",\"coordinates\":{}}}", positions),
+        "Polygon" => format!("{{\"type\":\"Polygon\",\"coordinates\":[[{}]]}}", positions),
+        _ => format!("{{\"type\":\"{}\",\"coordinates\":[{}]}}", kind, positions),
+    }
+}
+
+fn feature(geometry: &str, properties: &str) -> String {
+    format!("{{\"type\":\"Feature\",\"geometry\":{},\"properties\":{{{}}}}}", geometry, properties)
+}
+
+fn feature_collection(features: &[String]) -> String {
+    format!("{{\"type\":\"FeatureCollection\",\"features\":[{}]}}", features.join(","))
+}
+
+/// A JSON number, or null for the NaNs and infinities JSON can't hold.
+fn number<T: Float>(x: T) -> String {
+    match x.to_f64().filter(|x| x.is_finite()) {
+        Some(x) => format!("{:?}", x),
+        None => "null".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0, 2.0, 1.0, 1.0, 10.0, 10.0, 50.0, 50.0]).unwrap();
+        let labels = vec![1, 1, 1, 1, 1, 2, 0];
+        assert!(convex_hull(vec![(1.0, 1.0), (0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0), (0.0, 0.0)]) == vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+
+        let clusters = cluster_geojson(&data, &labels, Some(0), CoordinateOrder::XY);
+        assert!(clusters.keys().cloned().collect::<Vec<usize>>() == vec![1, 2]);
+        assert!(clusters[&1].contains("{\"type\":\"Polygon\",\"coordinates\":[[[0.0,0.0],[2.0,0.0],[2.0,2.0],[0.0,2.0],[0.0,0.0]]]},\"properties\":{\"cluster\":1,\"size\":5}"));
+        assert!(clusters[&2].starts_with("{\"type\":\"FeatureCollection\",\"features\":[{\"type\":\"Feature\",\"geometry\":{\"type\":\"Point\",\"coordinates\":[10.0,10.0]}"));
+        let all = to_geojson(&data, &labels, Some(0), CoordinateOrder::LatitudeLongitude);
+        assert!(all.matches("\"type\":\"Feature\"").count() == 5 && all.contains("{\"type\":\"MultiPoint\",\"coordinates\":[[50.0,50.0]]},\"properties\":{\"cluster\":null,\"size\":1}"));
+
+        // Without a noise label, 0 is a cluster like any other.
+        let summary = to_json_summary(&data, &[0, 0, 0, 0, 0, 1, 1], None);
+        assert!(summary == "{\"labels\":[0,0,0,0,0,1,1],\"noise\":null,\"clusters\":[{\"id\":0,\"size\":5,\"centroid\":[1.0,1.0]},{\"id\":1,\"size\":2,\"centroid\":[30.0,30.0]}]}");
+        assert!(to_json_summary(&data, &labels, Some(0)).contains("\"noise\":0,\"clusters\":[{\"id\":1,"));
+    }
+}
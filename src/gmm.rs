@@ -0,0 +1,209 @@
+use crate::kmeans::Kmeans;
+use crate::rng::default_rng;
+use crate::traits::Predict;
+use ndarray::prelude::*;
+use ndarray::ScalarOperand;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use rand::Rng;
+use std::iter::Sum;
+use std::ops::AddAssign;
+
+/// Shape of each component's covariance: a full matrix, a diagonal matrix, or a single variance shared by
+/// every dimension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CovarianceType {
+    Full,
+    Diagonal,
+    Spherical,
+}
+
+/// Gaussian mixture fitted by expectation-maximisation. `covariances` are always stored as full matrices; for
+/// `Diagonal` and `Spherical` fits the off-diagonal entries are zero.
+#[derive(Debug)]
+pub struct GaussianMixture<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> {
+    pub covariance_type: CovarianceType,
+    pub weights: Vec<T>,
+    pub means: Vec<Array1<T>>,
+    pub covariances: Vec<Array2<T>>,
+    /// Mean log-likelihood per point at the final iteration.
+    pub log_likelihood: T,
+    pub converged: bool,
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> GaussianMixture<T> {
+    /// Fits `k` components starting from a k-means partition, stopping after `max_iter` EM iterations or once the
+    /// mean log-likelihood improves by less than `tol`.
+    pub fn fit(data: &Array2<T>, k: usize, covariance_type: CovarianceType, max_iter: usize, tol: T) -> GaussianMixture<T> {
+        GaussianMixture::fit_with_rng(data, k, covariance_type, max_iter, tol, &mut default_rng())
+    }
+
+    pub fn fit_with_rng<R: Rng>(data: &Array2<T>, k: usize, covariance_type: CovarianceType, max_iter: usize, tol: T, rng: &mut R) -> GaussianMixture<T> {
+        let kmeans = Kmeans::fit_with_rng(data, k, 100, T::zero(), rng);
+        let mut responsibilities = Array2::zeros((data.rows(), kmeans.centers.len()));
+        for (row_idx, cluster) in kmeans.clusters.iter().enumerate() {
+            responsibilities[[row_idx, *cluster]] = T::one();
+        }
+        let mut model = GaussianMixture {
+            covariance_type,
+            weights: Vec::new(),
+            means: Vec::new(),
+            covariances: Vec::new(),
+            log_likelihood: T::neg_infinity(),
+            converged: false,
+        };
+        model.maximise(data, &responsibilities);
+        for _ in 0..max_iter {
+            let (responsibilities, log_likelihood) = model.expect(data);
+            model.maximise(data, &responsibilities);
+            let improvement = log_likelihood - model.log_likelihood;
+            model.log_likelihood = log_likelihood;
+            if improvement.abs() < tol {
+                model.converged = true;
+                break;
+            }
+        }
+        model
+    }
+
+    /// The most probable component of each row.
+    pub fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        self.predict_proba(data)
+            .outer_iter()
+            .map(|row| row.iter().enumerate().fold((0, T::neg_infinity()), |best, (c, p)| if *p > best.1 { (c, *p) } else { best }).0)
+            .collect::<Vec<usize>>()
+    }
+
+    /// Posterior probability of each component for each row, one row per point.
+    pub fn predict_proba(&self, data: &Array2<T>) -> Array2<T> {
+        self.expect(data).0
+    }
+
+    fn expect(&self, data: &Array2<T>) -> (Array2<T>, T) {
+        let two_pi = T::from(2.0 * std::f64::consts::PI).expect("T::from(f64)");
+        let half = T::from(0.5).expect("T::from(f64)");
+        let mut log_probabilities = Array2::zeros((data.rows(), self.means.len()));
+        for (c, (mean, covariance)) in self.means.iter().zip(self.covariances.iter()).enumerate() {
+            let lower = cholesky(covariance);
+            let log_det = lower.diag().iter().fold(T::zero(), |acc, x| acc + x.ln()) * (T::one() + T::one());
+            let constant = self.weights[c].ln() - half * (T::from(data.cols()).expect("T::from(usize)") * two_pi.ln() + log_det);
+            for (row, log_probability) in data.outer_iter().zip(log_probabilities.column_mut(c).iter_mut()) {
+                let y = forward_substitute(&lower, &(&row - mean));
+                *log_probability = constant - half * y.iter().fold(T::zero(), |acc, x| acc + *x * *x);
+            }
+        }
+        let mut total = T::zero();
+        for mut row in log_probabilities.outer_iter_mut() {
+            let max = row.iter().cloned().fold(T::neg_infinity(), T::max);
+            let log_sum = max + row.iter().fold(T::zero(), |acc, x| acc + (*x - max).exp()).ln();
+            row.mapv_inplace(|x| (x - log_sum).exp());
+            total += log_sum;
+        }
+        (log_probabilities, total / T::from(data.rows()).expect("T::from(usize)"))
+    }
+
+    fn maximise(&mut self, data: &Array2<T>, responsibilities: &Array2<T>) {
+        let regularisation = T::from(1e-6).expect("T::from(f64)");
+        let tiny = T::epsilon() * T::from(10.0).expect("T::from(f64)");
+        let dims = data.cols();
+        self.weights.clear();
+        self.means.clear();
+        self.covariances.clear();
+        for weights in responsibilities.gencolumns() {
+            let total = weights.sum() + tiny;
+            let mean = data.outer_iter().zip(weights.iter()).fold(Array1::zeros(dims), |acc, (row, w)| acc + &row * *w) / total;
+            let mut covariance = Array2::zeros((dims, dims));
+            for (row, w) in data.outer_iter().zip(weights.iter()) {
+                let centred = &row - &mean;
+                for a in 0..dims {
+                    for b in 0..dims {
+                        covariance[[a, b]] += *w * centred[a] * centred[b];
+                    }
+                }
+            }
+            covariance.mapv_inplace(|x| x / total);
+            match self.covariance_type {
+                CovarianceType::Full => (),
+                CovarianceType::Diagonal => {
+                    let diagonal = covariance.diag().to_owned();
+                    covariance = Array2::zeros((dims, dims));
+                    covariance.diag_mut().assign(&diagonal);
+                }
+                CovarianceType::Spherical => {
+                    let variance = covariance.diag().sum() / T::from(dims).expect("T::from(usize)");
+                    covariance = Array2::eye(dims) * variance;
+                }
+            }
+            for a in 0..dims {
+                covariance[[a, a]] += regularisation;
+            }
+            self.weights.push(total / T::from(data.rows()).expect("T::from(usize)"));
+            self.means.push(mean);
+            self.covariances.push(covariance);
+        }
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Predict<T> for GaussianMixture<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        GaussianMixture::predict(self, data)
+    }
+}
+
+/// Lower-triangular `L` with `L L^T = a`, for symmetric positive definite `a`.
+fn cholesky<T: Float + AddAssign>(a: &Array2<T>) -> Array2<T> {
+    let n = a.rows();
+    let mut lower = Array2::zeros((n, n));
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = (0..j).fold(T::zero(), |acc, k| acc + lower[[i, k]] * lower[[j, k]]);
+            lower[[i, j]] = if i == j { (a[[i, i]] - sum).max(T::epsilon()).sqrt() } else { (a[[i, j]] - sum) / lower[[j, j]] };
+        }
+    }
+    lower
+}
+
+/// Solves `lower * y = b` for `y`.
+fn forward_substitute<T: Float>(lower: &Array2<T>, b: &Array1<T>) -> Array1<T> {
+    let mut y = Array1::zeros(b.len());
+    for i in 0..b.len() {
+        let sum = (0..i).fold(T::zero(), |acc, k| acc + lower[[i, k]] * y[k]);
+        y[i] = (b[i] - sum) / lower[[i, i]];
+    }
+    y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::distributions::StandardNormal;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_gaussian_mixture() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut values = Vec::new();
+        for _ in 0..200 {
+            values.push(rng.sample::<f64, _>(StandardNormal) * 0.5);
+            values.push(rng.sample::<f64, _>(StandardNormal) * 2.0);
+        }
+        for _ in 0..200 {
+            values.push(8.0 + rng.sample::<f64, _>(StandardNormal));
+            values.push(rng.sample::<f64, _>(StandardNormal));
+        }
+        let data = Array2::from_shape_vec((400, 2), values).unwrap();
+        for covariance_type in [CovarianceType::Full, CovarianceType::Diagonal, CovarianceType::Spherical].iter() {
+            let model = GaussianMixture::fit_with_rng(&data, 2, *covariance_type, 100, 1e-6, &mut StdRng::seed_from_u64(1));
+            assert!(model.converged);
+            let labels = model.predict(&data);
+            assert!(labels[..200].iter().all(|l| *l == labels[0]) && labels[200..].iter().all(|l| *l == labels[200]) && labels[0] != labels[200]);
+            let proba = model.predict_proba(&data);
+            assert!(proba.outer_iter().all(|row| (row.sum() - 1.0).abs() < 1e-9));
+        }
+        let full = GaussianMixture::fit_with_rng(&data, 2, CovarianceType::Full, 100, 1e-6, &mut StdRng::seed_from_u64(1));
+        let first = full.predict(&data)[0];
+        assert!(full.covariances[first][[1, 1]] > 2.0 && full.covariances[first][[0, 0]] < 0.5);
+        assert!((full.weights[0] - 0.5).abs() < 0.05);
+    }
+}
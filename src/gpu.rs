@@ -0,0 +1,214 @@
+//! Brute-force region queries on the GPU through wgpu, for `NeighbourSearch::Gpu`. Every point is compared
+//! with every other in a compute shader, a block of query rows at a time, and each block comes back as a
+//! bitmask of the pairs within the search radius. On dense high-dimensional data, where a KdTree prunes almost
+//! nothing, this beats it by an order of magnitude. Distances are taken in `f32`, so `region_queries` searches
+//! a radius widened by their rounding error and settles the pairs it finds exactly.
+
+use ndarray::prelude::*;
+use ndarray::Data;
+use num_traits::float::Float;
+use std::sync::OnceLock;
+use wgpu::util::DeviceExt;
+
+/// Threads per workgroup along the compared points.
+const WORKGROUP_SIZE: u32 = 64;
+/// Most bytes of bitmask a block of query rows writes, which bounds the device memory a fit takes beyond the points.
+const MAX_MASK_BYTES: usize = 1 << 26;
+
+/// Marks `mask` bit `j` of row `q` when the squared distance from point `first + q` to point `j` is at most
+/// `squared_radius`.
+const SHADER: &str = "
+struct Params {
+    n: u32,
+    dims: u32,
+    first: u32,
+    rows: u32,
+    squared_radius: f32,
+    words: u32,
+}
+
+@group(0) @binding(0) var<storage, read> points: array<f32>;
+@group(0) @binding(1) var<uniform> params: Params;
+@group(0) @binding(2) var<storage, read_write> mask: array<atomic<u32>>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let j = id.x;
+    let q = id.y;
+    if (j >= params.n || q >= params.rows) {
+        return;
+    }
+    let a = (params.first + q) * params.dims;
+    let b = j * params.dims;
+    var sum = 0.0;
+    for (var d = 0u; d < params.dims; d = d + 1u) {
+        let difference = points[a + d] - points[b + d];
+        sum = sum + difference * difference;
+    }
+    if (sum <= params.squared_radius) {
+        atomicOr(&mask[q * params.words + j / 32u], 1u << (j % 32u));
+    }
+}
+";
+
+pub(crate) struct GpuNeighbours {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+/// Whether a GPU adapter was found, so that `NeighbourSearch::Gpu` runs on it rather than falling back to the
+/// neighbour index.
+pub fn available() -> bool {
+    GpuNeighbours::get().is_some()
+}
+
+impl GpuNeighbours {
+    /// The device every GPU fit shares, opened on first use; `None` when there is no adapter.
+    pub(crate) fn get() -> Option<&'static GpuNeighbours> {
+        static DEVICE: OnceLock<Option<GpuNeighbours>> = OnceLock::new();
+        DEVICE.get_or_init(|| pollster::block_on(GpuNeighbours::open())).as_ref()
+    }
+
+    async fn open() -> Option<GpuNeighbours> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle_from_env());
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions { power_preference: wgpu::PowerPreference::HighPerformance, ..Default::default() }).await.ok()?;
+        let descriptor = wgpu::DeviceDescriptor { label: Some("clust-rs"), required_limits: adapter.limits(), ..Default::default() };
+        let (device, queue) = adapter.request_device(&descriptor).await.ok()?;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor { label: Some("region query"), source: wgpu::ShaderSource::Wgsl(SHADER.into()) });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("region query"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Some(GpuNeighbours { device, queue, pipeline })
+    }
+
+    /// For each of the `dims`-wide rows of the row-major `points`, the rows within `radius` of it in `f32`
+    /// arithmetic, itself included, in ascending order. `None` when the points don't fit in one buffer on this
+    /// device or are too many to dispatch.
+    pub(crate) fn within_all(&self, points: &[f32], dims: usize, radius: f32) -> Option<Vec<Vec<usize>>> {
+        let dims = dims.max(1);
+        let n = points.len() / dims;
+        let limits = self.device.limits();
+        let groups = n.div_ceil(WORKGROUP_SIZE as usize);
+        if n == 0 || points.len() * 4 > limits.max_storage_buffer_binding_size as usize || groups > limits.max_compute_workgroups_per_dimension as usize {
+            return if n == 0 { Some(Vec::new()) } else { None };
+        }
+        let words = n.div_ceil(32);
+        let block = (MAX_MASK_BYTES.min(limits.max_storage_buffer_binding_size as usize) / (words * 4)).clamp(1, limits.max_compute_workgroups_per_dimension as usize).min(n);
+        let mask_size = (block * words * 4) as u64;
+
+        let points = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("points"),
+            contents: &points.iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>(),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let params = self.device.create_buffer(&wgpu::BufferDescriptor { label: Some("params"), size: 32, usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false });
+        let mask = self.device.create_buffer(&wgpu::BufferDescriptor { label: Some("mask"), size: mask_size, usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor { label: Some("staging"), size: mask_size, usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST, mapped_at_creation: false });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("region query"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: points.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: mask.as_entire_binding() },
+            ],
+        });
+
+        let mut neighbours = Vec::with_capacity(n);
+        for first in (0..n).step_by(block) {
+            let rows = block.min(n - first);
+            let values = [n as u32, dims as u32, first as u32, rows as u32, (radius * radius).to_bits(), words as u32, 0, 0];
+            self.queue.write_buffer(&params, 0, &values.iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>());
+            let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("region query") });
+            encoder.clear_buffer(&mask, 0, None);
+            {
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: Some("region query"), timestamp_writes: None });
+                pass.set_pipeline(&self.pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                pass.dispatch_workgroups(groups as u32, rows as u32, 1);
+            }
+            let used = (rows * words * 4) as u64;
+            encoder.copy_buffer_to_buffer(&mask, 0, &staging, 0, used);
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = staging.slice(..used);
+            slice.map_async(wgpu::MapMode::Read, |_| ());
+            self.device.poll(wgpu::PollType::wait_indefinitely()).ok()?;
+            {
+                let bytes = slice.get_mapped_range().ok()?;
+                let bits = bytes.chunks_exact(4).map(|word| u32::from_ne_bytes([word[0], word[1], word[2], word[3]])).collect::<Vec<u32>>();
+                neighbours.extend(bits.chunks_exact(words).map(set_bits));
+            }
+            staging.unmap();
+        }
+        Some(neighbours)
+    }
+}
+
+/// Each row's neighbours within `eps` of it, itself included and in ascending order, found on the GPU; `None`
+/// when there is no adapter or `data` is too large for it. The GPU compares `f32` copies of the rows within a
+/// radius widened by their rounding error, and the pairs it finds are then checked exactly in `T`, so the
+/// neighbourhoods are those an exact search gives.
+pub(crate) fn region_queries<T: Float, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, eps: T) -> Option<Vec<Vec<usize>>> {
+    let gpu = GpuNeighbours::get()?;
+    let points = data.iter().map(|x| x.to_f32().expect("T::to_f32")).collect::<Vec<f32>>();
+    // Rounding moves each point by at most sqrt(dims) ulps of the largest coordinate, and the f32 arithmetic
+    // adds about dims ulps of the distance itself; the margin allows twice both.
+    let dims = data.cols() as f32;
+    let largest = points.iter().fold(0.0f32, |acc, x| acc.max(x.abs()));
+    let search = eps.to_f32().expect("T::to_f32");
+    let search = search + f32::EPSILON * 2.0 * (2.0 * dims.sqrt() * largest + dims * search);
+    let mut neighbours = gpu.within_all(&points, data.cols(), search)?;
+    let exact = |a: usize, b: usize| data.row(a).iter().zip(data.row(b).iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y).powi(2)) <= eps * eps;
+    for (row, candidates) in neighbours.iter_mut().enumerate() {
+        candidates.retain(|other| exact(row, *other));
+    }
+    Some(neighbours)
+}
+
+/// Positions of the set bits of `words`, least significant bit of the first word first.
+fn set_bits(words: &[u32]) -> Vec<usize> {
+    let mut set = Vec::new();
+    for (w, word) in words.iter().enumerate() {
+        let mut word = *word;
+        while word != 0 {
+            set.push(w * 32 + word.trailing_zeros() as usize);
+            word &= word - 1;
+        }
+    }
+    set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shader_validates() {
+        let module = wgpu::naga::front::wgsl::parse_str(SHADER).unwrap();
+        wgpu::naga::valid::Validator::new(wgpu::naga::valid::ValidationFlags::all(), wgpu::naga::valid::Capabilities::empty()).validate(&module).unwrap();
+        assert!(SHADER.contains(&format!("@workgroup_size({})", WORKGROUP_SIZE)));
+        assert!(set_bits(&[0b1001, 0, 1 << 31]) == vec![0, 3, 95] && set_bits(&[0, 0]).is_empty());
+    }
+
+    #[test]
+    fn test_within_all() {
+        // Needs an adapter; machines without one only check the shader above.
+        let gpu = match GpuNeighbours::get() {
+            Some(gpu) => gpu,
+            None => return,
+        };
+        let points = (0..300).flat_map(|i| vec![(i % 7) as f32 * 0.3, (i / 7) as f32 * 0.3, 1.0]).collect::<Vec<f32>>();
+        let within = gpu.within_all(&points, 3, 0.35).unwrap();
+        for (i, row) in points.chunks_exact(3).enumerate() {
+            let expected = points.chunks_exact(3).enumerate().filter(|(_, other)| row.iter().zip(other.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f32>() <= 0.35 * 0.35).map(|(j, _)| j).collect::<Vec<usize>>();
+            assert!(within[i] == expected);
+        }
+    }
+}
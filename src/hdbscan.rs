@@ -0,0 +1,472 @@
+use crate::hierarchical::Dendrogram;
+use crate::index::{index_rows, row_slice, squared_euclidean};
+use crate::itertools::Itertools;
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use crate::union_find::UnionFind;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::collections::HashMap;
+
+/// Minimum spanning tree of the mutual reachability graph, with edges `(a, b, weight)` sorted by ascending weight.
+/// Cutting every edge heavier than some level gives the single-linkage clusters at that level.
+pub fn robust_single_linkage<T: Float + One + Zero>(data: &Array2<T>, min_points: usize) -> Vec<(usize, usize, T)> {
+    let mut edges = minimum_spanning_tree(&mutual_reachability(data, min_points));
+    edges.sort_by(|a, b| a.2.partial_cmp(&b.2).expect("PartialOrd edge weight"));
+    edges
+}
+
+/// Single-linkage hierarchy of DBSCAN clusterings over every eps, with borders assigned as in
+/// `Dbscan::new(data, eps, min_points, true)`. Merges are recorded in increasing eps, linkage style: points are
+/// clusters `0..n` and the i-th merge creates cluster `n + i`.
+#[derive(Debug)]
+pub struct DbscanHierarchy<T: Float + One + Zero> {
+    pub min_points: usize,
+    pub merges: Vec<(usize, usize, T)>,
+    core_distances: Vec<T>,
+    borders: Vec<(T, usize)>,
+}
+
+impl<T: Float + One + Zero> DbscanHierarchy<T> {
+    pub fn new(data: &Array2<T>, min_points: usize) -> DbscanHierarchy<T> {
+        let core = core_distances(data, min_points);
+        let mut borders = Vec::with_capacity(data.rows());
+        for (idx, row) in data.outer_iter().enumerate() {
+            let border = data
+                .outer_iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(other, other_row)| (squared_euclidean(&row_slice(row.view()), &row_slice(other_row.view())).sqrt().max(core[other]), other))
+                .min_by(|a, b| a.0.partial_cmp(&b.0).expect("PartialOrd distance"))
+                .unwrap_or((T::infinity(), idx));
+            borders.push(border);
+        }
+
+        let n = data.rows();
+        let mut components = UnionFind::new(2 * n);
+        let mut representative = (0..n).collect::<Vec<usize>>();
+        let mut merges = Vec::with_capacity(n.saturating_sub(1));
+        for (a, b, eps) in robust_single_linkage(data, min_points) {
+            let cluster_a = representative[components.find(a)];
+            let cluster_b = representative[components.find(b)];
+            let root = components.union(a, b);
+            representative[root] = n + merges.len();
+            merges.push((cluster_a, cluster_b, eps));
+        }
+
+        DbscanHierarchy {
+            min_points,
+            merges,
+            core_distances: core,
+            borders,
+        }
+    }
+
+    /// The merges as a `Dendrogram` over the core points' eps levels; noise and borders, which `labels_at`
+    /// handles apart from the merges, aren't in it.
+    pub fn dendrogram(&self) -> Dendrogram<T> {
+        let n = self.core_distances.len();
+        let mut sizes = Vec::with_capacity(self.merges.len());
+        for (a, b, _) in self.merges.iter() {
+            let size = |node: usize| if node < n { 1 } else { sizes[node - n] };
+            sizes.push(size(*a) + size(*b));
+        }
+        Dendrogram::from_linkage(self.merges.iter().zip(sizes).map(|((a, b, eps), size)| (*a, *b, *eps, size)).collect::<Vec<(usize, usize, T, usize)>>())
+    }
+
+    /// Flat DBSCAN labels at `eps`, numbered from 1 in order of each cluster's first point; noise is 0.
+    pub fn labels_at(&self, eps: T) -> Vec<usize> {
+        let n = self.core_distances.len();
+        let mut components = UnionFind::new(2 * n);
+        for (i, (a, b, weight)) in self.merges.iter().enumerate() {
+            if *weight <= eps {
+                components.union(*a, n + i);
+                components.union(*b, n + i);
+            }
+        }
+
+        let mut ids = vec![0; 2 * n];
+        let mut next = 1;
+        let mut labels = vec![0; n];
+        for (idx, label) in labels.iter_mut().enumerate() {
+            let core = if self.core_distances[idx] <= eps {
+                Some(idx)
+            } else if self.borders[idx].0 <= eps {
+                Some(self.borders[idx].1)
+            } else {
+                None
+            };
+            if let Some(core) = core {
+                let root = components.find(core);
+                if ids[root] == 0 {
+                    ids[root] = next;
+                    next += 1;
+                }
+                *label = ids[root];
+            }
+        }
+        labels
+    }
+}
+
+/// HDBSCAN (Campello et al., 2013): builds the single-linkage tree of the mutual reachability graph, condenses it
+/// to the splits where both sides keep at least `min_cluster_size` points, and picks the flat set of clusters with
+/// the greatest total stability. `clusters` is 0 for noise and numbers the rest from 1 in order of their first point.
+///
+/// `condensed_tree` holds `(parent, child, lambda, size)` with `lambda = 1 / distance`: cluster ids start at `n`
+/// (the root), and a child below `n` is a point falling out of its parent.
+#[derive(Debug)]
+pub struct Hdbscan<T: Float + One + Zero> {
+    pub min_points: usize,
+    pub min_cluster_size: usize,
+    pub clusters: Vec<usize>,
+    pub condensed_tree: Vec<(usize, usize, T, usize)>,
+}
+
+impl<T: Float + One + Zero> Hdbscan<T> {
+    pub fn new(data: &Array2<T>, min_points: usize, min_cluster_size: usize) -> Hdbscan<T> {
+        assert!(min_cluster_size >= 2, "min_cluster_size must be at least 2");
+        let n = data.rows();
+        let condensed_tree = condense(&single_linkage_tree(data, min_points), n, min_cluster_size);
+        let clusters = select_clusters(&condensed_tree, n);
+        Hdbscan {
+            min_points,
+            min_cluster_size,
+            clusters,
+            condensed_tree,
+        }
+    }
+}
+
+impl<T: Float + One + Zero> Hdbscan<T> {
+    pub fn tree(&self) -> CondensedTree<T> {
+        CondensedTree {
+            n_points: self.clusters.len(),
+            edges: self.condensed_tree.clone(),
+        }
+    }
+}
+
+/// HDBSCAN's condensed tree, for walking the hierarchy of clusters behind the flat labels. Clusters are
+/// numbered from `n_points`, the root; each is born at the lambda (1 / distance) its parent split at, and
+/// loses points one by one or in groups too small to count until it splits or empties.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CondensedTree<T: Float> {
+    pub n_points: usize,
+    /// `(parent, child, lambda, size)` as in `Hdbscan::condensed_tree`.
+    pub edges: Vec<(usize, usize, T, usize)>,
+}
+
+impl<T: Float> CondensedTree<T> {
+    pub fn root(&self) -> usize {
+        self.n_points
+    }
+
+    /// Every cluster, root first, in ascending order.
+    pub fn clusters(&self) -> Vec<usize> {
+        std::iter::once(self.root()).chain(self.edges.iter().map(|e| e.1).filter(|c| *c >= self.n_points)).sorted().collect::<Vec<usize>>()
+    }
+
+    pub fn parent(&self, cluster: usize) -> Option<usize> {
+        self.edges.iter().find(|e| e.1 == cluster && cluster >= self.n_points).map(|e| e.0)
+    }
+
+    /// The clusters `cluster` splits into, empty if it only loses points.
+    pub fn children(&self, cluster: usize) -> Vec<usize> {
+        self.edges.iter().filter(|e| e.0 == cluster && e.1 >= self.n_points).map(|e| e.1).collect::<Vec<usize>>()
+    }
+
+    /// The points falling out of `cluster` itself, with the lambda each leaves at.
+    pub fn points(&self, cluster: usize) -> Vec<(usize, T)> {
+        self.edges.iter().filter(|e| e.0 == cluster && e.1 < self.n_points).map(|e| (e.1, e.2)).collect::<Vec<(usize, T)>>()
+    }
+
+    /// The lambda `cluster` is born at, 0 for the root.
+    pub fn birth(&self, cluster: usize) -> T {
+        self.edges.iter().find(|e| e.1 == cluster && cluster >= self.n_points).map_or(T::zero(), |e| e.2)
+    }
+
+    /// Number of points in `cluster` when it's born.
+    pub fn size(&self, cluster: usize) -> usize {
+        match cluster == self.root() {
+            true => self.n_points,
+            false => self.edges.iter().find(|e| e.1 == cluster).map_or(0, |e| e.3),
+        }
+    }
+
+    /// Excess of mass, the stability HDBSCAN selects clusters by: the lambda each point stays in `cluster`
+    /// past its birth, summed.
+    pub fn stability(&self, cluster: usize) -> T {
+        let birth = self.birth(cluster);
+        self.edges.iter().filter(|e| e.0 == cluster).fold(T::zero(), |acc, e| acc + (e.2 - birth) * T::from(e.3).expect("T::from(usize)"))
+    }
+
+    /// Flat labels at `lambda`: each point that hasn't yet fallen out takes the deepest cluster born below
+    /// `lambda` that holds it, as in DBSCAN* at eps = 1 / lambda without clusters smaller than the tree's
+    /// minimum cluster size. Clusters are numbered from 1 in order of their first point; noise is 0.
+    pub fn labels_at(&self, lambda: T) -> Vec<usize> {
+        let mut ids = HashMap::new();
+        let mut labels = vec![0; self.n_points];
+        let mut owners = vec![None; self.n_points];
+        for (cluster, point, leaves, _) in self.edges.iter().filter(|e| e.1 < self.n_points) {
+            if lambda <= *leaves {
+                let mut cluster = *cluster;
+                while cluster != self.root() && self.birth(cluster) >= lambda {
+                    cluster = self.parent(cluster).expect("parent cluster");
+                }
+                owners[*point] = Some(cluster);
+            }
+        }
+        for (label, owner) in labels.iter_mut().zip(owners.iter()) {
+            if let Some(cluster) = owner {
+                let next = ids.len() + 1;
+                *label = *ids.entry(*cluster).or_insert(next);
+            }
+        }
+        labels
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for Hdbscan<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+/// Merges of the minimum spanning tree as `(a, b, distance, size)`, linkage style: the i-th merge creates node `n + i`.
+fn single_linkage_tree<T: Float + One + Zero>(data: &Array2<T>, min_points: usize) -> Vec<(usize, usize, T, usize)> {
+    let n = data.rows();
+    let mut components = UnionFind::new(n);
+    let mut representative = (0..n).collect::<Vec<usize>>();
+    let mut tree: Vec<(usize, usize, T, usize)> = Vec::with_capacity(n.saturating_sub(1));
+    for (a, b, distance) in robust_single_linkage(data, min_points) {
+        let node_a = representative[components.find(a)];
+        let node_b = representative[components.find(b)];
+        let size = |node: usize| if node < n { 1 } else { tree[node - n].3 };
+        let merged = (node_a, node_b, distance, size(node_a) + size(node_b));
+        let root = components.union(a, b);
+        representative[root] = n + tree.len();
+        tree.push(merged);
+    }
+    tree
+}
+
+fn condense<T: Float>(tree: &[(usize, usize, T, usize)], n: usize, min_cluster_size: usize) -> Vec<(usize, usize, T, usize)> {
+    let mut condensed = Vec::new();
+    if tree.is_empty() {
+        return condensed;
+    }
+    let size = |node: usize| if node < n { 1 } else { tree[node - n].3 };
+    let points = |node: usize| {
+        let mut stack = vec![node];
+        let mut points = Vec::new();
+        while let Some(node) = stack.pop() {
+            if node < n {
+                points.push(node);
+            } else {
+                stack.push(tree[node - n].0);
+                stack.push(tree[node - n].1);
+            }
+        }
+        points
+    };
+    let mut next = n + 1;
+    let mut stack = vec![(n + tree.len() - 1, n)];
+    while let Some((node, label)) = stack.pop() {
+        let (left, right, distance, _) = tree[node - n];
+        let lambda = if distance > T::zero() { distance.recip() } else { T::max_value() };
+        for (child, sibling) in [(left, right), (right, left)].iter() {
+            if size(*child) < min_cluster_size {
+                condensed.extend(points(*child).into_iter().map(|point| (label, point, lambda, 1)));
+            } else if size(*sibling) < min_cluster_size {
+                stack.push((*child, label));
+            } else {
+                condensed.push((label, next, lambda, size(*child)));
+                stack.push((*child, next));
+                next += 1;
+            }
+        }
+    }
+    condensed
+}
+
+/// Excess-of-mass selection: a cluster is kept when its stability is at least the summed stability of the best
+/// clusters below it. The root is never selected.
+fn select_clusters<T: Float>(condensed: &[(usize, usize, T, usize)], n: usize) -> Vec<usize> {
+    let count = condensed.iter().map(|(_, child, _, _)| *child + 1).max().unwrap_or(0).max(n + 1) - n;
+    let mut birth = vec![T::zero(); count];
+    let mut parent = vec![0; count];
+    for (p, c, lambda, _) in condensed.iter().filter(|(_, c, _, _)| *c >= n) {
+        birth[c - n] = *lambda;
+        parent[c - n] = p - n;
+    }
+    let mut stability = vec![T::zero(); count];
+    for (p, _, lambda, size) in condensed.iter() {
+        stability[p - n] = stability[p - n] + (*lambda - birth[p - n]) * T::from(*size).expect("T::from(usize)");
+    }
+
+    let mut selected = vec![false; count];
+    let mut below = vec![T::zero(); count];
+    for c in (1..count).rev() {
+        selected[c] = stability[c] >= below[c];
+        below[parent[c]] = below[parent[c]] + if selected[c] { stability[c] } else { below[c] };
+    }
+    let mut covered = vec![false; count];
+    for c in 1..count {
+        covered[c] = parent[c] != 0 && (selected[parent[c]] || covered[parent[c]]);
+        selected[c] = selected[c] && !covered[c];
+    }
+
+    let mut owner = vec![count; n];
+    for (p, point, _, _) in condensed.iter().filter(|(_, c, _, _)| *c < n) {
+        let mut c = p - n;
+        while c != 0 && !selected[c] {
+            c = parent[c];
+        }
+        owner[*point] = if c == 0 { count } else { c };
+    }
+    let mut ids = vec![0; count + 1];
+    let mut next = 1;
+    owner
+        .iter()
+        .map(|c| {
+            if *c < count && ids[*c] == 0 {
+                ids[*c] = next;
+                next += 1;
+            }
+            ids[*c]
+        })
+        .collect::<Vec<usize>>()
+}
+
+/// Distance from each point to its `min_points`-th nearest, itself included: the smallest eps at which it is
+/// core. Infinite when there are fewer than `min_points` points, since then no eps makes it core.
+fn core_distances<T: Float + One + Zero>(data: &Array2<T>, min_points: usize) -> Vec<T> {
+    let index = index_rows(data);
+    data.outer_iter()
+        .map(|row| {
+            let nearest = index.nearest(&row_slice(row.view()), min_points);
+            if nearest.len() < min_points {
+                T::infinity()
+            } else {
+                nearest.last().map_or(T::zero(), |(distance, _)| *distance)
+            }
+        })
+        .collect::<Vec<T>>()
+}
+
+fn mutual_reachability<T: Float + One + Zero>(data: &Array2<T>, min_points: usize) -> Array2<T> {
+    let core = core_distances(data, min_points);
+    let mut distances = Array2::zeros((data.rows(), data.rows()));
+    for (a, row_a) in data.outer_iter().enumerate() {
+        for (b, row_b) in data.outer_iter().enumerate().skip(a + 1) {
+            let distance = squared_euclidean(&row_slice(row_a.view()), &row_slice(row_b.view())).sqrt();
+            let reachability = distance.max(core[a]).max(core[b]);
+            distances[[a, b]] = reachability;
+            distances[[b, a]] = reachability;
+        }
+    }
+    distances
+}
+
+fn minimum_spanning_tree<T: Float>(distances: &Array2<T>) -> Vec<(usize, usize, T)> {
+    let n = distances.rows();
+    let mut edges = Vec::with_capacity(n.saturating_sub(1));
+    if n == 0 {
+        return edges;
+    }
+    let mut in_tree = vec![false; n];
+    let mut best = vec![(T::infinity(), 0); n];
+    let mut current = 0;
+    in_tree[current] = true;
+    for _ in 1..n {
+        for (other, candidate) in best.iter_mut().enumerate() {
+            if !in_tree[other] && distances[[current, other]] < candidate.0 {
+                *candidate = (distances[[current, other]], current);
+            }
+        }
+        let (next, (weight, from)) = best
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !in_tree[*idx])
+            .min_by(|(_, a), (_, b)| a.0.partial_cmp(&b.0).expect("PartialOrd edge weight"))
+            .expect("remaining vertex");
+        edges.push((*from, next, *weight));
+        in_tree[next] = true;
+        current = next;
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use crate::index::column_major;
+    use crate::labels::same_partition;
+    use ndarray::s;
+
+    #[test]
+    fn test_robust_single_linkage() {
+        let data = Array2::from_shape_vec((8, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.1, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 5.1, 5.1]).unwrap();
+        let edges = robust_single_linkage(&data, 2);
+        assert!(edges.len() == 7);
+        assert!(edges.windows(2).all(|w| w[0].2 <= w[1].2));
+        let (a, b, weight) = *edges.last().unwrap();
+        assert!((a < 4) != (b < 4));
+        assert!(weight > 6.0);
+        assert!(edges.iter().take(6).all(|e| e.2 < 0.2));
+        assert!(robust_single_linkage(&column_major(&data), 2) == edges);
+    }
+
+    #[test]
+    fn test_hierarchy_matches_dbscan() {
+        let data = Array2::from_shape_vec((8, 1), vec![0.0, 0.1, 0.2, 0.3, 1.0, 1.1, 1.2, 2.0]).unwrap();
+        let hierarchy = DbscanHierarchy::new(&data, 3);
+        assert!(hierarchy.merges.len() == 7);
+        let dendrogram = hierarchy.dendrogram();
+        assert!(dendrogram.size(dendrogram.root()) == 8 && dendrogram.cut(3) == vec![1, 1, 1, 1, 2, 2, 2, 3]);
+        for eps in [0.15, 0.5, 0.75, 1.5].iter() {
+            let labels = hierarchy.labels_at(*eps);
+            let model = Dbscan::new(&data, *eps, 3, true);
+            assert!(same_partition(&labels, &model.clusters), "eps {}: {:?} vs {:?}", eps, labels, model.clusters);
+        }
+
+        // With fewer points than min_points nothing is ever core, so every eps leaves them all noise.
+        let few = data.slice(s![..2, ..]).to_owned();
+        let hierarchy = DbscanHierarchy::new(&few, 3);
+        for eps in [0.15, 1.5, 1e9].iter() {
+            assert!(hierarchy.labels_at(*eps) == vec![0, 0] && Dbscan::new(&few, *eps, 3, true).clusters == vec![0, 0]);
+        }
+        assert!(core_distances(&few, 3) == vec![f64::INFINITY; 2] && core_distances(&few, 2) == vec![0.1; 2]);
+    }
+
+    #[test]
+    fn test_hdbscan_varying_density() {
+        let data = Array2::from_shape_vec(
+            (11, 2),
+            vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.1, 0.1, 0.05, 0.05, 20.0, 20.0, 21.0, 20.0, 20.0, 21.0, 21.0, 21.0, 20.5, 20.5, 100.0, 100.0],
+        )
+        .unwrap();
+        let model = Hdbscan::new(&data, 3, 3);
+        assert!(model.clusters == vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 0], "{:?}", model.clusters);
+        assert!(model.condensed_tree.iter().filter(|(_, child, _, _)| *child < 11).count() == 11);
+        assert!(Hdbscan::new(&data.slice(s![..1, ..]).to_owned(), 3, 3).clusters == vec![0]);
+        assert!(Hdbscan::new(&column_major(&data), 3, 3).clusters == model.clusters && DbscanHierarchy::new(&column_major(&data), 3).merges == DbscanHierarchy::new(&data, 3).merges);
+
+        let tree = model.tree();
+        let (root, clusters) = (tree.root(), tree.clusters());
+        assert!(clusters.len() == 3 && tree.children(root) == clusters[1..].to_vec() && tree.parent(clusters[1]) == Some(root) && tree.points(root).len() == 1);
+        assert!(tree.size(root) == 11 && tree.size(clusters[1]) + tree.size(clusters[2]) == 10 && tree.stability(clusters[1]) > 0.0);
+        // Just past the split both blobs are clusters, before it only the outlier stands apart, and far past it
+        // every point has fallen out.
+        let split = tree.birth(clusters[1]);
+        assert!(tree.labels_at(split * 1.01) == vec![1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 0] && tree.labels_at(split * 0.99) == vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0]);
+        assert!(tree.labels_at(1e9) == vec![0; 11]);
+    }
+}
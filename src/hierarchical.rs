@@ -0,0 +1,268 @@
+use crate::index::{row_slice, squared_euclidean};
+use crate::union_find::UnionFind;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+
+/// Distance between two clusters in terms of the distances between their points. `Ward` merges the pair that
+/// least increases the within-cluster sum of squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Linkage {
+    Single,
+    Complete,
+    Average,
+    Ward,
+}
+
+/// Agglomerative clustering with Euclidean distances. `merges` is a linkage matrix of `(a, b, distance, size)`
+/// in merge order: points are clusters `0..n` and the i-th merge creates cluster `n + i` of `size` points.
+#[derive(Debug)]
+pub struct Agglomerative<T: Float + One + Zero> {
+    pub linkage: Linkage,
+    pub n_points: usize,
+    pub merges: Vec<(usize, usize, T, usize)>,
+}
+
+impl<T: Float + One + Zero> Agglomerative<T> {
+    /// Builds the full dendrogram in O(n^3) time from an O(n^2) distance matrix, updated by the Lance-Williams formula.
+    pub fn new(data: &Array2<T>, linkage: Linkage) -> Agglomerative<T> {
+        let n = data.rows();
+        let mut distances = Array2::zeros((n, n));
+        for (a, row_a) in data.outer_iter().enumerate() {
+            for (b, row_b) in data.outer_iter().enumerate().skip(a + 1) {
+                let distance = squared_euclidean(&row_slice(row_a.view()), &row_slice(row_b.view())).sqrt();
+                distances[[a, b]] = distance;
+                distances[[b, a]] = distance;
+            }
+        }
+
+        // Each active slot holds one cluster: its id in the linkage matrix and its size.
+        let mut active = (0..n).map(|idx| Some((idx, 1))).collect::<Vec<Option<(usize, usize)>>>();
+        let mut merges = Vec::with_capacity(n.saturating_sub(1));
+        for _ in 1..n {
+            let mut best = (T::infinity(), 0, 0);
+            for a in (0..n).filter(|a| active[*a].is_some()) {
+                for b in (a + 1..n).filter(|b| active[*b].is_some()) {
+                    if distances[[a, b]] < best.0 {
+                        best = (distances[[a, b]], a, b);
+                    }
+                }
+            }
+            let (distance, a, b) = best;
+            let (id_a, size_a) = active[a].expect("active cluster");
+            let (id_b, size_b) = active[b].expect("active cluster");
+            for other in (0..n).filter(|other| *other != a && *other != b) {
+                if let Some((_, size_other)) = active[other] {
+                    let updated = lance_williams(linkage, distances[[a, other]], distances[[b, other]], distance, size_a, size_b, size_other);
+                    distances[[a, other]] = updated;
+                    distances[[other, a]] = updated;
+                }
+            }
+            active[a] = Some((n + merges.len(), size_a + size_b));
+            active[b] = None;
+            merges.push((id_a, id_b, distance, size_a + size_b));
+        }
+
+        Agglomerative { linkage, n_points: n, merges }
+    }
+
+    /// Flat labels with `k` clusters, numbered from 1 in order of each cluster's first point.
+    pub fn cut(&self, k: usize) -> Vec<usize> {
+        self.labels_after(self.n_points.saturating_sub(k.max(1)))
+    }
+
+    /// Flat labels from every merge at or below `distance`, numbered from 1 in order of each cluster's first point.
+    pub fn cut_distance(&self, distance: T) -> Vec<usize> {
+        self.labels_after(self.merges.iter().take_while(|(_, _, d, _)| *d <= distance).count())
+    }
+
+    fn labels_after(&self, count: usize) -> Vec<usize> {
+        flat_labels(self.n_points, &self.merges, count)
+    }
+
+    pub fn dendrogram(&self) -> Dendrogram<T> {
+        Dendrogram::from_linkage(self.merges.clone())
+    }
+}
+
+/// A binary merge tree over n points, for walking the hierarchy rather than only cutting it. Nodes are
+/// numbered as in a linkage matrix: points are leaves `0..n`, the i-th merge creates node `n + i` at the
+/// height it merged, and the last merge is the root.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dendrogram<T: Float> {
+    /// `(a, b, height, size)` in merge order, with heights non-decreasing.
+    pub merges: Vec<(usize, usize, T, usize)>,
+    parents: Vec<Option<usize>>,
+}
+
+impl<T: Float> Dendrogram<T> {
+    /// From a linkage matrix of `(a, b, height, size)` rows, as `Agglomerative::merges` holds; no merges is a
+    /// single point.
+    pub fn from_linkage(merges: Vec<(usize, usize, T, usize)>) -> Dendrogram<T> {
+        let n = merges.len() + 1;
+        let mut parents = vec![None; 2 * n - 1];
+        for (i, (a, b, _, _)) in merges.iter().enumerate() {
+            assert!(*a < n + i && *b < n + i && parents[*a].is_none() && parents[*b].is_none(), "merge {} joins a missing or already merged node", i);
+            parents[*a] = Some(n + i);
+            parents[*b] = Some(n + i);
+        }
+        Dendrogram { merges, parents }
+    }
+
+    pub fn n_points(&self) -> usize {
+        self.merges.len() + 1
+    }
+
+    pub fn root(&self) -> usize {
+        2 * self.merges.len()
+    }
+
+    /// The two nodes merged into `node`, or `None` for a point.
+    pub fn children(&self, node: usize) -> Option<(usize, usize)> {
+        node.checked_sub(self.n_points()).map(|i| (self.merges[i].0, self.merges[i].1))
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.parents[node]
+    }
+
+    /// The height `node` was formed at, 0 for a point.
+    pub fn height(&self, node: usize) -> T {
+        node.checked_sub(self.n_points()).map_or(T::zero(), |i| self.merges[i].2)
+    }
+
+    /// Number of points under `node`.
+    pub fn size(&self, node: usize) -> usize {
+        node.checked_sub(self.n_points()).map_or(1, |i| self.merges[i].3)
+    }
+
+    /// The points under `node`, in ascending order.
+    pub fn leaves(&self, node: usize) -> Vec<usize> {
+        let mut stack = vec![node];
+        let mut leaves = Vec::with_capacity(self.size(node));
+        while let Some(node) = stack.pop() {
+            match self.children(node) {
+                Some((a, b)) => stack.extend_from_slice(&[a, b]),
+                None => leaves.push(node),
+            }
+        }
+        leaves.sort_unstable();
+        leaves
+    }
+
+    /// Flat labels with `k` clusters, numbered from 1 in order of each cluster's first point.
+    pub fn cut(&self, k: usize) -> Vec<usize> {
+        flat_labels(self.n_points(), &self.merges, self.n_points().saturating_sub(k.max(1)))
+    }
+
+    /// Flat labels from every merge at or below `height`, numbered from 1 in order of each cluster's first point.
+    pub fn cut_height(&self, height: T) -> Vec<usize> {
+        flat_labels(self.n_points(), &self.merges, self.merges.iter().take_while(|(_, _, h, _)| *h <= height).count())
+    }
+
+    /// The merges as an (n - 1) × 4 matrix in SciPy's layout, for `scipy.cluster.hierarchy` and its plots.
+    pub fn linkage_matrix(&self) -> Array2<f64> {
+        Array2::from_shape_fn((self.merges.len(), 4), |(row, col)| {
+            let (a, b, height, size) = self.merges[row];
+            match col {
+                0 => a as f64,
+                1 => b as f64,
+                2 => height.to_f64().expect("T::to_f64"),
+                _ => size as f64,
+            }
+        })
+    }
+
+    /// The tree in Newick format, leaves named by point index and branch lengths the height each node gains
+    /// to its parent, as phylogenetics viewers and `ete3` read it.
+    pub fn to_newick(&self) -> String {
+        let n = self.n_points();
+        let mut subtrees = (0..n).map(|point| Some(point.to_string())).collect::<Vec<Option<String>>>();
+        for (a, b, height, _) in self.merges.iter() {
+            let mut branch = |node: usize| format!("{}:{}", subtrees[node].take().expect("unmerged subtree"), (*height - self.height(node)).to_f64().expect("T::to_f64"));
+            let merged = format!("({},{})", branch(*a), branch(*b));
+            subtrees.push(Some(merged));
+        }
+        format!("{};", subtrees[self.root()].take().expect("root subtree"))
+    }
+}
+
+/// Labels of `n` points after the first `count` merges, numbered from 1 in order of each cluster's first point.
+fn flat_labels<T>(n: usize, merges: &[(usize, usize, T, usize)], count: usize) -> Vec<usize> {
+    let mut components = UnionFind::new(2 * n);
+    for (i, (a, b, _, _)) in merges.iter().take(count).enumerate() {
+        components.union(*a, n + i);
+        components.union(*b, n + i);
+    }
+    let mut ids = vec![0; 2 * n];
+    let mut next = 1;
+    (0..n)
+        .map(|idx| {
+            let root = components.find(idx);
+            if ids[root] == 0 {
+                ids[root] = next;
+                next += 1;
+            }
+            ids[root]
+        })
+        .collect::<Vec<usize>>()
+}
+
+/// Distance from the merge of clusters `a` and `b` to `other`, from the distances before the merge.
+fn lance_williams<T: Float>(linkage: Linkage, to_a: T, to_b: T, a_to_b: T, size_a: usize, size_b: usize, size_other: usize) -> T {
+    let size = |s: usize| T::from(s).expect("T::from(usize)");
+    match linkage {
+        Linkage::Single => to_a.min(to_b),
+        Linkage::Complete => to_a.max(to_b),
+        Linkage::Average => (size(size_a) * to_a + size(size_b) * to_b) / size(size_a + size_b),
+        Linkage::Ward => {
+            let total = size(size_a + size_b + size_other);
+            ((size(size_a + size_other) * to_a.powi(2) + size(size_b + size_other) * to_b.powi(2) - size(size_other) * a_to_b.powi(2)) / total)
+                .max(T::zero())
+                .sqrt()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+
+    #[test]
+    fn test_agglomerative() {
+        let data = Array2::from_shape_vec((7, 1), vec![0.0, 0.1, 0.3, 5.0, 5.2, 5.3, 20.0]).unwrap();
+        for linkage in [Linkage::Single, Linkage::Complete, Linkage::Average, Linkage::Ward].iter() {
+            let model = Agglomerative::new(&data, *linkage);
+            assert!(model.merges.len() == 6 && model.merges.last().unwrap().3 == 7);
+            assert!(model.merges.windows(2).all(|w| w[0].2 <= w[1].2));
+            assert!(model.cut(3) == vec![1, 1, 1, 2, 2, 2, 3]);
+            assert!(model.cut(1) == vec![1; 7] && model.cut(7) == (1..=7).collect::<Vec<usize>>());
+        }
+        let single = Agglomerative::new(&data, Linkage::Single);
+        assert!((single.merges[0].2 - 0.1).abs() < 1e-12);
+        assert!(single.cut_distance(0.25) == vec![1, 1, 1, 2, 2, 2, 3]);
+        let complete = Agglomerative::new(&data, Linkage::Complete);
+        assert!(complete.cut_distance(0.25) == vec![1, 1, 2, 3, 4, 4, 5]);
+
+        // One point has no merges but still one label, as its dendrogram gives it, and no points have none.
+        let one = Agglomerative::new(&arr2(&[[1.0, 2.0]]), Linkage::Single);
+        assert!(one.cut(1) == vec![1] && one.cut_distance(0.0) == vec![1] && one.cut(1) == one.dendrogram().cut(1));
+        assert!(Agglomerative::new(&Array2::<f64>::zeros((0, 2)), Linkage::Single).cut(1).is_empty());
+        let plane = arr2(&[[0.0, 0.0], [0.1, 0.2], [5.0, 5.0], [5.2, 5.1], [0.3, -0.1]]);
+        assert!(Agglomerative::new(&column_major(&plane), Linkage::Average).merges == Agglomerative::new(&plane, Linkage::Average).merges);
+    }
+
+    #[test]
+    fn test_dendrogram() {
+        let data = Array2::from_shape_vec((4, 1), vec![0.0, 1.0, 3.0, 10.0]).unwrap();
+        let model = Agglomerative::new(&data, Linkage::Single);
+        let tree = model.dendrogram();
+        assert!(tree.n_points() == 4 && tree.root() == 6 && tree.children(6) == Some((5, 3)) && tree.children(2).is_none());
+        assert!(tree.parent(0) == Some(4) && tree.parent(6).is_none() && tree.height(5) == 2.0 && tree.size(5) == 3 && tree.leaves(5) == vec![0, 1, 2]);
+        assert!(tree.cut(2) == model.cut(2) && tree.cut_height(1.5) == model.cut_distance(1.5));
+        assert!(tree.linkage_matrix().row(1).to_vec() == vec![4.0, 2.0, 2.0, 3.0]);
+        assert!(tree.to_newick() == "(((0:1,1:1):1,2:2):5,3:7);");
+        assert!(Dendrogram::<f64>::from_linkage(Vec::new()).to_newick() == "0;");
+    }
+}
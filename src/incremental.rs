@@ -0,0 +1,392 @@
+use crate::index::Index;
+use crate::labels::LabelSet;
+use crate::traits::{Clustering, PartialFit};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Range;
+
+/// DBSCAN that keeps its neighbour index and counts so points can be appended after fitting. Each insert only
+/// re-examines the new point's eps-neighbourhood: points that become core there grow, merge or start clusters,
+/// and noise within eps of a core point becomes a border point. Labels follow `Dbscan` with borders assigned
+/// (0 is noise), but ids may have gaps once clusters merge.
+#[derive(Debug)]
+pub struct IncrementalDbscan<T: Float + One + Zero> {
+    pub eps: T,
+    pub min_points: usize,
+    pub clusters: Vec<usize>,
+    points: Vec<Vec<T>>,
+    counts: Vec<usize>,
+    index: Index<T, Vec<T>>,
+    next_cluster: usize,
+}
+
+impl<T: Float + One + Zero> IncrementalDbscan<T> {
+    pub fn new(data: &Array2<T>, eps: T, min_points: usize) -> IncrementalDbscan<T> {
+        let mut model = IncrementalDbscan {
+            eps,
+            min_points,
+            clusters: Vec::with_capacity(data.rows()),
+            points: Vec::with_capacity(data.rows()),
+            counts: Vec::with_capacity(data.rows()),
+            index: Index::new(data.cols()),
+            next_cluster: 1,
+        };
+        model.partial_fit(data);
+        model
+    }
+
+    /// Adds `point` and returns its label.
+    pub fn insert(&mut self, point: &[T]) -> usize {
+        let idx = self.clusters.len();
+        let mut neighbours = Vec::new();
+        self.index.within(point, self.eps, &mut neighbours);
+        self.index.add(point.to_vec(), idx);
+        self.points.push(point.to_vec());
+        self.clusters.push(0);
+        self.counts.push(neighbours.len() + 1);
+        let mut new_cores = Vec::new();
+        for neighbour in neighbours.iter() {
+            self.counts[*neighbour] += 1;
+            if self.counts[*neighbour] == self.min_points {
+                new_cores.push(*neighbour);
+            }
+        }
+        if self.counts[idx] >= self.min_points {
+            new_cores.push(idx);
+        }
+        neighbours.push(idx);
+
+        let mut settled = vec![false; new_cores.len()];
+        for (i, core) in new_cores.iter().enumerate() {
+            let mut around = Vec::new();
+            self.index.within(&self.points[*core], self.eps, &mut around);
+            let is_settled = |other: &usize| match new_cores.iter().position(|c| c == other) {
+                Some(j) => settled[j],
+                None => self.counts[*other] >= self.min_points,
+            };
+            let joined = around.iter().filter(|other| is_settled(other)).map(|other| self.clusters[*other]).collect::<Vec<usize>>();
+            let label = match joined.iter().min() {
+                Some(label) => {
+                    let label = *label;
+                    for c in self.clusters.iter_mut().filter(|c| joined.contains(c)) {
+                        *c = label;
+                    }
+                    label
+                }
+                None => {
+                    self.next_cluster += 1;
+                    self.next_cluster - 1
+                }
+            };
+            self.clusters[*core] = label;
+            for other in around.iter() {
+                if self.counts[*other] < self.min_points && self.clusters[*other] == 0 {
+                    self.clusters[*other] = label;
+                }
+            }
+            settled[i] = true;
+        }
+
+        if self.clusters[idx] == 0 {
+            if let Some(core) = neighbours.iter().find(|n| self.counts[**n] >= self.min_points) {
+                self.clusters[idx] = self.clusters[*core];
+            }
+        }
+        self.clusters[idx]
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for IncrementalDbscan<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+impl<T: Float + One + Zero> PartialFit<T> for IncrementalDbscan<T> {
+    fn partial_fit(&mut self, batch: &Array2<T>) {
+        for row in batch.outer_iter() {
+            self.insert(&row.to_vec());
+        }
+    }
+}
+
+/// DBSCAN over a sliding time window of an event stream: each insert first expires the events that have
+/// fallen out of the window, then adds the new one as `IncrementalDbscan` does. Expiring an event can only
+/// shrink or split clusters, so only the clusters holding it or a neighbour that stops being core are
+/// relabelled, by regrowing them from their remaining core points. A cluster that splits keeps its id for the
+/// part holding its earliest core point, and the other parts take fresh ids.
+///
+/// Events are identified by their arrival number, from 0, and must arrive in non-decreasing time order.
+#[derive(Debug)]
+pub struct RollingDbscan<T: Float + One + Zero> {
+    pub eps: T,
+    pub min_points: usize,
+    /// Events stay in the window while they are less than `window` older than the latest time seen.
+    pub window: T,
+    events: VecDeque<Event<T>>,
+    first_id: usize,
+    index: Index<T, Vec<T>>,
+    indexed: usize,
+    dims: usize,
+    next_cluster: usize,
+}
+
+#[derive(Debug)]
+struct Event<T> {
+    time: T,
+    point: Vec<T>,
+    count: usize,
+    cluster: usize,
+}
+
+impl<T: Float + One + Zero> RollingDbscan<T> {
+    pub fn new(dims: usize, eps: T, min_points: usize, window: T) -> RollingDbscan<T> {
+        RollingDbscan {
+            eps,
+            min_points,
+            window,
+            events: VecDeque::new(),
+            first_id: 0,
+            index: Index::new(dims),
+            indexed: 0,
+            dims,
+            next_cluster: 1,
+        }
+    }
+
+    /// Adds an event at `time` and returns its id.
+    pub fn insert(&mut self, time: T, point: &[T]) -> usize {
+        assert!(point.len() == self.dims, "event has {} dimensions, expected {}", point.len(), self.dims);
+        assert!(self.events.back().map_or(true, |last| last.time <= time), "events must arrive in time order");
+        self.expire(time);
+        let id = self.first_id + self.events.len();
+        let neighbours = self.neighbours(point);
+        self.index.add(point.to_vec(), id);
+        self.indexed += 1;
+        self.events.push_back(Event { time, point: point.to_vec(), count: neighbours.len() + 1, cluster: 0 });
+
+        let mut new_cores = Vec::new();
+        for neighbour in neighbours.iter() {
+            let event = self.event_mut(*neighbour);
+            event.count += 1;
+            if event.count == self.min_points {
+                new_cores.push(*neighbour);
+            }
+        }
+        if self.is_core(id) {
+            new_cores.push(id);
+        }
+        for core in new_cores {
+            let around = self.neighbours(&self.event(core).point.clone());
+            let joined = around.iter().filter(|other| self.is_core(**other)).map(|other| self.event(*other).cluster).filter(|c| *c > 0).collect::<BTreeSet<usize>>();
+            let label = match joined.iter().next() {
+                Some(label) => *label,
+                None => {
+                    self.next_cluster += 1;
+                    self.next_cluster - 1
+                }
+            };
+            for event in self.events.iter_mut().filter(|e| joined.contains(&e.cluster)) {
+                event.cluster = label;
+            }
+            self.event_mut(core).cluster = label;
+            for other in around {
+                if self.event(other).cluster == 0 {
+                    self.event_mut(other).cluster = label;
+                }
+            }
+        }
+        if self.event(id).cluster == 0 {
+            if let Some(core) = neighbours.iter().find(|n| self.is_core(**n)) {
+                self.event_mut(id).cluster = self.event(*core).cluster;
+            }
+        }
+        id
+    }
+
+    /// Expires every event at least `window` older than `now` without inserting one.
+    pub fn expire(&mut self, now: T) {
+        while self.events.front().is_some_and(|event| now - event.time >= self.window) {
+            let removed = self.events.pop_front().expect("front event");
+            self.first_id += 1;
+            let min_points = self.min_points;
+            let mut affected = BTreeSet::new();
+            if removed.count >= min_points {
+                affected.insert(removed.cluster);
+            }
+            for neighbour in self.neighbours(&removed.point) {
+                let event = self.event_mut(neighbour);
+                if event.count == min_points {
+                    affected.insert(event.cluster);
+                }
+                event.count -= 1;
+            }
+            for cluster in affected.into_iter().filter(|c| *c > 0) {
+                self.regrow(cluster);
+            }
+        }
+        // Expired events stay in the index until they outnumber the live ones.
+        if self.indexed > 2 * self.events.len().max(16) {
+            self.index = Index::new(self.dims);
+            for (offset, event) in self.events.iter().enumerate() {
+                self.index.add(event.point.clone(), self.first_id + offset);
+            }
+            self.indexed = self.events.len();
+        }
+    }
+
+    /// The label of event `id`, 0 for noise, or `None` once it has expired or if it hasn't arrived.
+    pub fn label(&self, id: usize) -> Option<usize> {
+        self.ids().contains(&id).then(|| self.event(id).cluster)
+    }
+
+    /// Ids of the events in the window, in arrival order.
+    pub fn ids(&self) -> Range<usize> {
+        self.first_id..self.first_id + self.events.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    fn regrow(&mut self, cluster: usize) {
+        let members = self.ids().filter(|id| self.event(*id).cluster == cluster).collect::<Vec<usize>>();
+        for id in members.iter() {
+            self.event_mut(*id).cluster = 0;
+        }
+        let cores = members.iter().cloned().filter(|id| self.is_core(*id)).collect::<Vec<usize>>();
+        let mut regrown = 0;
+        for seed in cores {
+            if self.event(seed).cluster > 0 {
+                continue;
+            }
+            let label = match regrown {
+                0 => cluster,
+                _ => {
+                    self.next_cluster += 1;
+                    self.next_cluster - 1
+                }
+            };
+            regrown += 1;
+            self.event_mut(seed).cluster = label;
+            let mut stack = vec![seed];
+            while let Some(id) = stack.pop() {
+                for other in self.neighbours(&self.event(id).point.clone()) {
+                    if self.event(other).cluster == 0 {
+                        self.event_mut(other).cluster = label;
+                        if self.is_core(other) {
+                            stack.push(other);
+                        }
+                    }
+                }
+            }
+        }
+        // Borders no remaining core of the cluster reaches may still border another cluster.
+        for id in members {
+            if self.event(id).cluster > 0 {
+                continue;
+            }
+            if let Some(core) = self.neighbours(&self.event(id).point.clone()).into_iter().find(|other| self.is_core(*other)) {
+                self.event_mut(id).cluster = self.event(core).cluster;
+            }
+        }
+    }
+
+    /// Ids of the live events within eps of `point`.
+    fn neighbours(&self, point: &[T]) -> Vec<usize> {
+        let mut neighbours = Vec::new();
+        self.index.within(point, self.eps, &mut neighbours);
+        neighbours.retain(|id| *id >= self.first_id);
+        neighbours.sort_unstable();
+        neighbours
+    }
+
+    fn is_core(&self, id: usize) -> bool {
+        self.event(id).count >= self.min_points
+    }
+
+    fn event(&self, id: usize) -> &Event<T> {
+        &self.events[id - self.first_id]
+    }
+
+    fn event_mut(&mut self, id: usize) -> &mut Event<T> {
+        &mut self.events[id - self.first_id]
+    }
+}
+
+/// Labels of the events in the window, in arrival order.
+impl<T: Float + One + Zero> Clustering for RollingDbscan<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.events.iter().map(|event| event.cluster).collect::<Vec<usize>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::{Dbscan, PointType};
+    use crate::metrics::adjusted_rand_index;
+    use ndarray::s;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_incremental() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let mut model = IncrementalDbscan::new(&data.slice(s![..5, ..]).to_owned(), 0.5, 2);
+        assert!(model.clusters == vec![1, 1, 1, 1, 0]);
+        model.partial_fit(&data.slice(s![5.., ..]).to_owned());
+        let batch = Dbscan::new(&data, 0.5, 2, true).clusters;
+        assert!(model.labels().iter().zip(batch.iter()).all(|(a, b)| (*a == 0) == (*b == 0)));
+        assert!(model.clusters[4] == model.clusters[5] && model.clusters[4] != model.clusters[0]);
+
+        // A point between two clusters is core and merges them.
+        let mut bridge = IncrementalDbscan::new(&Array2::from_shape_vec((6, 1), vec![0.0, 0.2, 0.4, 1.4, 1.6, 1.8]).unwrap(), 0.55, 3);
+        assert!(bridge.clusters == vec![1, 1, 1, 2, 2, 2]);
+        assert!(bridge.insert(&[2.3]) == 2);
+        assert!(bridge.insert(&[0.9]) == 1);
+        assert!(bridge.clusters == vec![1; 8]);
+    }
+
+    #[test]
+    fn test_rolling() {
+        // The chain's middle event arrives first, so it expires first and the chain splits in two.
+        let mut rolling = RollingDbscan::new(1, 0.5, 2, 10.0);
+        for (time, x) in [(0.0, 0.8), (1.0, 0.0), (1.0, 0.4), (2.0, 1.2), (2.0, 1.6)] {
+            rolling.insert(time, &[x]);
+        }
+        assert!(rolling.labels() == vec![1; 5]);
+        let far = rolling.insert(10.5, &[5.0]);
+        assert!(rolling.ids() == (1..6) && rolling.label(0).is_none() && rolling.label(far) == Some(0));
+        assert!(rolling.labels() == vec![1, 1, 2, 2, 0]);
+
+        // At every step the window matches a batch fit of the events in it, up to border ties.
+        let mut rng = SmallRng::seed_from_u64(3);
+        let mut rolling = RollingDbscan::new(2, 0.6, 4, 50.0);
+        let mut stream = Vec::new();
+        for step in 0..400 {
+            let point = [rng.gen_range(0.0, 6.0), rng.gen_range(0.0, 3.0)];
+            let id = rolling.insert(step as f64, &point);
+            stream.push(point);
+            if step % 40 == 39 {
+                let live = Array2::from_shape_fn((rolling.len(), 2), |(row, col)| stream[rolling.ids().start + row][col]);
+                let batch = Dbscan::new(&live, 0.6, 4, true);
+                let cores = (0..live.rows()).filter(|row| batch.point_types[*row] == PointType::Core).collect::<Vec<usize>>();
+                let labels = rolling.labels();
+                assert!(labels.iter().zip(batch.clusters.iter()).all(|(a, b)| (*a == 0) == (*b == 0)) && rolling.label(id).is_some());
+                let pick = |labels: &[usize]| cores.iter().map(|row| labels[*row]).collect::<Vec<usize>>();
+                assert!(adjusted_rand_index(&pick(&labels), &pick(&batch.clusters)) == 1.0);
+            }
+        }
+    }
+}
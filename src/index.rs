@@ -0,0 +1,514 @@
+use ndarray::prelude::*;
+use ndarray::Data;
+use rand::distributions::StandardNormal;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::borrow::Cow;
+
+#[cfg(feature = "kdtree")]
+pub(crate) type Index<T, U> = KdTreeIndex<T, U>;
+#[cfg(not(feature = "kdtree"))]
+pub(crate) type Index<T, U> = BruteForceIndex<T, U>;
+
+/// Most points a ball tree leaf holds before it splits.
+const BALL_TREE_LEAF_SIZE: usize = 16;
+
+/// Hash tables per LSH index; more tables raise recall at the cost of memory and query time.
+const LSH_TABLES: usize = 16;
+/// Projections hashed together per table; more projections give smaller buckets and faster, lower-recall queries.
+const LSH_HASHES: usize = 4;
+
+/// An index borrowing the rows of `data`, or copies of them where they aren't contiguous, as in a column-major array.
+pub(crate) fn index_rows<T: Float + One + Zero, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>) -> Index<T, Cow<'_, [T]>> {
+    let mut index = Index::new(data.cols());
+    for (idx, row) in data.outer_iter().enumerate() {
+        index.add(row_slice(row), idx);
+    }
+    index
+}
+
+/// `row` as a slice: borrowed when its elements are contiguous, and copied when they aren't, as with a row of a
+/// column-major array or a column view.
+pub(crate) fn row_slice<T: Clone>(row: ArrayView1<'_, T>) -> Cow<'_, [T]> {
+    if row.as_slice().is_some() {
+        Cow::Borrowed(row.into_slice().expect("contiguous row"))
+    } else {
+        Cow::Owned(row.to_vec())
+    }
+}
+
+/// An LSH index over the rows of `data` for queries within `radius`, borrowing them as `index_rows` does.
+pub(crate) fn lsh_rows<T: Float, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, radius: T) -> LshIndex<T, Cow<'_, [T]>> {
+    let mut index = LshIndex::new(data.cols(), radius);
+    for (idx, row) in data.outer_iter().enumerate() {
+        index.add(row_slice(row), idx);
+    }
+    index
+}
+
+/// A ball tree borrowing the rows of `data` as `index_rows` does, for `NeighbourSearch::BallTree`.
+pub(crate) fn ball_tree_rows<T: Float, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>) -> BallTreeIndex<T, Cow<'_, [T]>> {
+    let mut index = BallTreeIndex::new(data.cols());
+    for (idx, row) in data.outer_iter().enumerate() {
+        index.add(row_slice(row), idx);
+    }
+    index
+}
+
+pub(crate) fn index_owned_rows<T: Float + One + Zero>(data: &Array2<T>) -> Index<T, Vec<T>> {
+    let mut index = Index::new(data.cols());
+    for (idx, row) in data.outer_iter().enumerate() {
+        index.add(row.to_vec(), idx);
+    }
+    index
+}
+
+/// A row-major copy of `data` when its rows can't be borrowed as slices, as with a transposed view.
+pub(crate) fn row_major_copy<T: Copy, S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>) -> Option<Array2<T>> {
+    if data.cols() <= 1 || data.rows() == 0 || data.strides()[1] == 1 {
+        None
+    } else {
+        Some(Array2::from_shape_vec(data.dim(), data.iter().cloned().collect()).expect("shape of copied rows"))
+    }
+}
+
+/// A column-major copy of `data`, for checking that either layout gives the same results.
+#[cfg(test)]
+pub(crate) fn column_major<T: Copy>(data: &Array2<T>) -> Array2<T> {
+    Array2::from_shape_vec(data.dim().f(), data.t().iter().cloned().collect()).expect("shape of copied columns")
+}
+
+pub(crate) fn squared_euclidean<T: Float>(a: &[T], b: &[T]) -> T {
+    a.iter().zip(b.iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y).powi(2))
+}
+
+/// Accumulators `squared_euclidean_lanes` keeps, enough for one 256-bit register of f32 or two of f64.
+#[cfg(not(feature = "simd"))]
+const LANES: usize = 8;
+
+/// `squared_euclidean` summed in `LANES` independent accumulators, which the compiler turns into SIMD adds and
+/// multiplies on stable Rust without `std::simd`; the single running sum of `squared_euclidean` can't be
+/// reordered so. Results differ from it only in rounding.
+#[cfg(not(feature = "simd"))]
+pub(crate) fn squared_euclidean_lanes<T: Float>(a: &[T], b: &[T]) -> T {
+    let mut sums = [T::zero(); LANES];
+    let (chunks_a, chunks_b) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+    let tail = squared_euclidean(chunks_a.remainder(), chunks_b.remainder());
+    for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+        for lane in 0..LANES {
+            let difference = chunk_a[lane] - chunk_b[lane];
+            sums[lane] = sums[lane] + difference * difference;
+        }
+    }
+    sums.iter().fold(tail, |acc, sum| acc + *sum)
+}
+
+/// Vectors `squared_euclidean_lanes` sums into independently, so that each add needn't wait for the last.
+#[cfg(feature = "simd")]
+const ACCUMULATORS: usize = 4;
+
+/// `squared_euclidean` in explicit SIMD vectors from the `wide` crate: `f32x8` for four-byte floats and
+/// `f64x4` otherwise, which covers `f32` and `f64`. Results differ from `squared_euclidean` only in rounding.
+#[cfg(feature = "simd")]
+pub(crate) fn squared_euclidean_lanes<T: Float>(a: &[T], b: &[T]) -> T {
+    if std::mem::size_of::<T>() == 4 {
+        let lanes = |chunk: &[T]| wide::f32x8::new(std::array::from_fn(|lane| chunk[lane].to_f32().expect("T::to_f32")));
+        let (chunks_a, chunks_b) = (a.chunks_exact(8 * ACCUMULATORS), b.chunks_exact(8 * ACCUMULATORS));
+        let tail = squared_euclidean(chunks_a.remainder(), chunks_b.remainder());
+        let mut sums = [wide::f32x8::ZERO; ACCUMULATORS];
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            for (sum, (a, b)) in sums.iter_mut().zip(chunk_a.chunks_exact(8).zip(chunk_b.chunks_exact(8))) {
+                let difference = lanes(a) - lanes(b);
+                *sum += difference * difference;
+            }
+        }
+        tail + T::from(sums.iter().fold(wide::f32x8::ZERO, |acc, sum| acc + *sum).reduce_add()).expect("T::from(f32)")
+    } else {
+        let lanes = |chunk: &[T]| wide::f64x4::new(std::array::from_fn(|lane| chunk[lane].to_f64().expect("T::to_f64")));
+        let (chunks_a, chunks_b) = (a.chunks_exact(4 * ACCUMULATORS), b.chunks_exact(4 * ACCUMULATORS));
+        let tail = squared_euclidean(chunks_a.remainder(), chunks_b.remainder());
+        let mut sums = [wide::f64x4::ZERO; ACCUMULATORS];
+        for (chunk_a, chunk_b) in chunks_a.zip(chunks_b) {
+            for (sum, (a, b)) in sums.iter_mut().zip(chunk_a.chunks_exact(4).zip(chunk_b.chunks_exact(4))) {
+                let difference = lanes(a) - lanes(b);
+                *sum += difference * difference;
+            }
+        }
+        tail + T::from(sums.iter().fold(wide::f64x4::ZERO, |acc, sum| acc + *sum).reduce_add()).expect("T::from(f64)")
+    }
+}
+
+/// Indices of the `dims`-wide rows of the row-major `points` within `radius` of `point`, by checking every row.
+pub(crate) fn brute_force_within<T: Float>(points: &[T], dims: usize, point: &[T], radius: T, neighbours: &mut Vec<usize>) {
+    let radius = radius.powi(2);
+    neighbours.extend(points.chunks_exact(dims.max(1)).enumerate().filter(|(_, other)| squared_euclidean_lanes(point, other) <= radius).map(|(idx, _)| idx));
+}
+
+#[cfg(feature = "kdtree")]
+#[derive(Debug)]
+pub(crate) struct KdTreeIndex<T: Float + One + Zero, U: AsRef<[T]>> {
+    kdt: kdtree::KdTree<T, usize, U>,
+}
+
+#[cfg(feature = "kdtree")]
+impl<T: Float + One + Zero, U: AsRef<[T]>> KdTreeIndex<T, U> {
+    pub(crate) fn new(dims: usize) -> KdTreeIndex<T, U> {
+        KdTreeIndex { kdt: kdtree::KdTree::new(dims) }
+    }
+
+    pub(crate) fn add(&mut self, point: U, idx: usize) {
+        self.kdt.add(point, idx).expect("KdTree error adding point");
+    }
+
+    pub(crate) fn within(&self, point: &[T], radius: T, neighbours: &mut Vec<usize>) {
+        for (_, neighbour_idx) in self.kdt.within(point, radius.powi(2), &squared_euclidean).expect("KdTree error checking point") {
+            neighbours.push(*neighbour_idx);
+        }
+    }
+
+    /// The `k` nearest points accepted by `matching`, as `(distance, index)` in ascending distance.
+    pub(crate) fn nearest_matching<F: Fn(usize) -> bool>(&self, point: &[T], k: usize, matching: F) -> Vec<(T, usize)> {
+        self.kdt
+            .iter_nearest(point, &squared_euclidean)
+            .expect("KdTree error checking point")
+            .filter(|(_, idx)| matching(**idx))
+            .take(k)
+            .map(|(distance, idx)| (distance.sqrt(), *idx))
+            .collect::<Vec<(T, usize)>>()
+    }
+
+    pub(crate) fn nearest(&self, point: &[T], k: usize) -> Vec<(T, usize)> {
+        self.kdt
+            .nearest(point, k, &squared_euclidean)
+            .expect("KdTree error checking point")
+            .into_iter()
+            .map(|(distance, idx)| (distance.sqrt(), *idx))
+            .collect::<Vec<(T, usize)>>()
+    }
+}
+
+#[cfg(any(not(feature = "kdtree"), test))]
+#[derive(Debug)]
+pub(crate) struct BruteForceIndex<T: Float, U: AsRef<[T]>> {
+    dims: usize,
+    points: Vec<(U, usize)>,
+    _marker: std::marker::PhantomData<T>,
+}
+
+#[cfg(any(not(feature = "kdtree"), test))]
+impl<T: Float, U: AsRef<[T]>> BruteForceIndex<T, U> {
+    pub(crate) fn new(dims: usize) -> BruteForceIndex<T, U> {
+        BruteForceIndex {
+            dims,
+            points: Vec::new(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub(crate) fn add(&mut self, point: U, idx: usize) {
+        assert!(point.as_ref().len() == self.dims, "wrong dimension");
+        self.points.push((point, idx));
+    }
+
+    pub(crate) fn within(&self, point: &[T], radius: T, neighbours: &mut Vec<usize>) {
+        let radius = radius.powi(2);
+        neighbours.extend(self.points.iter().filter(|(p, _)| squared_euclidean(point, p.as_ref()) <= radius).map(|(_, idx)| *idx));
+    }
+
+    pub(crate) fn nearest_matching<F: Fn(usize) -> bool>(&self, point: &[T], k: usize, matching: F) -> Vec<(T, usize)> {
+        let mut nearest = self
+            .points
+            .iter()
+            .filter(|(_, idx)| matching(*idx))
+            .map(|(p, idx)| (squared_euclidean(point, p.as_ref()), *idx))
+            .collect::<Vec<(T, usize)>>();
+        nearest.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd distance"));
+        nearest.truncate(k);
+        nearest.into_iter().map(|(distance, idx)| (distance.sqrt(), idx)).collect::<Vec<(T, usize)>>()
+    }
+
+    pub(crate) fn nearest(&self, point: &[T], k: usize) -> Vec<(T, usize)> {
+        self.nearest_matching(point, k, |_| true)
+    }
+}
+
+/// Approximate radius search by p-stable locality-sensitive hashing (Datar et al., 2004), for
+/// `NeighbourSearch::Lsh`. Each table hashes a point to the cells it falls in along `LSH_HASHES` random
+/// projections, and a radius query only checks points sharing a cell with the query in some table, so it never
+/// returns a point outside the radius but can miss some inside it. Cells are four times the radius the index is
+/// built for; queries over a larger radius fall back to an exact scan.
+#[derive(Debug)]
+pub(crate) struct LshIndex<T: Float, U: AsRef<[T]>> {
+    dims: usize,
+    radius: T,
+    width: T,
+    projections: Vec<(Vec<T>, T)>,
+    buckets: Vec<HashMap<Vec<i64>, Vec<usize>>>,
+    points: Vec<(U, usize)>,
+}
+
+impl<T: Float, U: AsRef<[T]>> LshIndex<T, U> {
+    pub(crate) fn new(dims: usize, radius: T) -> LshIndex<T, U> {
+        let width = radius * T::from(4.0).expect("T::from(f64)");
+        let mut rng = StdRng::seed_from_u64(0);
+        let projections = (0..LSH_TABLES * LSH_HASHES)
+            .map(|_| {
+                let direction = (0..dims).map(|_| T::from(rng.sample(StandardNormal)).expect("T::from(f64)")).collect::<Vec<T>>();
+                (direction, width * T::from(rng.gen_range(0.0, 1.0)).expect("T::from(f64)"))
+            })
+            .collect::<Vec<(Vec<T>, T)>>();
+        LshIndex {
+            dims,
+            radius,
+            width,
+            projections,
+            buckets: vec![HashMap::new(); LSH_TABLES],
+            points: Vec::new(),
+        }
+    }
+
+    fn key(&self, table: usize, point: &[T]) -> Vec<i64> {
+        self.projections[table * LSH_HASHES..(table + 1) * LSH_HASHES]
+            .iter()
+            .map(|(direction, offset)| {
+                let projected = direction.iter().zip(point.iter()).fold(*offset, |acc, (a, x)| acc + *a * *x);
+                (projected / self.width).floor().to_i64().unwrap_or(0)
+            })
+            .collect::<Vec<i64>>()
+    }
+
+    pub(crate) fn add(&mut self, point: U, idx: usize) {
+        assert!(point.as_ref().len() == self.dims, "wrong dimension");
+        for table in 0..LSH_TABLES {
+            let key = self.key(table, point.as_ref());
+            self.buckets[table].entry(key).or_default().push(self.points.len());
+        }
+        self.points.push((point, idx));
+    }
+
+    pub(crate) fn within(&self, point: &[T], radius: T, neighbours: &mut Vec<usize>) {
+        let squared_radius = radius.powi(2);
+        let matches = |(p, _): &&(U, usize)| squared_euclidean(point, p.as_ref()) <= squared_radius;
+        if radius > self.radius {
+            neighbours.extend(self.points.iter().filter(matches).map(|(_, idx)| *idx));
+            return;
+        }
+        let mut candidates = (0..LSH_TABLES).filter_map(|table| self.buckets[table].get(&self.key(table, point))).flatten().cloned().collect::<Vec<usize>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+        neighbours.extend(candidates.into_iter().map(|position| &self.points[position]).filter(matches).map(|(_, idx)| *idx));
+    }
+}
+
+/// Ball tree: every node bounds its points by a ball, and queries skip balls that can't hold a match, which
+/// keeps pruning effective in more dimensions than a KdTree's axis-aligned splits. Points are inserted one at
+/// a time down the nearer child, growing the balls they pass through, and a leaf over `BALL_TREE_LEAF_SIZE`
+/// points splits around its two most distant points, unless they all coincide.
+#[derive(Debug)]
+pub(crate) struct BallTreeIndex<T: Float, U: AsRef<[T]>> {
+    dims: usize,
+    points: Vec<(U, usize)>,
+    nodes: Vec<BallNode<T>>,
+}
+
+#[derive(Debug)]
+struct BallNode<T> {
+    center: Vec<T>,
+    radius: T,
+    children: Option<(usize, usize)>,
+    members: Vec<usize>,
+}
+
+impl<T: Float, U: AsRef<[T]>> BallTreeIndex<T, U> {
+    pub(crate) fn new(dims: usize) -> BallTreeIndex<T, U> {
+        BallTreeIndex { dims, points: Vec::new(), nodes: Vec::new() }
+    }
+
+    pub(crate) fn add(&mut self, point: U, idx: usize) {
+        assert!(point.as_ref().len() == self.dims, "wrong dimension");
+        let position = self.points.len();
+        if self.nodes.is_empty() {
+            self.nodes.push(BallNode { center: point.as_ref().to_vec(), radius: T::zero(), children: None, members: Vec::new() });
+        }
+        let mut node = 0;
+        loop {
+            let distance = squared_euclidean(&self.nodes[node].center, point.as_ref()).sqrt();
+            self.nodes[node].radius = self.nodes[node].radius.max(distance);
+            match self.nodes[node].children {
+                Some((left, right)) => {
+                    let to_left = squared_euclidean(&self.nodes[left].center, point.as_ref());
+                    node = if to_left <= squared_euclidean(&self.nodes[right].center, point.as_ref()) { left } else { right };
+                }
+                None => break,
+            }
+        }
+        self.points.push((point, idx));
+        self.nodes[node].members.push(position);
+        if self.nodes[node].members.len() > BALL_TREE_LEAF_SIZE {
+            self.split(node);
+        }
+    }
+
+    fn split(&mut self, node: usize) {
+        let members = std::mem::take(&mut self.nodes[node].members);
+        let point = |position: usize| self.points[position].0.as_ref();
+        let farthest_from = |from: usize| *members.iter().max_by(|a, b| squared_euclidean(point(from), point(**a)).partial_cmp(&squared_euclidean(point(from), point(**b))).expect("PartialOrd distance")).expect("leaf members");
+        let a = farthest_from(members[0]);
+        let b = farthest_from(a);
+        if squared_euclidean(point(a), point(b)).is_zero() {
+            // Every member is the same point, which no pivots can split; the leaf stays whole.
+            self.nodes[node].members = members;
+            return;
+        }
+        let (near_a, near_b): (Vec<usize>, Vec<usize>) = members.iter().partition(|m| squared_euclidean(point(**m), point(a)) <= squared_euclidean(point(**m), point(b)));
+        let children = [near_a, near_b]
+            .iter()
+            .map(|group| {
+                let n = T::from(group.len()).expect("T::from(usize)");
+                let center = (0..self.dims).map(|d| group.iter().fold(T::zero(), |acc, m| acc + point(*m)[d]) / n).collect::<Vec<T>>();
+                let radius = group.iter().map(|m| squared_euclidean(&center, point(*m)).sqrt()).fold(T::zero(), T::max);
+                BallNode { center, radius, children: None, members: group.clone() }
+            })
+            .collect::<Vec<BallNode<T>>>();
+        let first = self.nodes.len();
+        self.nodes.extend(children);
+        self.nodes[node].children = Some((first, first + 1));
+    }
+
+    pub(crate) fn within(&self, point: &[T], radius: T, neighbours: &mut Vec<usize>) {
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if squared_euclidean(&node.center, point).sqrt() - node.radius > radius {
+                continue;
+            }
+            match node.children {
+                Some((left, right)) => stack.extend_from_slice(&[left, right]),
+                None => neighbours.extend(
+                    node.members.iter().map(|m| &self.points[*m]).filter(|(p, _)| squared_euclidean(point, p.as_ref()) <= radius.powi(2)).map(|(_, idx)| *idx),
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod ball_tree_tests {
+    use super::*;
+
+    #[test]
+    fn test_ball_tree_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let data = Array2::from_shape_fn((400, 30), |_| rng.sample(StandardNormal));
+        let mut tree = BallTreeIndex::new(30);
+        let mut brute = BruteForceIndex::new(30);
+        for (idx, row) in data.outer_iter().enumerate() {
+            tree.add(row.to_vec(), idx);
+            brute.add(row.to_vec(), idx);
+            if idx == 50 {
+                // Queries between insertions see the points added so far.
+                let (mut from_tree, mut from_brute) = (Vec::new(), Vec::new());
+                tree.within(row.as_slice().unwrap(), 7.0, &mut from_tree);
+                brute.within(row.as_slice().unwrap(), 7.0, &mut from_brute);
+                from_tree.sort_unstable();
+                assert!(from_tree == from_brute);
+            }
+        }
+        for row in data.outer_iter().take(50) {
+            let (mut from_tree, mut from_brute) = (Vec::new(), Vec::new());
+            tree.within(row.as_slice().unwrap(), 7.0, &mut from_tree);
+            brute.within(row.as_slice().unwrap(), 7.0, &mut from_brute);
+            from_tree.sort_unstable();
+            assert!(from_tree == from_brute && from_brute.len() > 1);
+        }
+        let mut none = Vec::new();
+        BallTreeIndex::<f64, Vec<f64>>::new(2).within(&[0.0, 0.0], 1.0, &mut none);
+        assert!(none.is_empty());
+
+        // Duplicates past the leaf size stay in one leaf rather than splitting off an empty ball.
+        let mut duplicates = BallTreeIndex::new(2);
+        for idx in 0..3 * BALL_TREE_LEAF_SIZE {
+            duplicates.add(vec![1.0, 2.0], idx);
+        }
+        duplicates.add(vec![5.0, 5.0], 3 * BALL_TREE_LEAF_SIZE);
+        assert!(duplicates.nodes.iter().all(|node| node.center.iter().all(|x| x.is_finite()) && (node.children.is_some() || !node.members.is_empty())));
+        let (mut found, mut apart) = (Vec::new(), Vec::new());
+        duplicates.within(&[1.0, 2.0], 0.5, &mut found);
+        duplicates.within(&[5.0, 5.0], 0.5, &mut apart);
+        assert!(found.len() == 3 * BALL_TREE_LEAF_SIZE && apart == vec![3 * BALL_TREE_LEAF_SIZE]);
+    }
+}
+
+#[cfg(test)]
+mod lsh_tests {
+    use super::*;
+
+    #[test]
+    fn test_lsh_recall() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let data = Array2::from_shape_fn((500, 32), |_| rng.sample(StandardNormal));
+        let mut lsh = LshIndex::new(32, 6.0);
+        let mut brute = BruteForceIndex::new(32);
+        for (idx, row) in data.outer_iter().enumerate() {
+            lsh.add(row.to_vec(), idx);
+            brute.add(row.to_vec(), idx);
+        }
+        let (mut found, mut total) = (0, 0);
+        for row in data.outer_iter().take(100) {
+            let (mut from_lsh, mut from_brute) = (Vec::new(), Vec::new());
+            lsh.within(row.as_slice().unwrap(), 6.0, &mut from_lsh);
+            brute.within(row.as_slice().unwrap(), 6.0, &mut from_brute);
+            assert!(from_lsh.iter().all(|idx| from_brute.contains(idx)));
+            found += from_lsh.len();
+            total += from_brute.len();
+
+            // A larger radius than the index was built for is searched exactly.
+            let (mut wide_lsh, mut wide_brute) = (Vec::new(), Vec::new());
+            lsh.within(row.as_slice().unwrap(), 8.0, &mut wide_lsh);
+            brute.within(row.as_slice().unwrap(), 8.0, &mut wide_brute);
+            assert!(wide_lsh == wide_brute);
+        }
+        assert!(total > 200 && found as f64 >= 0.9 * total as f64);
+
+        // The cells follow the radius each index is built for, so a small radius keeps its recall too.
+        let mut small = LshIndex::new(32, 1.0);
+        for (idx, row) in data.outer_iter().enumerate() {
+            small.add(row.to_vec(), idx);
+        }
+        small.add(vec![100.0; 32], 500);
+        small.add(vec![100.1; 32], 501);
+        let mut neighbours = Vec::new();
+        small.within(&[100.0; 32], 1.0, &mut neighbours);
+        assert!(neighbours == vec![500, 501]);
+    }
+}
+
+#[cfg(all(test, feature = "kdtree"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backends_agree() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        let mut kdt = KdTreeIndex::new(2);
+        let mut brute = BruteForceIndex::new(2);
+        for (idx, row) in data.outer_iter().enumerate() {
+            kdt.add(row.to_vec(), idx);
+            brute.add(row.to_vec(), idx);
+        }
+        for row in data.outer_iter() {
+            let (mut from_kdt, mut from_brute) = (Vec::new(), Vec::new());
+            kdt.within(row.as_slice().unwrap(), 0.5, &mut from_kdt);
+            brute.within(row.as_slice().unwrap(), 0.5, &mut from_brute);
+            from_kdt.sort_unstable();
+            from_brute.sort_unstable();
+            assert!(from_kdt == from_brute);
+            let nearest = |index: &[(f64, usize)]| index.iter().map(|(d, _)| *d).collect::<Vec<f64>>();
+            assert!(nearest(&kdt.nearest(row.as_slice().unwrap(), 3)) == nearest(&brute.nearest(row.as_slice().unwrap(), 3)));
+            assert!(kdt.nearest_matching(row.as_slice().unwrap(), 2, |idx| idx % 2 == 0) == brute.nearest_matching(row.as_slice().unwrap(), 2, |idx| idx % 2 == 0));
+        }
+    }
+}
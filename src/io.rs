@@ -0,0 +1,155 @@
+use crate::arrow::batch_to_array;
+use arrow_array::RecordBatch;
+use ndarray::prelude::*;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::reader::ChunkReader;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Reads the CSV file at `path` as with `read_csv`.
+pub fn load_csv<P: AsRef<Path>>(path: P, columns: &[&str]) -> io::Result<Array2<f64>> {
+    read_csv(BufReader::new(File::open(path)?), columns)
+}
+
+/// Reads comma-separated rows with a header line, keeping the named `columns` in the order given, or every
+/// column when `columns` is empty. Fields may be double-quoted, with `""` for a literal quote, but can't span
+/// lines. Blank lines are skipped; any other field that doesn't parse as a number is an error.
+pub fn read_csv<R: BufRead>(reader: R, columns: &[&str]) -> io::Result<Array2<f64>> {
+    let mut lines = reader.lines();
+    let header = split_fields(&lines.next().unwrap_or_else(|| Ok(String::new()))?);
+    let indices = if columns.is_empty() {
+        (0..header.len()).collect::<Vec<usize>>()
+    } else {
+        columns
+            .iter()
+            .map(|name| header.iter().position(|field| field == name).ok_or_else(|| invalid(format!("no column named {}", name))))
+            .collect::<io::Result<Vec<usize>>>()?
+    };
+    read_rows(lines, &indices, 2)
+}
+
+/// Like `read_csv`, selecting columns by position; `header` says whether the first line is a header to skip.
+pub fn read_csv_indices<R: BufRead>(reader: R, columns: &[usize], header: bool) -> io::Result<Array2<f64>> {
+    let mut lines = reader.lines();
+    if header {
+        lines.next().transpose()?;
+    }
+    read_rows(lines, columns, if header { 2 } else { 1 })
+}
+
+/// Reads the Parquet file at `path` as with `read_parquet`.
+pub fn load_parquet<P: AsRef<Path>>(path: P, columns: &[&str]) -> io::Result<Array2<f64>> {
+    read_parquet(File::open(path)?, columns)
+}
+
+/// Reads every row group of a Parquet file, keeping the named numeric `columns` in the order given, or every
+/// column when `columns` is empty, as `arrow::batch_to_array` does. Pages may be uncompressed or compressed
+/// with snappy or lz4; other codecs are an error.
+pub fn read_parquet<R: ChunkReader + 'static>(reader: R, columns: &[&str]) -> io::Result<Array2<f64>> {
+    let batches = ParquetRecordBatchReaderBuilder::try_new(reader).and_then(|builder| builder.build()).map_err(|error| invalid(error.to_string()))?;
+    stack_batches(batches.map(|batch| batch.map_err(|error| invalid(error.to_string())).and_then(|batch| batch_to_array(&batch, columns))))
+}
+
+/// Stacks the named `columns` of Arrow record `batches`, as `arrow::batch_to_array` reads each one, into a
+/// single array.
+pub fn read_record_batches<I: IntoIterator<Item = RecordBatch>>(batches: I, columns: &[&str]) -> io::Result<Array2<f64>> {
+    stack_batches(batches.into_iter().map(|batch| batch_to_array(&batch, columns)))
+}
+
+fn stack_batches<I: Iterator<Item = io::Result<Array2<f64>>>>(batches: I) -> io::Result<Array2<f64>> {
+    let (mut values, mut cols) = (Vec::new(), 0);
+    for batch in batches {
+        let batch = batch?;
+        cols = batch.cols();
+        values.extend(batch.iter().cloned());
+    }
+    let rows = values.len().checked_div(cols).unwrap_or(0);
+    Ok(Array2::from_shape_vec((rows, cols), values).expect("batches with the same columns"))
+}
+
+fn read_rows<I: Iterator<Item = io::Result<String>>>(lines: I, columns: &[usize], first_line: usize) -> io::Result<Array2<f64>> {
+    let mut values = Vec::new();
+    let mut rows = 0;
+    for (number, line) in lines.enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_fields(&line);
+        for column in columns.iter() {
+            let field = fields.get(*column).ok_or_else(|| invalid(format!("line {} has no column {}", number + first_line, column)))?;
+            values.push(field.trim().parse::<f64>().map_err(|_| invalid(format!("line {}: {:?} is not a number", number + first_line, field)))?);
+        }
+        rows += 1;
+    }
+    Ok(Array2::from_shape_vec((rows, columns.len()), values).expect("one value per selected column"))
+}
+
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().expect("current field").push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().expect("current field").push(c),
+        }
+    }
+    fields
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{ArrayRef, Float32Array, Float64Array};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_read_csv() {
+        let text = "id,\"lat, deg\",lon,name\n1,51.5,-0.12,\"London \"\"UK\"\"\"\n\n2,48.85,2.35,Paris\n";
+        let data = read_csv(text.as_bytes(), &["lat, deg", "lon"]).unwrap();
+        assert!(data == arr2(&[[51.5, -0.12], [48.85, 2.35]]));
+        assert!(read_csv_indices(text.as_bytes(), &[2, 0], true).unwrap() == arr2(&[[-0.12, 1.0], [2.35, 2.0]]));
+        assert!(read_csv("a,b\n1,2\n3\n".as_bytes(), &[]).unwrap_err().to_string() == "line 3 has no column 1");
+        assert!(read_csv(text.as_bytes(), &["name"]).is_err() && read_csv(text.as_bytes(), &["height"]).is_err());
+        assert!(split_fields("a,\"b,\"\"c\"\"\",d") == vec!["a", "b,\"c\"", "d"]);
+    }
+
+    #[test]
+    fn test_read_parquet() {
+        let batch = |lat: Vec<f64>, lon: Vec<f32>| RecordBatch::try_from_iter(vec![("lat", Arc::new(Float64Array::from(lat)) as ArrayRef), ("lon", Arc::new(Float32Array::from(lon)) as ArrayRef)]).unwrap();
+        let batches = [batch(vec![51.5, 48.85], vec![-0.125, 2.25]), batch(vec![40.5], vec![-3.75])];
+        let expected = arr2(&[[-0.125, 51.5], [2.25, 48.85], [-3.75, 40.5]]);
+        assert!(read_record_batches(batches.iter().cloned(), &["lon", "lat"]).unwrap() == expected);
+        assert!(read_record_batches(Vec::new(), &[]).unwrap().shape() == [0, 0]);
+
+        let path = std::env::temp_dir().join(format!("clust-rs-test-{}.parquet", std::process::id()));
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).set_max_row_group_row_count(Some(2)).build();
+        let mut writer = ArrowWriter::try_new(File::create(&path).unwrap(), batches[0].schema(), Some(properties)).unwrap();
+        for batch in batches.iter() {
+            writer.write(batch).unwrap();
+        }
+        writer.close().unwrap();
+        let loaded = load_parquet(&path, &["lon", "lat"]);
+        let all = load_parquet(&path, &[]);
+        let missing = load_parquet(&path, &["height"]);
+        std::fs::write(&path, "not parquet").unwrap();
+        let garbled = load_parquet(&path, &[]);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.unwrap() == expected && all.unwrap() == arr2(&[[51.5, -0.125], [48.85, 2.25], [40.5, -3.75]]));
+        assert!(missing.unwrap_err().to_string() == "no column named height" && garbled.is_err());
+    }
+}
@@ -1,14 +1,19 @@
-use itertools::Itertools;
+use crate::error::Error;
+use crate::rng::default_rng;
+use crate::labels::LabelSet;
+use crate::traits::{Clustering, PartialFit, Predict};
+use crate::union_find::UnionFind;
 use ndarray::prelude::*;
 use ndarray::ScalarOperand;
 use num_traits::float::Float;
 use num_traits::identities::{One, Zero};
-use rand::prelude::thread_rng;
 use rand::prelude::IteratorRandom;
+use rand::rngs::StdRng;
 use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
 use std::f32;
 use std::iter::Sum;
-use std::ops::AddAssign;
+use std::ops::{AddAssign, RangeInclusive};
 
 #[derive(Debug)]
 pub struct Kmeans<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> {
@@ -19,14 +24,18 @@ pub struct Kmeans<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum
 
 impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Kmeans<T> {
     pub fn new(data: &Array2<T>, nclust: usize, iterations: usize, nseeds: usize) -> Kmeans<T> {
-        let mut rng = thread_rng();
+        Kmeans::new_with_rng(data, nclust, iterations, nseeds, &mut default_rng())
+    }
+
+    /// Like `new`, but draws the initial centers from `rng`, so a seeded generator gives reproducible fits.
+    pub fn new_with_rng<R: Rng>(data: &Array2<T>, nclust: usize, iterations: usize, nseeds: usize, rng: &mut R) -> Kmeans<T> {
         (0..nseeds)
             .map(|_| {
-                let mut centers = data.outer_iter().choose_multiple(&mut rng, nclust).iter().map(|row| row.to_owned()).collect::<Vec<Array1<T>>>();
+                let mut centers = data.outer_iter().choose_multiple(rng, nclust).iter().map(|row| row.to_owned()).collect::<Vec<Array1<T>>>();
                 let mut clusters = vec![0; data.rows()];
                 let mut withinss = vec![T::zero(); nclust];
                 for _ in 0..iterations {
-                    let mut sums = vec![Array1::zeros((data.cols())); nclust];
+                    let mut sums = vec![Array1::zeros(data.cols()); nclust];
                     let mut counts = vec![0; nclust];
                     withinss = vec![T::zero(); nclust];
                     for (row_idx, row) in data.outer_iter().enumerate() {
@@ -35,7 +44,7 @@ impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Kmeans<T> {
                             .enumerate()
                             .map(|(i, center)| (i, ((&row - center) * (&row - center)).sum()))
                             .map(|(i, x)| if x.is_nan() { (i, T::from(f32::MAX).expect("T::from(f32::MAX)")) } else { (i, x) })
-                            .min_by(|(_, a), (_, b)| a.partial_cmp(&b).expect("PartialOrd distance from center"))
+                            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance from center"))
                             .expect("min distance from center");
                         clusters[row_idx] = cluster;
                         sums[cluster] = &sums[cluster] + &row;
@@ -44,20 +53,171 @@ impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Kmeans<T> {
                     }
                     centers = sums
                         .into_iter()
-                        .zip(counts.into_iter())
+                        .zip(counts)
                         .map(|(sum, count)| sum / T::from(count).expect("T::from(usize)"))
                         .collect::<Vec<Array1<T>>>();
                 }
                 Kmeans {
-                    centers: centers,
-                    clusters: clusters,
-                    withinss: withinss,
+                    centers,
+                    clusters,
+                    withinss,
                 }
             })
             .min_by(|a, b| a.withinss.iter().cloned().sum::<T>().partial_cmp(&b.withinss.iter().cloned().sum::<T>()).expect("withinss is not NAN"))
             .expect("min withinss")
     }
 
+    /// Lloyd's algorithm from k-means++ initial centers, stopping after `max_iter` iterations or once no center
+    /// moves further than `tol`. A cluster that loses all its points keeps its previous center.
+    pub fn fit(data: &Array2<T>, k: usize, max_iter: usize, tol: T) -> Kmeans<T> {
+        Kmeans::fit_with_rng(data, k, max_iter, tol, &mut default_rng())
+    }
+
+    pub fn fit_with_rng<R: Rng>(data: &Array2<T>, k: usize, max_iter: usize, tol: T, rng: &mut R) -> Kmeans<T> {
+        let mut centers = kmeans_plus_plus(data, k, rng);
+        let mut clusters = vec![0; data.rows()];
+        let mut withinss = vec![T::zero(); centers.len()];
+        for _ in 0..max_iter {
+            let mut sums = vec![Array1::zeros(data.cols()); centers.len()];
+            let mut counts = vec![0; centers.len()];
+            withinss = vec![T::zero(); centers.len()];
+            for (row, cluster) in data.outer_iter().zip(clusters.iter_mut()) {
+                *cluster = Self::predict_with_centers(&centers, &row);
+                sums[*cluster] = &sums[*cluster] + &row;
+                counts[*cluster] += 1;
+                withinss[*cluster] += ((&row - &centers[*cluster]) * (&row - &centers[*cluster])).sum();
+            }
+            let mut shift = T::zero();
+            for ((center, sum), count) in centers.iter_mut().zip(sums).zip(counts).filter(|(_, count)| *count > 0) {
+                let updated = sum / T::from(count).expect("T::from(usize)");
+                shift = shift.max(((&updated - &*center) * (&updated - &*center)).sum().sqrt());
+                *center = updated;
+            }
+            if shift <= tol {
+                break;
+            }
+        }
+        Kmeans {
+            centers,
+            clusters,
+            withinss,
+        }
+    }
+
+    /// COP-KMeans (Wagstaff et al., 2001): `fit` with pairwise constraints from labelled points. Each pass
+    /// assigns the points in order to the nearest center that keeps every `must_link` pair together and every
+    /// `cannot_link` pair apart among the points assigned so far, with must-links followed transitively. A point
+    /// with no such center fails the fit with `Error::Unsatisfiable`, which can depend on the initial centers,
+    /// and a cannot-link inside a group of must-linked points with `Error::ConflictingConstraints`.
+    pub fn fit_constrained(data: &Array2<T>, k: usize, max_iter: usize, tol: T, must_link: &[(usize, usize)], cannot_link: &[(usize, usize)]) -> Result<Kmeans<T>, Error> {
+        Kmeans::fit_constrained_with_rng(data, k, max_iter, tol, must_link, cannot_link, &mut default_rng())
+    }
+
+    pub fn fit_constrained_with_rng<R: Rng>(
+        data: &Array2<T>,
+        k: usize,
+        max_iter: usize,
+        tol: T,
+        must_link: &[(usize, usize)],
+        cannot_link: &[(usize, usize)],
+        rng: &mut R,
+    ) -> Result<Kmeans<T>, Error> {
+        let n = data.rows();
+        let mut groups = UnionFind::new(n);
+        for (a, b) in must_link.iter() {
+            groups.union(*a, *b);
+        }
+        let group = (0..n).map(|idx| groups.find(idx)).collect::<Vec<usize>>();
+        let mut members = vec![Vec::new(); n];
+        for (idx, root) in group.iter().enumerate() {
+            members[*root].push(idx);
+        }
+        let mut apart = vec![Vec::new(); n];
+        for (a, b) in cannot_link.iter() {
+            if group[*a] == group[*b] {
+                return Err(Error::ConflictingConstraints { a: *a, b: *b });
+            }
+            apart[group[*a]].push(group[*b]);
+            apart[group[*b]].push(group[*a]);
+        }
+
+        let mut centers = kmeans_plus_plus(data, k, rng);
+        let mut clusters = vec![0; n];
+        let mut withinss = vec![T::zero(); centers.len()];
+        for _ in 0..max_iter {
+            // Each must-link group goes where its first point goes, so only the first needs a choice.
+            let mut assigned: Vec<Option<usize>> = vec![None; n];
+            for (row_idx, row) in data.outer_iter().enumerate() {
+                let root = group[row_idx];
+                if assigned[root].is_none() {
+                    let allowed = |c: &usize| apart[root].iter().all(|other| assigned[*other] != Some(*c));
+                    let distance = |c: usize| ((&row - &centers[c]) * (&row - &centers[c])).sum();
+                    let nearest = (0..centers.len()).filter(allowed).min_by(|a, b| distance(*a).partial_cmp(&distance(*b)).expect("PartialOrd distance from center"));
+                    assigned[root] = Some(nearest.ok_or(Error::Unsatisfiable { point: row_idx })?);
+                }
+                clusters[row_idx] = assigned[root].expect("assigned group");
+            }
+            let mut sums = vec![Array1::zeros(data.cols()); centers.len()];
+            let mut counts = vec![0; centers.len()];
+            withinss = vec![T::zero(); centers.len()];
+            for (row, cluster) in data.outer_iter().zip(clusters.iter()) {
+                sums[*cluster] = &sums[*cluster] + &row;
+                counts[*cluster] += 1;
+                withinss[*cluster] += ((&row - &centers[*cluster]) * (&row - &centers[*cluster])).sum();
+            }
+            let mut shift = T::zero();
+            for ((center, sum), count) in centers.iter_mut().zip(sums).zip(counts).filter(|(_, count)| *count > 0) {
+                let updated = sum / T::from(count).expect("T::from(usize)");
+                shift = shift.max(((&updated - &*center) * (&updated - &*center)).sum().sqrt());
+                *center = updated;
+            }
+            if shift <= tol {
+                break;
+            }
+        }
+        Ok(Kmeans { centers, clusters, withinss })
+    }
+
+    /// Fits `fit` for every k in `ks` and keeps the model with the highest `bic`, so the number of clusters
+    /// doesn't have to be known in advance.
+    pub fn fit_best_k(data: &Array2<T>, ks: RangeInclusive<usize>, max_iter: usize, tol: T) -> Kmeans<T> {
+        Kmeans::fit_best_k_with_rng(data, ks, max_iter, tol, &mut default_rng())
+    }
+
+    pub fn fit_best_k_with_rng<R: Rng>(data: &Array2<T>, ks: RangeInclusive<usize>, max_iter: usize, tol: T, rng: &mut R) -> Kmeans<T> {
+        ks.map(|k| Kmeans::fit_with_rng(data, k, max_iter, tol, rng))
+            .map(|model| (model.bic(data), model))
+            .max_by(|(a, _), (b, _)| a.partial_cmp(b).expect("PartialOrd BIC"))
+            .expect("non-empty range of k")
+            .1
+    }
+
+    /// Bayesian information criterion of the fit as a mixture of spherical Gaussians with a shared variance,
+    /// as in X-means (Pelleg & Moore, 2000): the log-likelihood less `p / 2 * ln n` for `p = k * (d + 1)`
+    /// parameters. Higher is better; negative infinity when there are no more points than clusters.
+    pub fn bic(&self, data: &Array2<T>) -> T {
+        let (n, d, k) = (data.rows(), data.cols(), self.centers.len());
+        if n <= k {
+            return T::neg_infinity();
+        }
+        let float = |x: usize| T::from(x).expect("T::from(usize)");
+        let two = T::one() + T::one();
+        let variance = self.inertia() / (float(d) * float(n - k));
+        let mut sizes = vec![0; k];
+        for cluster in self.clusters.iter() {
+            sizes[*cluster] += 1;
+        }
+        let log_likelihood = sizes.iter().filter(|size| **size > 0).fold(T::zero(), |acc, size| acc + float(*size) * (float(*size) / float(n)).ln())
+            - float(n * d) / two * (two * T::from(std::f64::consts::PI).expect("T::from(f64)") * variance).ln()
+            - float(d * (n - k)) / two;
+        log_likelihood - float(k * (d + 1)) / two * float(n).ln()
+    }
+
+    /// Total within-cluster sum of squared distances to the centers.
+    pub fn inertia(&self) -> T {
+        self.withinss.iter().cloned().sum()
+    }
+
     pub fn predict(&self, data: &Array2<T>) -> Vec<usize> {
         data.outer_iter().map(|row| Self::predict_with_centers(&self.centers, &row)).collect::<Vec<usize>>()
     }
@@ -68,15 +228,263 @@ impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Kmeans<T> {
             .enumerate()
             .map(|(i, center)| (i, ((row - center) * (row - center)).sum()))
             .map(|(i, x)| if x.is_nan() { (i, T::from(f32::MAX).expect("T::from(f32::MAX)")) } else { (i, x) })
-            .min_by(|(_, a), (_, b)| a.partial_cmp(&b).expect("PartialOrd distance from center"))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance from center"))
             .expect("min distance from center")
             .0
     }
 }
 
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Clustering for Kmeans<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Predict<T> for Kmeans<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        Kmeans::predict(self, data)
+    }
+}
+
+/// k-means++ seeding: the first center is a uniformly random row, each further one a row drawn with
+/// probability proportional to its squared distance from the nearest center chosen so far.
+fn kmeans_plus_plus<T: Float + ScalarOperand + Sum, R: Rng>(data: &Array2<T>, k: usize, rng: &mut R) -> Vec<Array1<T>> {
+    let mut centers = vec![data.row(rng.gen_range(0, data.rows())).to_owned()];
+    let mut distances = data.outer_iter().map(|row| ((&row - &centers[0]) * (&row - &centers[0])).sum()).collect::<Vec<T>>();
+    while centers.len() < k.min(data.rows()) {
+        let total = distances.iter().cloned().sum::<T>();
+        let mut target = T::from(rng.gen::<f64>()).expect("T::from(f64)") * total;
+        let next = distances
+            .iter()
+            .position(|d| {
+                target = target - *d;
+                target < T::zero()
+            })
+            .unwrap_or_else(|| distances.iter().rposition(|d| *d > T::zero()).unwrap_or(0));
+        let center = data.row(next).to_owned();
+        for (distance, row) in distances.iter_mut().zip(data.outer_iter()) {
+            *distance = distance.min(((&row - &center) * (&row - &center)).sum());
+        }
+        centers.push(center);
+    }
+    centers
+}
+
+/// Mini-batch k-means (Sculley, 2010): each iteration assigns a random sample of `batch_size` rows to the
+/// current centers and moves each center towards its rows with a learning rate of `1 / count`. Centers start
+/// from a random row followed by farthest-point picks, so well-separated groups each get a center.
+/// `n_iter` is the number of mini-batches used.
+#[derive(Debug)]
+pub struct MiniBatchKmeans<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> {
+    pub centers: Vec<Array1<T>>,
+    pub clusters: Vec<usize>,
+    pub n_iter: usize,
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> MiniBatchKmeans<T> {
+    /// Fits `k` centers from `max_iter` mini-batches; `clusters` comes from a final pass over all of `data`.
+    pub fn new(data: &Array2<T>, k: usize, batch_size: usize, max_iter: usize, seed: u64) -> MiniBatchKmeans<T> {
+        MiniBatchKmeans::fit(data, k, batch_size, max_iter, T::zero(), T::zero(), seed)
+    }
+
+    /// Like `new`, with two refinements. Every ten batches, centers assigned fewer than `reassignment_ratio`
+    /// times as many rows as the busiest center are moved to random rows of the current batch, so centers
+    /// stranded on outliers are put back to use. Fitting stops early once no center moves further than `tol`
+    /// in a batch; a `tol` of zero always runs `max_iter` batches.
+    pub fn fit(data: &Array2<T>, k: usize, batch_size: usize, max_iter: usize, reassignment_ratio: T, tol: T, seed: u64) -> MiniBatchKmeans<T> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut centers = vec![data.row(rng.gen_range(0, data.rows())).to_owned()];
+        while centers.len() < k.min(data.rows()) {
+            let farthest = data
+                .outer_iter()
+                .map(|row| centers.iter().map(|center| ((&row - center) * (&row - center)).sum()).fold(T::infinity(), T::min))
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance from center"))
+                .expect("max distance from centers")
+                .0;
+            centers.push(data.row(farthest).to_owned());
+        }
+        let mut counts = vec![0; centers.len()];
+        let mut n_iter = 0;
+        while n_iter < max_iter {
+            n_iter += 1;
+            let batch = sample(&mut rng, data.rows(), batch_size.min(data.rows()));
+            let assigned = batch.iter().map(|row_idx| (row_idx, Kmeans::predict_with_centers(&centers, &data.row(row_idx)))).collect::<Vec<(usize, usize)>>();
+            let previous = centers.clone();
+            for (row_idx, cluster) in assigned {
+                let row = data.row(row_idx);
+                let center = &mut centers[cluster];
+                counts[cluster] += 1;
+                let rate = T::one() / T::from(counts[cluster]).expect("T::from(usize)");
+                *center = &*center + &((&row - &*center) * rate);
+            }
+
+            if reassignment_ratio > T::zero() && n_iter % 10 == 0 {
+                let busiest = T::from(counts.iter().cloned().max().unwrap_or(0)).expect("T::from(usize)");
+                let starved = (0..centers.len()).filter(|c| T::from(counts[*c]).expect("T::from(usize)") < reassignment_ratio * busiest).collect::<Vec<usize>>();
+                if !starved.is_empty() && starved.len() < centers.len() {
+                    let floor = (0..centers.len()).filter(|c| !starved.contains(c)).map(|c| counts[c]).min().unwrap_or(0);
+                    for (cluster, row_idx) in starved.iter().zip(batch.iter().choose_multiple(&mut rng, starved.len())) {
+                        centers[*cluster] = data.row(row_idx).to_owned();
+                        counts[*cluster] = floor;
+                    }
+                    continue;
+                }
+            }
+            let moved = centers.iter().zip(previous.iter()).map(|(a, b)| ((a - b) * (a - b)).sum().sqrt()).fold(T::zero(), T::max);
+            if tol > T::zero() && moved <= tol {
+                break;
+            }
+        }
+        let clusters = data.outer_iter().map(|row| Kmeans::predict_with_centers(&centers, &row)).collect::<Vec<usize>>();
+        MiniBatchKmeans { centers, clusters, n_iter }
+    }
+
+    pub fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        data.outer_iter().map(|row| Kmeans::predict_with_centers(&self.centers, &row)).collect::<Vec<usize>>()
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Clustering for MiniBatchKmeans<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Predict<T> for MiniBatchKmeans<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        MiniBatchKmeans::predict(self, data)
+    }
+}
+
+/// Fuzzy c-means (Bezdek, 1981): every point belongs to every cluster with a membership between 0 and 1,
+/// memberships summing to 1 over the clusters. The fuzzifier `m > 1` sets how soft the partition is; close
+/// to 1 it approaches k-means, and the usual choice of 2 gives a point midway between two centers half of
+/// each. `clusters` holds each point's cluster of highest membership, indexing `centers`.
+#[derive(Debug)]
+pub struct FuzzyCmeans<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> {
+    pub centers: Vec<Array1<T>>,
+    /// One row per point and one column per cluster.
+    pub memberships: Array2<T>,
+    pub clusters: Vec<usize>,
+    pub m: T,
+    pub n_iter: usize,
+    pub converged: bool,
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> FuzzyCmeans<T> {
+    /// Alternates membership and center updates from k-means++ initial centers, stopping after `max_iter`
+    /// iterations or once no membership changes by more than `tol`.
+    pub fn fit(data: &Array2<T>, c: usize, m: T, max_iter: usize, tol: T) -> FuzzyCmeans<T> {
+        FuzzyCmeans::fit_with_rng(data, c, m, max_iter, tol, &mut default_rng())
+    }
+
+    pub fn fit_with_rng<R: Rng>(data: &Array2<T>, c: usize, m: T, max_iter: usize, tol: T, rng: &mut R) -> FuzzyCmeans<T> {
+        assert!(m > T::one(), "the fuzzifier m must be greater than 1");
+        let mut centers = kmeans_plus_plus(data, c, rng);
+        let mut memberships = fuzzy_memberships(data, &centers, m);
+        let (mut n_iter, mut converged) = (0, false);
+        while n_iter < max_iter && !converged {
+            n_iter += 1;
+            let weights = memberships.mapv(|u| u.powf(m));
+            centers = (0..centers.len())
+                .map(|k| {
+                    let column = weights.column(k);
+                    data.outer_iter().zip(column.iter()).fold(Array1::zeros(data.cols()), |acc, (row, w)| acc + &(&row * *w)) / column.sum()
+                })
+                .collect::<Vec<Array1<T>>>();
+            let updated = fuzzy_memberships(data, &centers, m);
+            converged = updated.iter().zip(memberships.iter()).all(|(a, b)| (*a - *b).abs() <= tol);
+            memberships = updated;
+        }
+        let clusters = hardest(&memberships);
+        FuzzyCmeans { centers, memberships, clusters, m, n_iter, converged }
+    }
+
+    /// Memberships of new points in the fitted clusters, one row per point.
+    pub fn predict_memberships(&self, data: &Array2<T>) -> Array2<T> {
+        fuzzy_memberships(data, &self.centers, self.m)
+    }
+
+    pub fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        hardest(&self.predict_memberships(data))
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Clustering for FuzzyCmeans<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Predict<T> for FuzzyCmeans<T> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        FuzzyCmeans::predict(self, data)
+    }
+}
+
+/// `u_ik = 1 / sum_j (d_ik / d_ij)^(2 / (m - 1))`, computed from squared distances. A point on a center
+/// belongs to it alone.
+fn fuzzy_memberships<T: Float + ScalarOperand + Sum>(data: &Array2<T>, centers: &[Array1<T>], m: T) -> Array2<T> {
+    let exponent = T::one() / (m - T::one());
+    let mut memberships = Array2::zeros((data.rows(), centers.len()));
+    for (row, mut u) in data.outer_iter().zip(memberships.outer_iter_mut()) {
+        let distances = centers.iter().map(|center| ((&row - center) * (&row - center)).sum()).collect::<Vec<T>>();
+        match distances.iter().position(|d| *d == T::zero()) {
+            Some(k) => u[k] = T::one(),
+            None => {
+                for (k, d) in distances.iter().enumerate() {
+                    u[k] = T::one() / distances.iter().map(|other| (*d / *other).powf(exponent)).sum::<T>();
+                }
+            }
+        }
+    }
+    memberships
+}
+
+fn hardest<T: Float>(memberships: &Array2<T>) -> Vec<usize> {
+    memberships
+        .outer_iter()
+        .map(|u| u.iter().enumerate().fold(0, |best, (k, x)| if *x > u[best] { k } else { best }))
+        .collect::<Vec<usize>>()
+}
+
+/// Online (MacQueen) updates: each new point moves its nearest center towards it by `1 / count`.
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> PartialFit<T> for Kmeans<T> {
+    fn partial_fit(&mut self, batch: &Array2<T>) {
+        let mut counts = vec![0; self.centers.len()];
+        for cluster in self.clusters.iter() {
+            counts[*cluster] += 1;
+        }
+        for row in batch.outer_iter() {
+            let cluster = Self::predict_with_centers(&self.centers, &row);
+            let center = &mut self.centers[cluster];
+            self.withinss[cluster] += ((&row - &*center) * (&row - &*center)).sum();
+            counts[cluster] += 1;
+            let rate = T::one() / T::from(counts[cluster]).expect("T::from(usize)");
+            *center = &*center + &((&row - &*center) * rate);
+            self.clusters.push(cluster);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use itertools::Itertools;
+    use rand::rngs::mock::StepRng;
 
     #[test]
     fn test_kmeans() {
@@ -97,4 +505,135 @@ mod tests {
         assert!(classes.iter().take(3).all(|x| *x == class_a));
         assert!(classes.iter().skip(3).all(|x| *x != class_a));
     }
+
+    #[test]
+    fn test_partial_fit() {
+        let data = Array2::from_shape_vec((4, 2), vec![0.0, 0.0, 0.1, 0.1, 10.0, 10.0, 10.1, 10.1]).unwrap();
+        let mut model = Kmeans::new(&data, 2, 10, 5);
+        let first = Array2::from_shape_vec((2, 2), vec![0.2, 0.0, 9.9, 10.0]).unwrap();
+        let second = Array2::from_shape_vec((1, 2), vec![0.0, 0.2]).unwrap();
+        {
+            let streaming: &mut dyn PartialFit<f64> = &mut model;
+            streaming.partial_fit(&first);
+            streaming.partial_fit(&second);
+        }
+        let labels = model.labels();
+        assert!(labels.len() == 7);
+        assert!(labels[4] == labels[0] && labels[6] == labels[0]);
+        assert!(labels[5] == labels[2] && labels[5] != labels[0]);
+    }
+
+    #[test]
+    fn test_reproducible_with_rng() {
+        let data = Array2::from_shape_vec((6, 2), vec![0.0, 0.0, 0.1, 0.1, 0.2, 0.0, 5.0, 5.0, 5.1, 5.1, 5.2, 5.0]).unwrap();
+        let first = Kmeans::new_with_rng(&data, 2, 10, 3, &mut StepRng::new(0, 1 << 60));
+        let second = Kmeans::new_with_rng(&data, 2, 10, 3, &mut StepRng::new(0, 1 << 60));
+        assert!(first.clusters == second.clusters);
+        assert!(first.centers == second.centers);
+    }
+
+    #[test]
+    fn test_mini_batch_kmeans() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let blobs = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)];
+        let mut values = Vec::new();
+        for (x, y) in blobs.iter() {
+            for _ in 0..300 {
+                values.push(x + rng.gen_range(-1.0, 1.0));
+                values.push(y + rng.gen_range(-1.0, 1.0));
+            }
+        }
+        let data = Array2::from_shape_vec((900, 2), values).unwrap();
+        let model = MiniBatchKmeans::new(&data, 3, 30, 50, 42);
+        assert!(model.centers.len() == 3 && model.clusters.len() == 900);
+        let firsts = model.clusters.chunks(300).map(|blob| blob[0]).collect::<Vec<usize>>();
+        assert!(model.clusters.chunks(300).zip(firsts.iter()).all(|(blob, first)| blob.iter().all(|c| c == first)));
+        assert!(firsts[0] != firsts[1] && firsts[1] != firsts[2] && firsts[0] != firsts[2]);
+        assert!(model.predict(&data) == model.clusters);
+    }
+
+    #[test]
+    fn test_fit_best_k() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let centers = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)];
+        let values = (0..300).flat_map(|i| {
+            let (x, y) = centers[i % 3];
+            vec![x + rng.gen_range(-1.0, 1.0), y + rng.gen_range(-1.0, 1.0)]
+        });
+        let data = Array2::from_shape_vec((300, 2), values.collect::<Vec<f64>>()).unwrap();
+        let model = Kmeans::fit_best_k_with_rng(&data, 1..=6, 100, 1e-6, &mut StdRng::seed_from_u64(0));
+        assert!(model.centers.len() == 3);
+        assert!((0..300).all(|i| model.clusters[i] == model.clusters[i % 3]));
+        let one = Kmeans::fit_with_rng(&data, 1, 100, 1e-6, &mut StdRng::seed_from_u64(0));
+        assert!(model.bic(&data) > one.bic(&data));
+    }
+
+    #[test]
+    fn test_mini_batch_kmeans_fit() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut values = Vec::new();
+        for (x, y) in [(0.0, 0.0), (10.0, 0.0)].iter() {
+            for _ in 0..300 {
+                values.push(x + rng.gen_range(-1.0, 1.0));
+                values.push(y + rng.gen_range(-1.0, 1.0));
+            }
+        }
+        values.extend_from_slice(&[100.0, 100.0]);
+        let data = Array2::from_shape_vec((601, 2), values).unwrap();
+
+        // Farthest-point seeding puts a center on the outlier, which reassignment moves back into the data.
+        let stranded = MiniBatchKmeans::new(&data, 3, 50, 40, 3);
+        assert!(stranded.centers.iter().any(|center| center[0] == 100.0));
+        let model = MiniBatchKmeans::fit(&data, 3, 50, 40, 0.05, 0.0, 3);
+        assert!(model.centers.iter().all(|center| center[0] < 50.0) && model.n_iter == 40);
+        assert!(model.clusters[..300].iter().all(|c| model.clusters[300..600].iter().all(|d| c != d)));
+
+        let early = MiniBatchKmeans::fit(&data, 3, 50, 1000, 0.05, 0.05, 3);
+        assert!(early.n_iter < 1000);
+    }
+
+    #[test]
+    fn test_fit() {
+        let data = Array2::from_shape_vec((9, 2), vec![0.0, 0.0, 0.1, 0.1, 0.2, 0.0, 5.0, 5.0, 5.1, 5.1, 5.2, 5.0, 0.0, 9.0, 0.1, 9.1, 0.2, 9.0]).unwrap();
+        let model = Kmeans::fit_with_rng(&data, 3, 100, 1e-9, &mut StdRng::seed_from_u64(1));
+        assert!(model.centers.len() == 3);
+        assert!(model.clusters.chunks(3).all(|group| group.iter().all(|c| *c == group[0])));
+        assert!(model.clusters[0] != model.clusters[3] && model.clusters[3] != model.clusters[6] && model.clusters[0] != model.clusters[6]);
+        assert!(model.inertia() < 0.2);
+        assert!(model.predict(&Array2::from_shape_vec((1, 2), vec![5.05, 4.95]).unwrap()) == vec![model.clusters[3]]);
+    }
+
+    #[test]
+    fn test_fuzzy_cmeans() {
+        let data = Array2::from_shape_vec((9, 2), vec![0.0, 0.0, 0.1, 0.1, 0.2, 0.0, 5.0, 5.0, 5.1, 5.1, 5.2, 5.0, 0.0, 9.0, 0.1, 9.1, 0.2, 9.0]).unwrap();
+        let model = FuzzyCmeans::fit_with_rng(&data, 3, 2.0, 100, 1e-9, &mut StdRng::seed_from_u64(1));
+        assert!(model.converged && model.memberships.dim() == (9, 3));
+        assert!(model.memberships.outer_iter().all(|u| (u.sum() - 1.0).abs() < 1e-12));
+        assert!(model.clusters.chunks(3).all(|group| group.iter().all(|c| *c == group[0])) && model.clusters.iter().unique().count() == 3);
+        assert!(model.memberships.outer_iter().all(|u| u.iter().cloned().fold(0.0, f64::max) > 0.9));
+
+        // Midway between two centers a point belongs half to each, and with a smaller fuzzifier it's crisper.
+        let centers = [&model.centers[model.clusters[0]], &model.centers[model.clusters[3]]];
+        let midway = Array2::from_shape_fn((1, 2), |(_, col)| (centers[0][col] + centers[1][col]) / 2.0);
+        let u = model.predict_memberships(&midway);
+        assert!((u[[0, model.clusters[0]]] - u[[0, model.clusters[3]]]).abs() < 1e-9 && model.predict(&data) == model.clusters);
+        let crisp = FuzzyCmeans::fit_with_rng(&data, 3, 1.2, 100, 1e-9, &mut StdRng::seed_from_u64(1));
+        let strongest = |memberships: &Array2<f64>| memberships.outer_iter().map(|u| u.iter().cloned().fold(0.0, f64::max)).collect::<Vec<f64>>();
+        assert!(crisp.clusters == model.clusters && strongest(&crisp.memberships).iter().zip(strongest(&model.memberships)).all(|(a, b)| *a > b));
+    }
+
+    #[test]
+    fn test_fit_constrained() {
+        let data = Array2::from_shape_vec((6, 1), vec![0.0, 0.1, 0.2, 5.0, 5.1, 5.2]).unwrap();
+        let free = Kmeans::fit_constrained_with_rng(&data, 2, 100, 1e-9, &[], &[], &mut StdRng::seed_from_u64(1)).unwrap();
+        assert!(free.clusters[..3].iter().all(|c| *c == free.clusters[0]) && free.clusters[3..].iter().all(|c| *c != free.clusters[0]));
+
+        // Labels saying 0.2 belongs with the far group, and 0.0 never with 0.1, override the geometry.
+        let model = Kmeans::fit_constrained_with_rng(&data, 2, 100, 1e-9, &[(2, 5)], &[(0, 1)], &mut StdRng::seed_from_u64(1)).unwrap();
+        assert!(model.clusters[2] == model.clusters[5] && model.clusters[0] != model.clusters[1]);
+
+        let three = [(0, 1), (1, 2), (0, 2)];
+        assert!(Kmeans::fit_constrained_with_rng(&data, 2, 100, 1e-9, &[], &three, &mut StdRng::seed_from_u64(1)).err() == Some(Error::Unsatisfiable { point: 2 }));
+        assert!(Kmeans::fit_constrained_with_rng(&data, 2, 100, 1e-9, &[(0, 1), (1, 2)], &[(0, 2)], &mut StdRng::seed_from_u64(1)).is_err());
+    }
 }
@@ -0,0 +1,184 @@
+use crate::index::{row_slice, squared_euclidean};
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+
+/// k-medoids: picks `k` of the points themselves as cluster representatives, minimising the total
+/// dissimilarity of every point to its nearest medoid. Only pairwise dissimilarities are needed, so it works
+/// for strings, graphs or precomputed distances where a centroid means nothing. Medoids start from the
+/// greedy PAM BUILD step and are improved by FasterPAM swaps (Schubert & Rousseeuw, 2021), which take the
+/// best medoid to trade for each candidate point in O(n) rather than PAM's O(nk). `clusters` indexes into
+/// `medoids`, which holds point indices, as `Kmeans` labels index into its centers.
+#[derive(Debug)]
+pub struct KMedoids<T: Float> {
+    pub medoids: Vec<usize>,
+    pub clusters: Vec<usize>,
+    /// Total dissimilarity of the points to their medoids.
+    pub loss: T,
+    pub n_iter: usize,
+    pub converged: bool,
+}
+
+impl<T: Float> KMedoids<T> {
+    /// Clusters the rows of `data` under Euclidean distance.
+    pub fn new(data: &Array2<T>, k: usize, max_iter: usize) -> KMedoids<T> {
+        let n = data.rows();
+        let distances = Array2::from_shape_fn((n, n), |(a, b)| squared_euclidean(&row_slice(data.row(a)), &row_slice(data.row(b))).sqrt());
+        KMedoids::from_distances(&distances, k, max_iter)
+    }
+
+    /// Clusters arbitrary items under `dissimilarity`, which should be symmetric and zero from an item to itself.
+    pub fn with_dissimilarity<I, F: Fn(&I, &I) -> T>(items: &[I], k: usize, max_iter: usize, dissimilarity: F) -> KMedoids<T> {
+        let distances = Array2::from_shape_fn((items.len(), items.len()), |(a, b)| dissimilarity(&items[a], &items[b]));
+        KMedoids::from_distances(&distances, k, max_iter)
+    }
+
+    /// Clusters from a square matrix of pairwise dissimilarities. `max_iter` bounds the passes over the
+    /// non-medoid points; `converged` says whether a pass found no improving swap.
+    pub fn from_distances(distances: &Array2<T>, k: usize, max_iter: usize) -> KMedoids<T> {
+        let n = distances.rows();
+        assert!(distances.cols() == n, "distance matrix must be square");
+        assert!(k > 0 && k <= n, "k must be between 1 and the number of points");
+
+        // BUILD: start from the most central point, then repeatedly add the point that lowers the loss most.
+        let mut medoids = Vec::with_capacity(k);
+        let mut nearest = vec![T::infinity(); n];
+        while medoids.len() < k {
+            let gain = |x: usize| match medoids.is_empty() {
+                true => (0..n).fold(T::zero(), |acc, o| acc - distances[[o, x]]),
+                false => (0..n).fold(T::zero(), |acc, o| acc + (nearest[o] - distances[[o, x]]).max(T::zero())),
+            };
+            let next = (0..n)
+                .filter(|x| !medoids.contains(x))
+                .map(|x| (x, gain(x)))
+                .fold(None, |best: Option<(usize, T)>, (x, g)| match best {
+                    Some((_, best_gain)) if best_gain >= g => best,
+                    _ => Some((x, g)),
+                })
+                .expect("a non-medoid point")
+                .0;
+            medoids.push(next);
+            for (o, d) in nearest.iter_mut().enumerate() {
+                *d = d.min(distances[[o, next]]);
+            }
+        }
+
+        // SWAP: for each candidate, find the medoid whose replacement by it lowers the loss most, and take
+        // that swap at once if it helps, as FasterPAM does.
+        let mut assignment = Assignment::new(distances, &medoids);
+        let (mut n_iter, mut converged) = (0, false);
+        while n_iter < max_iter && !converged {
+            n_iter += 1;
+            converged = true;
+            for candidate in 0..n {
+                if medoids.contains(&candidate) {
+                    continue;
+                }
+                let mut delta = assignment.removal_loss(k);
+                let mut added = T::zero();
+                for o in 0..n {
+                    let d = distances[[o, candidate]];
+                    if d < assignment.nearest[o] {
+                        added = added + d - assignment.nearest[o];
+                        delta[assignment.clusters[o]] = delta[assignment.clusters[o]] + assignment.nearest[o] - assignment.second[o];
+                    } else if d < assignment.second[o] {
+                        delta[assignment.clusters[o]] = delta[assignment.clusters[o]] + d - assignment.second[o];
+                    }
+                }
+                let (replaced, delta) = delta.iter().enumerate().fold((0, T::infinity()), |best, (m, d)| if *d < best.1 { (m, *d) } else { best });
+                // Ignore changes within rounding error, which could otherwise swap back and forth forever.
+                if delta + added < -T::epsilon() * assignment.loss().max(T::one()) {
+                    medoids[replaced] = candidate;
+                    assignment = Assignment::new(distances, &medoids);
+                    converged = false;
+                }
+            }
+        }
+
+        let loss = assignment.loss();
+        KMedoids { medoids, clusters: assignment.clusters, loss, n_iter, converged }
+    }
+}
+
+impl<T: Float> Clustering for KMedoids<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+/// Each point's nearest medoid, with the distances to its nearest and second nearest medoids.
+struct Assignment<T> {
+    clusters: Vec<usize>,
+    nearest: Vec<T>,
+    second: Vec<T>,
+}
+
+impl<T: Float> Assignment<T> {
+    fn new(distances: &Array2<T>, medoids: &[usize]) -> Assignment<T> {
+        let n = distances.rows();
+        let mut assignment = Assignment { clusters: vec![0; n], nearest: vec![T::infinity(); n], second: vec![T::infinity(); n] };
+        for o in 0..n {
+            for (m, medoid) in medoids.iter().enumerate() {
+                let d = distances[[o, *medoid]];
+                if d < assignment.nearest[o] {
+                    assignment.second[o] = assignment.nearest[o];
+                    assignment.nearest[o] = d;
+                    assignment.clusters[o] = m;
+                } else if d < assignment.second[o] {
+                    assignment.second[o] = d;
+                }
+            }
+        }
+        assignment
+    }
+
+    /// Loss increase from removing each medoid, with its points moving to their second nearest medoid.
+    fn removal_loss(&self, k: usize) -> Vec<T> {
+        let mut loss = vec![T::zero(); k];
+        for o in 0..self.clusters.len() {
+            loss[self.clusters[o]] = loss[self.clusters[o]] + self.second[o] - self.nearest[o];
+        }
+        loss
+    }
+
+    fn loss(&self) -> T {
+        self.nearest.iter().fold(T::zero(), |acc, d| acc + *d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+    use itertools::Itertools;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_kmedoids() {
+        let data = Array2::from_shape_vec((9, 1), vec![0.0, 0.1, 0.3, 5.0, 5.2, 5.3, 9.0, 9.5, 9.6]).unwrap();
+        let model = KMedoids::new(&data, 3, 10);
+        assert!(model.converged && model.medoids.iter().sorted().cloned().collect::<Vec<usize>>() == vec![1, 4, 7]);
+        assert!((0..9).all(|o| model.clusters[o] == model.clusters[o / 3 * 3]) && (model.loss - 1.2).abs() < 1e-12);
+
+        // Only dissimilarities are needed, here between words by length and first letter.
+        let words = ["apple", "apricot", "avocado", "kiwi", "kumquat", "kale"];
+        let dissimilarity = |a: &&str, b: &&str| (a.len() as f64 - b.len() as f64).abs() + if a.as_bytes()[0] == b.as_bytes()[0] { 0.0 } else { 10.0 };
+        let words = KMedoids::with_dissimilarity(&words, 2, 10, dissimilarity);
+        assert!(words.clusters[..3].iter().all_equal() && words.clusters[3..].iter().all_equal() && words.clusters[0] != words.clusters[3]);
+
+        // Swaps improve on BUILD's greedy start, here reaching the best of every possible set of medoids.
+        let mut rng = StdRng::seed_from_u64(4);
+        let data = Array2::from_shape_fn((14, 2), |_| rng.gen_range(0.0, 1.0));
+        let model = KMedoids::new(&data, 3, 20);
+        let distances = Array2::from_shape_fn((14, 14), |(a, b)| squared_euclidean(data.row(a).as_slice().unwrap(), data.row(b).as_slice().unwrap()).sqrt());
+        let best = (0..14).combinations(3).map(|medoids| Assignment::new(&distances, &medoids).loss()).fold(f64::INFINITY, f64::min);
+        assert!(model.converged && (model.loss - best).abs() < 1e-12);
+        assert!(KMedoids::new(&column_major(&data), 3, 20).medoids == model.medoids);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::index::{index_owned_rows, Index};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::collections::HashMap;
+
+/// k-nearest-neighbour classifier over labelled training points, e.g. a fitted clustering. Noise points
+/// (label 0) never vote.
+#[derive(Debug)]
+pub struct KnnClassifier<T: Float + One + Zero> {
+    pub labels: Vec<usize>,
+    pub k: usize,
+    index: Index<T, Vec<T>>,
+}
+
+impl<T: Float + One + Zero> KnnClassifier<T> {
+    /// Classifies by the `labels` of the rows of `data`, one per row.
+    pub fn from_labels(data: &Array2<T>, labels: &[usize], k: usize) -> KnnClassifier<T> {
+        assert!(labels.len() == data.rows(), "labels has {} entries for {} rows of data", labels.len(), data.rows());
+        KnnClassifier {
+            labels: labels.to_vec(),
+            k,
+            index: index_owned_rows(data),
+        }
+    }
+
+    /// Majority label among the `k` nearest training points, ties going to the label seen nearest.
+    /// Predicts 0 when there are no clustered training points.
+    pub fn predict(&self, new_data: &Array2<T>) -> Vec<usize> {
+        new_data
+            .outer_iter()
+            .map(|row| {
+                let nearest = self.index.nearest_matching(&row.to_vec(), self.k, |idx| self.labels[idx] > 0);
+                let mut votes: HashMap<usize, (usize, usize)> = HashMap::new();
+                for (rank, (_, idx)) in nearest.iter().enumerate() {
+                    votes.entry(self.labels[*idx]).or_insert((0, rank)).0 += 1;
+                }
+                votes
+                    .into_iter()
+                    .max_by(|(_, (count_a, rank_a)), (_, (count_b, rank_b))| count_a.cmp(count_b).then(rank_b.cmp(rank_a)))
+                    .map_or(0, |(label, _)| label)
+            })
+            .collect::<Vec<usize>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use ndarray::s;
+
+    #[test]
+    fn test_knn_non_convex() {
+        let mut points = (0..24)
+            .map(|i| f64::from(i) * std::f64::consts::PI / 12.0)
+            .flat_map(|angle| vec![3.0 * angle.cos(), 3.0 * angle.sin()])
+            .collect::<Vec<f64>>();
+        points.extend_from_slice(&[5.5, 0.0, 5.6, 0.1, 5.6, -0.1]);
+        let data = Array2::from_shape_vec((27, 2), points).unwrap();
+        let model = Dbscan::new(&data, 1.0, 2, false);
+        let (ring, blob) = (model.clusters[0], model.clusters[24]);
+        assert!(ring != blob && ring > 0 && blob > 0);
+
+        let query = Array2::from_shape_vec((1, 2), vec![3.0, 0.1]).unwrap();
+        let classifier = KnnClassifier::from_labels(&data, &model.clusters, 3);
+        assert!(classifier.predict(&query) == vec![ring]);
+
+        let ring_centroid = data.slice(s![0..24, ..]).mean_axis(Axis(0));
+        let blob_centroid = data.slice(s![24..27, ..]).mean_axis(Axis(0));
+        let distance = |centroid: &Array1<f64>| (&query.row(0) - centroid).mapv(|x| x * x).sum();
+        assert!(distance(&blob_centroid) < distance(&ring_centroid));
+
+        // A short label list would index past its end at prediction time, so it is refused up front.
+        assert!(std::panic::catch_unwind(|| KnnClassifier::from_labels(&data, &model.clusters[..26], 3)).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! A labelling of points with the bookkeeping around it: which clusters there are, who is in them and how two
+//! labellings of the same points line up. `LabelSet` derefs to the `[usize]` the models produce, so it goes
+//! wherever a label slice does, and any model gives one through `Clustering::label_set`. `matching` and
+//! `aligned_to` pair the clusters of two runs, across parameter settings or days of data, so that the same
+//! cluster keeps the same label.
+
+use ndarray::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Deref;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct LabelSet {
+    labels: Vec<usize>,
+}
+
+impl LabelSet {
+    pub fn new(labels: Vec<usize>) -> LabelSet {
+        LabelSet { labels }
+    }
+
+    pub fn into_vec(self) -> Vec<usize> {
+        self.labels
+    }
+
+    /// The distinct labels in ascending order, noise included when there is any.
+    pub fn clusters(&self) -> Vec<usize> {
+        self.members().into_keys().collect::<Vec<usize>>()
+    }
+
+    /// Each distinct label in ascending order with its points in ascending order.
+    pub fn iter_clusters(&self) -> impl Iterator<Item = (usize, Vec<usize>)> {
+        self.members().into_iter()
+    }
+
+    /// Number of points labelled `cluster`.
+    pub fn len_of(&self, cluster: usize) -> usize {
+        self.labels.iter().filter(|label| **label == cluster).count()
+    }
+
+    /// `points x clusters` membership matrix with a column for every distinct label, in `clusters` order. For
+    /// density-based labels that includes a noise column; `Dbscan::one_hot` leaves it out.
+    pub fn to_onehot(&self) -> Array2<u8> {
+        let clusters = self.clusters();
+        let columns = clusters.iter().enumerate().map(|(column, label)| (*label, column)).collect::<HashMap<usize, usize>>();
+        let mut encoded = Array2::zeros((self.labels.len(), clusters.len()));
+        for (row, label) in self.labels.iter().enumerate() {
+            encoded[[row, columns[label]]] = 1;
+        }
+        encoded
+    }
+
+    /// Every label passed through `f`, which may merge clusters by mapping them to the same label.
+    pub fn remap<F: FnMut(usize) -> usize>(&self, f: F) -> LabelSet {
+        LabelSet::new(self.labels.iter().cloned().map(f).collect::<Vec<usize>>())
+    }
+
+    /// Labels renumbered from `first` in order of first appearance, so equal partitions get equal labels.
+    pub fn canonical(&self, first: usize) -> LabelSet {
+        let mut numbers = HashMap::new();
+        self.remap(|label| {
+            let next = first + numbers.len();
+            *numbers.entry(label).or_insert(next)
+        })
+    }
+
+    /// The contingency matrix against `other`: entry `[i, j]` counts the points labelled `rows[i]` here and
+    /// `columns[j]` there, with both label lists ascending. Returned as `(rows, columns, counts)`.
+    pub fn contingency(&self, other: &LabelSet) -> (Vec<usize>, Vec<usize>, Array2<usize>) {
+        assert!(self.labels.len() == other.labels.len(), "labellings of different numbers of points");
+        let (rows, columns) = (self.clusters(), other.clusters());
+        let row_index = rows.iter().enumerate().map(|(i, label)| (*label, i)).collect::<HashMap<usize, usize>>();
+        let column_index = columns.iter().enumerate().map(|(j, label)| (*label, j)).collect::<HashMap<usize, usize>>();
+        let mut counts = Array2::zeros((rows.len(), columns.len()));
+        for (a, b) in self.labels.iter().zip(other.labels.iter()) {
+            counts[[row_index[a], column_index[b]]] += 1;
+        }
+        (rows, columns, counts)
+    }
+
+    /// Whether every cluster here lies inside a single cluster of `other`, as when `other` merges some of them.
+    pub fn refines(&self, other: &LabelSet) -> bool {
+        let mut containing = HashMap::new();
+        self.labels.len() == other.labels.len() && self.labels.iter().zip(other.labels.iter()).all(|(a, b)| *containing.entry(*a).or_insert(*b) == *b)
+    }
+
+    /// Whether both labellings group the points the same way, whatever numbers they give the groups.
+    pub fn same_partition(&self, other: &LabelSet) -> bool {
+        self.refines(other) && other.refines(self)
+    }
+
+    /// Pairs each cluster here with at most one cluster of `reference` so that the total overlap is as large as
+    /// possible (the Hungarian method on the contingency matrix), one entry per label in ascending order. The
+    /// `noise` label, if any, is left out on both sides.
+    pub fn matching(&self, reference: &LabelSet, noise: Option<usize>) -> Vec<ClusterMatch> {
+        let (rows, columns, counts) = self.contingency(reference);
+        let rows_kept = (0..rows.len()).filter(|i| Some(rows[*i]) != noise).collect::<Vec<usize>>();
+        let columns_kept = (0..columns.len()).filter(|j| Some(columns[*j]) != noise).collect::<Vec<usize>>();
+        let size = rows_kept.len().max(columns_kept.len());
+        let cost = Array2::from_shape_fn((size, size), |(i, j)| match (rows_kept.get(i), columns_kept.get(j)) {
+            (Some(row), Some(column)) => -(counts[[*row, *column]] as f64),
+            _ => 0.0,
+        });
+        let assignment = hungarian(&cost);
+        rows_kept
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let size = counts.row(*row).sum();
+                match columns_kept.get(assignment[i]).filter(|column| counts[[*row, **column]] > 0) {
+                    Some(column) => {
+                        let overlap = counts[[*row, *column]];
+                        let reference_size = counts.column(*column).sum();
+                        ClusterMatch { label: rows[*row], matched: Some(columns[*column]), size, overlap, jaccard: overlap as f64 / (size + reference_size - overlap) as f64 }
+                    }
+                    None => ClusterMatch { label: rows[*row], matched: None, size, overlap: 0, jaccard: 0.0 },
+                }
+            })
+            .collect::<Vec<ClusterMatch>>()
+    }
+
+    /// These labels renamed to their `matching` clusters in `reference`. Clusters left unmatched get new labels
+    /// above every label in `reference`, and `noise` stays as it is.
+    pub fn aligned_to(&self, reference: &LabelSet, noise: Option<usize>) -> LabelSet {
+        let mut next = reference.iter().chain(noise.iter()).cloned().max().map_or(0, |max| max + 1);
+        let mut names = HashMap::new();
+        for cluster in self.matching(reference, noise) {
+            let name = cluster.matched.unwrap_or_else(|| {
+                next += 1;
+                next - 1
+            });
+            names.insert(cluster.label, name);
+        }
+        self.remap(|label| names.get(&label).cloned().unwrap_or(label))
+    }
+
+    fn members(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (point, label) in self.labels.iter().enumerate() {
+            members.entry(*label).or_default().push(point);
+        }
+        members
+    }
+}
+
+/// One cluster's best match in another labelling, from `LabelSet::matching`: the points they share and their
+/// Jaccard index, the share over the points in either.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterMatch {
+    pub label: usize,
+    pub matched: Option<usize>,
+    pub size: usize,
+    pub overlap: usize,
+    pub jaccard: f64,
+}
+
+/// Minimum-cost assignment of rows to columns of a square matrix, by the O(n^3) Hungarian method with
+/// potentials: row `i` goes to column `assignment[i]`.
+fn hungarian(cost: &Array2<f64>) -> Vec<usize> {
+    let n = cost.rows();
+    let (mut u, mut v) = (vec![0.0; n + 1], vec![0.0; n + 1]);
+    // `owner[j]` is the row, counted from 1, assigned to column j, with column 0 standing for the row being added.
+    let mut owner = vec![0; n + 1];
+    let mut way = vec![0; n + 1];
+    for row in 1..=n {
+        owner[0] = row;
+        let mut column = 0;
+        let mut min_reduced = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[column] = true;
+            let current = owner[column];
+            let (mut delta, mut next) = (f64::INFINITY, 0);
+            for j in 1..=n {
+                if !used[j] {
+                    let reduced = cost[[current - 1, j - 1]] - u[current] - v[j];
+                    if reduced < min_reduced[j] {
+                        min_reduced[j] = reduced;
+                        way[j] = column;
+                    }
+                    if min_reduced[j] < delta {
+                        delta = min_reduced[j];
+                        next = j;
+                    }
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[owner[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_reduced[j] -= delta;
+                }
+            }
+            column = next;
+            if owner[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let previous = way[column];
+            owner[column] = owner[previous];
+            column = previous;
+        }
+    }
+    let mut assignment = vec![0; n];
+    for j in 1..=n {
+        assignment[owner[j] - 1] = j - 1;
+    }
+    assignment
+}
+
+impl Deref for LabelSet {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.labels
+    }
+}
+
+impl From<Vec<usize>> for LabelSet {
+    fn from(labels: Vec<usize>) -> LabelSet {
+        LabelSet::new(labels)
+    }
+}
+
+impl From<LabelSet> for Vec<usize> {
+    fn from(labels: LabelSet) -> Vec<usize> {
+        labels.labels
+    }
+}
+
+/// Whether two fits of the same points give the same clusters and the same noise (label 0), whatever numbers
+/// they give the clusters; the comparison the model tests make between fits.
+#[cfg(test)]
+pub(crate) fn same_partition(a: &[usize], b: &[usize]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| (*x == 0) == (*y == 0)) && LabelSet::from(a.to_vec()).same_partition(&LabelSet::from(b.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use crate::metrics::adjusted_rand_index;
+    use crate::traits::Clustering;
+
+    #[test]
+    fn test_label_set() {
+        let labels = LabelSet::from(vec![2, 2, 0, 1, 1, 2]);
+        assert!(labels.clusters() == vec![0, 1, 2] && labels.len_of(2) == 3 && labels.len_of(5) == 0 && labels.len() == 6);
+        assert!(labels.iter_clusters().collect::<Vec<(usize, Vec<usize>)>>() == vec![(0, vec![2]), (1, vec![3, 4]), (2, vec![0, 1, 5])]);
+        assert!(labels.to_onehot().row(0).to_vec() == vec![0, 0, 1] && labels.to_onehot().sum_axis(Axis(0)).to_vec() == vec![1, 2, 3]);
+        assert!(labels.canonical(1).into_vec() == vec![1, 1, 2, 3, 3, 1] && adjusted_rand_index(&labels, &labels.canonical(1)) == 1.0);
+
+        let merged = labels.remap(|label| label.min(1));
+        assert!(labels.refines(&merged) && !merged.refines(&labels) && labels.same_partition(&labels.canonical(7)));
+        let (rows, columns, counts) = labels.contingency(&merged);
+        assert!(rows == vec![0, 1, 2] && columns == vec![0, 1] && counts == arr2(&[[1, 0], [0, 2], [0, 3]]));
+
+        assert!(hungarian(&arr2(&[[4.0, 1.0, 3.0], [2.0, 0.0, 5.0], [3.0, 2.0, 2.0]])) == vec![1, 0, 2]);
+        let run = LabelSet::from(vec![0, 0, 0, 1, 1, 2, 2, 2, 9]);
+        let reference = LabelSet::from(vec![5, 5, 5, 3, 3, 7, 7, 4, 9]);
+        let matches = run.matching(&reference, Some(9));
+        assert!(matches.iter().map(|m| (m.label, m.matched, m.overlap)).collect::<Vec<(usize, Option<usize>, usize)>>() == vec![(0, Some(5), 3), (1, Some(3), 2), (2, Some(7), 2)]);
+        assert!(matches[2].size == 3 && (matches[2].jaccard - 2.0 / 3.0).abs() < 1e-12);
+        assert!(run.aligned_to(&reference, Some(9)).into_vec() == vec![5, 5, 5, 3, 3, 7, 7, 7, 9]);
+        // With more clusters than the reference, the leftover one gets a new label.
+        let split = LabelSet::from(vec![0, 0, 1, 1, 1, 2, 2]).aligned_to(&LabelSet::from(vec![1, 1, 1, 1, 1, 2, 2]), None);
+        assert!(split.into_vec() == vec![3, 3, 1, 1, 1, 2, 2]);
+
+        let data = Array2::from_shape_vec((5, 1), vec![0.0, 0.1, 0.2, 5.0, 5.1]).unwrap();
+        let model = Dbscan::new(&data, 0.5, 2, true);
+        assert!(model.label_set().same_partition(&LabelSet::from(vec![1, 1, 1, 2, 2])));
+    }
+}
@@ -1,10 +1,157 @@
+//! Data clustering over `ndarray` arrays. Every algorithm is generic over its float type, and `f32` is
+//! supported end to end: it halves the memory taken by the data and by the neighbour index built over it,
+//! and the KdTree and brute-force backends treat it exactly like `f64`. Distances carry about seven
+//! significant digits, so an eps below roughly 1e-6 of the data's scale can't be resolved, and totals such
+//! as k-means inertia or the metrics accumulate in `T`, losing a little precision over millions of points.
+//!
+//! All of that needs the default `std` feature. Without it the crate is `no_std` and holds only `embedded`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_cast;
+#[cfg(feature = "arrow")]
+extern crate arrow_ipc;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+#[cfg(feature = "std")]
 extern crate itertools;
+#[cfg(feature = "kdtree")]
 extern crate kdtree;
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+#[cfg(feature = "std")]
 extern crate ndarray;
+#[cfg(feature = "io")]
+extern crate parquet;
+#[cfg(feature = "std")]
 extern crate rand;
+#[cfg(feature = "polars")]
+extern crate polars;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "simd")]
+extern crate wide;
 
+#[cfg(feature = "std")]
+pub mod affinity_propagation;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "std")]
+pub mod birch;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "std")]
+pub mod clique;
+#[cfg(feature = "std")]
+pub mod components;
+#[cfg(feature = "polars")]
+pub mod dataframe;
+#[cfg(feature = "std")]
+pub mod datasets;
+#[cfg(feature = "std")]
 pub mod dbscan;
+#[cfg(feature = "std")]
+pub mod density_peaks;
+#[cfg(feature = "std")]
+pub mod drift;
+pub mod embedded;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod gmm;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "std")]
+pub mod hdbscan;
+#[cfg(feature = "std")]
+pub mod hierarchical;
+#[cfg(feature = "std")]
+pub mod incremental;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "std")]
 pub mod kmeans;
+#[cfg(feature = "std")]
+pub mod kmedoids;
+#[cfg(feature = "std")]
+pub mod knn;
+#[cfg(feature = "std")]
+pub mod labels;
+#[cfg(feature = "std")]
+pub mod mean_shift;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "std")]
+pub mod mixed;
+#[cfg(feature = "std")]
+pub mod optics;
+#[cfg(feature = "std")]
+pub mod outlier;
+#[cfg(feature = "std")]
+pub mod postprocess;
+#[cfg(feature = "std")]
+pub mod preprocess;
+#[cfg(feature = "std")]
+pub mod reduce;
+#[cfg(feature = "std")]
+pub mod sparse;
+#[cfg(feature = "std")]
+pub mod spectral;
+#[cfg(feature = "std")]
+pub mod stability;
+#[cfg(feature = "std")]
+pub mod traclus;
+#[cfg(feature = "std")]
+pub mod traits;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod rng;
+#[cfg(feature = "std")]
+mod union_find;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::dbscan::{Dbscan, DbscanBuilder};
+    use crate::kmeans::Kmeans;
+    use crate::metrics::{davies_bouldin, silhouette_score};
+    use crate::optics::Optics;
+    use ndarray::prelude::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_f32_end_to_end() {
+        let values = vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -2.1, 2.9, -1.0, -2.0];
+        let data32 = Array2::from_shape_vec((8, 2), values.iter().map(|x| *x as f32).collect::<Vec<f32>>()).unwrap();
+        let data64 = Array2::from_shape_vec((8, 2), values).unwrap();
+        let model32 = Dbscan::with_seed(&data32, 0.5, 2, true, 1);
+        let model64 = Dbscan::with_seed(&data64, 0.5, 2, true, 1);
+        assert!(model32.clusters == model64.clusters && model32.point_types == model64.point_types);
+        let new_data = Array2::from_shape_vec((2, 2), vec![1.05f32, 2.05, 9.0, 9.0]).unwrap();
+        assert!(model32.predict(&data32, &new_data) == vec![vec![model32.clusters[0]], vec![0]]);
+        assert!(Dbscan::try_new(&data32, 0.5, 2, true).is_ok());
+        assert!(DbscanBuilder::new(0.5f32, 2).seed(1).fit(&data32).predict(&new_data)[0] == vec![model32.clusters[0]]);
 
-#[cfg(test)]
-mod tests {}
+        let kmeans = Kmeans::fit_with_rng(&data32, 3, 100, 1e-6, &mut StdRng::seed_from_u64(0));
+        assert!(kmeans.clusters == Kmeans::fit_with_rng(&data64, 3, 100, 1e-6, &mut StdRng::seed_from_u64(0)).clusters);
+        assert!((silhouette_score(&data32, &model32.clusters) as f64 - silhouette_score(&data64, &model64.clusters)).abs() < 1e-5);
+        assert!((davies_bouldin(&data32, &model32.clusters) as f64 - davies_bouldin(&data64, &model64.clusters)).abs() < 1e-5);
+        assert!(Optics::new(&data32, 2, 1.0f32).labels_at(0.5) == Optics::new(&data64, 2, 1.0).labels_at(0.5));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::index::{index_rows, row_slice, squared_euclidean};
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::cmp::Reverse;
+
+/// Weighting of neighbours when shifting towards the local mean: `Flat` weighs every point within the
+/// bandwidth equally, `Gaussian` weighs points by `exp(-d^2 / (2 h^2))` out to three bandwidths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    Flat,
+    Gaussian,
+}
+
+/// Mean-shift clustering: every point climbs to a mode of the kernel density estimate, modes within one
+/// bandwidth of a denser mode are merged into it, and each point takes the nearest remaining mode.
+/// `clusters` indexes into `centers`, which are ordered by decreasing density.
+#[derive(Debug)]
+pub struct MeanShift<T: Float + One + Zero> {
+    pub bandwidth: T,
+    pub centers: Vec<Array1<T>>,
+    pub clusters: Vec<usize>,
+}
+
+impl<T: Float + One + Zero> MeanShift<T> {
+    pub fn new(data: &Array2<T>, bandwidth: T, kernel: Kernel, max_iter: usize) -> MeanShift<T> {
+        let index = index_rows(data);
+        let reach = match kernel {
+            Kernel::Flat => bandwidth,
+            Kernel::Gaussian => bandwidth * T::from(3.0).expect("T::from(f64)"),
+        };
+        let two_h2 = (T::one() + T::one()) * bandwidth.powi(2);
+        let tolerance = bandwidth * T::from(1e-3).expect("T::from(f64)");
+        let mut neighbours = Vec::with_capacity(data.rows());
+        let mut modes = data
+            .outer_iter()
+            .map(|seed| {
+                let mut mode = seed.to_vec();
+                for _ in 0..max_iter {
+                    neighbours.clear();
+                    index.within(&mode, reach, &mut neighbours);
+                    let mut shifted = vec![T::zero(); data.cols()];
+                    let mut total = T::zero();
+                    for neighbour in neighbours.iter() {
+                        let row = data.row(*neighbour);
+                        let weight = match kernel {
+                            Kernel::Flat => T::one(),
+                            Kernel::Gaussian => (-squared_euclidean(&mode, &row_slice(row.view())) / two_h2).exp(),
+                        };
+                        for (s, x) in shifted.iter_mut().zip(row.iter()) {
+                            *s = *s + weight * *x;
+                        }
+                        total = total + weight;
+                    }
+                    if total <= T::zero() {
+                        break;
+                    }
+                    shifted.iter_mut().for_each(|s| *s = *s / total);
+                    let shift = squared_euclidean(&shifted, &mode).sqrt();
+                    mode = shifted;
+                    if shift < tolerance {
+                        break;
+                    }
+                }
+                neighbours.clear();
+                index.within(&mode, bandwidth, &mut neighbours);
+                (mode, neighbours.len())
+            })
+            .collect::<Vec<(Vec<T>, usize)>>();
+
+        modes.sort_by_key(|(_, count)| Reverse(*count));
+        let mut centers: Vec<Vec<T>> = Vec::new();
+        for (mode, _) in modes {
+            if centers.iter().all(|center| squared_euclidean(center, &mode) > bandwidth.powi(2)) {
+                centers.push(mode);
+            }
+        }
+        let clusters = data
+            .outer_iter()
+            .map(|row| {
+                centers
+                    .iter()
+                    .map(|center| squared_euclidean(center, &row_slice(row.view())))
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("PartialOrd distance from center"))
+                    .expect("at least one center")
+                    .0
+            })
+            .collect::<Vec<usize>>();
+
+        MeanShift {
+            bandwidth,
+            centers: centers.into_iter().map(Array1::from_vec).collect::<Vec<Array1<T>>>(),
+            clusters,
+        }
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for MeanShift<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+/// Bandwidth estimate: the mean distance from each point to its k-th nearest neighbour (itself included), with
+/// `k = quantile * n`. Smaller quantiles give smaller bandwidths and more clusters.
+pub fn estimate_bandwidth<T: Float + One + Zero>(data: &Array2<T>, quantile: T) -> T {
+    let n = data.rows();
+    let k = (T::from(n).expect("T::from(usize)") * quantile).to_usize().unwrap_or(1).clamp(1, n.max(1));
+    let index = index_rows(data);
+    let total = data
+        .outer_iter()
+        .map(|row| index.nearest(&row_slice(row.view()), k).last().map_or(T::zero(), |(distance, _)| *distance))
+        .fold(T::zero(), |acc, d| acc + d);
+    total / T::from(n.max(1)).expect("T::from(usize)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+
+    #[test]
+    fn test_mean_shift() {
+        let data = Array2::from_shape_vec(
+            (10, 2),
+            vec![0.0, 0.0, 0.2, 0.0, 0.0, 0.2, 0.2, 0.2, 0.1, 0.1, 6.0, 6.0, 6.2, 6.0, 6.0, 6.2, 6.2, 6.2, 6.1, 6.1],
+        )
+        .unwrap();
+        let bandwidth = estimate_bandwidth(&data, 0.3);
+        assert!(bandwidth > 0.1 && bandwidth < 1.0 && estimate_bandwidth(&column_major(&data), 0.3) == bandwidth);
+        for kernel in [Kernel::Flat, Kernel::Gaussian].iter() {
+            let model = MeanShift::new(&data, bandwidth, *kernel, 300);
+            assert!(model.centers.len() == 2);
+            assert!(model.clusters[..5].iter().all(|c| *c == model.clusters[0]) && model.clusters[5..].iter().all(|c| *c == model.clusters[5]));
+            assert!(model.clusters[0] != model.clusters[5] && MeanShift::new(&column_major(&data), bandwidth, *kernel, 300).clusters == model.clusters);
+            let center = &model.centers[model.clusters[0]];
+            assert!((center[0] - 0.1).abs() < 0.05 && (center[1] - 0.1).abs() < 0.05);
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::collections::BTreeMap;
+
+/// Converts labels from another source to the crate's convention: `noise` becomes 0 and the remaining labels
+/// are compacted, in ascending order of their original value, to contiguous ids starting at 1.
+pub fn normalize_labels(labels: &[usize], noise: usize) -> Vec<usize> {
+    let mut ids = labels.iter().filter(|l| **l != noise).map(|l| (*l, 0)).collect::<BTreeMap<usize, usize>>();
+    for (next, id) in ids.values_mut().enumerate() {
+        *id = next + 1;
+    }
+    labels.iter().map(|l| if *l == noise { 0 } else { ids[l] }).collect::<Vec<usize>>()
+}
+
+/// Minimum distance between points of different clusters over the maximum within-cluster diameter. Noise
+/// (label 0) is ignored; with fewer than two clusters there is no separation and the index is zero. When every
+/// cluster is a single point there is no diameter to divide by either, and the index is NaN.
+pub fn dunn_index<T: Float>(data: &Array2<T>, labels: &[usize]) -> T {
+    let mut min_between = T::infinity();
+    let mut max_within = T::zero();
+    let mut any_within = false;
+    for (a, (row_a, label_a)) in data.outer_iter().zip(labels.iter()).enumerate().filter(|(_, (_, l))| **l > 0) {
+        for (row_b, label_b) in data.outer_iter().zip(labels.iter()).skip(a + 1).filter(|(_, l)| **l > 0) {
+            let distance = euclidean(&row_a, &row_b);
+            if label_a == label_b {
+                max_within = max_within.max(distance);
+                any_within = true;
+            } else {
+                min_between = min_between.min(distance);
+            }
+        }
+    }
+    if !min_between.is_finite() {
+        T::zero()
+    } else if any_within {
+        min_between / max_within
+    } else {
+        T::nan()
+    }
+}
+
+/// Mean silhouette coefficient over the clustered points, ignoring noise (label 0). Points alone in their
+/// cluster score 0, and a labelling with fewer than two clusters scores 0.
+pub fn silhouette_score<T: Float>(data: &Array2<T>, labels: &[usize]) -> T {
+    let coefficients = silhouette_samples(data, labels);
+    mean(&coefficients.iter().zip(labels.iter()).filter(|(_, l)| **l > 0).map(|(s, _)| *s).collect::<Vec<T>>())
+}
+
+/// Mean silhouette coefficient of the points in each cluster, leaving out noise.
+pub fn silhouette_per_cluster<T: Float>(data: &Array2<T>, labels: &[usize]) -> BTreeMap<usize, T> {
+    let mut coefficients: BTreeMap<usize, Vec<T>> = BTreeMap::new();
+    for (s, label) in silhouette_samples(data, labels).into_iter().zip(labels.iter()).filter(|(_, l)| **l > 0) {
+        coefficients.entry(*label).or_default().push(s);
+    }
+    coefficients.into_iter().map(|(label, s)| (label, mean(&s))).collect()
+}
+
+/// Davies-Bouldin index: the mean, over clusters, of the worst ratio of summed spread (mean distance to the
+/// centroid) to centroid separation. Lower is better; noise is ignored and fewer than two clusters score 0.
+pub fn davies_bouldin<T: Float>(data: &Array2<T>, labels: &[usize]) -> T {
+    let centroids = centroids(data, labels);
+    if centroids.len() < 2 {
+        return T::zero();
+    }
+    let mut spread: BTreeMap<usize, Vec<T>> = BTreeMap::new();
+    for (row, label) in data.outer_iter().zip(labels.iter()).filter(|(_, l)| **l > 0) {
+        spread.entry(*label).or_default().push(euclidean(&row, &centroids[label].view()));
+    }
+    let spread = spread.into_iter().map(|(label, distances)| (label, mean(&distances))).collect::<BTreeMap<usize, T>>();
+    let worst = centroids
+        .iter()
+        .map(|(a, centroid_a)| {
+            centroids
+                .iter()
+                .filter(|(b, _)| *b != a)
+                .map(|(b, centroid_b)| (spread[a] + spread[b]) / euclidean(&centroid_a.view(), &centroid_b.view()))
+                .fold(T::zero(), T::max)
+        })
+        .collect::<Vec<T>>();
+    mean(&worst)
+}
+
+/// Calinski-Harabasz index: between-cluster over within-cluster dispersion, each divided by its degrees of
+/// freedom. Higher is better; noise is ignored, fewer than two clusters score 0, and zero within-cluster
+/// dispersion scores 1.
+pub fn calinski_harabasz<T: Float>(data: &Array2<T>, labels: &[usize]) -> T {
+    let centroids = centroids(data, labels);
+    let sizes = cluster_sizes(labels);
+    let n = sizes.values().sum::<usize>();
+    if centroids.len() < 2 || n <= centroids.len() {
+        return T::zero();
+    }
+    let overall = data.outer_iter().zip(labels.iter()).filter(|(_, l)| **l > 0).fold(Array1::zeros(data.cols()), |acc, (row, _)| acc + row)
+        .mapv(|x| x / T::from(n).expect("T::from(usize)"));
+    let between = centroids
+        .iter()
+        .fold(T::zero(), |acc, (label, centroid)| acc + euclidean(&centroid.view(), &overall.view()).powi(2) * T::from(sizes[label]).expect("T::from(usize)"));
+    let within = data
+        .outer_iter()
+        .zip(labels.iter())
+        .filter(|(_, l)| **l > 0)
+        .fold(T::zero(), |acc, (row, label)| acc + euclidean(&row, &centroids[label].view()).powi(2));
+    if within == T::zero() {
+        return T::one();
+    }
+    let k = T::from(centroids.len()).expect("T::from(usize)");
+    (between / (k - T::one())) / (within / (T::from(n).expect("T::from(usize)") - k))
+}
+
+/// Adjusted Rand index between two labellings: 1 for identical partitions, around 0 for chance agreement.
+/// Every label, 0 included, is treated as its own group.
+pub fn adjusted_rand_index(a: &[usize], b: &[usize]) -> f64 {
+    assert!(a.len() == b.len(), "labellings differ in length");
+    let pairs = |count: usize| (count * count.saturating_sub(1)) as f64 / 2.0;
+    let (joint, rows, columns) = contingency(a, b);
+    let index = joint.values().map(|c| pairs(*c)).sum::<f64>();
+    let row_pairs = rows.values().map(|c| pairs(*c)).sum::<f64>();
+    let column_pairs = columns.values().map(|c| pairs(*c)).sum::<f64>();
+    let expected = row_pairs * column_pairs / pairs(a.len()).max(1.0);
+    let maximum = (row_pairs + column_pairs) / 2.0;
+    if maximum == expected {
+        1.0
+    } else {
+        (index - expected) / (maximum - expected)
+    }
+}
+
+/// Mutual information between two labellings over the arithmetic mean of their entropies, from 0 (independent)
+/// to 1 (identical up to renaming). Every label, 0 included, is treated as its own group.
+pub fn normalized_mutual_info(a: &[usize], b: &[usize]) -> f64 {
+    assert!(a.len() == b.len(), "labellings differ in length");
+    let (joint, rows, columns) = contingency(a, b);
+    let n = a.len() as f64;
+    let (entropy_a, entropy_b) = (entropy(&rows, n), entropy(&columns, n));
+    if entropy_a == 0.0 && entropy_b == 0.0 {
+        return 1.0;
+    }
+    let mutual = joint.iter().map(|((x, y), c)| (*c as f64 / n) * ((*c as f64 * n) / (rows[x] as f64 * columns[y] as f64)).ln()).sum::<f64>();
+    mutual / ((entropy_a + entropy_b) / 2.0)
+}
+
+/// Homogeneity (each predicted cluster holds a single true class) and completeness (each true class sits in a
+/// single predicted cluster) of `predicted` against `truth`, each from 0 to 1.
+pub fn homogeneity_completeness(truth: &[usize], predicted: &[usize]) -> (f64, f64) {
+    assert!(truth.len() == predicted.len(), "labellings differ in length");
+    let (joint, classes, clusters) = contingency(truth, predicted);
+    let n = truth.len() as f64;
+    let conditional = |given: &Counts, second: bool| {
+        joint
+            .iter()
+            .map(|((x, y), c)| {
+                let total = given[if second { y } else { x }] as f64;
+                -(*c as f64 / n) * (*c as f64 / total).ln()
+            })
+            .sum::<f64>()
+    };
+    let (entropy_classes, entropy_clusters) = (entropy(&classes, n), entropy(&clusters, n));
+    let homogeneity = if entropy_classes == 0.0 { 1.0 } else { 1.0 - conditional(&clusters, true) / entropy_classes };
+    let completeness = if entropy_clusters == 0.0 { 1.0 } else { 1.0 - conditional(&classes, false) / entropy_clusters };
+    (homogeneity, completeness)
+}
+
+/// Silhouette score that can be updated cheaply as single points change cluster. It caches the pairwise
+/// distance matrix and, per point, the summed distance to every cluster, so moving one point costs
+/// O(n * clusters) rather than a full O(n^2) recomputation.
+#[derive(Debug)]
+pub struct SilhouetteCache<T: Float> {
+    pub labels: Vec<usize>,
+    distances: Array2<T>,
+    sums: Vec<BTreeMap<usize, T>>,
+    sizes: BTreeMap<usize, usize>,
+}
+
+impl<T: Float> SilhouetteCache<T> {
+    pub fn new(data: &Array2<T>, labels: &[usize]) -> SilhouetteCache<T> {
+        let mut distances = Array2::zeros((data.rows(), data.rows()));
+        for (a, row_a) in data.outer_iter().enumerate() {
+            for (b, row_b) in data.outer_iter().enumerate().skip(a + 1) {
+                let distance = euclidean(&row_a, &row_b);
+                distances[[a, b]] = distance;
+                distances[[b, a]] = distance;
+            }
+        }
+        let sums = distances
+            .outer_iter()
+            .enumerate()
+            .map(|(a, row)| {
+                let mut sums = BTreeMap::new();
+                for (_, (distance, label)) in row.iter().zip(labels.iter()).enumerate().filter(|(b, (_, l))| **l > 0 && *b != a) {
+                    let sum = sums.entry(*label).or_insert_with(T::zero);
+                    *sum = *sum + *distance;
+                }
+                sums
+            })
+            .collect::<Vec<BTreeMap<usize, T>>>();
+        SilhouetteCache {
+            labels: labels.to_vec(),
+            distances,
+            sums,
+            sizes: cluster_sizes(labels),
+        }
+    }
+
+    pub fn score(&self) -> T {
+        let coefficients = self
+            .labels
+            .iter()
+            .zip(self.sums.iter())
+            .filter(|(l, _)| **l > 0)
+            .map(|(l, sums)| silhouette_coefficient(*l, sums, &self.sizes))
+            .collect::<Vec<T>>();
+        mean(&coefficients)
+    }
+
+    /// Moves `point` from `old_label` to `new_label` and returns the updated overall score.
+    pub fn update(&mut self, point: usize, old_label: usize, new_label: usize) -> T {
+        assert!(self.labels[point] == old_label, "point {} is not labelled {}", point, old_label);
+        for (other, sums) in self.sums.iter_mut().enumerate().filter(|(other, _)| *other != point) {
+            let distance = self.distances[[other, point]];
+            if old_label > 0 {
+                let sum = sums.entry(old_label).or_insert_with(T::zero);
+                *sum = *sum - distance;
+            }
+            if new_label > 0 {
+                let sum = sums.entry(new_label).or_insert_with(T::zero);
+                *sum = *sum + distance;
+            }
+        }
+        if old_label > 0 {
+            let size = self.sizes.get_mut(&old_label).expect("cluster size");
+            *size -= 1;
+            if *size == 0 {
+                self.sizes.remove(&old_label);
+            }
+        }
+        if new_label > 0 {
+            *self.sizes.entry(new_label).or_insert(0) += 1;
+        }
+        self.labels[point] = new_label;
+        self.score()
+    }
+}
+
+fn silhouette_samples<T: Float>(data: &Array2<T>, labels: &[usize]) -> Vec<T> {
+    let sizes = cluster_sizes(labels);
+    data.outer_iter()
+        .zip(labels.iter())
+        .enumerate()
+        .map(|(a, (row_a, label_a))| {
+            if *label_a == 0 {
+                return T::zero();
+            }
+            let mut sums: BTreeMap<usize, T> = BTreeMap::new();
+            for (b, (row_b, label_b)) in data.outer_iter().zip(labels.iter()).enumerate().filter(|(_, (_, l))| **l > 0) {
+                if a != b {
+                    let sum = sums.entry(*label_b).or_insert_with(T::zero);
+                    *sum = *sum + euclidean(&row_a, &row_b);
+                }
+            }
+            silhouette_coefficient(*label_a, &sums, &sizes)
+        })
+        .collect::<Vec<T>>()
+}
+
+fn silhouette_coefficient<T: Float>(label: usize, sums: &BTreeMap<usize, T>, sizes: &BTreeMap<usize, usize>) -> T {
+    if sizes.len() < 2 || sizes[&label] < 2 {
+        return T::zero();
+    }
+    let mean = |other: &usize| sums.get(other).cloned().unwrap_or_else(T::zero) / T::from(if *other == label { sizes[other] - 1 } else { sizes[other] }).expect("T::from(usize)");
+    let within = mean(&label);
+    let between = sizes.keys().filter(|l| **l != label).map(mean).fold(T::infinity(), T::min);
+    let scale = within.max(between);
+    if scale > T::zero() {
+        (between - within) / scale
+    } else {
+        T::zero()
+    }
+}
+
+fn cluster_sizes(labels: &[usize]) -> BTreeMap<usize, usize> {
+    let mut sizes = BTreeMap::new();
+    for label in labels.iter().filter(|l| **l > 0) {
+        *sizes.entry(*label).or_insert(0) += 1;
+    }
+    sizes
+}
+
+fn centroids<T: Float>(data: &Array2<T>, labels: &[usize]) -> BTreeMap<usize, Array1<T>> {
+    let mut sums: BTreeMap<usize, (Array1<T>, usize)> = BTreeMap::new();
+    for (row, label) in data.outer_iter().zip(labels.iter()).filter(|(_, l)| **l > 0) {
+        let entry = sums.entry(*label).or_insert_with(|| (Array1::zeros(data.cols()), 0));
+        entry.0 = &entry.0 + &row;
+        entry.1 += 1;
+    }
+    sums.into_iter().map(|(label, (sum, count))| (label, sum.mapv(|x| x / T::from(count).expect("T::from(usize)")))).collect()
+}
+
+type Counts = BTreeMap<usize, usize>;
+
+/// Counts of each `(a, b)` label pair and of each label on its own.
+fn contingency(a: &[usize], b: &[usize]) -> (BTreeMap<(usize, usize), usize>, Counts, Counts) {
+    let mut joint = BTreeMap::new();
+    let mut rows = BTreeMap::new();
+    let mut columns = BTreeMap::new();
+    for (x, y) in a.iter().zip(b.iter()) {
+        *joint.entry((*x, *y)).or_insert(0) += 1;
+        *rows.entry(*x).or_insert(0) += 1;
+        *columns.entry(*y).or_insert(0) += 1;
+    }
+    (joint, rows, columns)
+}
+
+fn entropy(counts: &Counts, n: f64) -> f64 {
+    counts.values().map(|c| *c as f64 / n).map(|p| -p * p.ln()).sum()
+}
+
+fn mean<T: Float>(values: &[T]) -> T {
+    if values.is_empty() {
+        T::zero()
+    } else {
+        values.iter().fold(T::zero(), |acc, x| acc + *x) / T::from(values.len()).expect("T::from(usize)")
+    }
+}
+
+fn euclidean<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    a.iter().zip(b.iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_labels() {
+        let labels = vec![7, 7, usize::MAX, 3, 12, usize::MAX, 3];
+        assert!(normalize_labels(&labels, usize::MAX) == vec![2, 2, 0, 1, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_dunn_index() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 50.0, 50.0]).unwrap();
+        let dunn = dunn_index(&data, &[1, 1, 1, 2, 2, 2, 0]);
+        assert!(dunn > 1.0);
+        assert!(dunn_index(&data, &[1, 1, 1, 1, 1, 1, 0]) == 0.0);
+        assert!(dunn_index(&data, &[1, 2, 3, 4, 5, 6, 7]).is_nan() && dunn_index(&data, &[1, 0, 0, 2, 0, 0, 0]).is_nan());
+    }
+
+    #[test]
+    fn test_silhouette_score() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 50.0, 50.0]).unwrap();
+        assert!(silhouette_score(&data, &[1, 1, 1, 2, 2, 2, 0]) > 0.9);
+        assert!(silhouette_score(&data, &[1, 2, 1, 2, 1, 2, 0]) < 0.0);
+        assert!(silhouette_score(&data, &[1, 1, 1, 1, 1, 1, 0]) == 0.0);
+    }
+
+    #[test]
+    fn test_silhouette_cache_update() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 2.5, 2.4]).unwrap();
+        let mut labels = vec![1, 1, 1, 2, 2, 2, 0];
+        let mut cache = SilhouetteCache::new(&data, &labels);
+        assert!((cache.score() - silhouette_score(&data, &labels)).abs() < 1e-12);
+        for (point, label) in [(6, 1), (2, 2), (6, 0), (3, 3)].iter() {
+            let updated = cache.update(*point, labels[*point], *label);
+            labels[*point] = *label;
+            assert!((updated - silhouette_score(&data, &labels)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_silhouette_per_cluster() {
+        let data = Array2::from_shape_vec(
+            (10, 2),
+            vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 1.0, 0.0, 3.0, 0.0, 2.0, 1.0, 2.0, -1.0, 10.0, 10.0, 10.1, 10.0, 10.0, 10.1],
+        )
+        .unwrap();
+        let per_cluster = silhouette_per_cluster(&data, &[1, 1, 1, 2, 2, 2, 2, 3, 3, 3]);
+        assert!(per_cluster.len() == 3);
+        assert!(per_cluster[&1] > 0.5 && per_cluster[&3] > 0.9);
+        assert!(per_cluster[&2] < per_cluster[&1] - 0.5);
+    }
+
+    #[test]
+    fn test_internal_indices() {
+        let data = Array2::from_shape_vec((7, 2), vec![0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 50.0, 50.0]).unwrap();
+        let good = [1, 1, 1, 2, 2, 2, 0];
+        let bad = [1, 2, 1, 2, 1, 2, 0];
+        assert!(davies_bouldin(&data, &good) < 0.1 && davies_bouldin(&data, &bad) > davies_bouldin(&data, &good));
+        assert!(calinski_harabasz(&data, &good) > 1000.0 && calinski_harabasz(&data, &bad) < 1.0);
+        assert!(davies_bouldin(&data, &[1, 1, 1, 1, 1, 1, 0]) == 0.0 && calinski_harabasz(&data, &[1, 1, 1, 1, 1, 1, 0]) == 0.0);
+    }
+
+    #[test]
+    fn test_external_indices() {
+        let truth = [0, 0, 0, 1, 1, 1];
+        assert!((adjusted_rand_index(&truth, &[5, 5, 5, 2, 2, 2]) - 1.0).abs() < 1e-12);
+        assert!((normalized_mutual_info(&truth, &[5, 5, 5, 2, 2, 2]) - 1.0).abs() < 1e-12);
+        assert!((adjusted_rand_index(&[0, 0, 1, 1], &[0, 1, 0, 1]) + 0.5).abs() < 1e-12);
+        assert!(normalized_mutual_info(&[0, 0, 1, 1], &[0, 1, 0, 1]).abs() < 1e-12);
+        let (homogeneity, completeness) = homogeneity_completeness(&truth, &[1, 1, 2, 3, 3, 4]);
+        assert!((homogeneity - 1.0).abs() < 1e-12 && completeness < 1.0);
+        let (homogeneity, completeness) = homogeneity_completeness(&truth, &[1; 6]);
+        assert!(homogeneity.abs() < 1e-12 && (completeness - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,107 @@
+//! Distances for survey-style data mixing numeric and categorical columns, so DBSCAN and k-medoids can run
+//! on it without one-hot encoding. Categories are stored in the data array as any distinct float codes, one
+//! column per question; the column types say which columns to compare by value and which only by equality.
+//! The distance matrices feed `Dbscan::from_distances` and `KMedoids::from_distances`, and are O(n^2).
+
+use ndarray::prelude::*;
+use num_traits::float::Float;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Numeric,
+    /// Codes compared for equality only; their order and spacing mean nothing.
+    Categorical,
+}
+
+/// Gower's distance (Gower, 1971): the mean over columns of `|x - y| / range` for numeric columns and 0 or 1
+/// for matching or differing categories, so every column counts equally and every distance lies in [0, 1].
+/// Ranges are those of the data it is fit on; numeric columns with a single value contribute 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gower<T: Float> {
+    pub columns: Vec<ColumnType>,
+    pub ranges: Vec<T>,
+}
+
+impl<T: Float> Gower<T> {
+    pub fn fit(data: &Array2<T>, columns: &[ColumnType]) -> Gower<T> {
+        assert!(columns.len() == data.cols(), "need one column type per column");
+        let ranges = data
+            .gencolumns()
+            .into_iter()
+            .map(|column| column.iter().cloned().fold(T::neg_infinity(), T::max) - column.iter().cloned().fold(T::infinity(), T::min))
+            .collect::<Vec<T>>();
+        Gower { columns: columns.to_vec(), ranges }
+    }
+
+    pub fn distance(&self, a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+        let total = a.iter().zip(b.iter()).zip(self.columns.iter().zip(self.ranges.iter())).fold(T::zero(), |acc, ((x, y), (column, range))| {
+            acc + match column {
+                ColumnType::Categorical if x != y => T::one(),
+                ColumnType::Numeric if *range > T::zero() => (*x - *y).abs() / *range,
+                _ => T::zero(),
+            }
+        });
+        total / T::from(self.columns.len().max(1)).expect("T::from(usize)")
+    }
+
+    /// The n × n matrix of distances between the rows of `data`.
+    pub fn distances(&self, data: &Array2<T>) -> Array2<T> {
+        pairwise(data, |a, b| self.distance(a, b))
+    }
+}
+
+/// The fraction of columns in which `a` and `b` differ, for purely categorical rows.
+pub fn hamming<T: Float>(a: &ArrayView1<T>, b: &ArrayView1<T>) -> T {
+    let differing = a.iter().zip(b.iter()).filter(|(x, y)| x != y).count();
+    T::from(differing).expect("T::from(usize)") / T::from(a.len().max(1)).expect("T::from(usize)")
+}
+
+/// The n × n matrix of `hamming` distances between the rows of `data`.
+pub fn hamming_distances<T: Float>(data: &Array2<T>) -> Array2<T> {
+    pairwise(data, hamming)
+}
+
+fn pairwise<T: Float, F: Fn(&ArrayView1<T>, &ArrayView1<T>) -> T>(data: &Array2<T>, distance: F) -> Array2<T> {
+    let n = data.rows();
+    let mut distances = Array2::zeros((n, n));
+    for a in 0..n {
+        for b in a + 1..n {
+            let d = distance(&data.row(a), &data.row(b));
+            distances[[a, b]] = d;
+            distances[[b, a]] = d;
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+    use crate::kmedoids::KMedoids;
+    use crate::metrics::adjusted_rand_index;
+
+    #[test]
+    fn test_mixed() {
+        // Age, income in thousands, region code and a yes/no answer: two kinds of respondent, young city renters
+        // and older rural owners, with ages and incomes overlapping too much to separate them alone.
+        let columns = [ColumnType::Numeric, ColumnType::Numeric, ColumnType::Categorical, ColumnType::Categorical];
+        let data = Array2::from_shape_vec(
+            (8, 4),
+            vec![25.0, 40.0, 1.0, 0.0, 31.0, 52.0, 1.0, 0.0, 38.0, 45.0, 1.0, 0.0, 29.0, 61.0, 1.0, 0.0, 35.0, 50.0, 7.0, 1.0, 44.0, 43.0, 7.0, 1.0, 52.0, 58.0, 7.0, 1.0, 40.0, 62.0, 7.0, 1.0],
+        )
+        .unwrap();
+        let gower = Gower::fit(&data, &columns);
+        assert!(gower.ranges == vec![27.0, 22.0, 6.0, 1.0]);
+        assert!((gower.distance(&data.row(0), &data.row(4)) - (10.0 / 27.0 + 10.0 / 22.0 + 2.0) / 4.0).abs() < 1e-12);
+        let distances = gower.distances(&data);
+        assert!(distances.iter().all(|d| (0.0..=1.0).contains(d)) && distances == distances.t());
+
+        let truth = vec![1, 1, 1, 1, 2, 2, 2, 2];
+        assert!(adjusted_rand_index(&Dbscan::from_distances(&distances, 0.4, 3, true).clusters, &truth) == 1.0);
+        assert!(adjusted_rand_index(&KMedoids::from_distances(&distances, 2, 100).clusters, &truth) == 1.0);
+
+        let answers = Array2::from_shape_vec((3, 4), vec![0.0, 1.0, 2.0, 3.0, 0.0, 1.0, 2.0, 4.0, 1.0, 0.0, 2.0, 4.0]).unwrap();
+        assert!(hamming_distances(&answers).row(0).to_vec() == vec![0.0, 0.25, 0.75]);
+    }
+}
@@ -0,0 +1,135 @@
+//! Rows kept in a memory-mapped file instead of on the heap, for fits on more data than fits in memory. The
+//! OS pages rows in as the region queries touch them and drops them again under memory pressure.
+//! `MappedRows::view` is an ordinary `ArrayView2`, so `Dbscan::new` and the other fits take it as they take
+//! any array; the neighbour index borrows its rows from the mapping too, leaving only the tree, the labels and
+//! the visit order on the heap.
+//!
+//! `write_rows` spills chunks of rows to a file, as `Dbscan::new_chunked` would gather them in memory, and
+//! `Dbscan::new(&unsafe { MappedRows::<f32>::open(path, 3)? }.view(), eps, min_points, true)` fits them.
+
+use memmap2::Mmap;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::path::Path;
+
+/// The float types a mapped file can hold, stored as their native-endian bytes.
+pub trait MappedFloat: Float + private::Sealed {
+    fn write_ne<W: Write>(self, writer: &mut W) -> io::Result<()>;
+}
+
+impl MappedFloat for f32 {
+    fn write_ne<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_ne_bytes())
+    }
+}
+
+impl MappedFloat for f64 {
+    fn write_ne<W: Write>(self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_ne_bytes())
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// A file of row-major native-endian values, mapped read-only and viewed as rows of `cols` columns.
+pub struct MappedRows<T: MappedFloat> {
+    map: Mmap,
+    rows: usize,
+    cols: usize,
+    float: PhantomData<T>,
+}
+
+impl<T: MappedFloat> MappedRows<T> {
+    /// Maps the file at `path` as rows of `cols` values of `T`. The file's length must be a whole number of
+    /// rows, and `cols` must not be zero.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be written to or truncated, by this process or any other, while it is mapped:
+    /// the rows are read straight from the mapping, so a change underneath them is undefined behaviour.
+    pub unsafe fn open<P: AsRef<Path>>(path: P, cols: usize) -> io::Result<MappedRows<T>> {
+        let file = File::open(path)?;
+        let map = Mmap::map(&file)?;
+        let row_bytes = cols * size_of::<T>();
+        if row_bytes == 0 || map.len() % row_bytes != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} bytes isn't a whole number of {}-column rows", map.len(), cols)));
+        }
+        Ok(MappedRows { rows: map.len() / row_bytes, map, cols, float: PhantomData })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The mapped rows as an array view, without copying them.
+    pub fn view(&self) -> ArrayView2<'_, T> {
+        let values = if self.rows == 0 {
+            &[]
+        } else {
+            // The mapping starts on a page boundary, so it is aligned for T, and `open` checked that it holds
+            // rows * cols values; every bit pattern is a valid f32 or f64.
+            unsafe { std::slice::from_raw_parts(self.map.as_ptr() as *const T, self.rows * self.cols) }
+        };
+        ArrayView2::from_shape((self.rows, self.cols), values).expect("whole rows")
+    }
+}
+
+/// Writes `chunks` of rows in order to a new file at `path`, replacing any file there, in the layout
+/// `MappedRows::open` maps. Chunks must agree on their number of columns. Each chunk is written and dropped
+/// before the next is read, so only one is in memory at a time. Returns the number of rows written.
+pub fn write_rows<T: MappedFloat, P: AsRef<Path>, I: IntoIterator<Item = Array2<T>>>(path: P, chunks: I) -> io::Result<usize> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    let (mut rows, mut cols) = (0, None);
+    for chunk in chunks {
+        if *cols.get_or_insert(chunk.cols()) != chunk.cols() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "chunks must have the same number of columns"));
+        }
+        for x in chunk.iter() {
+            x.write_ne(&mut writer)?;
+        }
+        rows += chunk.rows();
+    }
+    writer.flush()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::Dbscan;
+    use ndarray::s;
+
+    #[test]
+    fn test_mapped_rows() {
+        let (data, _) = blobs(300, &arr2(&[[0.0, 0.0, 0.0], [5.0, 5.0, 5.0]]), 0.5, 7);
+        let path = std::env::temp_dir().join(format!("clust-rs-test-{}.bin", std::process::id()));
+        let chunks = (0..300).step_by(64).map(|start| data.slice(s![start..(start + 64).min(300), ..]).to_owned());
+        assert!(write_rows(&path, chunks).unwrap() == 300);
+        let rows = unsafe { MappedRows::<f64>::open(&path, 3).unwrap() };
+        assert!(rows.rows() == 300 && rows.cols() == 3 && rows.view() == data);
+        assert!(Dbscan::with_seed(&rows.view(), 0.6, 5, true, 2).clusters == Dbscan::with_seed(&data, 0.6, 5, true, 2).clusters);
+        let misshapen = unsafe { MappedRows::<f64>::open(&path, 7) };
+        let narrow = unsafe { MappedRows::<f32>::open(&path, 2).unwrap() };
+        assert!(misshapen.is_err() && narrow.rows() == 900);
+
+        let mixed = vec![Array2::<f32>::zeros((2, 3)), Array2::zeros((2, 2))];
+        assert!(write_rows(&path, mixed).is_err());
+        assert!(write_rows(&path, Vec::<Array2<f32>>::new()).unwrap() == 0);
+        let empty = unsafe { MappedRows::<f32>::open(&path, 3).unwrap() };
+        assert!(empty.rows() == 0 && empty.view().shape() == [0, 3]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
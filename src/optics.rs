@@ -0,0 +1,294 @@
+use crate::index::{index_rows, row_slice, squared_euclidean};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// OPTICS (Ankerst et al., 1999) cluster ordering. `reachability` and `core_distances` are indexed by point and
+/// are infinite where nothing lies within `max_eps`; the reachability plot is `reachability` read in `ordering`.
+#[derive(Debug)]
+pub struct Optics<T: Float + One + Zero> {
+    pub min_points: usize,
+    pub max_eps: T,
+    pub ordering: Vec<usize>,
+    pub reachability: Vec<T>,
+    pub core_distances: Vec<T>,
+}
+
+impl<T: Float + One + Zero> Optics<T> {
+    pub fn new(data: &Array2<T>, min_points: usize, max_eps: T) -> Optics<T> {
+        let n = data.rows();
+        let index = index_rows(data);
+        let core_distances = data
+            .outer_iter()
+            .map(|row| {
+                let kth = index.nearest(&row_slice(row.view()), min_points);
+                match kth.last() {
+                    Some((distance, _)) if kth.len() == min_points && *distance <= max_eps => *distance,
+                    _ => T::infinity(),
+                }
+            })
+            .collect::<Vec<T>>();
+
+        let mut reachability = vec![T::infinity(); n];
+        let mut processed = vec![false; n];
+        let mut ordering = Vec::with_capacity(n);
+        let mut neighbours = Vec::with_capacity(n);
+        // The seed list, with a point pushed again each time its reachability drops rather than moved up the
+        // heap; entries a later push has superseded are skipped as they come off. Once it runs dry, the next
+        // unprocessed point in index order starts a new stretch of the ordering.
+        let mut seeds = BinaryHeap::new();
+        let mut unseeded = 0;
+        while ordering.len() < n {
+            let point = match seeds.pop() {
+                Some(Seed { point, reachability: reach }) => {
+                    if processed[point] || reach > reachability[point] {
+                        continue;
+                    }
+                    point
+                }
+                None => {
+                    while processed[unseeded] {
+                        unseeded += 1;
+                    }
+                    unseeded
+                }
+            };
+            processed[point] = true;
+            ordering.push(point);
+            if core_distances[point].is_infinite() {
+                continue;
+            }
+            let row = data.row(point);
+            neighbours.clear();
+            index.within(&row_slice(row.view()), max_eps, &mut neighbours);
+            for neighbour in neighbours.iter().filter(|idx| !processed[**idx]) {
+                let distance = squared_euclidean(&row_slice(row.view()), &row_slice(data.row(*neighbour))).sqrt();
+                let candidate = distance.max(core_distances[point]);
+                if candidate < reachability[*neighbour] {
+                    reachability[*neighbour] = candidate;
+                    seeds.push(Seed { point: *neighbour, reachability: candidate });
+                }
+            }
+        }
+
+        Optics {
+            min_points,
+            max_eps,
+            ordering,
+            reachability,
+            core_distances,
+        }
+    }
+
+    pub fn reachability_plot(&self) -> Vec<T> {
+        self.ordering.iter().map(|idx| self.reachability[*idx]).collect::<Vec<T>>()
+    }
+
+    /// DBSCAN-like labels for any `eps <= max_eps`: a new cluster starts at each core point that isn't reachable
+    /// within eps, and points that are neither reachable nor core are noise (0).
+    pub fn labels_at(&self, eps: T) -> Vec<usize> {
+        let mut labels = vec![0; self.ordering.len()];
+        let mut current = 0;
+        for idx in self.ordering.iter() {
+            if self.reachability[*idx] > eps {
+                if self.core_distances[*idx] <= eps {
+                    current += 1;
+                    labels[*idx] = current;
+                }
+            } else {
+                labels[*idx] = current;
+            }
+        }
+        labels
+    }
+
+    /// Labels from the xi method: clusters are stretches of the reachability plot that start with a steep
+    /// downward area and end with a steep upward one, where steep means a relative change of at least `xi`.
+    /// Nested clusters are resolved in favour of the innermost, and points outside every cluster are noise (0).
+    pub fn labels_xi(&self, xi: T, min_cluster_size: usize) -> Vec<usize> {
+        let mut labels = vec![0; self.ordering.len()];
+        let mut next = 1;
+        for (start, end) in self.xi_clusters(xi, min_cluster_size) {
+            if self.ordering[start..=end].iter().all(|idx| labels[*idx] == 0) {
+                for idx in self.ordering[start..=end].iter() {
+                    labels[*idx] = next;
+                }
+                next += 1;
+            }
+        }
+        labels
+    }
+
+    /// Clusters as inclusive ranges of positions in `ordering`.
+    fn xi_clusters(&self, xi: T, min_cluster_size: usize) -> Vec<(usize, usize)> {
+        let mut plot = self.reachability_plot();
+        plot.push(T::infinity());
+        let complement = T::one() - xi;
+        let ratios = plot.windows(2).map(|w| w[0] / w[1]).collect::<Vec<T>>();
+        let steep_up = ratios.iter().map(|r| *r <= complement).collect::<Vec<bool>>();
+        let steep_down = ratios.iter().map(|r| *r >= complement.recip()).collect::<Vec<bool>>();
+        let up = ratios.iter().map(|r| *r < T::one()).collect::<Vec<bool>>();
+        let down = ratios.iter().map(|r| *r > T::one()).collect::<Vec<bool>>();
+
+        // Steep down areas still open, as (start, end, maximum reachability seen since).
+        let mut areas: Vec<(usize, usize, T)> = Vec::new();
+        let mut clusters = Vec::new();
+        let mut index = 0;
+        let mut mib = T::zero();
+        for steep in (0..ratios.len()).filter(|i| steep_up[*i] || steep_down[*i]) {
+            if steep < index {
+                continue;
+            }
+            mib = plot[index..=steep].iter().fold(mib, |acc, r| acc.max(*r));
+            if mib.is_infinite() {
+                areas.clear();
+            } else {
+                areas.retain(|(start, _, _)| mib <= plot[*start] * complement);
+                for area in areas.iter_mut() {
+                    area.2 = area.2.max(mib);
+                }
+            }
+            if steep_down[steep] {
+                let end = extend_region(&steep_down, &up, steep, self.min_points);
+                areas.push((steep, end, T::zero()));
+                index = end + 1;
+            } else {
+                let up_end = extend_region(&steep_up, &down, steep, self.min_points);
+                index = up_end + 1;
+                let mut found = Vec::new();
+                for (down_start, down_end, down_mib) in areas.iter() {
+                    let (mut start, mut end) = (*down_start, up_end);
+                    if plot[end + 1] * complement < *down_mib {
+                        continue;
+                    }
+                    let down_max = plot[*down_start];
+                    if down_max * complement >= plot[end + 1] {
+                        while plot[start + 1] > plot[end + 1] && start < *down_end {
+                            start += 1;
+                        }
+                    } else if plot[end + 1] * complement >= down_max {
+                        while plot[end - 1] > down_max && end > steep {
+                            end -= 1;
+                        }
+                    }
+                    if end + 1 - start >= min_cluster_size && start <= *down_end && end >= steep {
+                        found.push((start, end));
+                    }
+                }
+                found.reverse();
+                clusters.extend(found);
+            }
+            mib = plot[index];
+        }
+        clusters
+    }
+}
+
+/// A point waiting in the OPTICS seed list. The heap pops the lowest reachability first, and the lowest point
+/// among equal reachabilities, so the ordering is that of always taking the first closest unprocessed point.
+struct Seed<T> {
+    point: usize,
+    reachability: T,
+}
+
+impl<T: Float> PartialEq for Seed<T> {
+    fn eq(&self, other: &Seed<T>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Float> Eq for Seed<T> {}
+
+impl<T: Float> PartialOrd for Seed<T> {
+    fn partial_cmp(&self, other: &Seed<T>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Float> Ord for Seed<T> {
+    fn cmp(&self, other: &Seed<T>) -> Ordering {
+        other.reachability.partial_cmp(&self.reachability).expect("PartialOrd reachability").then_with(|| other.point.cmp(&self.point))
+    }
+}
+
+/// End of the steep area beginning at `start`: it may run through at most `min_points` consecutive points that
+/// aren't steep, and stops at the first point going the other way.
+fn extend_region(steep: &[bool], other_way: &[bool], start: usize, min_points: usize) -> usize {
+    let mut end = start;
+    let mut flat = 0;
+    for idx in start..steep.len() {
+        if steep[idx] {
+            flat = 0;
+            end = idx;
+        } else if !other_way[idx] {
+            flat += 1;
+            if flat > min_points {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::Dbscan;
+    use crate::index::column_major;
+    use crate::itertools::Itertools;
+    use crate::labels::same_partition;
+
+    #[test]
+    fn test_optics() {
+        let data = Array2::from_shape_vec(
+            (13, 2),
+            vec![
+                0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 0.1, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 5.1, 5.1, 10.0, 0.0, 10.5, 0.0, 10.0, 0.5, 10.5, 0.5, 30.0, 30.0,
+            ],
+        )
+        .unwrap();
+        let optics = Optics::new(&data, 3, Float::infinity());
+        assert!(optics.ordering.iter().cloned().sorted().collect::<Vec<usize>>() == (0..13).collect::<Vec<usize>>());
+        assert!(optics.reachability_plot()[0].is_infinite());
+        assert!(same_partition(&optics.labels_at(0.6), &Dbscan::new(&data, 0.6, 3, false).clusters));
+        let columns = Optics::new(&column_major(&data), 3, Float::infinity());
+        assert!(columns.ordering == optics.ordering && columns.reachability_plot() == optics.reachability_plot());
+
+        let xi = optics.labels_xi(0.1, 3);
+        assert!(xi.iter().take(4).all(|l| *l == xi[0]) && xi.iter().skip(4).take(4).all(|l| *l == xi[4]));
+        assert!(xi.iter().skip(8).take(4).all(|l| *l == xi[8]));
+        assert!(xi[0] > 0 && xi[4] > 0 && xi[8] > 0 && xi[0] != xi[4] && xi[4] != xi[8] && xi[0] != xi[8]);
+    }
+
+    #[test]
+    fn test_seed_list_ordering() {
+        // The seed list gives the ordering of scanning every unprocessed point for the first closest one.
+        let (data, _) = blobs(400, &arr2(&[[0.0, 0.0], [3.0, 3.0], [8.0, 0.0]]), 0.8, 5);
+        let optics = Optics::new(&data, 5, 0.7);
+        let n = data.rows();
+        let mut reachability = vec![f64::infinity(); n];
+        let mut processed = vec![false; n];
+        for point in optics.ordering.iter() {
+            let first_closest = (0..n).filter(|idx| !processed[*idx]).fold(None, |best: Option<usize>, idx| match best {
+                Some(best) if reachability[best] <= reachability[idx] => Some(best),
+                _ => Some(idx),
+            });
+            assert!(first_closest == Some(*point));
+            processed[*point] = true;
+            if optics.core_distances[*point].is_finite() {
+                for other in (0..n).filter(|idx| !processed[*idx]) {
+                    let distance = squared_euclidean(&row_slice(data.row(*point)), &row_slice(data.row(other))).sqrt();
+                    if distance <= 0.7 {
+                        reachability[other] = reachability[other].min(distance.max(optics.core_distances[*point]));
+                    }
+                }
+            }
+        }
+        assert!(reachability == optics.reachability && optics.ordering.iter().filter(|idx| optics.reachability[**idx].is_infinite()).count() > 1);
+    }
+}
@@ -0,0 +1,67 @@
+//! Density-based outlier scores, the companion to density-based clustering: where DBSCAN only says noise or
+//! not, a score ranks how far out each point lies relative to its own neighbourhood.
+
+use crate::index::{index_rows, row_slice, Index};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+
+/// Local Outlier Factor (Breunig et al., 2000) of every row, from its `k` nearest other rows: the mean local
+/// reachability density of its neighbours divided by its own. Points inside a cluster score about 1 however
+/// dense the cluster, and the score grows as a point's neighbourhood gets sparser than its neighbours', so a
+/// point at the edge of a tight cluster stands out even next to a diffuse one. Ties among the k-th nearest are
+/// broken arbitrarily, as in scikit-learn, whose `negative_outlier_factor_` is minus these scores.
+pub fn local_outlier_factor<T: Float + One + Zero>(data: &Array2<T>, k: usize) -> Vec<T> {
+    lof_from_index(&index_rows(data), data, k)
+}
+
+pub(crate) fn lof_from_index<T: Float + One + Zero, U: AsRef<[T]>>(index: &Index<T, U>, data: &Array2<T>, k: usize) -> Vec<T> {
+    assert!(k > 0 && k < data.rows(), "k must be between 1 and the number of points less one");
+    let neighbours = data
+        .outer_iter()
+        .enumerate()
+        .map(|(row_idx, row)| index.nearest_matching(&row_slice(row.view()), k, |other| other != row_idx))
+        .collect::<Vec<Vec<(T, usize)>>>();
+    let k_distance = neighbours.iter().map(|nearest| nearest.last().map_or(T::zero(), |(distance, _)| *distance)).collect::<Vec<T>>();
+    let count = T::from(k).expect("T::from(usize)");
+    // Duplicates have zero reachability distances; the small offset keeps their density finite, as
+    // scikit-learn's 1e-10 does.
+    let density = neighbours
+        .iter()
+        .map(|nearest| {
+            let reach = nearest.iter().fold(T::zero(), |acc, (distance, other)| acc + distance.max(k_distance[*other]));
+            count / (reach + T::epsilon())
+        })
+        .collect::<Vec<T>>();
+    neighbours.iter().zip(density.iter()).map(|(nearest, own)| nearest.iter().fold(T::zero(), |acc, (_, other)| acc + density[*other]) / (count * *own)).collect::<Vec<T>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::DbscanBuilder;
+    use crate::index::column_major;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_local_outlier_factor() {
+        // Random points, the same points spread ten times wider, and one stray point just off the first set.
+        let mut rng = SmallRng::seed_from_u64(4);
+        let mut points = (0..25).map(|_| (rng.gen_range(0.0, 4.0), rng.gen_range(0.0, 4.0))).collect::<Vec<(f64, f64)>>();
+        points.extend(points.clone().into_iter().map(|(x, y)| (100.0 + 10.0 * x, 10.0 * y)));
+        points.push((7.0, 2.0));
+        let data = Array2::from_shape_fn((51, 2), |(row, col)| if col == 0 { points[row].0 } else { points[row].1 });
+        let scores = local_outlier_factor(&data, 4);
+        // Both sets score the same point for point whatever their density, and the stray point scores highest
+        // although it is closer to its neighbours than the wide set's points are to theirs.
+        assert!((0..25).all(|i| (scores[i] - scores[25 + i]).abs() < 1e-9 && scores[50] > scores[i]));
+        assert!(scores[50] > 2.0 && local_outlier_factor(&column_major(&data), 4) == scores);
+
+        let model = DbscanBuilder::new(1.0, 4).fit(&data);
+        assert!(model.local_outlier_factor(4) == scores);
+
+        let duplicates = Array2::from_shape_vec((4, 1), vec![0.0, 0.0, 0.0, 0.0]).unwrap();
+        assert!(local_outlier_factor(&duplicates, 2).iter().all(|s| s.is_finite()));
+    }
+}
@@ -0,0 +1,95 @@
+//! Cleanup of a labelling after the fit, in the density-based convention of 0 for noise and clusters from 1.
+//! Every function returns dense labels, so the clusters left are numbered 1 to k in the order of their
+//! original labels.
+
+use crate::metrics::normalize_labels;
+use crate::union_find::UnionFind;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use std::collections::BTreeMap;
+
+/// The point standing for a cluster when `merge_clusters` compares them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Representative {
+    /// The mean of the cluster's points.
+    Centroid,
+    /// The cluster's point with the least total distance to the others, which stays inside curved clusters.
+    Medoid,
+}
+
+/// Compacts the cluster labels to 1..=k in ascending order, keeping 0 for noise.
+pub fn relabel_dense(labels: &[usize]) -> Vec<usize> {
+    normalize_labels(labels, 0)
+}
+
+/// Turns the points of clusters with fewer than `min_size` points into noise.
+pub fn drop_small_clusters(labels: &[usize], min_size: usize) -> Vec<usize> {
+    let mut sizes = BTreeMap::new();
+    for label in labels.iter().filter(|l| **l > 0) {
+        *sizes.entry(*label).or_insert(0) += 1;
+    }
+    relabel_dense(&labels.iter().map(|l| if *l > 0 && sizes[l] < min_size { 0 } else { *l }).collect::<Vec<usize>>())
+}
+
+/// Merges clusters whose representatives lie within `threshold` of each other, transitively, so a chain of
+/// close clusters becomes one. Noise stays noise.
+pub fn merge_clusters<T: Float>(data: &Array2<T>, labels: &[usize], threshold: T, representative: Representative) -> Vec<usize> {
+    assert!(labels.len() == data.rows(), "need one label per row");
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (idx, label) in labels.iter().enumerate().filter(|(_, l)| **l > 0) {
+        members.entry(*label).or_default().push(idx);
+    }
+    let distance = |a: &ArrayView1<T>, b: &ArrayView1<T>| a.iter().zip(b.iter()).fold(T::zero(), |acc, (x, y)| acc + (*x - *y).powi(2)).sqrt();
+    let representatives = members
+        .values()
+        .map(|points| match representative {
+            Representative::Centroid => {
+                let count = T::from(points.len()).expect("T::from(usize)");
+                Array1::from_shape_fn(data.cols(), |col| points.iter().fold(T::zero(), |acc, idx| acc + data[[*idx, col]]) / count)
+            }
+            Representative::Medoid => {
+                let total = |a: usize| points.iter().fold(T::zero(), |acc, b| acc + distance(&data.row(a), &data.row(*b)));
+                let medoid = points.iter().cloned().fold(points[0], |best, idx| if total(idx) < total(best) { idx } else { best });
+                data.row(medoid).to_owned()
+            }
+        })
+        .collect::<Vec<Array1<T>>>();
+
+    let mut merged = UnionFind::new(representatives.len());
+    for a in 0..representatives.len() {
+        for b in a + 1..representatives.len() {
+            if distance(&representatives[a].view(), &representatives[b].view()) <= threshold {
+                merged.union(a, b);
+            }
+        }
+    }
+    // Each merged cluster takes the lowest of its original labels.
+    let originals = members.keys().cloned().collect::<Vec<usize>>();
+    let mut lowest = BTreeMap::new();
+    for (position, label) in originals.iter().enumerate() {
+        lowest.entry(merged.find(position)).or_insert(*label);
+    }
+    let renamed = originals.iter().enumerate().map(|(position, label)| (*label, lowest[&merged.find(position)])).collect::<BTreeMap<usize, usize>>();
+    relabel_dense(&labels.iter().map(|l| if *l > 0 { renamed[l] } else { 0 }).collect::<Vec<usize>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postprocess() {
+        let labels = vec![3, 3, 0, 7, 7, 7, 9];
+        assert!(relabel_dense(&labels) == vec![1, 1, 0, 2, 2, 2, 3]);
+        assert!(drop_small_clusters(&labels, 2) == vec![1, 1, 0, 2, 2, 2, 0]);
+        assert!(drop_small_clusters(&labels, 4) == vec![0; 7]);
+
+        // Clusters 1 and 2 are centred 2 apart and the others 8 or more, and a wide threshold chains them all.
+        let data = Array2::from_shape_vec((8, 1), vec![0.0, 1.0, 2.0, 3.0, 10.0, 10.5, 20.0, 30.0]).unwrap();
+        let labels = vec![1, 1, 2, 2, 0, 3, 4, 5];
+        assert!(merge_clusters(&data, &labels, 2.0, Representative::Centroid) == vec![1, 1, 1, 1, 0, 2, 3, 4]);
+        assert!(merge_clusters(&data, &labels, 1.5, Representative::Centroid) == relabel_dense(&labels));
+        assert!(merge_clusters(&data, &labels, 2.0, Representative::Medoid) == vec![1, 1, 1, 1, 0, 2, 3, 4]);
+        assert!(merge_clusters(&data, &labels, 10.0, Representative::Centroid) == vec![1, 1, 1, 1, 0, 1, 1, 1]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Feature scaling before clustering. Distance-based methods see every feature on its own scale, so one measured
+//! in thousands drowns out one measured in fractions and no single eps fits both. Each scaler is fit to the
+//! training data and keeps its parameters, so new points for `predict` are mapped exactly as the training
+//! points were.
+
+use crate::spectral::symmetric_eigen;
+use crate::traits::Transform;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+
+/// Centers each feature on its mean and divides it by its standard deviation. Constant features are centered
+/// but not scaled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StandardScaler<T: Float> {
+    pub mean: Array1<T>,
+    pub std: Array1<T>,
+}
+
+impl<T: Float> StandardScaler<T> {
+    pub fn fit(data: &Array2<T>) -> StandardScaler<T> {
+        let mean = column_means(data);
+        let n = T::from(data.rows().max(1)).expect("T::from(usize)");
+        let std = Array1::from_shape_fn(data.cols(), |col| {
+            let variance = data.column(col).iter().fold(T::zero(), |acc, x| acc + (*x - mean[col]).powi(2)) / n;
+            if variance > T::zero() {
+                variance.sqrt()
+            } else {
+                T::one()
+            }
+        });
+        StandardScaler { mean, std }
+    }
+
+    pub fn inverse_transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] * self.std[col] + self.mean[col])
+    }
+}
+
+impl<T: Float> Transform<T> for StandardScaler<T> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| (data[[row, col]] - self.mean[col]) / self.std[col])
+    }
+}
+
+/// Maps each feature's training range onto [0, 1]. New points outside the training range land outside [0, 1],
+/// and constant features map to 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinMaxScaler<T: Float> {
+    pub min: Array1<T>,
+    pub range: Array1<T>,
+}
+
+impl<T: Float> MinMaxScaler<T> {
+    pub fn fit(data: &Array2<T>) -> MinMaxScaler<T> {
+        let min = Array1::from_shape_fn(data.cols(), |col| data.column(col).iter().cloned().fold(T::infinity(), T::min));
+        let range = Array1::from_shape_fn(data.cols(), |col| {
+            let range = data.column(col).iter().cloned().fold(T::neg_infinity(), T::max) - min[col];
+            if range > T::zero() {
+                range
+            } else {
+                T::one()
+            }
+        });
+        MinMaxScaler { min, range }
+    }
+
+    pub fn inverse_transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] * self.range[col] + self.min[col])
+    }
+}
+
+impl<T: Float> Transform<T> for MinMaxScaler<T> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| (data[[row, col]] - self.min[col]) / self.range[col])
+    }
+}
+
+/// ZCA whitening: centers the data and multiplies it by the inverse square root of its covariance, so the
+/// features come out uncorrelated with unit variance while staying as close as possible to the originals.
+/// Unlike per-feature scaling this also undoes correlations, turning elongated clusters round. Directions
+/// with no variance are dropped rather than blown up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Whitening<T: Float> {
+    pub mean: Array1<T>,
+    /// Applied on the right of the centered data.
+    pub matrix: Array2<T>,
+}
+
+impl<T: Float + 'static> Whitening<T> {
+    pub fn fit(data: &Array2<T>) -> Whitening<T> {
+        let mean = column_means(data);
+        let centered = Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] - mean[col]);
+        let n = T::from(data.rows().max(1)).expect("T::from(usize)");
+        let covariance = centered.t().dot(&centered).mapv(|x| x / n);
+        let (values, vectors) = symmetric_eigen(&covariance);
+        let largest = values.iter().cloned().fold(T::zero(), T::max);
+        let tolerance = largest * T::epsilon() * T::from(data.cols().max(1)).expect("T::from(usize)");
+        let scales = values.iter().map(|v| if *v > tolerance { v.sqrt().recip() } else { T::zero() }).collect::<Vec<T>>();
+        let matrix = Array2::from_shape_fn(covariance.dim(), |(a, b)| (0..scales.len()).fold(T::zero(), |acc, k| acc + vectors[[a, k]] * scales[k] * vectors[[b, k]]));
+        Whitening { mean, matrix }
+    }
+}
+
+impl<T: Float + 'static> Transform<T> for Whitening<T> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] - self.mean[col]).dot(&self.matrix)
+    }
+}
+
+fn column_means<T: Float>(data: &Array2<T>) -> Array1<T> {
+    let n = T::from(data.rows().max(1)).expect("T::from(usize)");
+    Array1::from_shape_fn(data.cols(), |col| data.column(col).iter().fold(T::zero(), |acc, x| acc + *x) / n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dbscan::Dbscan;
+
+    #[test]
+    fn test_preprocess() {
+        // Income in dollars and age in years: unscaled, income alone decides every distance.
+        let data = Array2::from_shape_vec((6, 2), vec![30_000.0, 25.0, 31_000.0, 60.0, 30_500.0, 26.0, 90_000.0, 61.0, 91_000.0, 24.0, 90_500.0, 59.0]).unwrap();
+        let standard = StandardScaler::fit(&data);
+        let scaled = standard.transform(&data);
+        assert!(column_means(&scaled).iter().all(|m| m.abs() < 1e-12) && StandardScaler::fit(&scaled).std.iter().all(|s| (s - 1.0).abs() < 1e-12));
+        assert!(standard.inverse_transform(&scaled).iter().zip(data.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+        let model = Dbscan::new_union_find(&scaled, 0.5, 2, true);
+        assert!(model.clusters == vec![1, 0, 1, 2, 0, 2] && Dbscan::new_union_find(&data, 1_000.0, 2, true).clusters == vec![1, 1, 1, 2, 2, 2]);
+
+        let min_max = MinMaxScaler::fit(&data);
+        let unit = min_max.transform(&data);
+        assert!(unit.iter().all(|x| (0.0..=1.0).contains(x)) && unit[[0, 0]] == 0.0 && unit[[4, 0]] == 1.0);
+        let new_point = Array2::from_shape_vec((1, 2), vec![152_000.0, 25.0]).unwrap();
+        assert!(min_max.transform(&new_point)[[0, 0]] == 2.0 && (min_max.inverse_transform(&min_max.transform(&new_point))[[0, 0]] - 152_000.0).abs() < 1e-9);
+
+        // Correlated features come out with identity covariance, and a constant column stays constant.
+        let correlated = Array2::from_shape_vec((5, 3), vec![1.0, 2.0, 7.0, 2.0, 4.5, 7.0, 3.0, 5.5, 7.0, 4.0, 8.0, 7.0, 5.0, 10.5, 7.0]).unwrap();
+        let white = Whitening::fit(&correlated).transform(&correlated);
+        let covariance = white.t().dot(&white) / 5.0;
+        assert!(covariance.indexed_iter().all(|((a, b), c)| (c - if a == b && a < 2 { 1.0 } else { 0.0 }).abs() < 1e-9));
+    }
+}
@@ -0,0 +1,172 @@
+//! Dimensionality reduction before clustering. In hundreds of dimensions distances concentrate, every point
+//! looking about as far from every other, and neighbour indexes degrade to brute force; projecting onto a few
+//! dozen dimensions first keeps the structure that matters and makes eps meaningful again. `Pipeline` chains
+//! a reduction fitted to the training data into any clusterer.
+
+use crate::datasets::normal;
+use crate::spectral::symmetric_eigen;
+use crate::labels::LabelSet;
+use crate::traits::{Clustering, Fit, Predict, Transform};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+/// Principal component analysis: projects the centered data onto the directions of greatest variance. The
+/// components are the leading eigenvectors of the covariance matrix, which are the right singular vectors of
+/// the centered data, so this is the SVD route without forming the n × d decomposition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pca<T: Float> {
+    pub mean: Array1<T>,
+    /// One column per component, in decreasing order of variance.
+    pub components: Array2<T>,
+    /// Variance of the data along each component.
+    pub explained_variance: Vec<T>,
+    /// Each component's share of the data's total variance.
+    pub explained_variance_ratio: Vec<T>,
+}
+
+impl<T: Float + 'static> Pca<T> {
+    pub fn fit(data: &Array2<T>, n_components: usize) -> Pca<T> {
+        assert!(n_components <= data.cols(), "can't keep more components than columns");
+        let n = T::from(data.rows().max(1)).expect("T::from(usize)");
+        let mean = Array1::from_shape_fn(data.cols(), |col| data.column(col).iter().fold(T::zero(), |acc, x| acc + *x) / n);
+        let centered = Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] - mean[col]);
+        let (values, vectors) = symmetric_eigen(&centered.t().dot(&centered).mapv(|x| x / n));
+        let mut order = (0..values.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| values[*b].partial_cmp(&values[*a]).expect("PartialOrd eigenvalue"));
+        order.truncate(n_components);
+
+        let total = values.iter().fold(T::zero(), |acc, v| acc + v.max(T::zero()));
+        let explained_variance = order.iter().map(|k| values[*k].max(T::zero())).collect::<Vec<T>>();
+        let explained_variance_ratio = explained_variance.iter().map(|v| if total > T::zero() { *v / total } else { T::zero() }).collect::<Vec<T>>();
+        let components = Array2::from_shape_fn((data.cols(), n_components), |(row, col)| vectors[[row, order[col]]]);
+        Pca { mean, components, explained_variance, explained_variance_ratio }
+    }
+
+    /// Maps reduced points back to the original space, onto the subspace the components span.
+    pub fn inverse_transform(&self, reduced: &Array2<T>) -> Array2<T> {
+        let projected = reduced.dot(&self.components.t());
+        Array2::from_shape_fn(projected.dim(), |(row, col)| projected[[row, col]] + self.mean[col])
+    }
+}
+
+impl<T: Float + 'static> Transform<T> for Pca<T> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T> {
+        Array2::from_shape_fn(data.dim(), |(row, col)| data[[row, col]] - self.mean[col]).dot(&self.components)
+    }
+}
+
+/// A random linear map to fewer dimensions. By the Johnson-Lindenstrauss lemma it keeps every pairwise
+/// distance within a factor of about `1 ± sqrt(8 ln(n) / components)` for n points, whatever the original
+/// dimension, and needs no fitting, which makes it the cheap choice for very wide data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomProjection<T: Float> {
+    /// `dims` rows by `components` columns, applied on the right of the data.
+    pub matrix: Array2<T>,
+}
+
+impl<T: Float + 'static> RandomProjection<T> {
+    /// Entries drawn from a Gaussian with variance `1 / components`.
+    pub fn gaussian(dims: usize, components: usize, seed: u64) -> RandomProjection<T> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let scale = T::from(components).expect("T::from(usize)").sqrt().recip();
+        RandomProjection { matrix: Array2::from_shape_fn((dims, components), |_| normal::<T, _>(&mut rng) * scale) }
+    }
+
+    /// Achlioptas' sparse projection: entries are `±sqrt(3 / components)` with probability 1/6 each and 0
+    /// otherwise, keeping the same guarantee with two thirds of the multiplications skipped.
+    pub fn sparse(dims: usize, components: usize, seed: u64) -> RandomProjection<T> {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let scale = (T::from(3.0).expect("T::from(f64)") / T::from(components).expect("T::from(usize)")).sqrt();
+        RandomProjection {
+            matrix: Array2::from_shape_fn((dims, components), |_| match rng.gen_range(0, 6) {
+                0 => scale,
+                1 => -scale,
+                _ => T::zero(),
+            }),
+        }
+    }
+}
+
+impl<T: Float + 'static> Transform<T> for RandomProjection<T> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T> {
+        data.dot(&self.matrix)
+    }
+}
+
+/// Fits a transform to the data, then a clusterer to the transformed data. `reduce` is any closure from the
+/// training data to a fitted `Transform`, so scalers chain in as well as reductions:
+/// `Pipeline { reduce: |data: &Array2<f64>| Pca::fit(data, 10), cluster: DbscanBuilder::new(0.5, 5) }`.
+#[derive(Debug, Clone, Copy)]
+pub struct Pipeline<R, F> {
+    pub reduce: R,
+    pub cluster: F,
+}
+
+/// A fitted `Pipeline`, which transforms new points the way the training points were before predicting.
+#[derive(Debug)]
+pub struct PipelineModel<D, M> {
+    pub transform: D,
+    pub model: M,
+}
+
+impl<T: Float, D: Transform<T>, R: Fn(&Array2<T>) -> D, F: Fit<T>> Fit<T> for Pipeline<R, F> {
+    type Model = PipelineModel<D, F::Model>;
+
+    fn fit(&self, data: &Array2<T>) -> PipelineModel<D, F::Model> {
+        let transform = (self.reduce)(data);
+        let model = self.cluster.fit(&transform.transform(data));
+        PipelineModel { transform, model }
+    }
+}
+
+impl<D, M: Clustering> Clustering for PipelineModel<D, M> {
+    fn labels(&self) -> Vec<usize> {
+        self.model.labels()
+    }
+    fn into_labels(self) -> LabelSet {
+        self.model.into_labels()
+    }
+}
+
+impl<T: Float, D: Transform<T>, M: Predict<T>> Predict<T> for PipelineModel<D, M> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize> {
+        self.model.predict(&self.transform.transform(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::DbscanBuilder;
+    use crate::metrics::adjusted_rand_index;
+    use ndarray::s;
+
+    #[test]
+    fn test_reduce() {
+        // Three blobs on a plane tilted through 20 dimensions.
+        let centers = Array2::from_shape_vec((3, 2), vec![0.0, 0.0, 10.0, 0.0, 0.0, 10.0]).unwrap();
+        let (plane, labels) = blobs(90, &centers, 0.5, 1);
+        let (_, basis) = symmetric_eigen(&Array2::from_shape_fn((20, 20), |(a, b)| ((a * 7 + b * 7) % 11) as f64));
+        let data = plane.dot(&basis.slice(s![..2, ..]));
+
+        let pca = Pca::fit(&data, 2);
+        assert!(pca.explained_variance_ratio.iter().sum::<f64>() > 1.0 - 1e-9 && pca.explained_variance[0] >= pca.explained_variance[1]);
+        assert!(pca.inverse_transform(&pca.transform(&data)).iter().zip(data.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+
+        let pipeline = Pipeline { reduce: |data: &Array2<f64>| Pca::fit(data, 2), cluster: DbscanBuilder::new(1.0, 4).borders(true).seed(1) };
+        let model = pipeline.fit(&data);
+        assert!(adjusted_rand_index(&model.labels(), &labels) == 1.0 && model.predict(&data.slice(s![..3, ..]).to_owned()) == model.labels()[..3].to_vec());
+
+        // Random projections from 500 dimensions to 100 roughly keep every distance.
+        let mut rng = SmallRng::seed_from_u64(2);
+        let wide = Array2::from_shape_fn((20, 500), |_| rng.gen_range(0.0, 1.0));
+        for projection in [RandomProjection::gaussian(500, 100, 3), RandomProjection::sparse(500, 100, 3)] {
+            let narrow = projection.transform(&wide);
+            let distance = |x: &Array2<f64>, a: usize, b: usize| (&x.row(a) - &x.row(b)).mapv(|d| d * d).sum().sqrt();
+            assert!((0..20).all(|a| (a + 1..20).all(|b| (0.6..1.4).contains(&(distance(&narrow, a, b) / distance(&wide, a, b))))));
+        }
+    }
+}
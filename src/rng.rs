@@ -0,0 +1,31 @@
+//! The random number generator behind every constructor that doesn't take one.
+
+#[cfg(not(feature = "wasm"))]
+use rand::rngs::ThreadRng;
+#[cfg(feature = "wasm")]
+use rand::{rngs::StdRng, SeedableRng};
+
+/// The thread-local generator, seeded from OS entropy.
+#[cfg(not(feature = "wasm"))]
+pub(crate) fn default_rng() -> ThreadRng {
+    rand::thread_rng()
+}
+
+/// A generator with a fixed seed, since `wasm32-unknown-unknown` has no OS entropy source. Fits without an
+/// explicit rng are therefore repeatable under this feature; pass one to the `_with_rng` constructors to vary them.
+#[cfg(feature = "wasm")]
+pub(crate) fn default_rng() -> StdRng {
+    StdRng::seed_from_u64(0)
+}
+
+#[cfg(all(test, feature = "wasm"))]
+mod tests {
+    use crate::dbscan::Dbscan;
+    use ndarray::prelude::*;
+
+    #[test]
+    fn test_wasm_fits_repeat() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -1.0, -2.0, -2.0, -1.0]).unwrap();
+        assert!((0..5).all(|_| Dbscan::new(&data, 0.5, 2, true).clusters == Dbscan::new(&data, 0.5, 2, true).clusters));
+    }
+}
@@ -0,0 +1,120 @@
+use num_traits::float::Float;
+
+/// Row-major sparse matrix in compressed sparse row form. Each row's column indices are kept sorted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseRows<T: Float> {
+    pub cols: usize,
+    pub indptr: Vec<usize>,
+    pub indices: Vec<usize>,
+    pub values: Vec<T>,
+}
+
+impl<T: Float> SparseRows<T> {
+    pub fn new(cols: usize) -> SparseRows<T> {
+        SparseRows {
+            cols,
+            indptr: vec![0],
+            indices: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Appends a row from `(column, value)` entries, which may be in any order.
+    pub fn push_row(&mut self, entries: &[(usize, T)]) {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|(col, _)| *col);
+        for (col, value) in entries {
+            assert!(col < self.cols, "column {} out of bounds for {} columns", col, self.cols);
+            self.indices.push(col);
+            self.values.push(value);
+        }
+        self.indptr.push(self.indices.len());
+    }
+
+    pub fn rows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    pub fn row(&self, idx: usize) -> (&[usize], &[T]) {
+        let (start, end) = (self.indptr[idx], self.indptr[idx + 1]);
+        (&self.indices[start..end], &self.values[start..end])
+    }
+
+    pub(crate) fn normalize(&mut self) {
+        for idx in 0..self.rows() {
+            let (start, end) = (self.indptr[idx], self.indptr[idx + 1]);
+            let norm = self.values[start..end].iter().fold(T::zero(), |acc, x| acc + *x * *x).sqrt();
+            if norm > T::zero() {
+                for value in self.values[start..end].iter_mut() {
+                    *value = *value / norm;
+                }
+            }
+        }
+    }
+
+    /// For each column, the rows with a nonzero in it, in ascending order.
+    pub(crate) fn postings(&self) -> Vec<Vec<usize>> {
+        let mut postings = vec![Vec::new(); self.cols];
+        for idx in 0..self.rows() {
+            for col in self.row(idx).0 {
+                postings[*col].push(idx);
+            }
+        }
+        postings
+    }
+
+    /// Dot product of rows `a` and `b`, touching only their shared nonzero columns.
+    pub(crate) fn dot(&self, a: usize, b: usize) -> T {
+        let (indices_a, values_a) = self.row(a);
+        let (indices_b, values_b) = self.row(b);
+        let (mut i, mut j) = (0, 0);
+        let mut dot = T::zero();
+        while i < indices_a.len() && j < indices_b.len() {
+            if indices_a[i] < indices_b[j] {
+                i += 1;
+            } else if indices_a[i] > indices_b[j] {
+                j += 1;
+            } else {
+                dot = dot + values_a[i] * values_b[j];
+                i += 1;
+                j += 1;
+            }
+        }
+        dot
+    }
+}
+
+/// Dot products of one row with all the others, filled in through the column postings so only rows sharing a
+/// nonzero column are touched. Reused from row to row, clearing only what the last row touched.
+#[derive(Debug)]
+pub(crate) struct DotAccumulator<T> {
+    pub(crate) dots: Vec<T>,
+    pub(crate) touched: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl<T: Float> DotAccumulator<T> {
+    pub(crate) fn new(rows: usize) -> DotAccumulator<T> {
+        DotAccumulator { dots: vec![T::zero(); rows], touched: Vec::new(), seen: vec![false; rows] }
+    }
+
+    /// Replaces the dot products with those of row `a` of `rows`; rows not in `touched` have zero.
+    pub(crate) fn accumulate(&mut self, rows: &SparseRows<T>, a: usize, postings: &[Vec<usize>]) {
+        for other in self.touched.drain(..) {
+            self.dots[other] = T::zero();
+            self.seen[other] = false;
+        }
+        let (indices, values) = rows.row(a);
+        for (col, value) in indices.iter().zip(values.iter()) {
+            for other in postings[*col].iter() {
+                let (other_indices, other_values) = rows.row(*other);
+                let at = other_indices.binary_search(col).expect("posting lists match the rows");
+                if !self.seen[*other] {
+                    self.seen[*other] = true;
+                    self.touched.push(*other);
+                }
+                self.dots[*other] = self.dots[*other] + *value * other_values[at];
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::index::{index_rows, row_slice, squared_euclidean};
+use crate::kmeans::Kmeans;
+use crate::rng::default_rng;
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use ndarray::ScalarOperand;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use rand::Rng;
+use std::iter::Sum;
+use std::ops::AddAssign;
+
+/// How the affinity graph is built: `Rbf(gamma)` connects every pair with weight `exp(-gamma * d^2)`, and
+/// `NearestNeighbours(k)` connects each point to its k nearest neighbours, averaged with the reverse edges so
+/// the graph is symmetric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Affinity<T: Float> {
+    Rbf(T),
+    NearestNeighbours(usize),
+}
+
+/// Spectral clustering (Ng, Jordan & Weiss, 2001): k-means on the rows of the leading eigenvectors of the
+/// normalised affinity matrix, each row scaled to unit length. `embedding` holds those rows and `clusters`
+/// are the k-means labels, numbered from 0 as in `Kmeans`.
+#[derive(Debug)]
+pub struct Spectral<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> {
+    pub embedding: Array2<T>,
+    pub clusters: Vec<usize>,
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Spectral<T> {
+    /// The eigenvectors come from a dense Jacobi eigensolver, so this needs O(n^2) memory and O(n^3) time.
+    pub fn new(data: &Array2<T>, k: usize, affinity: Affinity<T>) -> Spectral<T> {
+        Spectral::new_with_rng(data, k, affinity, &mut default_rng())
+    }
+
+    pub fn new_with_rng<R: Rng>(data: &Array2<T>, k: usize, affinity: Affinity<T>, rng: &mut R) -> Spectral<T> {
+        let n = data.rows();
+        let mut weights = affinity_matrix(data, affinity);
+        let scale = weights.outer_iter().map(|row| row.sum()).map(|degree| if degree > T::zero() { degree.sqrt().recip() } else { T::zero() }).collect::<Vec<T>>();
+        for ((a, b), w) in weights.indexed_iter_mut() {
+            *w = *w * scale[a] * scale[b];
+        }
+
+        // The leading eigenvectors of D^-1/2 W D^-1/2 are the trailing ones of the normalised Laplacian.
+        let (values, vectors) = symmetric_eigen(&weights);
+        let mut order = (0..n).collect::<Vec<usize>>();
+        order.sort_by(|a, b| values[*b].partial_cmp(&values[*a]).expect("PartialOrd eigenvalue"));
+        let k = k.min(n);
+        let mut embedding = Array2::zeros((n, k));
+        for (column, idx) in order.iter().take(k).enumerate() {
+            embedding.column_mut(column).assign(&vectors.column(*idx));
+        }
+        for mut row in embedding.outer_iter_mut() {
+            let norm = row.iter().fold(T::zero(), |acc, x| acc + *x * *x).sqrt();
+            if norm > T::zero() {
+                row.mapv_inplace(|x| x / norm);
+            }
+        }
+
+        let clusters = Kmeans::fit_with_rng(&embedding, k, 300, T::zero(), rng).clusters;
+        Spectral { embedding, clusters }
+    }
+}
+
+impl<T: Float + One + Zero + ScalarOperand + AddAssign + Copy + Sum> Clustering for Spectral<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+fn affinity_matrix<T: Float + One + Zero>(data: &Array2<T>, affinity: Affinity<T>) -> Array2<T> {
+    let n = data.rows();
+    let mut weights = Array2::zeros((n, n));
+    match affinity {
+        Affinity::Rbf(gamma) => {
+            for (a, row_a) in data.outer_iter().enumerate() {
+                for (b, row_b) in data.outer_iter().enumerate().skip(a + 1) {
+                    let w = (-gamma * squared_euclidean(&row_slice(row_a.view()), &row_slice(row_b.view()))).exp();
+                    weights[[a, b]] = w;
+                    weights[[b, a]] = w;
+                }
+            }
+        }
+        Affinity::NearestNeighbours(k) => {
+            let index = index_rows(data);
+            let half = T::from(0.5).expect("T::from(f64)");
+            for (a, row) in data.outer_iter().enumerate() {
+                for (_, b) in index.nearest_matching(&row_slice(row.view()), k, |b| b != a) {
+                    weights[[a, b]] = weights[[a, b]] + half;
+                    weights[[b, a]] = weights[[b, a]] + half;
+                }
+            }
+        }
+    }
+    weights
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix by cyclic Jacobi rotations.
+pub(crate) fn symmetric_eigen<T: Float>(matrix: &Array2<T>) -> (Vec<T>, Array2<T>) {
+    let n = matrix.rows();
+    let mut a = matrix.to_owned();
+    let mut vectors = Array2::eye(n);
+    let scale = a.iter().fold(T::zero(), |acc, x| acc + *x * *x);
+    let tolerance = T::epsilon() * T::epsilon() * scale;
+    let two = T::one() + T::one();
+    for _ in 0..100 {
+        let off_diagonal = a.indexed_iter().filter(|((i, j), _)| i != j).fold(T::zero(), |acc, (_, x)| acc + *x * *x);
+        if off_diagonal <= tolerance {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[[p, q]] == T::zero() {
+                    continue;
+                }
+                let theta = (a[[q, q]] - a[[p, p]]) / (two * a[[p, q]]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + T::one()).sqrt());
+                let c = (t * t + T::one()).sqrt().recip();
+                let s = t * c;
+                for k in 0..n {
+                    let (kp, kq) = (a[[k, p]], a[[k, q]]);
+                    a[[k, p]] = c * kp - s * kq;
+                    a[[k, q]] = s * kp + c * kq;
+                }
+                for k in 0..n {
+                    let (pk, qk) = (a[[p, k]], a[[q, k]]);
+                    a[[p, k]] = c * pk - s * qk;
+                    a[[q, k]] = s * pk + c * qk;
+                }
+                for k in 0..n {
+                    let (kp, kq) = (vectors[[k, p]], vectors[[k, q]]);
+                    vectors[[k, p]] = c * kp - s * kq;
+                    vectors[[k, q]] = s * kp + c * kq;
+                }
+            }
+        }
+    }
+    (a.diag().to_vec(), vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::column_major;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_spectral_rings() {
+        let mut values = Vec::new();
+        for radius in [1.0, 5.0].iter() {
+            for i in 0..30 {
+                let angle = i as f64 * 2.0 * std::f64::consts::PI / 30.0;
+                values.push(radius * angle.cos());
+                values.push(radius * angle.sin());
+            }
+        }
+        let data = Array2::from_shape_vec((60, 2), values).unwrap();
+        let model = Spectral::new_with_rng(&data, 2, Affinity::NearestNeighbours(4), &mut StdRng::seed_from_u64(0));
+        assert!(model.clusters[..30].iter().all(|c| *c == model.clusters[0]) && model.clusters[30..].iter().all(|c| *c == model.clusters[30]));
+        assert!(model.clusters[0] != model.clusters[30]);
+        let fit = |data: &Array2<f64>, affinity| Spectral::new_with_rng(data, 2, affinity, &mut StdRng::seed_from_u64(0)).clusters;
+        assert!(fit(&column_major(&data), Affinity::NearestNeighbours(4)) == model.clusters);
+        let blobs = arr2(&[[0.0, 0.0], [0.2, 0.1], [0.1, 0.3], [5.0, 5.0], [5.1, 4.8], [4.9, 5.2]]);
+        assert!(fit(&column_major(&blobs), Affinity::Rbf(1.0)) == fit(&blobs, Affinity::Rbf(1.0)));
+
+        let matrix = Array2::from_shape_vec((3, 3), vec![2.0, 1.0, 0.0, 1.0, 3.0, 1.0, 0.0, 1.0, 4.0]).unwrap();
+        let (eigenvalues, eigenvectors) = symmetric_eigen(&matrix);
+        for (value, vector) in eigenvalues.iter().zip(eigenvectors.gencolumns()) {
+            assert!((matrix.dot(&vector) - &vector * *value).iter().all(|x| x.abs() < 1e-9));
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! How robust a clustering is to the sample it was fit on. The data are refit on bootstrap resamples or
+//! noise-perturbed copies, and each cluster of the fit on the full data is compared against its best match in
+//! every refit (Hennig, 2007). Clusters that are artifacts of one eps or one draw of the data keep falling apart.
+
+use crate::datasets::normal;
+use crate::traits::{Clustering, Fit};
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+/// How each refit's data are drawn from the original.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resampling<T> {
+    /// As many rows as the data, drawn with replacement; rows drawn several times are refit as duplicates.
+    Bootstrap,
+    /// Every row with Gaussian noise of this standard deviation added to each coordinate.
+    Jitter(T),
+}
+
+#[derive(Debug)]
+pub struct Stability {
+    /// Mean Jaccard similarity of each cluster of the full fit to its best match in each refit, keyed by
+    /// label. Above about 0.75 a cluster is usually taken as stable, and below 0.5 as dissolved.
+    pub jaccard: BTreeMap<usize, f64>,
+    /// For each pair of rows, the fraction of refits containing both in which they shared a cluster.
+    pub coassociation: Array2<f64>,
+}
+
+/// Fits `params` to `data`, then refits it on `n_resamples` resamples drawn by `resampling` from a `SmallRng`
+/// seeded with `seed`. Points labelled `noise`, as 0 is for the density-based models, belong to no cluster:
+/// they count towards neither a Jaccard similarity nor the co-association of a pair.
+pub fn stability<T: Float, F: Fit<T>>(data: &Array2<T>, params: &F, resampling: Resampling<T>, n_resamples: usize, noise: Option<usize>, seed: u64) -> Stability {
+    let n = data.rows();
+    let reference = params.fit(data).labels();
+    let mut rng = SmallRng::seed_from_u64(seed);
+    let mut jaccard: BTreeMap<usize, (f64, usize)> = BTreeMap::new();
+    let (mut together, mut sampled) = (Array2::<f64>::zeros((n, n)), Array2::<f64>::zeros((n, n)));
+
+    for _ in 0..n_resamples {
+        // The refit's label for each original row, or None where the resample left it out.
+        let mut labels = vec![None; n];
+        match resampling {
+            Resampling::Bootstrap => {
+                let rows = (0..n).map(|_| rng.gen_range(0, n)).collect::<Vec<usize>>();
+                let resample = Array2::from_shape_fn((n, data.cols()), |(idx, col)| data[[rows[idx], col]]);
+                for (row, label) in rows.iter().zip(params.fit(&resample).labels()) {
+                    labels[*row].get_or_insert(label);
+                }
+            }
+            Resampling::Jitter(std) => {
+                let jittered = data.mapv(|x| x + std * normal(&mut rng));
+                for (row, label) in params.fit(&jittered).labels().into_iter().enumerate() {
+                    labels[row] = Some(label);
+                }
+            }
+        }
+        let clustered = |label: Option<usize>| label.filter(|label| Some(*label) != noise);
+
+        for a in (0..n).filter(|a| labels[*a].is_some()) {
+            for b in (0..n).filter(|b| labels[*b].is_some()) {
+                sampled[[a, b]] += 1.0;
+                if clustered(labels[a]).is_some() && labels[a] == labels[b] {
+                    together[[a, b]] += 1.0;
+                }
+            }
+        }
+
+        // Each reference cluster, restricted to the rows present, against every refit cluster.
+        let mut overlaps: BTreeMap<usize, BTreeMap<usize, usize>> = BTreeMap::new();
+        let (mut reference_sizes, mut refit_sizes) = (BTreeMap::new(), BTreeMap::new());
+        for row in 0..n {
+            let refit = match labels[row] {
+                Some(label) => clustered(Some(label)),
+                None => continue,
+            };
+            if let Some(cluster) = clustered(Some(reference[row])) {
+                *reference_sizes.entry(cluster).or_insert(0) += 1;
+                if let Some(refit) = refit {
+                    *overlaps.entry(cluster).or_default().entry(refit).or_insert(0) += 1;
+                }
+            }
+            if let Some(refit) = refit {
+                *refit_sizes.entry(refit).or_insert(0) += 1;
+            }
+        }
+        for (cluster, size) in reference_sizes {
+            let best = overlaps.get(&cluster).map_or(0.0, |overlaps| {
+                overlaps.iter().map(|(refit, shared)| *shared as f64 / (size + refit_sizes[refit] - shared) as f64).fold(0.0, f64::max)
+            });
+            let entry = jaccard.entry(cluster).or_insert((0.0, 0));
+            *entry = (entry.0 + best, entry.1 + 1);
+        }
+    }
+
+    Stability {
+        jaccard: jaccard.into_iter().map(|(cluster, (total, count))| (cluster, total / count as f64)).collect::<BTreeMap<usize, f64>>(),
+        coassociation: Array2::from_shape_fn((n, n), |(a, b)| if sampled[[a, b]] > 0.0 { together[[a, b]] / sampled[[a, b]] } else { 0.0 }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasets::blobs;
+    use crate::dbscan::Dbscan;
+    use crate::kmedoids::KMedoids;
+
+    #[test]
+    fn test_stability() {
+        let centers = Array2::from_shape_vec((2, 2), vec![0.0, 0.0, 10.0, 0.0]).unwrap();
+        let (data, truth) = blobs(60, &centers, 0.5, 1);
+        let dbscan = |data: &Array2<f64>| Dbscan::with_seed(data, 1.0, 4, true, 1);
+        let stable = stability(&data, &dbscan, Resampling::Bootstrap, 20, Some(0), 2);
+        assert!(stable.jaccard.len() == 2 && stable.jaccard.values().all(|j| *j > 0.9));
+        assert!((0..60).all(|a| (0..60).all(|b| stable.coassociation[[a, b]] == stable.coassociation[[b, a]])));
+        assert!((0..60).all(|b| truth[b] == truth[0] || stable.coassociation[[0, b]] == 0.0));
+
+        // Four medoids must cut each blob in two, and where the cut falls changes from one refit to the next.
+        let split = stability(&data, &|data: &Array2<f64>| KMedoids::new(data, 4, 10), Resampling::Jitter(0.5), 20, None, 2);
+        assert!(split.jaccard.len() == 4 && split.jaccard.values().cloned().fold(1.0, f64::min) < stable.jaccard.values().cloned().fold(1.0, f64::min));
+        assert!((0..60).all(|a| split.coassociation[[a, a]] == 1.0));
+    }
+}
@@ -0,0 +1,245 @@
+//! TRACLUS (Lee, Han & Whang, 2007): common sub-routes of 2D trajectories. Each trajectory is cut at its
+//! characteristic points into line segments, the segments of all trajectories are clustered by DBSCAN under a
+//! line-segment distance, and each cluster is summarised by a representative trajectory through its middle.
+//! Unlike `Dbscan::new_trajectory`, which compares whole trajectories, two routes sharing only a stretch of
+//! road still meet along that stretch.
+
+use crate::dbscan::Dbscan;
+use crate::postprocess::relabel_dense;
+use crate::rng::default_rng;
+use crate::labels::LabelSet;
+use crate::traits::Clustering;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+use num_traits::identities::{One, Zero};
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A piece of one input trajectory between two of its characteristic points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment<T: Float> {
+    pub start: [T; 2],
+    pub end: [T; 2],
+    /// Index of the trajectory the segment was cut from.
+    pub trajectory: usize,
+}
+
+#[derive(Debug)]
+pub struct Traclus<T: Float + One + Zero> {
+    pub segments: Vec<Segment<T>>,
+    /// One label per segment: 0 for noise, clusters numbered from 1.
+    pub clusters: Vec<usize>,
+    /// One representative trajectory per cluster, `representatives[c - 1]` for cluster `c`.
+    pub representatives: Vec<Vec<[T; 2]>>,
+}
+
+impl<T: Float + One + Zero> Traclus<T> {
+    /// Clusters the segments of `trajectories`, each an n × 2 array of points in travel order. Segments within
+    /// `eps` of at least `min_lines` segments (themselves included) are core, and a cluster whose segments come
+    /// from fewer than `min_lines` distinct trajectories is dropped as noise, being one trip's detour rather than
+    /// a shared route. Representatives are sampled where at least `min_lines` segments overlap, with points at
+    /// least `gamma` apart along the cluster's main direction.
+    pub fn new(trajectories: &[Array2<T>], eps: T, min_lines: usize, gamma: T) -> Traclus<T> {
+        Traclus::new_with_rng(trajectories, eps, min_lines, gamma, &mut default_rng())
+    }
+
+    pub fn new_with_rng<R: Rng>(trajectories: &[Array2<T>], eps: T, min_lines: usize, gamma: T, rng: &mut R) -> Traclus<T> {
+        let mut segments = Vec::new();
+        for (trajectory, points) in trajectories.iter().enumerate() {
+            assert!(points.cols() == 2, "trajectories must be n × 2 arrays of points");
+            let point = |i: usize| [points[[i, 0]], points[[i, 1]]];
+            for pair in partition(points).windows(2) {
+                segments.push(Segment { start: point(pair[0]), end: point(pair[1]), trajectory });
+            }
+        }
+
+        let distances = Array2::from_shape_fn((segments.len(), segments.len()), |(a, b)| segment_distance(&segments[a], &segments[b]));
+        let mut clusters = Dbscan::from_distances_with_rng(&distances, eps, min_lines, true, rng).clusters;
+        let mut trips: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (segment, cluster) in segments.iter().zip(clusters.iter()).filter(|(_, c)| **c > 0) {
+            trips.entry(*cluster).or_default().insert(segment.trajectory);
+        }
+        for cluster in clusters.iter_mut().filter(|c| **c > 0) {
+            if trips[cluster].len() < min_lines {
+                *cluster = 0;
+            }
+        }
+        let clusters = relabel_dense(&clusters);
+
+        let n_clusters = clusters.iter().cloned().max().unwrap_or(0);
+        let representatives = (1..=n_clusters)
+            .map(|c| {
+                let members = segments.iter().zip(clusters.iter()).filter(|(_, l)| **l == c).map(|(s, _)| *s).collect::<Vec<Segment<T>>>();
+                representative(&members, min_lines, gamma)
+            })
+            .collect::<Vec<Vec<[T; 2]>>>();
+        Traclus { segments, clusters, representatives }
+    }
+}
+
+impl<T: Float + One + Zero> Clustering for Traclus<T> {
+    fn labels(&self) -> Vec<usize> {
+        self.clusters.clone()
+    }
+
+    fn into_labels(self) -> LabelSet {
+        LabelSet::new(self.clusters)
+    }
+}
+
+/// Indices of the characteristic points of a trajectory, its first and last points included: where the path
+/// changes enough that describing it by one straight segment would cost more, in the minimum description
+/// length sense, than keeping the points in between. Costs are `log2(1 + x)` of lengths and distances, so they
+/// stay positive for small deviations; as the cost of keeping points grows with their number, densely sampled
+/// paths are cut less eagerly than sparse ones and may want thinning first.
+pub fn partition<T: Float>(points: &Array2<T>) -> Vec<usize> {
+    let n = points.rows();
+    if n < 2 {
+        return (0..n).collect();
+    }
+    let point = |i: usize| [points[[i, 0]], points[[i, 1]]];
+    let cost = |x: T| (T::one() + x).log2();
+    let mdl_partitioned = |start: usize, end: usize| {
+        let hypothesis = Segment { start: point(start), end: point(end), trajectory: 0 };
+        let (perpendicular, angle) = (start..end).fold((T::zero(), T::zero()), |(p, a), k| {
+            let (perpendicular, _, angle) = components(&hypothesis, &Segment { start: point(k), end: point(k + 1), trajectory: 0 });
+            (p + perpendicular, a + angle)
+        });
+        cost(length(&hypothesis)) + cost(perpendicular) + cost(angle)
+    };
+    let mdl_unpartitioned = |start: usize, end: usize| (start..end).fold(T::zero(), |acc, k| acc + cost(distance(point(k), point(k + 1))));
+
+    let mut characteristic = vec![0];
+    let (mut start, mut length) = (0, 1);
+    while start + length < n {
+        let current = start + length;
+        if length > 1 && mdl_partitioned(start, current) > mdl_unpartitioned(start, current) {
+            characteristic.push(current - 1);
+            start = current - 1;
+            length = 1;
+        } else {
+            length += 1;
+        }
+    }
+    characteristic.push(n - 1);
+    characteristic
+}
+
+/// TRACLUS's distance between two segments: the sum of their perpendicular, parallel and angle distances,
+/// measured from the longer segment. Zero for equal segments, and symmetric.
+pub fn segment_distance<T: Float>(a: &Segment<T>, b: &Segment<T>) -> T {
+    let (longer, shorter) = if length(a) >= length(b) { (a, b) } else { (b, a) };
+    let (perpendicular, parallel, angle) = components(longer, shorter);
+    perpendicular + parallel + angle
+}
+
+/// Perpendicular, parallel and angle distances of `shorter` from `longer`.
+fn components<T: Float>(longer: &Segment<T>, shorter: &Segment<T>) -> (T, T, T) {
+    let direction = [longer.end[0] - longer.start[0], longer.end[1] - longer.start[1]];
+    let squared_length = direction[0] * direction[0] + direction[1] * direction[1];
+    if squared_length == T::zero() {
+        return (distance(longer.start, shorter.start).max(distance(longer.start, shorter.end)), T::zero(), T::zero());
+    }
+    let project = |p: [T; 2]| {
+        let u = ((p[0] - longer.start[0]) * direction[0] + (p[1] - longer.start[1]) * direction[1]) / squared_length;
+        [longer.start[0] + u * direction[0], longer.start[1] + u * direction[1]]
+    };
+    let (projected_start, projected_end) = (project(shorter.start), project(shorter.end));
+
+    let (l1, l2) = (distance(shorter.start, projected_start), distance(shorter.end, projected_end));
+    let perpendicular = if l1 + l2 > T::zero() { (l1 * l1 + l2 * l2) / (l1 + l2) } else { T::zero() };
+    let to_ends = |p: [T; 2]| distance(p, longer.start).min(distance(p, longer.end));
+    let parallel = to_ends(projected_start).min(to_ends(projected_end));
+
+    let other = [shorter.end[0] - shorter.start[0], shorter.end[1] - shorter.start[1]];
+    let dot = direction[0] * other[0] + direction[1] * other[1];
+    let cross = (direction[0] * other[1] - direction[1] * other[0]).abs();
+    // |shorter| sin θ, or all of |shorter| once the segments point more than a right angle apart.
+    let angle = if dot >= T::zero() { cross / squared_length.sqrt() } else { length(shorter) };
+    (perpendicular, parallel, angle)
+}
+
+/// Sweeps a line across the segments along their average direction; wherever at least `min_lines` segments
+/// cross it, and at least `gamma` on from the last point, the mean of their crossing points joins the path.
+fn representative<T: Float>(segments: &[Segment<T>], min_lines: usize, gamma: T) -> Vec<[T; 2]> {
+    let (dx, dy) = segments.iter().fold((T::zero(), T::zero()), |(x, y), s| (x + s.end[0] - s.start[0], y + s.end[1] - s.start[1]));
+    let norm = (dx * dx + dy * dy).sqrt();
+    if norm == T::zero() {
+        return Vec::new();
+    }
+    let (cos, sin) = (dx / norm, dy / norm);
+    let rotate = |p: [T; 2]| (p[0] * cos + p[1] * sin, p[1] * cos - p[0] * sin);
+    let rotated = segments
+        .iter()
+        .map(|s| {
+            let (a, b) = (rotate(s.start), rotate(s.end));
+            if a.0 <= b.0 {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        })
+        .collect::<Vec<((T, T), (T, T))>>();
+    let mut sweep = rotated.iter().flat_map(|(a, b)| vec![a.0, b.0]).collect::<Vec<T>>();
+    sweep.sort_by(|a, b| a.partial_cmp(b).expect("PartialOrd coordinate"));
+
+    let mut path: Vec<[T; 2]> = Vec::new();
+    let mut last: Option<T> = None;
+    for x in sweep {
+        if last.is_some_and(|last| x - last < gamma) {
+            continue;
+        }
+        let crossing = rotated.iter().filter(|(a, b)| a.0 <= x && x <= b.0).map(|(a, b)| if b.0 > a.0 { a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0) } else { a.1 }).collect::<Vec<T>>();
+        if crossing.len() >= min_lines {
+            let y = crossing.iter().fold(T::zero(), |acc, y| acc + *y) / T::from(crossing.len()).expect("T::from(usize)");
+            path.push([x * cos - y * sin, x * sin + y * cos]);
+            last = Some(x);
+        }
+    }
+    path
+}
+
+fn length<T: Float>(segment: &Segment<T>) -> T {
+    distance(segment.start, segment.end)
+}
+
+fn distance<T: Float>(a: [T; 2], b: [T; 2]) -> T {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_traclus() {
+        // An L-shaped path is cut only at its corner.
+        let corner = Array2::from_shape_fn((11, 2), |(i, col)| match col {
+            0 => i.min(5) as f64,
+            _ => i.saturating_sub(5) as f64,
+        });
+        assert!(partition(&corner) == vec![0, 5, 10]);
+
+        let a = Segment { start: [0.0, 0.0], end: [10.0, 0.0], trajectory: 0 };
+        let b = Segment { start: [2.0, 1.0], end: [8.0, 1.0], trajectory: 1 };
+        assert!(segment_distance(&a, &a) == 0.0 && segment_distance(&a, &b) == segment_distance(&b, &a) && (segment_distance(&a, &b) - 3.0).abs() < 1e-12);
+
+        // Three trips share a road along y = 0 before turning off in different directions, and a fourth runs alone.
+        let trip = |turn: f64, offset: f64| Array2::from_shape_fn((9, 2), |(i, col)| match (col, i <= 4) {
+            (0, true) => i as f64 * 2.5,
+            (1, true) => offset,
+            (0, false) => 10.0 + (i - 4) as f64 * 2.5 * turn.cos(),
+            _ => offset + (i - 4) as f64 * 2.5 * turn.sin(),
+        });
+        let lone = Array2::from_shape_fn((11, 2), |(i, col)| if col == 0 { i as f64 } else { 50.0 });
+        let trips = vec![trip(1.5, 0.0), trip(-1.5, 0.2), trip(0.0, -0.2), lone];
+        let model = Traclus::new_with_rng(&trips, 1.0, 3, 1.0, &mut StdRng::seed_from_u64(1));
+        let shared = model.segments.iter().zip(model.clusters.iter()).filter(|(s, _)| s.start[0] < 10.0 && s.trajectory < 3).map(|(_, c)| *c).collect::<Vec<usize>>();
+        assert!(model.representatives.len() == 1 && shared.iter().all(|c| *c == 1));
+        assert!(model.segments.iter().zip(model.clusters.iter()).all(|(s, c)| s.trajectory < 3 || *c == 0));
+        let road = &model.representatives[0];
+        assert!(road.len() == 2 && road[0][0] == 0.0 && road[1][0] == 10.0 && road.iter().all(|p| p[1].abs() < 1e-12));
+    }
+}
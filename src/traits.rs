@@ -0,0 +1,154 @@
+use crate::labels::LabelSet;
+use ndarray::prelude::*;
+use num_traits::float::Float;
+
+/// A fitted clustering, labelling each point it was fit on. Density-based models label noise 0 and number
+/// their clusters from 1; the others index into their centers, exemplars or medoids from 0.
+pub trait Clustering {
+    fn labels(&self) -> Vec<usize>;
+
+    /// `labels` as a signed 64-bit array, one entry per training row in row order, for handing to dataframe
+    /// and array code that expects an integer column.
+    fn labels_array(&self) -> Array1<i64> {
+        self.labels().into_iter().map(|label| label as i64).collect::<Array1<i64>>()
+    }
+
+    /// `labels` as a `LabelSet`, for working with the clusters rather than the raw assignment.
+    fn label_set(&self) -> LabelSet {
+        LabelSet::from(self.labels())
+    }
+
+    /// The model's assignment as a `LabelSet`, consuming the model. Models that keep their assignment move
+    /// it across rather than copying it, so this is the way to keep the labels of a fit without its model.
+    fn into_labels(self) -> LabelSet
+    where
+        Self: Sized,
+    {
+        self.label_set()
+    }
+}
+
+/// Clustering parameters that can be fit to data, so pipelines and parameter searches can swap one algorithm
+/// for another. Any closure from data to a model is one: `|data: &Array2<f64>| Kmeans::fit(data, 3, 100, 1e-4)`.
+pub trait Fit<T: Float> {
+    type Model: Clustering;
+    fn fit(&self, data: &Array2<T>) -> Self::Model;
+}
+
+impl<T: Float, M: Clustering, F: Fn(&Array2<T>) -> M> Fit<T> for F {
+    type Model = M;
+
+    fn fit(&self, data: &Array2<T>) -> M {
+        self(data)
+    }
+}
+
+/// Fitted models that can label new points, with the same labels as their training points.
+pub trait Predict<T: Float> {
+    fn predict(&self, data: &Array2<T>) -> Vec<usize>;
+}
+
+/// A transform fitted to training data, such as a feature scaling, that maps new data the same way so
+/// `predict` sees inputs on the scale the model was fit on.
+pub trait Transform<T: Float> {
+    fn transform(&self, data: &Array2<T>) -> Array2<T>;
+}
+
+/// Receives progress from long fits. `report` gets the fraction of points processed and the number of
+/// clusters found so far, and returning false cancels the fit. Any `FnMut(f64, usize) -> bool` is one.
+pub trait Progress {
+    fn report(&mut self, fraction: f64, clusters: usize) -> bool;
+}
+
+impl<F: FnMut(f64, usize) -> bool> Progress for F {
+    fn report(&mut self, fraction: f64, clusters: usize) -> bool {
+        self(fraction, clusters)
+    }
+}
+
+/// Clusterers that can ingest data in batches, labelling every point seen so far in arrival order.
+pub trait PartialFit<T: Float>: Clustering {
+    fn partial_fit(&mut self, batch: &Array2<T>);
+
+    /// Feeds batches from a fallible source, such as a reader decoding record batches off a stream, in order
+    /// until it runs out or fails, returning the number of rows taken or the first error. Batches before the
+    /// error stay fitted. Fits that need every row at once take the same batches through `Dbscan::try_new_chunked`.
+    fn partial_fit_stream<E, I: IntoIterator<Item = Result<Array2<T>, E>>>(&mut self, batches: I) -> Result<usize, E>
+    where
+        Self: Sized,
+    {
+        let mut rows = 0;
+        for batch in batches {
+            let batch = batch?;
+            self.partial_fit(&batch);
+            rows += batch.rows();
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::birch::Birch;
+    use crate::dbscan::DbscanBuilder;
+    use crate::gmm::{CovarianceType, GaussianMixture};
+    use crate::kmeans::Kmeans;
+    use crate::kmedoids::KMedoids;
+    use crate::metrics::adjusted_rand_index;
+    use ndarray::s;
+
+    #[test]
+    fn test_partial_fit_stream() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -2.1, 2.9, -1.9, 3.0]).unwrap();
+        let batches = vec![data.slice(s![..3, ..]).to_owned(), data.slice(s![3.., ..]).to_owned()];
+        let mut streamed = Birch::new(0.5, 10, Some(2));
+        assert!(streamed.partial_fit_stream(batches.clone().into_iter().map(Ok::<Array2<f64>, String>)) == Ok(8));
+        let mut direct = Birch::new(0.5, 10, Some(2));
+        direct.partial_fit(&data);
+        assert!(streamed.labels() == direct.labels());
+
+        // A failing source stops the stream, keeping the batches before it.
+        let mut partial = Birch::new(0.5, 10, Some(2));
+        let failing = vec![Ok(batches[0].clone()), Err("truncated stream"), Ok(batches[1].clone())];
+        assert!(partial.partial_fit_stream(failing) == Err("truncated stream") && partial.labels().len() == 3);
+    }
+
+    fn fit_labels<F: Fit<f64>>(params: &F, data: &Array2<f64>) -> Vec<usize> {
+        params.fit(data).labels()
+    }
+
+    #[test]
+    fn test_interchangeable() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -2.1, 2.9, -1.9, 3.0]).unwrap();
+        let truth = vec![0, 0, 0, 0, 1, 1, 1, 1];
+        let dbscan = fit_labels(&DbscanBuilder::new(0.5, 2).seed(1), &data);
+        let kmeans = fit_labels(&|data: &Array2<f64>| Kmeans::fit(data, 2, 100, 1e-6), &data);
+        let kmedoids = fit_labels(&|data: &Array2<f64>| KMedoids::new(data, 2, 10), &data);
+        assert!(DbscanBuilder::new(0.5, 3).seed(1).fit(&data).labels_array().iter().all(|label| *label > 0) && KMedoids::new(&data, 2, 10).labels_array().len() == 8);
+        for labels in [dbscan, kmeans, kmedoids] {
+            assert!(adjusted_rand_index(&labels, &truth) == 1.0);
+        }
+
+        let new_data = Array2::from_shape_vec((2, 2), vec![1.05, 2.05, -2.05, 3.05]).unwrap();
+        let dbscan = DbscanBuilder::new(0.5, 2).seed(1).fit(&data);
+        let kmeans = Kmeans::fit(&data, 2, 100, 1e-6);
+        let gmm = GaussianMixture::fit(&data, 2, CovarianceType::Spherical, 100, 1e-6);
+        let models: [(&dyn Predict<f64>, Vec<usize>); 3] = [(&dbscan, dbscan.labels()), (&kmeans, kmeans.labels()), (&gmm, gmm.predict(&data))];
+        for (model, labels) in models.iter() {
+            assert!(model.predict(&new_data) == vec![labels[0], labels[4]]);
+        }
+    }
+
+    #[test]
+    fn test_into_labels() {
+        let data = Array2::from_shape_vec((8, 2), vec![1.0, 2.0, 1.1, 2.2, 0.9, 1.9, 1.0, 2.1, -2.0, 3.0, -2.2, 3.1, -2.1, 2.9, -1.9, 3.0]).unwrap();
+        let dbscan = DbscanBuilder::new(0.5, 2).seed(1).fit(&data);
+        let kmeans = Kmeans::fit(&data, 2, 100, 1e-6);
+        let mut birch = Birch::new(0.5, 10, Some(2));
+        birch.partial_fit(&data);
+        let expected = [dbscan.label_set(), kmeans.label_set(), birch.label_set()];
+        let labels = [dbscan.into_labels(), kmeans.into_labels(), birch.into_labels()];
+        assert!(labels == expected && labels[1].same_partition(&labels[2]) && labels[0].len() == 8);
+    }
+}
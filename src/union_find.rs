@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub(crate) struct UnionFind {
+    parents: Vec<usize>,
+    ranks: Vec<usize>,
+}
+
+impl UnionFind {
+    pub(crate) fn new(size: usize) -> UnionFind {
+        UnionFind {
+            parents: (0..size).collect(),
+            ranks: vec![0; size],
+        }
+    }
+
+    pub(crate) fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut x = x;
+        while self.parents[x] != root {
+            let next = self.parents[x];
+            self.parents[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    pub(crate) fn union(&mut self, a: usize, b: usize) -> usize {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return a;
+        }
+        let (root, child) = if self.ranks[a] < self.ranks[b] { (b, a) } else { (a, b) };
+        self.parents[child] = root;
+        if self.ranks[root] == self.ranks[child] {
+            self.ranks[root] += 1;
+        }
+        root
+    }
+}
+
+/// Disjoint sets that many threads can union at once. Roots are linked lower index over higher with a
+/// compare-and-swap that only succeeds while the higher one is still a root, and finds halve their paths as
+/// they go, so no locks are needed and the sets come out the same whatever order the unions ran in.
+pub(crate) struct AtomicUnionFind {
+    parents: Vec<AtomicUsize>,
+}
+
+impl AtomicUnionFind {
+    pub(crate) fn new(size: usize) -> AtomicUnionFind {
+        AtomicUnionFind { parents: (0..size).map(AtomicUsize::new).collect() }
+    }
+
+    pub(crate) fn find(&self, x: usize) -> usize {
+        let mut x = x;
+        loop {
+            let parent = self.parents[x].load(Ordering::Acquire);
+            if parent == x {
+                return x;
+            }
+            let grandparent = self.parents[parent].load(Ordering::Acquire);
+            // Losing this race only means another thread already moved x higher up.
+            let _ = self.parents[x].compare_exchange(parent, grandparent, Ordering::AcqRel, Ordering::Acquire);
+            x = grandparent;
+        }
+    }
+
+    pub(crate) fn union(&self, a: usize, b: usize) {
+        loop {
+            let (a, b) = (self.find(a), self.find(b));
+            if a == b {
+                return;
+            }
+            let (root, child) = if a < b { (a, b) } else { (b, a) };
+            if self.parents[child].compare_exchange(child, root, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//! JavaScript bindings through `wasm-bindgen`, for clustering in the browser. Build a module with
+//! `cargo rustc --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib` and generate its
+//! JavaScript glue with `wasm-bindgen --target web`. Labels follow `Dbscan`, with 0 for noise and clusters
+//! from 1.
+
+use crate::dbscan::{validate, Dbscan};
+use ndarray::prelude::*;
+use wasm_bindgen::prelude::*;
+
+/// Fits DBSCAN to the row-major values of a `Float64Array`, `cols` to a row, seeding the fit with `seed` so
+/// the labels are reproducible, and returns each row's label as a `Uint32Array`. Throws if the values don't
+/// make whole rows, are empty or not all finite, `eps` is negative or NaN, or `min_points` is 0.
+#[wasm_bindgen(js_name = dbscanFit)]
+pub fn dbscan_fit(data: &[f64], cols: usize, eps: f64, min_points: usize, borders: bool, seed: u32) -> Result<Vec<u32>, JsError> {
+    fit_labels(data, cols, eps, min_points, borders, seed).map_err(|message| JsError::new(&message))
+}
+
+/// `dbscan_fit` with its errors as messages, since `JsError` can only be built on a wasm target.
+fn fit_labels(data: &[f64], cols: usize, eps: f64, min_points: usize, borders: bool, seed: u32) -> Result<Vec<u32>, String> {
+    if eps.is_nan() || eps < 0.0 {
+        return Err(format!("eps must be a non-negative number, not {}", eps));
+    }
+    if min_points == 0 {
+        return Err("min_points must be at least 1".to_string());
+    }
+    if cols == 0 || data.len() % cols != 0 {
+        return Err(format!("{} values don't make whole rows of {} columns", data.len(), cols));
+    }
+    let data = Array2::from_shape_vec((data.len() / cols, cols), data.to_vec()).expect("shape of whole rows");
+    validate(&data).map_err(|error| error.to_string())?;
+    Ok(Dbscan::with_seed(&data, eps, min_points, borders, u64::from(seed)).clusters.into_iter().map(|cluster| cluster as u32).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dbscan_fit() {
+        let values = [0.0, 0.0, 0.1, 0.0, 0.0, 0.1, 5.0, 5.0, 5.1, 5.0, 5.0, 5.1, 20.0, 20.0];
+        let labels = dbscan_fit(&values, 2, 0.5, 3, true, 1).unwrap();
+        assert!(labels[..3] == [labels[0]; 3] && labels[3..6] == [labels[3]; 3] && labels[0] != labels[3] && labels[6] == 0);
+        assert!(labels == dbscan_fit(&values, 2, 0.5, 3, true, 1).unwrap());
+
+        assert!(fit_labels(&values, 3, 0.5, 3, true, 1).unwrap_err() == "14 values don't make whole rows of 3 columns");
+        assert!(fit_labels(&values, 0, 0.5, 3, true, 1).is_err() && fit_labels(&[], 2, 0.5, 3, true, 1).is_err());
+        assert!(fit_labels(&[0.0, f64::NAN], 2, 0.5, 3, true, 1).unwrap_err() == "non-finite value at row 0, column 1");
+        for (eps, min_points) in [(f64::NAN, 3), (-0.5, 3), (0.5, 0)].iter() {
+            assert!(fit_labels(&values, 2, *eps, *min_points, true, 1).is_err());
+        }
+    }
+}